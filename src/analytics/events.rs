@@ -32,6 +32,10 @@ pub struct HookEvent {
     /// Reason for passthrough (if applicable).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub passthrough_reason: Option<String>,
+    /// Whether the decision was served from the router's decision cache
+    /// (absent when the cache was not consulted).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_hit: Option<bool>,
 }
 
 // ---------------------------------------------------------------------------
@@ -46,25 +50,32 @@ pub fn log_hook_event(event: &HookEvent) {
 }
 
 /// Convenience: log a rewrite event.
-pub fn log_rewrite(tool_name: &str, command: Option<&str>) {
+pub fn log_rewrite(tool_name: &str, command: Option<&str>, cache_hit: Option<bool>) {
     let event = HookEvent {
         timestamp: Utc::now().to_rfc3339(),
         tool_name: tool_name.to_string(),
         command: command.map(|s| s.to_string()),
         decision: "rewrite".to_string(),
         passthrough_reason: None,
+        cache_hit,
     };
     log_hook_event(&event);
 }
 
 /// Convenience: log a passthrough event.
-pub fn log_passthrough(tool_name: &str, command: Option<&str>, reason: &str) {
+pub fn log_passthrough(
+    tool_name: &str,
+    command: Option<&str>,
+    reason: &str,
+    cache_hit: Option<bool>,
+) {
     let event = HookEvent {
         timestamp: Utc::now().to_rfc3339(),
         tool_name: tool_name.to_string(),
         command: command.map(|s| s.to_string()),
         decision: "passthrough".to_string(),
         passthrough_reason: Some(reason.to_string()),
+        cache_hit,
     };
     log_hook_event(&event);
 }
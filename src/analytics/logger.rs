@@ -41,6 +41,12 @@ pub struct CommandLogEntry {
     /// Wall-clock time spent in the preprocessing pipeline (milliseconds).
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub preprocessing_duration_ms: Option<u64>,
+    /// Exit code of the wrapped command (`128 + signal` for signal kills).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub exit_code: Option<i32>,
+    /// Secrets masked in the output and command string (absent when none).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub redactions: Option<usize>,
 }
 
 fn default_true() -> bool {
@@ -80,6 +86,8 @@ pub fn log_command_result(
         None,
         None,
         None,
+        None,
+        None,
     )
 }
 
@@ -105,6 +113,8 @@ pub fn log_command_result_with_latency(
         None,
         None,
         None,
+        None,
+        None,
     )
 }
 
@@ -121,6 +131,8 @@ pub fn log_command_result_full(
     preprocessing_bytes_removed: Option<usize>,
     preprocessing_pct: Option<f64>,
     preprocessing_duration_ms: Option<u64>,
+    exit_code: Option<i32>,
+    redactions: Option<usize>,
 ) {
     let savings_pct = if original_tokens == 0 {
         0.0
@@ -141,6 +153,8 @@ pub fn log_command_result_full(
         preprocessing_bytes_removed,
         preprocessing_pct,
         preprocessing_duration_ms,
+        exit_code,
+        redactions,
     };
 
     let _ = append_log_entry(&entry);
@@ -20,6 +20,8 @@ pub struct Stats {
     pub total_original_tokens: usize,
     pub total_optimized_tokens: usize,
     pub total_savings_pct: f64,
+    /// Secrets masked across all commands.
+    pub total_redactions: usize,
     pub path_distribution: PathDistribution,
     pub command_stats: Vec<CommandStat>,
 }
@@ -107,6 +109,7 @@ fn build_stats(entries: &[CommandLogEntry]) -> Stats {
             total_original_tokens: 0,
             total_optimized_tokens: 0,
             total_savings_pct: 0.0,
+            total_redactions: 0,
             path_distribution: PathDistribution::default(),
             command_stats: Vec::new(),
         };
@@ -123,6 +126,7 @@ fn build_stats(entries: &[CommandLogEntry]) -> Stats {
         (saved as f64 / total_original_tokens as f64) * 100.0
     };
 
+    let total_redactions = entries.iter().filter_map(|e| e.redactions).sum();
     let path_distribution = compute_path_distribution(entries);
     let command_stats = compute_command_stats(entries);
 
@@ -131,6 +135,7 @@ fn build_stats(entries: &[CommandLogEntry]) -> Stats {
         total_original_tokens,
         total_optimized_tokens,
         total_savings_pct,
+        total_redactions,
         path_distribution,
         command_stats,
     }
@@ -232,7 +237,7 @@ pub fn discover_candidates(days: Option<u32>) -> Vec<DiscoveryCandidate> {
         .map(|(cmd, group)| {
             let count = group.len();
             let total_tokens: usize = group.iter().map(|e| e.original_tokens).sum();
-            let avg_tokens = if count == 0 { 0 } else { total_tokens / count };
+            let avg_tokens = total_tokens.checked_div(count).unwrap_or(0);
 
             // Most common path for this command
             let mut path_counts: HashMap<&str, usize> = HashMap::new();
@@ -256,7 +261,7 @@ pub fn discover_candidates(days: Option<u32>) -> Vec<DiscoveryCandidate> {
         .collect();
 
     // Sort by total token consumption descending
-    candidates.sort_by(|a, b| b.total_tokens.cmp(&a.total_tokens));
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.total_tokens));
 
     candidates
 }
@@ -329,6 +334,8 @@ mod tests {
                 preprocessing_bytes_removed: None,
                 preprocessing_pct: None,
                 preprocessing_duration_ms: None,
+                exit_code: None,
+                redactions: None,
             },
             CommandLogEntry {
                 timestamp: "2025-01-15T10:05:00+00:00".to_string(),
@@ -343,6 +350,8 @@ mod tests {
                 preprocessing_bytes_removed: None,
                 preprocessing_pct: None,
                 preprocessing_duration_ms: None,
+                exit_code: None,
+                redactions: None,
             },
             CommandLogEntry {
                 timestamp: "2025-01-15T10:10:00+00:00".to_string(),
@@ -357,6 +366,8 @@ mod tests {
                 preprocessing_bytes_removed: Some(500),
                 preprocessing_pct: Some(25.0),
                 preprocessing_duration_ms: None,
+                exit_code: None,
+                redactions: None,
             },
             CommandLogEntry {
                 timestamp: "2025-01-15T10:15:00+00:00".to_string(),
@@ -371,6 +382,8 @@ mod tests {
                 preprocessing_bytes_removed: None,
                 preprocessing_pct: None,
                 preprocessing_duration_ms: None,
+                exit_code: None,
+                redactions: Some(2),
             },
         ]
     }
@@ -384,6 +397,7 @@ mod tests {
         assert_eq!(stats.total_original_tokens, 3510);
         assert_eq!(stats.total_optimized_tokens, 860);
         assert!(stats.total_savings_pct > 75.0);
+        assert_eq!(stats.total_redactions, 2);
     }
 
     #[test]
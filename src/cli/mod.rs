@@ -6,6 +6,7 @@
 //! - `terse discover` — find high-frequency unoptimized commands
 //! - `terse health` — check Ollama, config, hook registration
 //! - `terse test "command"` — preview optimization pipeline
+//! - `terse raw <id>` — retrieve stashed raw output behind a truncation footer
 //! - `terse config show|init|set|reset` — configuration management
 
 use anyhow::{Context, Result};
@@ -18,6 +19,7 @@ use crate::llm::config::SmartPathConfig;
 use crate::llm::ollama::OllamaClient;
 use crate::router;
 use crate::safety::circuit_breaker::CircuitBreaker;
+use crate::stash;
 use crate::utils::process;
 
 /// Output format for analytics commands.
@@ -79,6 +81,13 @@ fn print_stats_table(stats: &Stats) {
         "Avg savings:   ".bold(),
         stats.total_savings_pct
     );
+    if stats.total_redactions > 0 {
+        println!(
+            "  {} {}",
+            "Secrets masked:".bold(),
+            format_number(stats.total_redactions)
+        );
+    }
     println!();
 
     // Path distribution
@@ -132,6 +141,7 @@ fn print_stats_json(stats: &Stats) -> Result<()> {
         "total_original_tokens": stats.total_original_tokens,
         "total_optimized_tokens": stats.total_optimized_tokens,
         "total_savings_pct": stats.total_savings_pct,
+        "total_redactions": stats.total_redactions,
         "path_distribution": {
             "fast": stats.path_distribution.fast,
             "smart": stats.path_distribution.smart,
@@ -449,6 +459,30 @@ pub fn run_health() -> Result<()> {
         if smart_ok { "open" } else { "tripped" },
     );
 
+    // 4b. Decision cache
+    let ttl = cfg.router.decision_cache_ttl_secs;
+    if ttl == 0 {
+        print_health_item("Decision cache", true, "disabled (ttl 0)");
+    } else {
+        let stats = router::DecisionCache::load(ttl, config::layer_fingerprint()).stats();
+        let lookups = stats.hits + stats.misses;
+        print_health_item(
+            "Decision cache",
+            true,
+            &if lookups == 0 {
+                format!("{} entries, no lookups yet (ttl {ttl}s)", stats.entries)
+            } else {
+                format!(
+                    "{} entries, {:.1}% hit rate ({}/{} lookups, ttl {ttl}s)",
+                    stats.entries,
+                    stats.hit_rate() * 100.0,
+                    stats.hits,
+                    lookups,
+                )
+            },
+        );
+    }
+
     // 5. Log file
     let log_exists = logger::command_log_path()
         .map(|p| p.exists())
@@ -782,6 +816,32 @@ pub fn run_test(command: &str) -> Result<()> {
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// terse raw
+// ---------------------------------------------------------------------------
+
+/// Print stashed raw output, optionally sliced by line range and/or regex.
+///
+/// Output is plain (no colors or headers) because the primary consumer is an
+/// agent following up on a truncation footer.
+pub fn run_raw(id: &str, lines: Option<&str>, grep: Option<&str>, stderr: bool) -> Result<()> {
+    let entry = stash::load(id)?;
+    let range = lines.map(stash::parse_line_range).transpose()?;
+    let pattern = grep
+        .map(regex::Regex::new)
+        .transpose()
+        .context("invalid --grep pattern")?;
+
+    let text = if stderr { &entry.stderr } else { &entry.stdout };
+    let sliced = stash::slice(text, range, pattern.as_ref());
+    if sliced.is_empty() {
+        println!("(no matching lines)");
+    } else {
+        println!("{}", sliced.trim_end_matches('\n'));
+    }
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 // Formatting helpers
 // ---------------------------------------------------------------------------
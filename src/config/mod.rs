@@ -31,7 +31,9 @@
 /// ```
 pub mod schema;
 
+use std::collections::hash_map::DefaultHasher;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
@@ -98,6 +100,35 @@ fn merge_config(base: &mut TerseConfig, overlay: &TerseConfig) {
     *base = overlay.clone();
 }
 
+/// Fingerprint of every input that feeds [`load`].
+///
+/// Hashes the terse version, the size and modification time of the running
+/// binary, the raw contents of the global and project config files, and all
+/// `TERSE_*` environment variables. Any change to a config layer or a
+/// rebuilt binary yields a different value, which lets file-backed caches
+/// (e.g. the router's decision cache) discard stale entries cheaply.
+pub fn layer_fingerprint() -> u64 {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    std::env::current_exe()
+        .and_then(fs::metadata)
+        .ok()
+        .map(|meta| (meta.len(), meta.modified().ok()))
+        .hash(&mut hasher);
+
+    for path in [global_config_path(), project_config_path()] {
+        path.and_then(|p| fs::read(p).ok()).hash(&mut hasher);
+    }
+
+    let mut vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(key, _)| key.starts_with("TERSE_"))
+        .collect();
+    vars.sort();
+    vars.hash(&mut hasher);
+
+    hasher.finish()
+}
+
 // ---------------------------------------------------------------------------
 // File paths
 // ---------------------------------------------------------------------------
@@ -368,6 +399,11 @@ mod tests {
         assert!(config.general.enabled);
     }
 
+    #[test]
+    fn layer_fingerprint_is_stable_without_changes() {
+        assert_eq!(layer_fingerprint(), layer_fingerprint());
+    }
+
     #[test]
     fn is_truthy_accepts_variants() {
         assert!(is_truthy("1"));
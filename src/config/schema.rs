@@ -2,8 +2,8 @@
 ///
 /// Defines the TOML-serializable configuration structure with all sections:
 /// `[general]`, `[fast_path]`, `[smart_path]`, `[output_thresholds]`,
-/// `[preprocessing]`, `[router]`, `[passthrough]`, `[logging]`, and
-/// `[whitespace]`.
+/// `[preprocessing]`, `[router]`, `[passthrough]`, `[logging]`,
+/// `[whitespace]`, `[stash]`, and `[stderr]`.
 ///
 /// Every field has a sensible built-in default. Users only need to set the
 /// values they want to override.
@@ -30,6 +30,8 @@ pub struct TerseConfig {
     pub passthrough: PassthroughConfig,
     pub logging: LoggingConfig,
     pub whitespace: WhitespaceConfig,
+    pub stash: StashConfig,
+    pub stderr: StderrConfig,
     pub optimizers: OptimizersConfig,
 }
 
@@ -147,7 +149,9 @@ impl Default for FastPathOptimizers {
 pub struct FastPathConfig {
     /// Whether the fast path is enabled.
     pub enabled: bool,
-    /// Maximum time budget for a fast-path optimizer (milliseconds).
+    /// Maximum time budget for a fast-path optimizer (milliseconds). An
+    /// optimizer that overruns is abandoned in favor of the preprocessed
+    /// output. `0` disables the limit.
     pub timeout_ms: u64,
     /// Per-optimizer enable/disable toggles.
     pub optimizers: FastPathOptimizers,
@@ -235,14 +239,44 @@ pub struct PreprocessingConfig {
     pub max_output_bytes: usize,
     /// Enable noise removal (ANSI codes, progress bars, boilerplate).
     pub noise_removal: bool,
+    /// Lines longer than this many characters keep only their start and
+    /// end; embedded base64/hex blobs are shortened too. `0` disables.
+    pub max_line_chars: usize,
     /// Enable path filtering (node_modules, target, etc.).
     pub path_filtering: bool,
     /// Path filter mode: `"summary"` (annotated) or `"remove"` (silent).
     pub path_filter_mode: String,
+    /// Shorten absolute paths to `./`, `<repo>/` and `~/` forms and factor
+    /// out a directory shared by most lines. Off by default; file reads and
+    /// diffs are never rewritten.
+    pub path_shortening: bool,
     /// Enable deduplication of repeated lines/blocks.
     pub deduplication: bool,
+    /// Deduplication mode for all commands: `"consecutive"` (runs only) or
+    /// `"global"` (also later duplicates anywhere in the output).
+    pub dedup_mode: String,
+    /// Command categories (`build_test`, `logs`, `container_tools`,
+    /// `version_control`, `file_operations`, `generic`) deduplicated
+    /// globally regardless of `dedup_mode`. Empty by default.
+    pub global_dedup_categories: Vec<String>,
+    /// Append the most frequent repeated lines after global deduplication.
+    pub dedup_summary: bool,
     /// Enable truncation with context preservation.
     pub truncation: bool,
+    /// Truncation strategy: `"salience"` (keep context around errors,
+    /// warnings and summaries) or `"head_tail"` (fixed head and tail).
+    pub truncation_mode: String,
+    /// Log template clustering: `"auto"` (log and container commands),
+    /// `"always"` or `"off"`.
+    pub log_clustering: String,
+    /// Minimum output lines before log clustering applies.
+    pub log_clustering_min_lines: usize,
+    /// Mask credentials in command output and logged command strings.
+    pub redaction: bool,
+    /// Additional secret regexes (the first capture group, or the whole
+    /// match, is masked).
+    #[serde(default)]
+    pub extra_secret_patterns: Vec<String>,
     /// Additional boilerplate patterns (appended to built-in list).
     #[serde(default)]
     pub extra_boilerplate: Vec<String>,
@@ -257,10 +291,20 @@ impl Default for PreprocessingConfig {
             enabled: true,
             max_output_bytes: 128 * 1024, // 128 KB
             noise_removal: true,
+            max_line_chars: 1000,
             path_filtering: true,
             path_filter_mode: "summary".to_string(),
+            path_shortening: false,
             deduplication: true,
+            dedup_mode: "consecutive".to_string(),
+            global_dedup_categories: Vec::new(),
+            dedup_summary: false,
             truncation: true,
+            truncation_mode: "salience".to_string(),
+            log_clustering: "auto".to_string(),
+            log_clustering_min_lines: 100,
+            redaction: true,
+            extra_secret_patterns: Vec::new(),
             extra_boilerplate: Vec::new(),
             extra_filtered_dirs: Vec::new(),
         }
@@ -275,7 +319,8 @@ impl Default for PreprocessingConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct RouterConfig {
-    /// TTL for the in-memory decision cache (seconds).
+    /// TTL for the file-backed hook decision cache (seconds). `0` disables
+    /// the cache.
     pub decision_cache_ttl_secs: u64,
     /// Failure rate threshold to trip the circuit breaker (0.0–1.0).
     pub circuit_breaker_threshold: f64,
@@ -283,6 +328,10 @@ pub struct RouterConfig {
     pub circuit_breaker_window: usize,
     /// Cooldown duration (seconds) after tripping the circuit breaker.
     pub circuit_breaker_cooldown_secs: i64,
+    /// Execution deadline for wrapped commands (seconds). On expiry the
+    /// process tree is killed and the partial output returned. `0` disables
+    /// the deadline.
+    pub command_timeout_secs: u64,
 }
 
 impl Default for RouterConfig {
@@ -292,6 +341,7 @@ impl Default for RouterConfig {
             circuit_breaker_threshold: 0.2,
             circuit_breaker_window: 10,
             circuit_breaker_cooldown_secs: 600,
+            command_timeout_secs: 600,
         }
     }
 }
@@ -301,11 +351,35 @@ impl Default for RouterConfig {
 // ---------------------------------------------------------------------------
 
 /// Passthrough command list — commands that should never be optimized.
+///
+/// Both lists apply to every command in a chain, pipeline, substitution, or
+/// wrapper (`sudo`, `xargs`, `bash -c`, ...), not just the last one. They
+/// extend the built-in danger table in [`crate::safety::classifier`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct PassthroughConfig {
-    /// Commands to always pass through (matched by first word).
+    /// Commands to always pass through (matched by command name).
     pub commands: Vec<String>,
+    /// Argument-aware passthrough rules (`[[passthrough.rules]]`).
+    pub rules: Vec<PassthroughRule>,
+}
+
+/// An argument-aware passthrough rule, e.g. `git push --force`.
+///
+/// A rule matches when every field that is set matches.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PassthroughRule {
+    /// Command name, matched case-insensitively without directory or `.exe`.
+    pub command: String,
+    /// Leading positional arguments, e.g. `"stash drop"`. Empty matches any.
+    pub subcommand: String,
+    /// Matches when any of these flags is present. Short flags also match
+    /// inside bundles (`-f` matches `-fdx`). Empty matches any.
+    pub flags: Vec<String>,
+    /// Regex matched against the space-joined arguments. An invalid regex
+    /// is ignored as if it matched, so a typo errs toward passthrough.
+    pub pattern: Option<String>,
 }
 
 impl Default for PassthroughConfig {
@@ -325,6 +399,7 @@ impl Default for PassthroughConfig {
                 "mv".to_string(),
                 "move".to_string(),
             ],
+            rules: Vec::new(),
         }
     }
 }
@@ -384,6 +459,74 @@ impl Default for WhitespaceConfig {
     }
 }
 
+// ---------------------------------------------------------------------------
+// [stash]
+// ---------------------------------------------------------------------------
+
+/// Raw-output stash settings (`~/.terse/raw/`, read back with `terse raw`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StashConfig {
+    /// Whether truncated runs save their full raw output.
+    pub enabled: bool,
+    /// Total size budget for the stash; least-recently-used entries are
+    /// evicted beyond this.
+    pub max_total_bytes: u64,
+    /// Maximum number of stashed outputs kept.
+    pub max_entries: usize,
+}
+
+impl Default for StashConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_total_bytes: 64 * 1024 * 1024,
+            max_entries: 200,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// [stderr]
+// ---------------------------------------------------------------------------
+
+/// How stderr-derived lines are presented on optimized paths.
+///
+/// Optimizers see stdout and stderr interleaved in arrival order. Afterwards,
+/// output lines that came verbatim from stderr (and not from stdout) can be
+/// marked or moved back to the real stderr. Passthrough output always keeps
+/// the two streams separate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StderrMode {
+    /// Prefix stderr-derived lines with `marker` when the command wrote to
+    /// both streams (default).
+    #[default]
+    Mark,
+    /// Leave the interleaved output unmarked.
+    Merge,
+    /// Write stderr-derived lines to terse's own stderr.
+    Separate,
+}
+
+/// Stderr presentation settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StderrConfig {
+    pub mode: StderrMode,
+    /// Prefix used by `mode = "mark"`.
+    pub marker: String,
+}
+
+impl Default for StderrConfig {
+    fn default() -> Self {
+        Self {
+            mode: StderrMode::Mark,
+            marker: "[stderr] ".to_string(),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // [optimizers] — per-optimizer configurable limits
 // ---------------------------------------------------------------------------
@@ -399,7 +542,10 @@ pub struct OptimizersConfig {
     pub git: GitOptimizerConfig,
     pub file: FileOptimizerConfig,
     pub build: BuildOptimizerConfig,
+    pub search: SearchOptimizerConfig,
     pub docker: DockerOptimizerConfig,
+    pub kubectl: KubectlOptimizerConfig,
+    pub json: JsonOptimizerConfig,
     pub generic: GenericOptimizerConfig,
 }
 
@@ -458,6 +604,12 @@ pub struct FileOptimizerConfig {
     pub cat_head_lines: usize,
     /// Lines to keep from the tail of `cat` output when truncating.
     pub cat_tail_lines: usize,
+    /// Outline source files shown with a plain `cat FILE` instead of
+    /// keeping their head and tail; lockfiles and minified files are
+    /// summarized.
+    pub cat_outline: bool,
+    /// Function bodies longer than this are folded in a `cat` outline.
+    pub cat_fold_lines: usize,
     /// Maximum lines for `wc` output.
     pub wc_max_lines: usize,
     /// Maximum lines for `tree` output.
@@ -479,6 +631,8 @@ impl Default for FileOptimizerConfig {
             cat_max_lines: 100,
             cat_head_lines: 60,
             cat_tail_lines: 30,
+            cat_outline: true,
+            cat_fold_lines: 10,
             wc_max_lines: 30,
             tree_max_lines: 60,
             tree_noise_dirs: default_tree_noise_dirs(),
@@ -540,6 +694,10 @@ pub struct BuildOptimizerConfig {
     pub build_max_warnings: usize,
     /// Maximum issue lines in lint output.
     pub lint_max_issue_lines: usize,
+    /// Re-run `cargo build|check|clippy` with `--message-format=short` when
+    /// no message format was given, trading code snippets for one line per
+    /// diagnostic.
+    pub cargo_short_messages: bool,
 }
 
 impl Default for BuildOptimizerConfig {
@@ -552,6 +710,35 @@ impl Default for BuildOptimizerConfig {
             build_max_error_lines: 60,
             build_max_warnings: 10,
             lint_max_issue_lines: 80,
+            cargo_short_messages: false,
+        }
+    }
+}
+
+/// Search (`grep` / `rg` / `ag` / `git grep`) optimizer limits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptimizerConfig {
+    /// Whether the search optimizer is enabled.
+    pub enabled: bool,
+    /// Maximum files whose matches are shown.
+    pub max_files: usize,
+    /// Maximum matches shown per file.
+    pub max_matches_per_file: usize,
+    /// Maximum length of a matching line; longer lines are cut around the match.
+    pub max_line_length: usize,
+    /// Maximum paths shown for `-l` / `-c` output.
+    pub max_listed_files: usize,
+}
+
+impl Default for SearchOptimizerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_files: 20,
+            max_matches_per_file: 5,
+            max_line_length: 160,
+            max_listed_files: 50,
         }
     }
 }
@@ -593,6 +780,86 @@ impl Default for DockerOptimizerConfig {
     }
 }
 
+/// Kubernetes (`kubectl` / `helm`) optimizer limits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KubectlOptimizerConfig {
+    /// Whether the kubectl/helm optimizer is enabled.
+    pub enabled: bool,
+    /// Maximum rows needing attention shown for `kubectl get`.
+    pub get_max_rows: usize,
+    /// Maximum events shown for `kubectl describe` (warnings first).
+    pub describe_max_events: usize,
+    /// Maximum tail lines for `kubectl logs`.
+    pub logs_max_tail: usize,
+    /// Maximum error lines extracted from `kubectl logs`.
+    pub logs_max_errors: usize,
+    /// Maximum rows for `helm list` output.
+    pub helm_max_rows: usize,
+    /// Maximum (most recent) revisions for `helm history`.
+    pub helm_history_max_rows: usize,
+    /// Maximum NOTES lines for `helm status`.
+    pub helm_notes_max_lines: usize,
+}
+
+impl Default for KubectlOptimizerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            get_max_rows: 30,
+            describe_max_events: 10,
+            logs_max_tail: 30,
+            logs_max_errors: 20,
+            helm_max_rows: 30,
+            helm_history_max_rows: 10,
+            helm_notes_max_lines: 10,
+        }
+    }
+}
+
+/// JSON / NDJSON summarizer limits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JsonOptimizerConfig {
+    /// Whether the JSON optimizer is enabled.
+    pub enabled: bool,
+    /// Array elements (and NDJSON records) kept; the rest are counted.
+    pub max_array_items: usize,
+    /// Strings longer than this many characters are cut.
+    pub max_string_length: usize,
+    /// Drop `null`, `""`, `[]` and `{}` fields below the top level.
+    pub drop_empty: bool,
+    /// Per-command overrides (`[[optimizers.json.commands]]`).
+    pub commands: Vec<JsonCommandConfig>,
+}
+
+/// JSON limits for one command, e.g. `gh api`.
+///
+/// Unset fields fall back to the `[optimizers.json]` values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JsonCommandConfig {
+    /// Leading words of the command, matched case-insensitively.
+    pub command: String,
+    /// Set to `false` to leave this command's JSON untouched.
+    pub enabled: Option<bool>,
+    pub max_array_items: Option<usize>,
+    pub max_string_length: Option<usize>,
+    pub drop_empty: Option<bool>,
+}
+
+impl Default for JsonOptimizerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_array_items: 3,
+            max_string_length: 200,
+            drop_empty: true,
+            commands: Vec::new(),
+        }
+    }
+}
+
 /// Generic (fallback) optimizer limits.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -676,7 +943,7 @@ safe_mode = false     # Set true or TERSE_SAFE_MODE=1 to disable all optimizatio
 
 [fast_path]
 enabled = true
-timeout_ms = 100
+timeout_ms = 100                      # Abandon optimizers that run longer; 0 = no limit
 
 [fast_path.optimizers]
 git = true
@@ -700,22 +967,40 @@ smart_path_above_bytes = 20480        # >= 20 KB -> smart path eligible
 enabled = true
 max_output_bytes = 32768              # Truncate to 32 KB after preprocessing
 noise_removal = true
+max_line_chars = 1000                 # Elide the middle of longer lines and blobs; 0 disables
 path_filtering = true
 path_filter_mode = "summary"          # "summary" (annotated) or "remove" (silent)
+path_shortening = false               # Rewrite absolute paths as ./, <repo>/ and ~/
 deduplication = true
+dedup_mode = "consecutive"            # "consecutive" (runs only) or "global" (anywhere)
+# global_dedup_categories = []        # Categories deduplicated globally, e.g. ["build_test"]
+dedup_summary = false                 # List the most frequent lines after global dedup
 truncation = true
+truncation_mode = "salience"          # "salience" (keep errors in context) or "head_tail"
+log_clustering = "auto"               # "auto" (logs/containers), "always" or "off"
+log_clustering_min_lines = 100        # Cluster log templates only in longer output
+redaction = true                      # Mask tokens, keys and passwords in output and logs
+# extra_secret_patterns = []          # Additional secret regexes
 # extra_boilerplate = []              # Additional boilerplate patterns
 # extra_filtered_dirs = []            # Additional directories to filter
 
 [router]
-decision_cache_ttl_secs = 300
+decision_cache_ttl_secs = 300         # Hook decision cache TTL; 0 disables caching
 circuit_breaker_threshold = 0.2
 circuit_breaker_window = 10
 circuit_breaker_cooldown_secs = 600
+command_timeout_secs = 600            # Kill hung commands (whole process tree); 0 = no limit
 
 [passthrough]
 commands = ["code", "vim", "vi", "nano", "emacs", "subl", "notepad", "rm", "rmdir", "del", "mv", "move"]
 
+# Argument-aware rules, checked against every command in a chain or wrapper:
+# [[passthrough.rules]]
+# command = "terraform"
+# subcommand = "apply"                # leading positional arguments
+# flags = ["-auto-approve"]           # any of these flags (empty = always)
+# pattern = "-target=prod"            # optional regex over the arguments
+
 [logging]
 enabled = true
 path = "~/.terse/command-log.jsonl"
@@ -727,6 +1012,15 @@ max_consecutive_newlines = 2
 normalize_tabs = true
 trim_trailing = true
 
+[stash]
+enabled = true                        # Save full output of truncated runs (terse raw <id>)
+max_total_bytes = 67108864            # 64 MB; least-recently-used entries evicted first
+max_entries = 200
+
+[stderr]
+mode = "mark"                         # mark | merge | separate (optimized paths only)
+marker = "[stderr] "                  # Prefix for stderr lines when mode = "mark"
+
 [optimizers.git]
 enabled = true
 log_max_entries = 50
@@ -745,6 +1039,8 @@ find_max_results = 40
 cat_max_lines = 100
 cat_head_lines = 60
 cat_tail_lines = 30
+cat_outline = true                    # Outline source files, summarize lockfiles and minified files
+cat_fold_lines = 10                   # Function bodies longer than this are folded in an outline
 wc_max_lines = 30
 tree_max_lines = 60
 
@@ -756,6 +1052,14 @@ test_max_warnings = 10
 build_max_error_lines = 60
 build_max_warnings = 10
 lint_max_issue_lines = 80
+cargo_short_messages = false          # Re-run cargo builds with --message-format=short
+
+[optimizers.search]
+enabled = true
+max_files = 20                        # Files shown with matches; the rest are counted
+max_matches_per_file = 5
+max_line_length = 160                 # Longer matching lines are cut around the match
+max_listed_files = 50                 # Paths shown for -l / -c output
 
 [optimizers.docker]
 enabled = true
@@ -767,6 +1071,28 @@ inspect_max_lines = 60
 compose_max_rows = 30
 resource_max_rows = 30
 
+[optimizers.kubectl]
+enabled = true
+get_max_rows = 30                     # Unhealthy rows shown; healthy rows are counted
+describe_max_events = 10
+logs_max_tail = 30
+logs_max_errors = 20
+helm_max_rows = 30
+helm_history_max_rows = 10
+helm_notes_max_lines = 10
+
+[optimizers.json]
+enabled = true
+max_array_items = 3                   # Array elements / NDJSON records kept; the rest are counted
+max_string_length = 200               # Longer strings are cut
+drop_empty = true                     # Drop null and empty fields below the top level
+
+# Per-command overrides, matched by leading words:
+# [[optimizers.json.commands]]
+# command = "gh api"
+# max_array_items = 10
+# enabled = false                     # leave this command's JSON untouched
+
 [optimizers.generic]
 enabled = true
 min_size_bytes = 512                  # Outputs below this size skip generic cleanup
@@ -853,6 +1179,8 @@ path_filtering = false
 path_filter_mode = "remove"
 deduplication = true
 truncation = false
+truncation_mode = "head_tail"
+log_clustering = "off"
 extra_boilerplate = ["custom pattern"]
 extra_filtered_dirs = [".custom_cache/"]
 
@@ -887,11 +1215,14 @@ trim_trailing = false
         assert!(!config.fast_path.optimizers.docker);
         assert_eq!(config.preprocessing.max_output_bytes, 16384);
         assert!(!config.preprocessing.path_filtering);
+        assert_eq!(config.preprocessing.truncation_mode, "head_tail");
+        assert_eq!(config.preprocessing.log_clustering, "off");
         assert_eq!(
             config.preprocessing.extra_boilerplate,
             vec!["custom pattern"]
         );
         assert_eq!(config.router.circuit_breaker_window, 5);
+        assert_eq!(config.router.command_timeout_secs, 600);
         assert_eq!(config.passthrough.commands, vec!["code", "vim"]);
         assert!(!config.logging.enabled);
         assert!(!config.whitespace.enabled);
@@ -965,11 +1296,78 @@ trim_trailing = false
         assert_eq!(Profile::Quality.to_string(), "quality");
     }
 
+    #[test]
+    fn stash_config_defaults() {
+        let config = StashConfig::default();
+        assert!(config.enabled);
+        assert_eq!(config.max_total_bytes, 64 * 1024 * 1024);
+        assert_eq!(config.max_entries, 200);
+    }
+
+    #[test]
+    fn stderr_config_defaults_and_parse() {
+        let config = StderrConfig::default();
+        assert_eq!(config.mode, StderrMode::Mark);
+        assert_eq!(config.marker, "[stderr] ");
+
+        let config: TerseConfig = toml::from_str("[stderr]\nmode = \"separate\"").unwrap();
+        assert_eq!(config.stderr.mode, StderrMode::Separate);
+        assert_eq!(config.stderr.marker, "[stderr] ");
+    }
+
     #[test]
     fn passthrough_config_defaults() {
         let config = PassthroughConfig::default();
         assert!(config.commands.contains(&"rm".to_string()));
         assert!(config.commands.contains(&"code".to_string()));
         assert!(config.commands.contains(&"vim".to_string()));
+        assert!(config.rules.is_empty());
+    }
+
+    #[test]
+    fn passthrough_rules_parse() {
+        let config: TerseConfig = toml::from_str(
+            r#"
+[[passthrough.rules]]
+command = "terraform"
+subcommand = "apply"
+flags = ["-auto-approve"]
+
+[[passthrough.rules]]
+command = "make"
+pattern = "^deploy"
+"#,
+        )
+        .unwrap();
+        let rules = &config.passthrough.rules;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].subcommand, "apply");
+        assert_eq!(rules[1].pattern.as_deref(), Some("^deploy"));
+        assert!(rules[1].flags.is_empty());
+    }
+
+    #[test]
+    fn json_command_overrides_parse() {
+        let config: TerseConfig = toml::from_str(
+            r#"
+[optimizers.json]
+max_array_items = 5
+
+[[optimizers.json.commands]]
+command = "gh api"
+max_array_items = 10
+
+[[optimizers.json.commands]]
+command = "cargo metadata"
+enabled = false
+"#,
+        )
+        .unwrap();
+        let json = &config.optimizers.json;
+        assert_eq!(json.max_array_items, 5);
+        assert_eq!(json.max_string_length, 200);
+        assert_eq!(json.commands[0].max_array_items, Some(10));
+        assert_eq!(json.commands[0].drop_empty, None);
+        assert_eq!(json.commands[1].enabled, Some(false));
     }
 }
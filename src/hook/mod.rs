@@ -9,7 +9,9 @@ use crate::analytics::events;
 use crate::hook::protocol::{
     CopilotHookRequest, CopilotHookResponse, HookRequest, HookResponse, ToolKind,
 };
+use crate::preprocessing::redact::Redactor;
 use crate::router::{self, HookDecision};
+use crate::utils::encoding::encode_base64;
 
 pub mod protocol;
 
@@ -52,20 +54,28 @@ fn handle_request(raw: &str) -> Result<HookResponse> {
     let request: HookRequest = serde_json::from_str(raw).context("invalid hook request JSON")?;
     let kind = request.tool_kind();
 
+    // Logs only ever see the command with its secrets masked.
+    let logged_command = request
+        .tool_input
+        .command
+        .as_deref()
+        .map(|command| Redactor::load().redact_command(command));
+
     log_hook_event(&format!(
         "request tool={} kind={} command=\"{}\"",
         request.tool_name,
         kind,
-        summarize_command(request.tool_input.command.as_deref()),
+        summarize_command(logged_command.as_deref()),
     ));
 
     match kind {
-        ToolKind::Bash => handle_bash(&request),
+        ToolKind::Bash => handle_bash(&request, logged_command.as_deref()),
         ToolKind::Unsupported => {
             events::log_passthrough(
                 &request.tool_name,
-                request.tool_input.command.as_deref(),
+                logged_command.as_deref(),
                 "unsupported tool",
+                None,
             );
             Ok(HookResponse::passthrough())
         }
@@ -77,24 +87,32 @@ fn handle_request(raw: &str) -> Result<HookResponse> {
 // ---------------------------------------------------------------------------
 
 /// Handle a Bash tool invocation — route through the optimizer pipeline.
-fn handle_bash(request: &HookRequest) -> Result<HookResponse> {
+///
+/// `logged_command` is the command with secrets masked, for the logs.
+fn handle_bash(request: &HookRequest, logged_command: Option<&str>) -> Result<HookResponse> {
     let Some(command) = request.tool_input.command.as_deref() else {
-        events::log_passthrough(&request.tool_name, None, "no command");
+        events::log_passthrough(&request.tool_name, None, "no command", None);
         return Ok(HookResponse::passthrough());
     };
 
-    let decision = router::decide_hook(command);
+    let routed = router::decide_hook_cached(command);
 
-    match &decision {
+    match &routed.decision {
         HookDecision::Rewrite => {
             let rewritten = build_rewrite_command(command)?;
-            log_hook_event(&format!("router decided rewrite; command: {rewritten}"));
-            events::log_rewrite(&request.tool_name, Some(command));
+            // The rewritten command embeds the original, secrets included.
+            log_hook_event("router decided rewrite");
+            events::log_rewrite(&request.tool_name, logged_command, routed.cache_hit);
             Ok(HookResponse::rewrite(&rewritten))
         }
         HookDecision::Passthrough(reason) => {
             log_hook_event(&format!("router decided passthrough ({reason})"));
-            events::log_passthrough(&request.tool_name, Some(command), &reason.to_string());
+            events::log_passthrough(
+                &request.tool_name,
+                logged_command,
+                &reason.to_string(),
+                routed.cache_hit,
+            );
             Ok(HookResponse::passthrough())
         }
     }
@@ -106,12 +124,21 @@ fn handle_bash(request: &HookRequest) -> Result<HookResponse> {
 /// rewrite works from both development builds and installed locations.
 fn build_rewrite_command(original_command: &str) -> Result<String> {
     let exe = std::env::current_exe().context("failed to determine terse executable path")?;
-    let exe_str = exe.display().to_string();
-
-    // Escape any double quotes in the original command
-    let escaped = original_command.replace('"', "\\\"");
+    Ok(encoded_run_command(
+        &exe.display().to_string(),
+        original_command,
+    ))
+}
 
-    Ok(format!("\"{exe_str}\" run \"{escaped}\""))
+/// Format `"<exe>" run --encoded <base64>` for an original command.
+///
+/// The command travels base64-encoded so the agent's shell cannot expand
+/// `$VAR`, `$(...)`, backticks, `!`, or backslashes before `terse run` sees
+/// it — the wrapped command receives exactly the argv it would have without
+/// terse.
+pub fn encoded_run_command(exe: &str, original_command: &str) -> String {
+    let payload = encode_base64(original_command.as_bytes());
+    format!("\"{exe}\" run --encoded {payload}")
 }
 
 fn summarize_command(command: Option<&str>) -> String {
@@ -195,21 +222,27 @@ fn handle_copilot_request(raw: &str) -> Result<CopilotHookResponse> {
         serde_json::from_str(raw).context("invalid copilot hook request JSON")?;
     let kind = request.tool_kind();
     let command = request.command();
+    let logged_command = command
+        .as_deref()
+        .map(|command| Redactor::load().redact_command(command));
 
     log_hook_event(&format!(
         "copilot request tool={} kind={} command=\"{}\"",
         request.tool_name,
         kind,
-        summarize_command(command.as_deref()),
+        summarize_command(logged_command.as_deref()),
     ));
 
     match kind {
-        ToolKind::Bash => handle_copilot_bash(&request, command.as_deref()),
+        ToolKind::Bash => {
+            handle_copilot_bash(&request, command.as_deref(), logged_command.as_deref())
+        }
         ToolKind::Unsupported => {
             events::log_passthrough(
                 &request.tool_name,
-                command.as_deref(),
+                logged_command.as_deref(),
                 "unsupported copilot tool",
+                None,
             );
             Ok(CopilotHookResponse::allow())
         }
@@ -219,30 +252,36 @@ fn handle_copilot_request(raw: &str) -> Result<CopilotHookResponse> {
 /// Handle a Copilot Bash tool invocation — route through the optimizer pipeline.
 ///
 /// Uses the same routing logic as Claude Code hooks. Optimizable commands are
-/// rewritten to `terse run "<cmd>"` via `hookSpecificOutput.updatedInput`.
+/// rewritten to `terse run --encoded <base64>` via `hookSpecificOutput.updatedInput`.
 /// The top-level `permissionDecision: "allow"` ensures safe fallback if
 /// the Copilot runtime does not process the rewrite.
 fn handle_copilot_bash(
     request: &CopilotHookRequest,
     command: Option<&str>,
+    logged_command: Option<&str>,
 ) -> Result<CopilotHookResponse> {
     let Some(command) = command else {
-        events::log_passthrough(&request.tool_name, None, "no command in copilot bash");
+        events::log_passthrough(&request.tool_name, None, "no command in copilot bash", None);
         return Ok(CopilotHookResponse::allow());
     };
 
-    let decision = router::decide_hook(command);
+    let routed = router::decide_hook_cached(command);
 
-    match &decision {
+    match &routed.decision {
         HookDecision::Rewrite => {
             let rewritten = build_rewrite_command(command)?;
-            log_hook_event(&format!("copilot: rewriting command: {rewritten}"));
-            events::log_rewrite(&request.tool_name, Some(command));
+            log_hook_event("copilot: rewriting command");
+            events::log_rewrite(&request.tool_name, logged_command, routed.cache_hit);
             Ok(CopilotHookResponse::rewrite(&rewritten))
         }
         HookDecision::Passthrough(reason) => {
             log_hook_event(&format!("copilot: passthrough ({reason})"));
-            events::log_passthrough(&request.tool_name, Some(command), &reason.to_string());
+            events::log_passthrough(
+                &request.tool_name,
+                logged_command,
+                &reason.to_string(),
+                routed.cache_hit,
+            );
             Ok(CopilotHookResponse::allow())
         }
     }
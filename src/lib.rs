@@ -9,5 +9,6 @@ pub mod preprocessing;
 pub mod router;
 pub mod run;
 pub mod safety;
+pub mod stash;
 pub mod utils;
 pub mod web;
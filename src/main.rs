@@ -12,6 +12,7 @@ mod preprocessing;
 mod router;
 mod run;
 mod safety;
+mod stash;
 mod utils;
 mod web;
 
@@ -30,8 +31,15 @@ enum Commands {
     CopilotHook,
     /// Execute a command with optimization and print the result to stdout
     Run {
+        /// Base64-encoded command (used by hook rewrites for shell-safe transport)
+        #[arg(long, value_name = "BASE64", conflicts_with = "args")]
+        encoded: Option<String>,
         /// The command to execute and optimize
-        #[arg(trailing_var_arg = true, required = true, allow_hyphen_values = true)]
+        #[arg(
+            trailing_var_arg = true,
+            required_unless_present = "encoded",
+            allow_hyphen_values = true
+        )]
         args: Vec<String>,
     },
     /// Show token savings statistics
@@ -69,6 +77,20 @@ enum Commands {
         #[arg(trailing_var_arg = true, required = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
+    /// Print the full raw output stashed behind a truncation footer
+    Raw {
+        /// Stash id from the `[output truncated ...]` footer
+        id: String,
+        /// Only print this 1-based line range (e.g. `120-180`, `500-`)
+        #[arg(long)]
+        lines: Option<String>,
+        /// Only print lines matching this regex, prefixed with line numbers
+        #[arg(long)]
+        grep: Option<String>,
+        /// Read the stashed stderr instead of stdout
+        #[arg(long)]
+        stderr: bool,
+    },
     /// Manage terse configuration
     Config {
         #[command(subcommand)]
@@ -124,9 +146,13 @@ fn main() -> Result<()> {
     match app.command {
         Commands::Hook => hook::run(),
         Commands::CopilotHook => hook::run_copilot(),
-        Commands::Run { args } => {
-            let command = args.join(" ");
-            run::execute(&command)
+        Commands::Run { encoded, args } => {
+            let command = match encoded {
+                Some(payload) => run::decode_command(&payload)?,
+                None => args.join(" "),
+            };
+            let code = run::execute(&command)?;
+            std::process::exit(code)
         }
         Commands::Stats { format, days } => {
             let fmt = cli::OutputFormat::from_str_opt(Some(&format));
@@ -145,6 +171,12 @@ fn main() -> Result<()> {
             let command = args.join(" ");
             cli::run_test(&command)
         }
+        Commands::Raw {
+            id,
+            lines,
+            grep,
+            stderr,
+        } => cli::run_raw(&id, lines.as_deref(), grep.as_deref(), stderr),
         Commands::Config { action } => match action {
             ConfigAction::Show => cli::run_config_show(),
             ConfigAction::Init { force } => cli::run_config_init(force),
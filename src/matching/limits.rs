@@ -0,0 +1,208 @@
+//! Output bounds and formats a command already asks for.
+//!
+//! `head -n 200 file`, `git log -n 50`, `docker logs --tail 500` and
+//! `find . | head` bound their own output; `git log --oneline` and
+//! `docker ps --format ...` pick their own format. Compacting such output
+//! further only causes confusion and re-runs, so optimizers and the
+//! preprocessing truncation stage consult [`ExplicitLimits`] instead of
+//! each recognizing these flags on its own.
+
+use super::shell;
+
+/// What the user explicitly asked of a command's output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExplicitLimits {
+    /// Lines (or entries, for `git log`) the command is limited to, by its
+    /// own flags or by a `head`, `tail` or `sed -n` stage it is piped into.
+    pub lines: Option<usize>,
+    /// The command selects its own output format (`--format`, `--pretty`,
+    /// `--oneline`, `-o wide`).
+    pub format: bool,
+}
+
+/// Lines printed by `head` and `tail` without `-n`.
+const HEAD_TAIL_DEFAULT_LINES: usize = 10;
+
+/// Parse the explicit limits of a full command line.
+///
+/// The limit is the smallest among the core command's own flags and the
+/// pipeline stages its output flows through. Commands that do not parse
+/// have none.
+pub fn explicit_limits(command: &str) -> ExplicitLimits {
+    let Ok(script) = shell::parse(command) else {
+        return ExplicitLimits::default();
+    };
+    let Some((span, filters)) = super::core_command(command, &script) else {
+        return ExplicitLimits::default();
+    };
+    let words = shell::split_words(&command[span]);
+
+    let lines = std::iter::once(command_limit(&words))
+        .chain(filters.iter().map(|filter| filter_limit(filter)))
+        .flatten()
+        .min();
+    ExplicitLimits {
+        lines,
+        format: has_format_request(&words),
+    }
+}
+
+/// Limit set by the core command's own flags.
+fn command_limit(words: &[String]) -> Option<usize> {
+    let (program, args) = words.split_first()?;
+    match super::program_name(program).as_str() {
+        "head" | "tail" | "sed" => filter_limit(words),
+        "git" => {
+            let sub = args.iter().find(|a| !a.starts_with('-'))?;
+            matches!(sub.as_str(), "log" | "reflog" | "shortlog" | "whatchanged")
+                .then(|| count_flag(args, &["-n", "--max-count"]).or_else(|| dash_number(args)))
+                .flatten()
+        }
+        "docker" | "podman" | "kubectl" | "oc" => {
+            // `docker logs`, `docker compose logs`, `kubectl logs`.
+            args.iter()
+                .take(3)
+                .any(|a| a == "logs")
+                .then(|| count_flag(args, &["--tail", "-n"]))
+                .flatten()
+        }
+        "journalctl" => count_flag(args, &["-n", "--lines"]),
+        _ => None,
+    }
+}
+
+/// Limit set by a `head`, `tail` or `sed -n 'A,Bp'` pipeline stage.
+fn filter_limit(words: &[String]) -> Option<usize> {
+    let (program, args) = words.split_first()?;
+    match super::program_name(program).as_str() {
+        "head" | "tail" => {
+            // Byte counts and `tail -n +N` (from line N) bound no lines.
+            if args.iter().any(|a| a == "-c" || a.starts_with("--bytes")) {
+                return None;
+            }
+            if args.iter().any(|a| a.starts_with('+')) {
+                return None;
+            }
+            count_flag(args, &["-n", "--lines"])
+                .or_else(|| dash_number(args))
+                .or(Some(HEAD_TAIL_DEFAULT_LINES))
+        }
+        "sed" if args.iter().any(|a| a == "-n") => args.iter().find_map(|a| sed_range(a)),
+        _ => None,
+    }
+}
+
+/// Value of a count option: `-n 20`, `-n20`, `--tail 500`, `--tail=500`.
+fn count_flag(args: &[String], flags: &[&str]) -> Option<usize> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        flags.iter().find_map(|&flag| {
+            if arg == flag {
+                args.get(i + 1)?.parse().ok()
+            } else if flag.starts_with("--") {
+                arg.strip_prefix(flag)?.strip_prefix('=')?.parse().ok()
+            } else {
+                arg.strip_prefix(flag)?.parse().ok()
+            }
+        })
+    })
+}
+
+/// A bare numeric option: `git log -10`, `head -5`.
+fn dash_number(args: &[String]) -> Option<usize> {
+    args.iter()
+        .find_map(|arg| arg.strip_prefix('-')?.parse().ok())
+}
+
+/// Lines printed by a `sed -n` script of the form `Np` or `A,Bp`.
+fn sed_range(script: &str) -> Option<usize> {
+    let range = script.strip_suffix('p')?;
+    let (start, end) = range.split_once(',').unwrap_or((range, range));
+    let start: usize = start.parse().ok()?;
+    let end: usize = end.parse().ok()?;
+    Some(end.saturating_sub(start) + 1)
+}
+
+/// Whether the command selects its own output format.
+fn has_format_request(words: &[String]) -> bool {
+    let Some((program, args)) = words.split_first() else {
+        return false;
+    };
+    let short_output = matches!(
+        super::program_name(program).as_str(),
+        "kubectl" | "oc" | "helm"
+    );
+    args.iter().any(|arg| {
+        ["--format", "--pretty", "--output"]
+            .iter()
+            .any(|flag| arg == flag || arg.starts_with(&format!("{flag}=")))
+            || arg == "--oneline"
+            || (short_output && arg.starts_with("-o"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(command: &str) -> Option<usize> {
+        explicit_limits(command).lines
+    }
+
+    #[test]
+    fn head_tail_and_sed_ranges() {
+        assert_eq!(lines("head -n 200 file"), Some(200));
+        assert_eq!(lines("head -n200 file"), Some(200));
+        assert_eq!(lines("tail -100 app.log"), Some(100));
+        assert_eq!(lines("tail --lines=30 app.log"), Some(30));
+        assert_eq!(lines("head README.md"), Some(10));
+        assert_eq!(lines("sed -n '10,59p' src/main.rs"), Some(50));
+        assert_eq!(lines("sed -n 5p src/main.rs"), Some(1));
+        assert_eq!(lines("tail -n +5 app.log"), None);
+        assert_eq!(lines("head -c 100 file"), None);
+        assert_eq!(lines("sed 's/a/b/' file"), None);
+        assert_eq!(lines("cat file"), None);
+    }
+
+    #[test]
+    fn git_log_limits() {
+        assert_eq!(lines("git log -10"), Some(10));
+        assert_eq!(lines("git log -n 20"), Some(20));
+        assert_eq!(lines("git log -5 --oneline"), Some(5));
+        assert_eq!(lines("git log --max-count=50"), Some(50));
+        assert_eq!(lines("git log --oneline"), None);
+        assert_eq!(lines("git log -p"), None);
+        assert_eq!(lines("git diff -U3"), None);
+    }
+
+    #[test]
+    fn log_tails() {
+        assert_eq!(lines("docker logs --tail 500 api"), Some(500));
+        assert_eq!(lines("docker compose logs --tail=50"), Some(50));
+        assert_eq!(lines("kubectl logs api --tail=200"), Some(200));
+        assert_eq!(lines("journalctl -u nginx -n 100"), Some(100));
+        assert_eq!(lines("docker ps -n 5"), None);
+    }
+
+    #[test]
+    fn pipeline_stages_bound_the_core_command() {
+        assert_eq!(lines("find . -name '*.rs' | head"), Some(10));
+        assert_eq!(lines("cd /repo && git log | head -n 30"), Some(30));
+        assert_eq!(lines("git log -n 50 | head -20"), Some(20));
+        assert_eq!(lines("bash -c 'ls -la | tail -5'"), Some(5));
+        assert_eq!(lines("find . | grep foo"), None);
+        // A limit in an earlier list item does not bound the core command.
+        assert_eq!(lines("head -n 5 a.txt; cat b.txt"), None);
+    }
+
+    #[test]
+    fn format_requests() {
+        assert!(explicit_limits("git log --oneline").format);
+        assert!(explicit_limits("git show --pretty=format:%H").format);
+        assert!(explicit_limits("docker ps --format '{{.Names}}'").format);
+        assert!(explicit_limits("kubectl get pods -o wide").format);
+        assert!(explicit_limits("kubectl get pods -ojson").format);
+        assert!(!explicit_limits("git log -n 5").format);
+        assert!(!explicit_limits("git commit -m 'use --oneline'").format);
+        assert!(!explicit_limits("gcc -o main main.c").format);
+    }
+}
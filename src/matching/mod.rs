@@ -7,6 +7,10 @@
 //!
 //! # Design
 //!
+//! Commands are parsed by the [`shell`] lexer/parser into a small AST, so
+//! quoting, escapes, `$(...)`, and `|` versus `||` are handled structurally
+//! rather than by scanning for substrings.
+//!
 //! The matching engine is used in two places:
 //! 1. **Hook** (`terse hook`): determines if any optimizer can handle the command
 //!    and whether the command is already a terse invocation (loop guard).
@@ -21,47 +25,43 @@
 //!
 //! // Claude sends: cd /repo && LANG=C git status
 //! // Matching engine extracts: git status
-//! // But terse rewrites: terse.exe run "cd /repo && LANG=C git status"
-//! // So the full command executes in the correct directory with the env var.
+//! // But terse executes the full command line through `terse run`,
+//! // so it runs in the correct directory with the env var.
 //! assert_eq!(extract_core_command("cd /repo && LANG=C git status"), "git status");
 //! ```
 
+pub mod limits;
+pub mod shell;
+
+use anyhow::Result;
+
+use shell::{Command, List, Separator, SimpleCommand, Span};
+
 /// Extract the core executable command from a shell invocation.
 ///
-/// Strips common wrappers to find the actual command for optimizer matching:
-/// - Subshell: `(cmd)` → `cmd`
-/// - Shell wrapper: `bash -c "cmd"` / `sh -c "cmd"` → `cmd`
-/// - Chain prefix: `cd /repo && cmd` → `cmd` (last `&&`/`;` segment)
-/// - Pipeline: `cmd | filter` → `cmd` (first pipe segment)
-/// - Env vars: `LANG=C cmd` → `cmd`
+/// Finds the actual command for optimizer matching:
+/// - Subshell / group: `(cmd)` / `{ cmd; }` → `cmd`
+/// - Shell wrapper: `bash -c "cmd"` / `cmd /c cmd` / `pwsh -Command cmd` → `cmd`
+/// - List: `cd /repo && cmd` → `cmd` (last command not run only on failure
+///   of the previous one, so `cmd || echo failed` → `cmd`)
+/// - Pipeline: `cmd | filter` → `cmd` (first pipeline stage)
+/// - Assignments: `LANG=C cmd` → `cmd`
 ///
 /// Returns a trimmed `&str` slice into the original string, suitable for
-/// prefix-matching against known command names.
+/// prefix-matching against known command names. Commands that do not parse
+/// yield an empty string — the shell would reject them too.
 pub fn extract_core_command(raw: &str) -> &str {
-    let mut s = raw.trim();
-    if s.is_empty() {
-        return s;
-    }
-
-    // 1. Unwrap subshell wrappers: (cmd) → cmd
-    s = unwrap_subshell(s);
-
-    // 2. Unwrap shell wrappers: bash -c "cmd" / sh -c "cmd" → cmd
-    s = unwrap_shell_wrapper(s);
-
-    // After unwrapping, the inner command might itself be subshelled
-    s = unwrap_subshell(s);
-
-    // 3. Take the last segment after && or ; (handles cd /path && cmd)
-    s = last_chain_segment(s);
-
-    // 4. Take first command before pipe: cmd | filter → cmd
-    s = first_pipe_segment(s);
-
-    // 5. Strip environment variable assignments: LANG=C cmd → cmd
-    s = strip_env_assignments(s);
+    core_command_span(raw).map_or("", |span| &raw[span])
+}
 
-    s
+/// Byte range of [`extract_core_command`] within `raw`, for edits that must
+/// land on the core command rather than on the first matching text.
+pub fn core_command_span(raw: &str) -> Option<Span> {
+    let script = shell::parse(raw).ok()?;
+    let span = core_span(raw, &script)?;
+    let text = &raw[span.clone()];
+    let start = span.start + (text.len() - text.trim_start().len());
+    Some(start..start + text.trim().len())
 }
 
 /// Check whether a command contains a heredoc (`<<`), which should always
@@ -69,33 +69,10 @@ pub fn extract_core_command(raw: &str) -> &str {
 ///
 /// Heredocs embed multi-line content inline and are structurally complex —
 /// rewriting them risks breaking the document boundary. RTK-AI skips these
-/// for the same reason.
+/// for the same reason. Here-strings (`<<<`) are treated the same way.
+///
+/// Commands that do not parse return `false`; the classifier rejects them.
 pub fn contains_heredoc(command: &str) -> bool {
-    let bytes = command.as_bytes();
-    let len = bytes.len();
-
-    let mut i = 0;
-    while i + 1 < len {
-        // Skip characters inside single or double quotes
-        if bytes[i] == b'\'' || bytes[i] == b'"' {
-            let quote = bytes[i];
-            i += 1;
-            while i < len && bytes[i] != quote {
-                i += 1;
-            }
-            i += 1; // skip closing quote
-            continue;
-        }
-
-        if bytes[i] == b'<' && bytes[i + 1] == b'<' {
-            // Distinguish heredoc (<<) from process substitution (<<<)
-            // <<< is a "here string", not a heredoc — still safe to skip
-            return true;
-        }
-
-        i += 1;
-    }
-
     // PowerShell here-strings: @"..."@ or @'...'@
     if command.contains("@\"\n") || command.contains("@'\n") {
         return true;
@@ -107,212 +84,222 @@ pub fn contains_heredoc(command: &str) -> bool {
         return true;
     }
 
-    false
+    let Ok(script) = shell::parse(command) else {
+        return false;
+    };
+    let mut found = false;
+    let _ = for_each_script(command, &script, &mut |list| {
+        found |= list.redirects().iter().any(|r| r.is_heredoc());
+    });
+    found
+}
+
+/// Visit a parsed command and every script nested in a shell wrapper
+/// (`bash -c '...'`, `cmd /c ...`, `pwsh -Command ...`).
+///
+/// `src` is the text `script` was parsed from. Fails if a wrapped script
+/// does not parse.
+pub fn for_each_script(src: &str, script: &List, visit: &mut dyn FnMut(&List)) -> Result<()> {
+    walk_scripts(src, script, false, visit)
+}
+
+/// Like [`for_each_script`], but also visits the bodies of command and
+/// process substitutions (`$(...)`, backticks, `<(...)`) — every script the
+/// shell would execute. Their output feeds the outer command, so this is for
+/// safety checks rather than redirect detection.
+pub fn for_each_executed_script(
+    src: &str,
+    script: &List,
+    visit: &mut dyn FnMut(&List),
+) -> Result<()> {
+    walk_scripts(src, script, true, visit)
 }
 
 /// Check whether a command is already a terse invocation (infinite loop guard).
 ///
 /// Detects commands like `terse run "..."`, `terse.exe run "..."`, or
-/// `"/path/to/terse.exe" run "..."`. Uses structural matching rather than a
-/// naive substring search to avoid false positives with directory names that
-/// happen to contain "terse" and "run".
+/// `"/path/to/terse.exe" run --encoded ...` in any segment of a chain or
+/// shell wrapper. Only the parsed program and its first argument are
+/// compared, so directory names and quoted text that happen to contain
+/// "terse" and "run" (`git commit -m "make terse run faster"`) do not match.
+///
+/// Commands that do not parse return `false`; the classifier rejects them.
 pub fn is_terse_invocation(command: &str) -> bool {
-    let lower = command.to_ascii_lowercase();
-
-    // Find the last occurrence of "terse" — in a path like
-    // "C:\source\terse\target\terse.exe", the last one is the binary name.
-    let Some(terse_pos) = lower.rfind("terse") else {
+    let Ok(script) = shell::parse(command) else {
         return false;
     };
-
-    let after_terse = &lower[terse_pos + 5..]; // skip "terse"
-
-    // Skip optional ".exe" suffix and optional closing quote
-    let after_suffix = after_terse.strip_prefix(".exe").unwrap_or(after_terse);
-    let after_quote = after_suffix.strip_prefix('"').unwrap_or(after_suffix);
-
-    // Must be followed by whitespace then "run"
-    let after_ws = after_quote.trim_start();
-    after_ws.starts_with("run")
+    let mut found = false;
+    let _ = for_each_script(command, &script, &mut |list| {
+        found |= list.simple_commands().iter().any(|cmd| {
+            matches!(
+                cmd.command_words(),
+                [program, subcommand, ..]
+                    if program_name(&program.text) == "terse" && subcommand.text == "run"
+            )
+        });
+    });
+    found
 }
 
 // ---------------------------------------------------------------------------
 // Internal helpers
 // ---------------------------------------------------------------------------
 
-/// Unwrap a single layer of subshell parentheses: `(cmd)` → `cmd`.
-fn unwrap_subshell(s: &str) -> &str {
-    let trimmed = s.trim();
-    if trimmed.starts_with('(') && trimmed.ends_with(')') {
-        trimmed[1..trimmed.len() - 1].trim()
-    } else {
-        trimmed
-    }
-}
-
-/// Unwrap shell wrappers across platforms.
-///
-/// Handles Unix shells (`bash -c`, `sh -c`) and Windows shells
-/// (`cmd /c`, `pwsh -Command`, `powershell -Command`).
-fn unwrap_shell_wrapper(s: &str) -> &str {
-    let trimmed = s.trim();
-
-    // Unix shell wrappers
-    for prefix in &["bash -c ", "sh -c "] {
-        if let Some(rest) = ascii_prefix_strip(trimmed, prefix) {
-            return strip_outer_quotes(rest.trim());
+fn walk_scripts(
+    src: &str,
+    script: &List,
+    substitutions: bool,
+    visit: &mut dyn FnMut(&List),
+) -> Result<()> {
+    visit(script);
+    for command in script.simple_commands() {
+        if let Some(span) = wrapped_script(src, command) {
+            let inner = shell::parse_range(src, span)?;
+            walk_scripts(src, &inner, substitutions, visit)?;
         }
     }
-
-    // Windows shell wrappers (cmd, pwsh, powershell).
-    // ascii_prefix_strip is case-insensitive so we only need lowercase.
-    for prefix in &[
-        "cmd /c ",
-        "cmd.exe /c ",
-        "pwsh -c ",
-        "pwsh -command ",
-        "pwsh.exe -c ",
-        "pwsh.exe -command ",
-        "pwsh -nologo -command ",
-        "pwsh -noprofile -command ",
-        "powershell -c ",
-        "powershell -command ",
-        "powershell.exe -c ",
-        "powershell.exe -command ",
-    ] {
-        if let Some(rest) = ascii_prefix_strip(trimmed, prefix) {
-            return strip_outer_quotes(rest.trim());
+    if substitutions {
+        let commands = script.simple_commands();
+        let words = commands
+            .iter()
+            .flat_map(|cmd| {
+                cmd.words
+                    .iter()
+                    .chain(cmd.assignments.iter().map(|a| &a.value))
+            })
+            .chain(script.redirects().into_iter().map(|r| &r.target));
+        for word in words {
+            for span in &word.substitutions {
+                let inner = shell::parse_range(src, span.clone())?;
+                walk_scripts(src, &inner, substitutions, visit)?;
+            }
         }
     }
-
-    trimmed
-}
-
-/// Case-insensitive prefix strip that returns the remainder as a slice.
-fn ascii_prefix_strip<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
-    if s.len() < prefix.len() {
-        return None;
-    }
-    if s[..prefix.len()].eq_ignore_ascii_case(prefix) {
-        Some(&s[prefix.len()..])
-    } else {
-        None
-    }
+    Ok(())
 }
 
-/// Strip a single layer of matching outer quotes from a string.
-fn strip_outer_quotes(s: &str) -> &str {
-    if s.len() >= 2
-        && ((s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')))
-    {
-        return &s[1..s.len() - 1];
-    }
-    s
+/// Source span of the core command of a parsed list.
+fn core_span(src: &str, list: &List) -> Option<Span> {
+    core_command(src, list).map(|(span, _)| span)
 }
 
-/// Take the last segment after `&&` or `;`.
-///
-/// Handles the common Claude pattern of `cd /path && actual_command`.
-fn last_chain_segment(s: &str) -> &str {
-    if let Some(pos) = s.rfind("&&") {
-        return s[pos + 2..].trim();
-    }
-    if let Some(pos) = s.rfind(';') {
-        return s[pos + 1..].trim();
-    }
-    s
-}
-
-/// Take the first command before a pipe (`|`), skipping logical-OR (`||`).
-fn first_pipe_segment(s: &str) -> &str {
-    let bytes = s.as_bytes();
-    let len = bytes.len();
+/// Source span of the core command of a parsed list, and the words of the
+/// pipeline stages its output flows through (`find . | head -n 5` →
+/// `[["head", "-n", "5"]]`), innermost first.
+fn core_command(src: &str, list: &List) -> Option<(Span, Vec<Vec<String>>)> {
+    // The last pipeline that runs on the success path: items after `||`
+    // are fallbacks, and closing keywords (`fi`, `done`) run nothing.
+    let item = list
+        .items
+        .iter()
+        .enumerate()
+        .filter(|&(i, item)| {
+            (i == 0 || list.items[i - 1].separator != Some(Separator::Or))
+                && !matches!(
+                    item.pipeline.commands.first(),
+                    Some(Command::Simple(cmd)) if cmd.command_words().is_empty()
+                )
+        })
+        .map(|(_, item)| item)
+        .next_back()?;
+
+    let (first, rest) = item.pipeline.commands.split_first()?;
+    let filters: Vec<Vec<String>> = rest
+        .iter()
+        .filter_map(|command| match command {
+            Command::Simple(cmd) => {
+                Some(cmd.command_words().iter().map(|w| w.text.clone()).collect())
+            }
+            _ => None,
+        })
+        .collect();
+    let with_outer = |(span, mut inner): (Span, Vec<Vec<String>>)| {
+        inner.extend(filters.iter().cloned());
+        (span, inner)
+    };
 
-    for i in 0..len {
-        if bytes[i] != b'|' {
-            continue;
-        }
-        // Skip logical-OR: ||
-        if i + 1 < len && bytes[i + 1] == b'|' {
-            continue;
+    match first {
+        Command::Subshell { body, .. } | Command::Group { body, .. } => {
+            core_command(src, body).map(with_outer)
         }
-        // Skip second char of ||
-        if i > 0 && bytes[i - 1] == b'|' {
-            continue;
+        Command::Simple(cmd) => {
+            if let Some(span) = wrapped_script(src, cmd) {
+                return match shell::parse_range(src, span.clone()) {
+                    Ok(inner) => core_command(src, &inner).map(with_outer),
+                    Err(_) => Some((span, filters)),
+                };
+            }
+            let first = cmd.command_words().first()?;
+            Some((first.span.start..cmd.span.end, filters))
         }
-        return s[..i].trim();
     }
-    s
 }
 
-/// Strip leading environment variable assignments (`WORD=value` prefixes).
+/// Source span of the script passed to a shell wrapper, if `cmd` is one.
 ///
-/// Iteratively removes `KEY=VALUE ` prefixes where KEY is a valid shell
-/// identifier (`[A-Za-z_][A-Za-z0-9_]*`) and VALUE is an unquoted or
-/// quoted token. Stops when the next token doesn't look like an assignment.
-fn strip_env_assignments(s: &str) -> &str {
-    let mut rest = s;
-
-    loop {
-        let trimmed = rest.trim_start();
-        if trimmed.is_empty() {
-            return trimmed;
+/// - `bash|sh|zsh|dash|ksh [opts] -c SCRIPT` (also combined flags like `-lc`)
+/// - `cmd [/d] /c|/k REST...`
+/// - `pwsh|powershell [opts] -c|-Command REST...`
+fn wrapped_script(src: &str, cmd: &SimpleCommand) -> Option<Span> {
+    let (name, args) = cmd.command_words().split_first()?;
+    let program = program_name(&name.text);
+
+    let (start, rest_of_line) = match program.as_str() {
+        "bash" | "sh" | "zsh" | "dash" | "ksh" => {
+            let flag = args.iter().position(|w| {
+                w.text.len() > 1
+                    && w.text.starts_with('-')
+                    && w.text[1..].bytes().all(|b| b.is_ascii_alphabetic())
+                    && w.text.contains('c')
+            })?;
+            (flag + 1, false)
         }
-
-        // An env assignment must start with a letter or underscore
-        let first = trimmed.as_bytes()[0];
-        if !first.is_ascii_alphabetic() && first != b'_' {
-            return trimmed;
+        "cmd" => {
+            let flag = args.iter().position(|w| {
+                w.text.eq_ignore_ascii_case("/c") || w.text.eq_ignore_ascii_case("/k")
+            })?;
+            (flag + 1, true)
         }
-
-        // Find the = sign
-        let Some(eq_pos) = trimmed.find('=') else {
-            return trimmed;
-        };
-
-        // Everything before = must be a valid shell identifier
-        let key = &trimmed[..eq_pos];
-        if !key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
-            return trimmed;
-        }
-
-        // Skip past the value (possibly quoted)
-        let after_eq = &trimmed[eq_pos + 1..];
-        let value_end = skip_value(after_eq);
-
-        if value_end >= after_eq.len() {
-            // Value consumes the rest of the string — no command follows
-            return trimmed;
+        "pwsh" | "powershell" => {
+            let flag = args.iter().position(|w| {
+                w.text.eq_ignore_ascii_case("-c") || w.text.eq_ignore_ascii_case("-command")
+            })?;
+            (flag + 1, true)
         }
+        _ => return None,
+    };
 
-        rest = &after_eq[value_end..];
+    match &args[start..] {
+        [] => None,
+        [only] => Some(unquoted_span(src, &only.span)),
+        // POSIX shells take only the first word as the script; the rest
+        // become positional parameters.
+        [first, ..] if !rest_of_line => Some(unquoted_span(src, &first.span)),
+        [first, .., last] => Some(first.span.start..last.span.end),
     }
 }
 
-/// Find the byte length of an env-var value (possibly quoted).
-///
-/// Returns the offset just past the value, so `&s[skip_value(s)..]` starts
-/// at the whitespace (or end) after the value.
-fn skip_value(s: &str) -> usize {
-    if s.is_empty() {
-        return 0;
+/// Lowercased program name without directory or `.exe` suffix.
+pub fn program_name(word: &str) -> String {
+    let base = word.rsplit(['/', '\\']).next().unwrap_or(word);
+    let lower = base.to_ascii_lowercase();
+    match lower.strip_suffix(".exe") {
+        Some(stem) => stem.to_string(),
+        None => lower,
     }
+}
 
-    let bytes = s.as_bytes();
-
-    // Quoted value: find matching close quote
-    if bytes[0] == b'"' || bytes[0] == b'\'' {
-        let quote = bytes[0];
-        for (i, &b) in bytes[1..].iter().enumerate() {
-            if b == quote {
-                return i + 2; // past the closing quote
-            }
-        }
-        return s.len(); // unclosed quote — consume everything
+/// Strip one layer of matching outer quotes from a word's span.
+fn unquoted_span(src: &str, span: &Span) -> Span {
+    let raw = &src[span.clone()];
+    if raw.len() >= 2
+        && ((raw.starts_with('"') && raw.ends_with('"'))
+            || (raw.starts_with('\'') && raw.ends_with('\'')))
+    {
+        span.start + 1..span.end - 1
+    } else {
+        span.clone()
     }
-
-    // Unquoted: value extends to next whitespace
-    s.find(char::is_whitespace).unwrap_or(s.len())
 }
 
 #[cfg(test)]
@@ -547,6 +534,14 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn detects_encoded_terse_run_with_terse_in_payload() {
+        // Base64 payloads can contain "terse" after the binary name.
+        assert!(is_terse_invocation(
+            "\"/usr/local/bin/terse\" run --encoded AAAterseAAA="
+        ));
+    }
+
     #[test]
     fn rejects_plain_git_status() {
         assert!(!is_terse_invocation("git status"));
@@ -565,6 +560,20 @@ mod tests {
         assert!(!is_terse_invocation("cd /terse/run/project && git status"));
     }
 
+    #[test]
+    fn detects_terse_run_in_chain_and_wrapper() {
+        assert!(is_terse_invocation("cd /repo && terse run \"git status\""));
+        assert!(is_terse_invocation("bash -c 'terse run \"git status\"'"));
+    }
+
+    #[test]
+    fn rejects_terse_run_in_arguments() {
+        assert!(!is_terse_invocation(
+            "git commit -m \"make terse run faster\""
+        ));
+        assert!(!is_terse_invocation("echo terse run"));
+    }
+
     #[test]
     fn rejects_terse_without_run() {
         assert!(!is_terse_invocation("terse stats"));
@@ -613,37 +622,115 @@ mod tests {
     }
 
     // -----------------------------------------------------------------------
-    // Internal helper tests
+    // Structural matching
     // -----------------------------------------------------------------------
 
     #[test]
-    fn strip_env_does_not_eat_flags_with_equals() {
-        // --format=oneline starts with -, not a letter → immediately returns
+    fn flags_with_equals_are_not_assignments() {
+        // --format=oneline starts with -, not a letter
+        assert_eq!(extract_core_command("--format=oneline"), "--format=oneline");
+    }
+
+    #[test]
+    fn env_assignment_with_path_value() {
+        assert_eq!(extract_core_command("HOME=/tmp git status"), "git status");
+    }
+
+    #[test]
+    fn logical_or_is_not_a_pipe() {
         assert_eq!(
-            strip_env_assignments("--format=oneline"),
-            "--format=oneline"
+            extract_core_command("git status || echo fallback"),
+            "git status"
         );
     }
 
     #[test]
-    fn strip_env_handles_path_value() {
+    fn single_pipe_takes_first_stage() {
+        assert_eq!(extract_core_command("git log | head"), "git log");
+    }
+
+    #[test]
+    fn chain_operators_inside_quotes_do_not_split() {
+        assert_eq!(
+            extract_core_command("git commit -m 'a && b; c'"),
+            "git commit -m 'a && b; c'"
+        );
+        assert_eq!(
+            extract_core_command("cd /repo && echo \"x | y\""),
+            "echo \"x | y\""
+        );
+    }
+
+    #[test]
+    fn command_substitution_does_not_split() {
+        assert_eq!(
+            extract_core_command("cd $(git rev-parse --show-toplevel) && cargo test"),
+            "cargo test"
+        );
+        assert_eq!(
+            extract_core_command("echo \"$(cd /x; ls)\""),
+            "echo \"$(cd /x; ls)\""
+        );
+    }
+
+    #[test]
+    fn background_and_newline_separators() {
+        assert_eq!(extract_core_command("cd /repo\ngit status"), "git status");
+        assert_eq!(extract_core_command("server & cargo test"), "cargo test");
+    }
+
+    #[test]
+    fn brace_group_and_compound_keywords() {
+        assert_eq!(extract_core_command("{ cd /repo; git log; }"), "git log");
         assert_eq!(
-            strip_env_assignments("HOME=/tmp git status").trim(),
+            extract_core_command("if true; then git status; fi"),
             "git status"
         );
     }
 
     #[test]
-    fn first_pipe_ignores_double_pipe() {
+    fn wrapper_with_combined_flags_and_path() {
         assert_eq!(
-            first_pipe_segment("git status || echo fallback"),
-            "git status || echo fallback"
+            extract_core_command("/bin/bash -lc 'cd /repo && git diff'"),
+            "git diff"
         );
     }
 
     #[test]
-    fn first_pipe_splits_single_pipe() {
-        assert_eq!(first_pipe_segment("git log | head"), "git log");
+    fn unparseable_command_has_no_core() {
+        assert_eq!(extract_core_command("echo 'unterminated"), "");
+    }
+
+    #[test]
+    fn heredoc_inside_wrapper_is_detected() {
+        assert!(contains_heredoc("bash -c 'cat <<EOF\nhi\nEOF'"));
+    }
+
+    #[test]
+    fn executed_scripts_include_substitutions() {
+        let src = "X=$(whoami) echo \"$(date)\" `uname` > $(mktemp); bash -c 'cat <(ls)'";
+        let script = shell::parse(src).unwrap();
+        let mut names = Vec::new();
+        for_each_executed_script(src, &script, &mut |list| {
+            for cmd in list.simple_commands() {
+                names.push(cmd.command_words()[0].text.clone());
+            }
+        })
+        .unwrap();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "bash", "cat", "date", "echo", "ls", "mktemp", "uname", "whoami"
+            ]
+        );
+
+        let mut wrapped_only = 0;
+        for_each_script(src, &script, &mut |list| {
+            wrapped_only += list.simple_commands().len();
+        })
+        .unwrap();
+        assert_eq!(wrapped_only, 3);
     }
 
     // -----------------------------------------------------------------------
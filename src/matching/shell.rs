@@ -0,0 +1,1195 @@
+//! POSIX shell lexer and parser.
+//!
+//! Produces a small AST — lists, pipelines, simple commands, subshells,
+//! brace groups, redirections, and assignments — so routing and the
+//! classifier can reason about command structure instead of guessing from
+//! substrings. It is a matching aid, not an interpreter:
+//!
+//! - Expansions (`$VAR`, `$(...)`, backticks, globs) are kept verbatim in
+//!   [`Word::text`]. Command substitution bodies are recorded as source
+//!   spans so callers can parse them recursively.
+//! - Compound commands (`if`, `while`, `for`, `case`) are not modeled. Their
+//!   keywords lex as ordinary words, and [`SimpleCommand::command_words`]
+//!   skips the leading ones so the commands inside still surface.
+//! - Heredoc bodies are consumed by the lexer and not kept.
+//!
+//! Spans are byte ranges into the parsed source, so callers can slice the
+//! original text (e.g. `git status` out of `cd /repo && git status`).
+
+use std::ops::Range;
+
+use anyhow::{Result, bail};
+
+/// Byte range into the parsed source.
+pub type Span = Range<usize>;
+
+// ---------------------------------------------------------------------------
+// AST
+// ---------------------------------------------------------------------------
+
+/// A shell word after quote removal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    /// Source range of the word, quotes included.
+    pub span: Span,
+    /// Value with quotes removed and escapes resolved. Expansions are kept
+    /// verbatim (`"$HOME"` → `$HOME`).
+    pub text: String,
+    /// Whether any part of the word was quoted or escaped.
+    pub quoted: bool,
+    /// Source ranges of command and process substitution bodies
+    /// (`$(...)`, backticks, `<(...)`, `>(...)`).
+    pub substitutions: Vec<Span>,
+}
+
+impl Word {
+    fn is_keyword(&self, keywords: &[&str]) -> bool {
+        !self.quoted && keywords.contains(&self.text.as_str())
+    }
+}
+
+/// Redirection operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectOp {
+    /// `<`
+    Input,
+    /// `>`
+    Output,
+    /// `>>`
+    Append,
+    /// `>|`
+    Clobber,
+    /// `<>`
+    ReadWrite,
+    /// `<&`
+    DupInput,
+    /// `>&`
+    DupOutput,
+    /// `<<`
+    Heredoc,
+    /// `<<-`
+    HeredocStripTabs,
+    /// `<<<`
+    HereString,
+    /// `&>`
+    OutputAll,
+    /// `&>>`
+    AppendAll,
+}
+
+/// A redirection such as `2>&1` or `> out.txt`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    /// Explicit file descriptor (`2` in `2>err.log`).
+    pub fd: Option<u32>,
+    pub op: RedirectOp,
+    /// Target file, file descriptor, or heredoc delimiter.
+    pub target: Word,
+}
+
+impl Redirect {
+    /// Whether the redirection sends stdout to a file.
+    ///
+    /// `2>err.log` and fd duplications like `2>&1` / `>&2` leave stdout
+    /// alone; `>`, `>>`, `>|`, `&>` and bash's `>&file` do not.
+    pub fn writes_stdout_to_file(&self) -> bool {
+        let stdout = self.fd.unwrap_or(1) == 1;
+        match self.op {
+            RedirectOp::Output | RedirectOp::Append | RedirectOp::Clobber => stdout,
+            RedirectOp::OutputAll | RedirectOp::AppendAll => true,
+            RedirectOp::DupOutput => {
+                let target = &self.target.text;
+                stdout && target != "-" && !target.bytes().all(|b| b.is_ascii_digit())
+            }
+            RedirectOp::ReadWrite => self.fd == Some(1),
+            _ => false,
+        }
+    }
+
+    /// Whether this is a heredoc (`<<`, `<<-`) or here-string (`<<<`).
+    pub fn is_heredoc(&self) -> bool {
+        matches!(
+            self.op,
+            RedirectOp::Heredoc | RedirectOp::HeredocStripTabs | RedirectOp::HereString
+        )
+    }
+}
+
+/// A `NAME=value` assignment prefixing a simple command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    pub name: String,
+    pub value: Word,
+}
+
+/// Keywords that introduce the command following them (`then git status`).
+const LEADING_KEYWORDS: &[&str] = &["if", "then", "else", "elif", "do", "while", "until", "!"];
+
+/// Keywords that close a compound command and run nothing themselves.
+const CLOSING_KEYWORDS: &[&str] = &["fi", "done", "esac"];
+
+/// A simple command: assignments, words, and redirections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimpleCommand {
+    /// Source range from the first to the last token of the command.
+    pub span: Span,
+    pub assignments: Vec<Assignment>,
+    pub words: Vec<Word>,
+    pub redirects: Vec<Redirect>,
+}
+
+impl SimpleCommand {
+    /// The words that form the executed command.
+    ///
+    /// Skips leading compound keywords (`then`, `do`, ...) and is empty for
+    /// commands made only of closing keywords (`fi`, `done`).
+    pub fn command_words(&self) -> &[Word] {
+        let start = self
+            .words
+            .iter()
+            .take_while(|w| w.is_keyword(LEADING_KEYWORDS))
+            .count();
+        let words = &self.words[start..];
+        if words.iter().all(|w| w.is_keyword(CLOSING_KEYWORDS)) {
+            &[]
+        } else {
+            words
+        }
+    }
+}
+
+/// One element of a pipeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Simple(SimpleCommand),
+    /// `( list )`
+    Subshell {
+        body: List,
+        redirects: Vec<Redirect>,
+    },
+    /// `{ list; }`
+    Group {
+        body: List,
+        redirects: Vec<Redirect>,
+    },
+}
+
+/// Commands joined by `|` or `|&`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    /// Prefixed with `!`.
+    pub negated: bool,
+    pub commands: Vec<Command>,
+}
+
+/// Operator following a pipeline in a list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    /// `&&`
+    And,
+    /// `||`
+    Or,
+    /// `;` or a newline
+    Sequence,
+    /// `&`
+    Background,
+}
+
+/// A pipeline and the operator that follows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListItem {
+    pub pipeline: Pipeline,
+    pub separator: Option<Separator>,
+}
+
+/// A sequence of pipelines joined by `&&`, `||`, `;`, `&`, or newlines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct List {
+    pub items: Vec<ListItem>,
+}
+
+impl List {
+    /// All simple commands, descending into subshells and brace groups.
+    pub fn simple_commands(&self) -> Vec<&SimpleCommand> {
+        let mut out = Vec::new();
+        for command in self.commands() {
+            match command {
+                Command::Simple(simple) => out.push(simple),
+                Command::Subshell { body, .. } | Command::Group { body, .. } => {
+                    out.extend(body.simple_commands());
+                }
+            }
+        }
+        out
+    }
+
+    /// All redirections, including those applied to subshells and groups.
+    pub fn redirects(&self) -> Vec<&Redirect> {
+        let mut out = Vec::new();
+        for command in self.commands() {
+            match command {
+                Command::Simple(simple) => out.extend(&simple.redirects),
+                Command::Subshell { body, redirects } | Command::Group { body, redirects } => {
+                    out.extend(redirects);
+                    out.extend(body.redirects());
+                }
+            }
+        }
+        out
+    }
+
+    fn commands(&self) -> impl Iterator<Item = &Command> {
+        self.items.iter().flat_map(|item| &item.pipeline.commands)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Parse a command line into a [`List`].
+pub fn parse(src: &str) -> Result<List> {
+    parse_range(src, 0..src.len())
+}
+
+/// Parse `src[range]`, reporting spans relative to the whole of `src`.
+///
+/// Used to parse a nested script — a substitution body or the argument of
+/// `bash -c` — while keeping spans sliceable from the outer command.
+pub fn parse_range(src: &str, range: Span) -> Result<List> {
+    let tokens = Lexer::new(src, range).tokenize()?;
+    let mut parser = Parser {
+        src,
+        tokens,
+        pos: 0,
+    };
+    parser.parse_list(Terminator::Eof)
+}
+
+/// Split a command line into unquoted words, ignoring operators.
+///
+/// Falls back to whitespace splitting when the input does not lex (e.g. an
+/// unterminated quote).
+pub fn split_words(src: &str) -> Vec<String> {
+    match Lexer::new(src, 0..src.len()).tokenize() {
+        Ok(tokens) => tokens
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Word(word) => Some(word.text),
+                _ => None,
+            })
+            .collect(),
+        Err(_) => src.split_whitespace().map(str::to_string).collect(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Lexer
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    AndIf,
+    OrIf,
+    Pipe,
+    PipeAll,
+    Semi,
+    DoubleSemi,
+    Amp,
+    LParen,
+    RParen,
+}
+
+impl Operator {
+    fn as_str(self) -> &'static str {
+        match self {
+            Operator::AndIf => "&&",
+            Operator::OrIf => "||",
+            Operator::Pipe => "|",
+            Operator::PipeAll => "|&",
+            Operator::Semi => ";",
+            Operator::DoubleSemi => ";;",
+            Operator::Amp => "&",
+            Operator::LParen => "(",
+            Operator::RParen => ")",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(Word),
+    Op(Operator, usize),
+    Redirect {
+        op: RedirectOp,
+        fd: Option<u32>,
+        start: usize,
+    },
+    Newline,
+}
+
+struct Lexer<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    end: usize,
+    /// Heredoc delimiters (and whether tabs are stripped) awaiting their
+    /// body after the next newline.
+    pending_heredocs: Vec<(String, bool)>,
+    /// Set after `<<` / `<<-`: the next word is a heredoc delimiter.
+    expect_delimiter: Option<bool>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(src: &'a str, range: Span) -> Self {
+        Self {
+            bytes: src.as_bytes(),
+            pos: range.start,
+            end: range.end.min(src.len()),
+            pending_heredocs: Vec::new(),
+            expect_delimiter: None,
+        }
+    }
+
+    fn tokenize(mut self) -> Result<Vec<Token>> {
+        let mut tokens = Vec::new();
+        while let Some(token) = self.next_token()? {
+            tokens.push(token);
+        }
+        Ok(tokens)
+    }
+
+    fn peek(&self, offset: usize) -> Option<u8> {
+        let i = self.pos + offset;
+        (i < self.end).then(|| self.bytes[i])
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>> {
+        self.skip_blanks();
+        let Some(c) = self.peek(0) else {
+            return Ok(None);
+        };
+        let start = self.pos;
+
+        let (op, len) = match (c, self.peek(1), self.peek(2)) {
+            (b'\n', _, _) => {
+                self.pos += 1;
+                self.read_heredoc_bodies();
+                return Ok(Some(Token::Newline));
+            }
+            (b'&', Some(b'&'), _) => (Operator::AndIf, 2),
+            (b'&', Some(b'>'), Some(b'>')) => {
+                return Ok(Some(self.redirect(RedirectOp::AppendAll, 3, None)));
+            }
+            (b'&', Some(b'>'), _) => {
+                return Ok(Some(self.redirect(RedirectOp::OutputAll, 2, None)));
+            }
+            (b'&', _, _) => (Operator::Amp, 1),
+            (b'|', Some(b'|'), _) => (Operator::OrIf, 2),
+            (b'|', Some(b'&'), _) => (Operator::PipeAll, 2),
+            (b'|', _, _) => (Operator::Pipe, 1),
+            (b';', Some(b';'), _) => (Operator::DoubleSemi, 2),
+            (b';', _, _) => (Operator::Semi, 1),
+            (b'(', _, _) => (Operator::LParen, 1),
+            (b')', _, _) => (Operator::RParen, 1),
+            // Process substitution starts a word, not a redirection.
+            (b'<' | b'>', Some(b'('), _) => return Ok(Some(self.word_token()?)),
+            (b'<' | b'>', _, _) => return Ok(Some(self.read_redirect(None))),
+            _ => return Ok(Some(self.word_token()?)),
+        };
+        self.pos += len;
+        Ok(Some(Token::Op(op, start)))
+    }
+
+    /// Skip spaces, tabs, line continuations, and comments.
+    fn skip_blanks(&mut self) {
+        while let Some(c) = self.peek(0) {
+            match c {
+                b' ' | b'\t' | b'\r' => self.pos += 1,
+                b'\\' if self.peek(1) == Some(b'\n') => self.pos += 2,
+                b'#' => {
+                    while self.peek(0).is_some_and(|c| c != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn word_token(&mut self) -> Result<Token> {
+        let word = self.read_word()?;
+
+        // IO number: digits immediately followed by a redirection operator.
+        if !word.quoted
+            && !word.text.is_empty()
+            && word.text.bytes().all(|b| b.is_ascii_digit())
+            && matches!(self.peek(0), Some(b'<' | b'>'))
+            && self.peek(1) != Some(b'(')
+            && let Ok(fd) = word.text.parse()
+        {
+            let mut token = self.read_redirect(Some(fd));
+            if let Token::Redirect { start, .. } = &mut token {
+                *start = word.span.start;
+            }
+            return Ok(token);
+        }
+
+        if let Some(strip_tabs) = self.expect_delimiter.take() {
+            self.pending_heredocs.push((word.text.clone(), strip_tabs));
+        }
+        Ok(Token::Word(word))
+    }
+
+    fn redirect(&mut self, op: RedirectOp, len: usize, fd: Option<u32>) -> Token {
+        let start = self.pos;
+        self.pos += len;
+        match op {
+            RedirectOp::Heredoc => self.expect_delimiter = Some(false),
+            RedirectOp::HeredocStripTabs => self.expect_delimiter = Some(true),
+            _ => {}
+        }
+        Token::Redirect { op, fd, start }
+    }
+
+    fn read_redirect(&mut self, fd: Option<u32>) -> Token {
+        let (op, len) = match (self.peek(0), self.peek(1), self.peek(2)) {
+            (Some(b'<'), Some(b'<'), Some(b'<')) => (RedirectOp::HereString, 3),
+            (Some(b'<'), Some(b'<'), Some(b'-')) => (RedirectOp::HeredocStripTabs, 3),
+            (Some(b'<'), Some(b'<'), _) => (RedirectOp::Heredoc, 2),
+            (Some(b'<'), Some(b'&'), _) => (RedirectOp::DupInput, 2),
+            (Some(b'<'), Some(b'>'), _) => (RedirectOp::ReadWrite, 2),
+            (Some(b'<'), _, _) => (RedirectOp::Input, 1),
+            (_, Some(b'>'), _) => (RedirectOp::Append, 2),
+            (_, Some(b'&'), _) => (RedirectOp::DupOutput, 2),
+            (_, Some(b'|'), _) => (RedirectOp::Clobber, 2),
+            _ => (RedirectOp::Output, 1),
+        };
+        self.redirect(op, len, fd)
+    }
+
+    /// Consume heredoc bodies queued on the line that just ended.
+    fn read_heredoc_bodies(&mut self) {
+        for (delimiter, strip_tabs) in std::mem::take(&mut self.pending_heredocs) {
+            while self.pos < self.end {
+                let line_end = self.bytes[self.pos..self.end]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(self.end, |i| self.pos + i);
+                let mut line = &self.bytes[self.pos..line_end];
+                self.pos = (line_end + 1).min(self.end);
+                if strip_tabs {
+                    while let [b'\t', rest @ ..] = line {
+                        line = rest;
+                    }
+                }
+                if line == delimiter.as_bytes() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn read_word(&mut self) -> Result<Word> {
+        let start = self.pos;
+        let mut text: Vec<u8> = Vec::new();
+        let mut quoted = false;
+        let mut substitutions = Vec::new();
+
+        while let Some(c) = self.peek(0) {
+            match c {
+                b' ' | b'\t' | b'\r' | b'\n' | b';' | b'&' | b'|' | b')' => break,
+                // Array assignment: `arr=(a b c)`.
+                b'(' if text.last() == Some(&b'=') => {
+                    let close = self.closing_paren(self.pos)?;
+                    text.extend_from_slice(&self.bytes[self.pos..=close]);
+                    self.pos = close + 1;
+                }
+                b'(' => break,
+                // Process substitution: `<(cmd)` / `>(cmd)` at word start.
+                b'<' | b'>' if self.pos == start && self.peek(1) == Some(b'(') => {
+                    let close = self.closing_paren(self.pos + 1)?;
+                    substitutions.push(self.pos + 2..close);
+                    text.extend_from_slice(&self.bytes[self.pos..=close]);
+                    self.pos = close + 1;
+                }
+                b'<' | b'>' => break,
+                b'\\' => match self.peek(1) {
+                    Some(b'\n') => self.pos += 2,
+                    Some(next) => {
+                        quoted = true;
+                        text.push(next);
+                        self.pos += 2;
+                    }
+                    None => {
+                        text.push(b'\\');
+                        self.pos += 1;
+                    }
+                },
+                b'\'' => {
+                    quoted = true;
+                    let close = self.find(b'\'', self.pos + 1, "unterminated single quote")?;
+                    text.extend_from_slice(&self.bytes[self.pos + 1..close]);
+                    self.pos = close + 1;
+                }
+                b'"' => {
+                    quoted = true;
+                    self.read_double_quoted(&mut text, &mut substitutions)?;
+                }
+                b'$' => self.read_dollar(&mut text, &mut substitutions, &mut quoted, false)?,
+                b'`' => self.read_backtick(&mut text, &mut substitutions)?,
+                _ => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        let text = String::from_utf8(text)
+            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned());
+        Ok(Word {
+            span: start..self.pos,
+            text,
+            quoted,
+            substitutions,
+        })
+    }
+
+    fn read_double_quoted(&mut self, text: &mut Vec<u8>, subs: &mut Vec<Span>) -> Result<()> {
+        self.pos += 1; // opening quote
+        loop {
+            let Some(c) = self.peek(0) else {
+                bail!("unterminated double quote");
+            };
+            match c {
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                b'\\' => match self.peek(1) {
+                    Some(b'\n') => self.pos += 2,
+                    Some(next @ (b'$' | b'`' | b'"' | b'\\')) => {
+                        text.push(next);
+                        self.pos += 2;
+                    }
+                    _ => {
+                        text.push(b'\\');
+                        self.pos += 1;
+                    }
+                },
+                b'$' => self.read_dollar(text, subs, &mut true, true)?,
+                b'`' => self.read_backtick(text, subs)?,
+                _ => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Read a `$` expansion. Expansions are copied verbatim; command
+    /// substitution bodies are recorded in `subs`.
+    fn read_dollar(
+        &mut self,
+        text: &mut Vec<u8>,
+        subs: &mut Vec<Span>,
+        quoted: &mut bool,
+        in_double_quotes: bool,
+    ) -> Result<()> {
+        let start = self.pos;
+        match self.peek(1) {
+            // Arithmetic `$((...))` — not a command.
+            Some(b'(') if self.peek(2) == Some(b'(') => {
+                let close = self.closing_paren(start + 1)?;
+                text.extend_from_slice(&self.bytes[start..=close]);
+                self.pos = close + 1;
+            }
+            Some(b'(') => {
+                let close = self.closing_paren(start + 1)?;
+                subs.push(start + 2..close);
+                text.extend_from_slice(&self.bytes[start..=close]);
+                self.pos = close + 1;
+            }
+            Some(b'{') => {
+                let close = self.closing_brace(start + 1)?;
+                text.extend_from_slice(&self.bytes[start..=close]);
+                self.pos = close + 1;
+            }
+            // ANSI-C quoting: $'...'
+            Some(b'\'') if !in_double_quotes => {
+                *quoted = true;
+                let mut i = start + 2;
+                loop {
+                    match self.bytes.get(i).filter(|_| i < self.end) {
+                        None => bail!("unterminated $'...' string"),
+                        Some(b'\'') => break,
+                        Some(b'\\') if i + 1 < self.end => {
+                            match self.bytes[i + 1] {
+                                b'n' => text.push(b'\n'),
+                                b't' => text.push(b'\t'),
+                                b'r' => text.push(b'\r'),
+                                b'\\' | b'\'' | b'"' => text.push(self.bytes[i + 1]),
+                                other => text.extend_from_slice(&[b'\\', other]),
+                            }
+                            i += 2;
+                        }
+                        Some(&b) => {
+                            text.push(b);
+                            i += 1;
+                        }
+                    }
+                }
+                self.pos = i + 1;
+            }
+            // Locale translation: $"..." — treat as a double-quoted string.
+            Some(b'"') if !in_double_quotes => self.pos += 1,
+            _ => {
+                text.push(b'$');
+                self.pos += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_backtick(&mut self, text: &mut Vec<u8>, subs: &mut Vec<Span>) -> Result<()> {
+        let start = self.pos;
+        let mut i = start + 1;
+        while i < self.end {
+            match self.bytes[i] {
+                b'\\' => i += 2,
+                b'`' => {
+                    subs.push(start + 1..i);
+                    text.extend_from_slice(&self.bytes[start..=i]);
+                    self.pos = i + 1;
+                    return Ok(());
+                }
+                _ => i += 1,
+            }
+        }
+        bail!("unterminated backtick substitution")
+    }
+
+    fn find(&self, needle: u8, from: usize, error: &str) -> Result<usize> {
+        match self.bytes[from.min(self.end)..self.end]
+            .iter()
+            .position(|&b| b == needle)
+        {
+            Some(i) => Ok(from + i),
+            None => bail!("{error}"),
+        }
+    }
+
+    /// Index of the `)` matching the `(` at `open`, skipping quoted text.
+    fn closing_paren(&self, open: usize) -> Result<usize> {
+        self.closing(open, b'(', b')')
+            .ok_or_else(|| anyhow::anyhow!("unterminated `(`"))
+    }
+
+    /// Index of the `}` matching the `{` at `open`, skipping quoted text.
+    fn closing_brace(&self, open: usize) -> Result<usize> {
+        self.closing(open, b'{', b'}')
+            .ok_or_else(|| anyhow::anyhow!("unterminated `${{`"))
+    }
+
+    fn closing(&self, open: usize, open_byte: u8, close_byte: u8) -> Option<usize> {
+        let mut depth = 0usize;
+        let mut i = open;
+        while i < self.end {
+            match self.bytes[i] {
+                b'\\' => i += 1,
+                b'\'' => i = self.find(b'\'', i + 1, "").ok()?,
+                b'"' => {
+                    i += 1;
+                    while i < self.end && self.bytes[i] != b'"' {
+                        if self.bytes[i] == b'\\' {
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+                }
+                b'`' => {
+                    i += 1;
+                    while i < self.end && self.bytes[i] != b'`' {
+                        if self.bytes[i] == b'\\' {
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+                }
+                b if b == open_byte => depth += 1,
+                b if b == close_byte => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        None
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Parser
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Terminator {
+    Eof,
+    Paren,
+    Brace,
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn skip_newlines(&mut self) {
+        while matches!(self.peek(), Some(Token::Newline)) {
+            self.pos += 1;
+        }
+    }
+
+    fn at_terminator(&self, terminator: Terminator) -> bool {
+        match (terminator, self.peek()) {
+            (_, None) => true,
+            (Terminator::Paren, Some(Token::Op(Operator::RParen, _))) => true,
+            (Terminator::Brace, Some(Token::Word(w))) => w.is_keyword(&["}"]),
+            _ => false,
+        }
+    }
+
+    fn parse_list(&mut self, terminator: Terminator) -> Result<List> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_newlines();
+            if self.at_terminator(terminator) {
+                break;
+            }
+            let pipeline = self.parse_pipeline()?;
+            let separator = match self.peek() {
+                Some(Token::Op(Operator::AndIf, _)) => Some(Separator::And),
+                Some(Token::Op(Operator::OrIf, _)) => Some(Separator::Or),
+                Some(Token::Op(Operator::Semi, _) | Token::Newline) => Some(Separator::Sequence),
+                Some(Token::Op(Operator::Amp, _)) => Some(Separator::Background),
+                _ => None,
+            };
+            items.push(ListItem {
+                pipeline,
+                separator,
+            });
+            let Some(separator) = separator else {
+                break;
+            };
+            self.pos += 1;
+            if matches!(separator, Separator::And | Separator::Or) {
+                self.skip_newlines();
+                if self.at_terminator(terminator) {
+                    bail!("expected a command after `&&` or `||`");
+                }
+            }
+        }
+
+        match terminator {
+            Terminator::Eof => {
+                if let Some(token) = self.peek() {
+                    bail!("unexpected {}", self.describe(token));
+                }
+            }
+            Terminator::Paren => match self.peek() {
+                Some(Token::Op(Operator::RParen, _)) => self.pos += 1,
+                _ => bail!("missing `)`"),
+            },
+            Terminator::Brace => match self.peek() {
+                Some(Token::Word(w)) if w.is_keyword(&["}"]) => self.pos += 1,
+                _ => bail!("missing `}}`"),
+            },
+        }
+        Ok(List { items })
+    }
+
+    fn parse_pipeline(&mut self) -> Result<Pipeline> {
+        let negated = matches!(self.peek(), Some(Token::Word(w)) if w.is_keyword(&["!"]));
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut commands = vec![self.parse_command()?];
+        while matches!(
+            self.peek(),
+            Some(Token::Op(Operator::Pipe | Operator::PipeAll, _))
+        ) {
+            self.pos += 1;
+            self.skip_newlines();
+            commands.push(self.parse_command()?);
+        }
+        Ok(Pipeline { negated, commands })
+    }
+
+    fn parse_command(&mut self) -> Result<Command> {
+        match self.peek() {
+            Some(Token::Op(Operator::LParen, _)) => {
+                self.pos += 1;
+                let body = self.parse_list(Terminator::Paren)?;
+                let redirects = self.parse_trailing_redirects()?;
+                Ok(Command::Subshell { body, redirects })
+            }
+            Some(Token::Word(w)) if w.is_keyword(&["{"]) => {
+                self.pos += 1;
+                let body = self.parse_list(Terminator::Brace)?;
+                let redirects = self.parse_trailing_redirects()?;
+                Ok(Command::Group { body, redirects })
+            }
+            _ => Ok(Command::Simple(self.parse_simple()?)),
+        }
+    }
+
+    fn parse_trailing_redirects(&mut self) -> Result<Vec<Redirect>> {
+        let mut redirects = Vec::new();
+        while matches!(self.peek(), Some(Token::Redirect { .. })) {
+            redirects.push(self.parse_redirect()?.0);
+        }
+        Ok(redirects)
+    }
+
+    fn parse_redirect(&mut self) -> Result<(Redirect, Span)> {
+        let Some(Token::Redirect { op, fd, start }) = self.peek().cloned() else {
+            bail!("expected a redirection");
+        };
+        self.pos += 1;
+        let Some(Token::Word(target)) = self.peek().cloned() else {
+            bail!("expected a word after redirection");
+        };
+        self.pos += 1;
+        let span = start..target.span.end;
+        Ok((Redirect { fd, op, target }, span))
+    }
+
+    fn parse_simple(&mut self) -> Result<SimpleCommand> {
+        let mut span: Option<Span> = None;
+        let mut assignments = Vec::new();
+        let mut words = Vec::new();
+        let mut redirects = Vec::new();
+
+        loop {
+            let token_span = match self.peek() {
+                Some(Token::Word(word)) => {
+                    let word = word.clone();
+                    self.pos += 1;
+                    let word_span = word.span.clone();
+                    match self.assignment(&word) {
+                        Some(assignment) if words.is_empty() => assignments.push(assignment),
+                        _ => words.push(word),
+                    }
+                    word_span
+                }
+                Some(Token::Redirect { .. }) => {
+                    let (redirect, redirect_span) = self.parse_redirect()?;
+                    redirects.push(redirect);
+                    redirect_span
+                }
+                _ => break,
+            };
+            span = Some(match span {
+                Some(s) => s.start..token_span.end,
+                None => token_span,
+            });
+        }
+
+        let Some(span) = span else {
+            match self.peek() {
+                Some(token) => bail!("unexpected {}", self.describe(token)),
+                None => bail!("expected a command"),
+            }
+        };
+        Ok(SimpleCommand {
+            span,
+            assignments,
+            words,
+            redirects,
+        })
+    }
+
+    /// Interpret a word as `NAME=value` when the name is an unquoted identifier.
+    fn assignment(&self, word: &Word) -> Option<Assignment> {
+        let raw = &self.src[word.span.clone()];
+        let eq = raw.find('=')?;
+        let name = &raw[..eq];
+        let mut chars = name.chars();
+        let first = chars.next()?;
+        if !(first.is_ascii_alphabetic() || first == '_')
+            || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return None;
+        }
+        let value_start = word.span.start + eq + 1;
+        Some(Assignment {
+            name: name.to_string(),
+            value: Word {
+                span: value_start..word.span.end,
+                text: word.text[eq + 1..].to_string(),
+                quoted: word.quoted,
+                substitutions: word.substitutions.clone(),
+            },
+        })
+    }
+
+    fn describe(&self, token: &Token) -> String {
+        match token {
+            Token::Word(w) => format!("word `{}`", w.text),
+            Token::Op(op, _) => format!("`{}`", op.as_str()),
+            Token::Redirect { .. } => "redirection".to_string(),
+            Token::Newline => "newline".to_string(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(src: &str) -> Vec<String> {
+        parse(src)
+            .unwrap()
+            .simple_commands()
+            .iter()
+            .filter_map(|c| c.command_words().first().map(|w| w.text.clone()))
+            .collect()
+    }
+
+    fn only_command(src: &str) -> SimpleCommand {
+        let list = parse(src).unwrap();
+        let commands = list.simple_commands();
+        assert_eq!(commands.len(), 1, "{src}");
+        commands[0].clone()
+    }
+
+    fn texts(words: &[Word]) -> Vec<&str> {
+        words.iter().map(|w| w.text.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_simple_command_words() {
+        let cmd = only_command("git log --oneline -n 5");
+        assert_eq!(texts(&cmd.words), ["git", "log", "--oneline", "-n", "5"]);
+        assert_eq!(cmd.span, 0..22);
+    }
+
+    #[test]
+    fn removes_quotes_and_resolves_escapes() {
+        let cmd = only_command(r#"echo 'a b' "c \"d\" $HOME" e\ f $'g\th'"#);
+        assert_eq!(
+            texts(&cmd.words),
+            ["echo", "a b", "c \"d\" $HOME", "e f", "g\th"]
+        );
+        assert!(cmd.words[1].quoted);
+        assert!(!cmd.words[0].quoted);
+    }
+
+    #[test]
+    fn lists_record_separators() {
+        let list = parse("a && b || c; d & e").unwrap();
+        let seps: Vec<_> = list.items.iter().map(|i| i.separator).collect();
+        assert_eq!(
+            seps,
+            [
+                Some(Separator::And),
+                Some(Separator::Or),
+                Some(Separator::Sequence),
+                Some(Separator::Background),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn distinguishes_pipe_from_logical_or() {
+        let list = parse("a | b || c").unwrap();
+        assert_eq!(list.items.len(), 2);
+        assert_eq!(list.items[0].pipeline.commands.len(), 2);
+    }
+
+    #[test]
+    fn operators_inside_quotes_are_literal() {
+        assert_eq!(names("echo 'a && b; c | d' \"x || y\""), ["echo"]);
+    }
+
+    #[test]
+    fn newlines_separate_commands() {
+        assert_eq!(names("cd /repo\ngit status\n\nls"), ["cd", "git", "ls"]);
+    }
+
+    #[test]
+    fn comments_are_ignored() {
+        assert_eq!(names("ls # && rm -rf /\necho a#b"), ["ls", "echo"]);
+    }
+
+    #[test]
+    fn parses_subshells_and_groups() {
+        let list = parse("(cd /repo && make) > log; { ls; pwd; }").unwrap();
+        assert!(matches!(
+            &list.items[0].pipeline.commands[0],
+            Command::Subshell { redirects, .. } if redirects.len() == 1
+        ));
+        assert!(matches!(
+            &list.items[1].pipeline.commands[0],
+            Command::Group { .. }
+        ));
+        assert_eq!(
+            names("(cd /repo && make) > log; { ls; pwd; }"),
+            ["cd", "make", "ls", "pwd"]
+        );
+    }
+
+    #[test]
+    fn parses_assignments() {
+        let cmd = only_command("LANG=C FOO=\"a b\" git status");
+        assert_eq!(cmd.assignments.len(), 2);
+        assert_eq!(cmd.assignments[1].name, "FOO");
+        assert_eq!(cmd.assignments[1].value.text, "a b");
+        assert_eq!(cmd.command_words()[0].text, "git");
+    }
+
+    #[test]
+    fn equals_in_arguments_is_not_an_assignment() {
+        let cmd = only_command("git log --format=oneline A=B");
+        assert!(cmd.assignments.is_empty());
+        assert_eq!(cmd.words.len(), 4);
+    }
+
+    #[test]
+    fn parses_redirections() {
+        let cmd = only_command("cmd <in >out 2>>err 2>&1 &>all >|clob");
+        let ops: Vec<_> = cmd.redirects.iter().map(|r| (r.fd, r.op)).collect();
+        assert_eq!(
+            ops,
+            [
+                (None, RedirectOp::Input),
+                (None, RedirectOp::Output),
+                (Some(2), RedirectOp::Append),
+                (Some(2), RedirectOp::DupOutput),
+                (None, RedirectOp::OutputAll),
+                (None, RedirectOp::Clobber),
+            ]
+        );
+        assert_eq!(texts(&cmd.words), ["cmd"]);
+    }
+
+    #[test]
+    fn stdout_file_redirect_detection() {
+        let writes = |src: &str| only_command(src).redirects[0].writes_stdout_to_file();
+        assert!(writes("ls > out"));
+        assert!(writes("ls 1>> out"));
+        assert!(writes("ls &> out"));
+        assert!(writes("ls >& out"));
+        assert!(!writes("ls 2> err"));
+        assert!(!writes("ls 2>&1"));
+        assert!(!writes("ls >&2"));
+        assert!(!writes("sort < in"));
+    }
+
+    #[test]
+    fn heredoc_body_is_consumed() {
+        let list = parse("cat <<EOF | grep x\nrm -rf / ; a && b\nEOF\nls").unwrap();
+        assert_eq!(
+            names("cat <<EOF | grep x\nrm -rf / ; a && b\nEOF\nls"),
+            ["cat", "grep", "ls"]
+        );
+        assert!(list.redirects()[0].is_heredoc());
+    }
+
+    #[test]
+    fn heredoc_strip_tabs_and_quoted_delimiter() {
+        assert_eq!(
+            names("cat <<-'END'\n\t$(rm x)\n\tEND\necho"),
+            ["cat", "echo"]
+        );
+    }
+
+    #[test]
+    fn records_substitution_spans() {
+        let src = "echo $(git status) `date` <(ls)";
+        let cmd = only_command(src);
+        let subs: Vec<&str> = cmd
+            .words
+            .iter()
+            .flat_map(|w| w.substitutions.iter().map(|s| &src[s.clone()]))
+            .collect();
+        assert_eq!(subs, ["git status", "date", "ls"]);
+    }
+
+    #[test]
+    fn nested_substitution_with_operators_stays_one_word() {
+        let cmd = only_command("echo \"$(cd /x && ls | wc -l)\" $((1 + 2))");
+        assert_eq!(cmd.words.len(), 3);
+        assert_eq!(cmd.words[1].substitutions.len(), 1);
+        assert!(cmd.words[2].substitutions.is_empty());
+    }
+
+    #[test]
+    fn compound_keywords_are_skipped() {
+        assert_eq!(
+            names("if true; then git status; else ls; fi"),
+            ["true", "git", "ls"]
+        );
+        assert_eq!(names("for f in *; do rm \"$f\"; done"), ["for", "rm"]);
+    }
+
+    #[test]
+    fn negated_pipeline() {
+        let list = parse("! grep -q x file").unwrap();
+        assert!(list.items[0].pipeline.negated);
+    }
+
+    #[test]
+    fn parse_range_reports_outer_spans() {
+        let src = "bash -c 'git status'";
+        let list = parse_range(src, 9..19).unwrap();
+        let cmd = list.simple_commands()[0];
+        assert_eq!(&src[cmd.span.clone()], "git status");
+    }
+
+    #[test]
+    fn rejects_malformed_commands() {
+        for src in [
+            "echo 'unterminated",
+            "echo \"unterminated",
+            "echo $(unterminated",
+            "a &&",
+            "| b",
+            "(a",
+            "a)",
+            "{ a",
+            "cat >",
+        ] {
+            assert!(parse(src).is_err(), "{src}");
+        }
+    }
+
+    #[test]
+    fn empty_input_is_an_empty_list() {
+        assert!(parse("").unwrap().items.is_empty());
+        assert!(parse("  \n # just a comment").unwrap().items.is_empty());
+    }
+
+    #[test]
+    fn split_words_unquotes() {
+        assert_eq!(
+            split_words("git commit -m \"fix --amend\" --no-verify"),
+            ["git", "commit", "-m", "fix --amend", "--no-verify"]
+        );
+        assert_eq!(split_words("a 'b"), ["a", "'b"]);
+    }
+}
@@ -1,6 +1,10 @@
 use anyhow::Result;
 
 use crate::config::schema::BuildOptimizerConfig;
+use crate::matching;
+use crate::optimizers::diagnostics::compact_diagnostics;
+use crate::optimizers::rustc::compact_cargo_output;
+use crate::optimizers::test_report::parse_test_output;
 use crate::optimizers::{CommandContext, OptimizedOutput, Optimizer};
 use crate::utils::token_counter::estimate_tokens;
 
@@ -23,6 +27,7 @@ enum BuildCommand {
 fn classify(lower: &str) -> Option<BuildCommand> {
     // Test commands
     if lower.starts_with("cargo test")
+        || lower.starts_with("cargo nextest")
         || lower.starts_with("npm test")
         || lower.starts_with("npm run test")
         || lower.starts_with("npx jest")
@@ -30,6 +35,7 @@ fn classify(lower: &str) -> Option<BuildCommand> {
         || lower.starts_with("dotnet test")
         || lower.starts_with("pytest")
         || lower.starts_with("python -m pytest")
+        || lower.starts_with("python3 -m pytest")
         || lower.starts_with("go test")
         || lower.starts_with("mvn test")
         || lower.starts_with("gradle test")
@@ -41,11 +47,14 @@ fn classify(lower: &str) -> Option<BuildCommand> {
 
     // Build/install commands
     if lower.starts_with("cargo build")
+        || lower.starts_with("cargo check")
         || lower.starts_with("cargo install")
         || lower.starts_with("npm install")
         || lower.starts_with("npm ci")
         || lower.starts_with("npm run build")
         || lower.starts_with("npx tsc")
+        || lower == "tsc"
+        || lower.starts_with("tsc ")
         || lower.starts_with("yarn install")
         || lower.starts_with("yarn build")
         || lower.starts_with("pnpm install")
@@ -73,7 +82,10 @@ fn classify(lower: &str) -> Option<BuildCommand> {
     if lower.starts_with("cargo clippy")
         || lower.starts_with("cargo fmt")
         || lower.starts_with("npx eslint")
+        || lower.starts_with("eslint")
         || lower.starts_with("npm run lint")
+        || lower.starts_with("yarn lint")
+        || lower.starts_with("pnpm lint")
         || lower.starts_with("dotnet format")
         || lower.starts_with("pylint")
         || lower.starts_with("flake8")
@@ -87,6 +99,26 @@ fn classify(lower: &str) -> Option<BuildCommand> {
     None
 }
 
+/// Cargo subcommands whose output is rustc diagnostics.
+const CARGO_COMPILE_COMMANDS: &[&str] = &[
+    "cargo build",
+    "cargo check",
+    "cargo clippy",
+    "cargo install",
+    "cargo test",
+];
+
+/// Cargo subcommands that accept `--message-format`.
+const CARGO_SHORT_FORMAT_COMMANDS: &[&str] = &["cargo build", "cargo check", "cargo clippy"];
+
+fn cargo_subcommand(lower: &str, subcommands: &[&'static str]) -> Option<&'static str> {
+    subcommands.iter().copied().find(|s| {
+        lower
+            .strip_prefix(s)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Optimizer
 // ---------------------------------------------------------------------------
@@ -98,6 +130,7 @@ pub struct BuildOptimizer {
     build_max_error_lines: usize,
     build_max_warnings: usize,
     lint_max_issue_lines: usize,
+    cargo_short_messages: bool,
 }
 
 impl Default for BuildOptimizer {
@@ -120,6 +153,7 @@ impl BuildOptimizer {
             build_max_error_lines: cfg.build_max_error_lines,
             build_max_warnings: cfg.build_max_warnings,
             lint_max_issue_lines: cfg.lint_max_issue_lines,
+            cargo_short_messages: cfg.cargo_short_messages,
         }
     }
 }
@@ -134,23 +168,90 @@ impl Optimizer for BuildOptimizer {
         classify(&lower).is_some()
     }
 
+    /// With `cargo_short_messages`, ask cargo for one line per diagnostic
+    /// unless the user already chose a message format.
+    fn rewrite_command(&self, ctx: &CommandContext) -> Option<String> {
+        let lower = ctx.core.to_ascii_lowercase();
+        if !self.cargo_short_messages || lower.contains("--message-format") {
+            return None;
+        }
+        let subcommand = cargo_subcommand(&lower, CARGO_SHORT_FORMAT_COMMANDS)?;
+        // Insert right after the subcommand of the core command; the same
+        // text may appear earlier (`echo "cargo build" && cargo build`).
+        let span = matching::core_command_span(ctx.original)?;
+        let core = &ctx.original[span.clone()];
+        if !core.to_ascii_lowercase().starts_with(subcommand) {
+            return None;
+        }
+        let at = span.start + subcommand.len();
+        Some(format!(
+            "{} --message-format=short{}",
+            &ctx.original[..at],
+            &ctx.original[at..]
+        ))
+    }
+
     fn optimize_output(&self, ctx: &CommandContext, raw_output: &str) -> Result<OptimizedOutput> {
         let lower = ctx.core.to_ascii_lowercase();
         let cmd = classify(&lower).unwrap_or(BuildCommand::Build);
 
+        let cargo = cargo_subcommand(&lower, CARGO_COMPILE_COMMANDS).is_some();
+
         let optimized = match cmd {
-            BuildCommand::Test => compact_test_output(
-                raw_output,
-                self.test_max_failure_lines,
-                self.test_max_error_lines,
-                self.test_max_warnings,
-            ),
-            BuildCommand::Build => compact_build_output(
+            // A test build that fails to compile prints diagnostics, not results.
+            BuildCommand::Test if cargo && raw_output.contains("error: could not compile") => {
+                compact_cargo_output(
+                    raw_output,
+                    self.test_max_error_lines,
+                    self.test_max_warnings,
+                )
+                .unwrap_or_else(|| {
+                    compact_test_output(
+                        raw_output,
+                        self.test_max_failure_lines,
+                        self.test_max_error_lines,
+                        self.test_max_warnings,
+                    )
+                })
+            }
+            BuildCommand::Test => parse_test_output(&lower, raw_output)
+                .map(|report| report.render(self.test_max_failure_lines))
+                .unwrap_or_else(|| {
+                    compact_test_output(
+                        raw_output,
+                        self.test_max_failure_lines,
+                        self.test_max_error_lines,
+                        self.test_max_warnings,
+                    )
+                }),
+            BuildCommand::Build if cargo => compact_cargo_output(
                 raw_output,
                 self.build_max_error_lines,
                 self.build_max_warnings,
-            ),
-            BuildCommand::Lint => compact_lint_output(raw_output, self.lint_max_issue_lines),
+            )
+            .unwrap_or_else(|| {
+                compact_build_output(
+                    raw_output,
+                    self.build_max_error_lines,
+                    self.build_max_warnings,
+                )
+            }),
+            BuildCommand::Build => compact_diagnostics(raw_output, self.build_max_error_lines)
+                .unwrap_or_else(|| {
+                    compact_build_output(
+                        raw_output,
+                        self.build_max_error_lines,
+                        self.build_max_warnings,
+                    )
+                }),
+            BuildCommand::Lint if cargo => compact_cargo_output(
+                raw_output,
+                self.lint_max_issue_lines,
+                self.build_max_warnings,
+            )
+            .unwrap_or_else(|| compact_lint_output(raw_output, self.lint_max_issue_lines)),
+            BuildCommand::Lint => compact_diagnostics(raw_output, self.lint_max_issue_lines)
+                .unwrap_or_else(|| compact_lint_output(raw_output, self.lint_max_issue_lines)),
         };
 
         Ok(OptimizedOutput {
@@ -684,6 +785,8 @@ mod tests {
         assert_eq!(classify("dotnet test"), Some(BuildCommand::Test));
         assert_eq!(classify("pytest"), Some(BuildCommand::Test));
         assert_eq!(classify("python -m pytest"), Some(BuildCommand::Test));
+        assert_eq!(classify("python3 -m pytest"), Some(BuildCommand::Test));
+        assert_eq!(classify("cargo nextest run"), Some(BuildCommand::Test));
         assert_eq!(classify("go test ./..."), Some(BuildCommand::Test));
         assert_eq!(classify("make test"), Some(BuildCommand::Test));
     }
@@ -711,6 +814,8 @@ mod tests {
         assert_eq!(classify("npx eslint ."), Some(BuildCommand::Lint));
         assert_eq!(classify("npm run lint"), Some(BuildCommand::Lint));
         assert_eq!(classify("ruff check ."), Some(BuildCommand::Lint));
+        assert_eq!(classify("eslint src"), Some(BuildCommand::Lint));
+        assert_eq!(classify("tsc --noEmit"), Some(BuildCommand::Build));
     }
 
     #[test]
@@ -732,6 +837,54 @@ mod tests {
 
     // compact_test_output ------------------------------------------------
 
+    #[test]
+    fn rewrites_cargo_builds_for_short_messages_when_enabled() {
+        let ctx = CommandContext::new("cd /repo && cargo build --release");
+        assert_eq!(BuildOptimizer::new().rewrite_command(&ctx), None);
+
+        let opt = BuildOptimizer::from_config(&BuildOptimizerConfig {
+            cargo_short_messages: true,
+            ..BuildOptimizerConfig::default()
+        });
+        assert_eq!(
+            opt.rewrite_command(&ctx).as_deref(),
+            Some("cd /repo && cargo build --message-format=short --release")
+        );
+        let echoed = CommandContext::new("echo \"cargo build\" && cargo build");
+        assert_eq!(
+            opt.rewrite_command(&echoed).as_deref(),
+            Some("echo \"cargo build\" && cargo build --message-format=short")
+        );
+        let json = CommandContext::new("cargo check --message-format=json");
+        assert_eq!(opt.rewrite_command(&json), None);
+        assert_eq!(
+            opt.rewrite_command(&CommandContext::new("cargo test")),
+            None
+        );
+    }
+
+    #[test]
+    fn cargo_build_groups_rustc_diagnostics() {
+        let input = "\
+   Compiling demo v0.1.0
+warning: unused variable: `x`
+ --> src/main.rs:2:9
+  |
+2 |     let x = 5;
+  |         ^
+  |
+  = note: `#[warn(unused_variables)]` on by default
+
+    Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.5s";
+        let opt = BuildOptimizer::new();
+        let result = opt
+            .optimize_output(&CommandContext::new("cargo check"), input)
+            .unwrap()
+            .output;
+        assert!(result.contains("unused_variables: unused variable: `x` \u{2014} src/main.rs:2:9"));
+        assert!(!result.contains("let x = 5"));
+    }
+
     #[test]
     fn test_output_empty() {
         assert_eq!(compact_test_output("", 80, 40, 10), "No test output");
@@ -0,0 +1,629 @@
+//! Grouping of TypeScript, ESLint and ruff/flake8 diagnostics.
+//!
+//! A single type change in a large TypeScript project produces hundreds of
+//! errors with the same code and the same message shape. This module parses
+//! `tsc`, ESLint (stylish, compact and unix formats) and ruff/flake8 output
+//! into individual findings, groups them by code or rule and then by file,
+//! and renders each group once with counts and representative messages.
+//! Lines that mention an error or warning but match none of these formats
+//! (a compiler error in a `make` log, a file-less `tsc` error) are kept
+//! verbatim.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+// ---------------------------------------------------------------------------
+// Finding model
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+/// One reported problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Finding {
+    severity: Severity,
+    /// `TS2345`, `no-unused-vars`, `F401`; empty for rule-less problems
+    /// such as ESLint parsing errors.
+    code: String,
+    file: String,
+    line: usize,
+    col: usize,
+    message: String,
+    /// Continuation lines of a multi-line `tsc` message.
+    detail: Vec<String>,
+}
+
+impl Finding {
+    fn location(&self) -> String {
+        format!("{}:{}:{}", self.file, self.line, self.col)
+    }
+}
+
+/// Findings of one code, in output order.
+struct Group<'a> {
+    severity: Severity,
+    code: &'a str,
+    findings: Vec<&'a Finding>,
+}
+
+/// Representative messages shown per group, including the first.
+const REPRESENTATIVE_MESSAGES: usize = 2;
+
+/// Files listed with counts per group.
+const MAX_FILES_PER_GROUP: usize = 5;
+
+/// Continuation lines kept from the first finding of a group.
+const MAX_DETAIL_LINES: usize = 2;
+
+// ---------------------------------------------------------------------------
+// Parsing
+// ---------------------------------------------------------------------------
+
+/// `src/a.ts(12,5): error TS2345: Argument of type ...`
+static TSC_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+?)\((\d+),(\d+)\): (error|warning) (TS\d+): (.*)$").unwrap());
+
+/// `src/a.ts:12:5 - error TS2345: Argument of type ...` (`--pretty`).
+static TSC_PRETTY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+?):(\d+):(\d+) - (error|warning) (TS\d+): (.*)$").unwrap());
+
+/// `  12:5  error  'x' is defined but never used  no-unused-vars` under a
+/// file header (ESLint stylish).
+static ESLINT_STYLISH_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s+(\d+):(\d+)\s+(error|warning)\s+(.+?)(?:\s{2,}(\S+))?$").unwrap()
+});
+
+/// `src/a.js: line 12, col 5, Error - message (rule)` (ESLint compact).
+static ESLINT_COMPACT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(.+): line (\d+), col (\d+), (Error|Warning) - (.*?)(?: \(([\w@/-]+)\))?$")
+        .unwrap()
+});
+
+/// `src/a.js:12:5: message [Error/rule]` (ESLint unix).
+static ESLINT_UNIX_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(.+?):(\d+):(\d+): (.*) \[(Error|Warning)(?:/(\S+))?\]$").unwrap()
+});
+
+/// `src/a.py:1:8: F401 [*] `os` imported but unused` (ruff concise, flake8).
+static CONCISE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+?):(\d+):(\d+): ([A-Z]+\d+):? (?:\[\*\] )?(.*)$").unwrap());
+
+/// `F401 [*] `os` imported but unused`, located by the next `-->` line
+/// (ruff full format).
+static RUFF_HEADER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([A-Z]+\d+) (?:\[\*\] )?(.*)$").unwrap());
+
+static RUFF_LOCATION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*--> (.+?):(\d+):(\d+)$").unwrap());
+
+/// An `error` or `warning` word outside a path or identifier.
+static UNPARSED_PROBLEM_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(?:^|[^\w./-])(?:error|warning)(?:[^\w./-]|$)").unwrap());
+
+/// Totals repeated by the header: `Found 3 errors.`, `✖ 5 problems (...)`.
+static SUMMARY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:Found \d+ (?:error|problem)|✖ \d+ problem)").unwrap());
+
+fn severity(word: &str) -> Severity {
+    if word.eq_ignore_ascii_case("warning") {
+        Severity::Warning
+    } else {
+        Severity::Error
+    }
+}
+
+/// Everything recognized in the output.
+#[derive(Debug, Default)]
+struct Report {
+    findings: Vec<Finding>,
+    /// `... fixable with the --fix option` lines.
+    notes: Vec<String>,
+    /// Lines mentioning an error or warning in no recognized format.
+    unparsed: Vec<String>,
+}
+
+fn parse(raw_output: &str) -> Report {
+    let mut report = Report::default();
+    // ESLint stylish file header, and a ruff full-format header awaiting
+    // its `-->` location.
+    let mut stylish_file: Option<&str> = None;
+    let mut ruff_pending: Option<(String, String)> = None;
+    // Whether the previous line was a `tsc` finding that may continue.
+    let mut tsc_open = false;
+
+    for line in raw_output.lines() {
+        let line = line.trim_end();
+
+        if tsc_open && line.starts_with(' ') && !line.trim().is_empty() {
+            if let Some(last) = report.findings.last_mut() {
+                last.detail.push(line.trim().to_string());
+            }
+            continue;
+        }
+        tsc_open = false;
+
+        if let Some(caps) = TSC_RE
+            .captures(line)
+            .or_else(|| TSC_PRETTY_RE.captures(line))
+        {
+            report.findings.push(Finding {
+                severity: severity(&caps[4]),
+                code: caps[5].to_string(),
+                file: caps[1].to_string(),
+                line: caps[2].parse().unwrap_or(0),
+                col: caps[3].parse().unwrap_or(0),
+                message: caps[6].to_string(),
+                detail: Vec::new(),
+            });
+            tsc_open = true;
+            continue;
+        }
+        if let Some(caps) = ESLINT_COMPACT_RE.captures(line) {
+            report.findings.push(Finding {
+                severity: severity(&caps[4]),
+                code: caps.get(6).map_or("", |m| m.as_str()).to_string(),
+                file: caps[1].to_string(),
+                line: caps[2].parse().unwrap_or(0),
+                col: caps[3].parse().unwrap_or(0),
+                message: caps[5].to_string(),
+                detail: Vec::new(),
+            });
+            continue;
+        }
+        if let Some(caps) = ESLINT_UNIX_RE.captures(line) {
+            report.findings.push(Finding {
+                severity: severity(&caps[5]),
+                code: caps.get(6).map_or("", |m| m.as_str()).to_string(),
+                file: caps[1].to_string(),
+                line: caps[2].parse().unwrap_or(0),
+                col: caps[3].parse().unwrap_or(0),
+                message: caps[4].to_string(),
+                detail: Vec::new(),
+            });
+            continue;
+        }
+        if let Some(caps) = CONCISE_RE.captures(line) {
+            report.findings.push(Finding {
+                severity: Severity::Error,
+                code: caps[4].to_string(),
+                file: caps[1].to_string(),
+                line: caps[2].parse().unwrap_or(0),
+                col: caps[3].parse().unwrap_or(0),
+                message: caps[5].to_string(),
+                detail: Vec::new(),
+            });
+            continue;
+        }
+        if let Some(file) = stylish_file
+            && let Some(caps) = ESLINT_STYLISH_RE.captures(line)
+        {
+            report.findings.push(Finding {
+                severity: severity(&caps[3]),
+                code: caps.get(5).map_or("", |m| m.as_str()).to_string(),
+                file: file.to_string(),
+                line: caps[1].parse().unwrap_or(0),
+                col: caps[2].parse().unwrap_or(0),
+                message: caps[4].to_string(),
+                detail: Vec::new(),
+            });
+            continue;
+        }
+        if let Some(caps) = RUFF_LOCATION_RE.captures(line)
+            && let Some((code, message)) = ruff_pending.take()
+        {
+            report.findings.push(Finding {
+                severity: Severity::Error,
+                code,
+                file: caps[1].to_string(),
+                line: caps[2].parse().unwrap_or(0),
+                col: caps[3].parse().unwrap_or(0),
+                message,
+                detail: Vec::new(),
+            });
+            continue;
+        }
+        if let Some(caps) = RUFF_HEADER_RE.captures(line) {
+            ruff_pending = Some((caps[1].to_string(), caps[2].to_string()));
+            continue;
+        }
+        if line.contains("fixable with the") {
+            report.notes.push(line.trim().to_string());
+            continue;
+        }
+        if UNPARSED_PROBLEM_RE.is_match(line) && !SUMMARY_RE.is_match(line.trim_start()) {
+            report.unparsed.push(line.to_string());
+        }
+        if !line.is_empty() && !line.starts_with(char::is_whitespace) {
+            stylish_file = Some(line);
+        }
+    }
+    report
+}
+
+// ---------------------------------------------------------------------------
+// Rendering
+// ---------------------------------------------------------------------------
+
+/// Compact `tsc`, ESLint and ruff/flake8 output grouped by code.
+///
+/// Every code keeps its first occurrence; counts, files and a second
+/// representative message are added while `max_lines` allows. Unparsed
+/// error and warning lines follow under `OTHER:`. Returns `None` when no
+/// diagnostics are recognized.
+pub(crate) fn compact_diagnostics(raw_output: &str, max_lines: usize) -> Option<String> {
+    let report = parse(raw_output);
+    if report.findings.is_empty() {
+        return None;
+    }
+
+    let mut groups: Vec<Group> = Vec::new();
+    for finding in &report.findings {
+        match groups.iter_mut().find(|g| g.code == finding.code) {
+            Some(group) => {
+                group.severity = group.severity.min(finding.severity);
+                group.findings.push(finding);
+            }
+            None => groups.push(Group {
+                severity: finding.severity,
+                code: &finding.code,
+                findings: vec![finding],
+            }),
+        }
+    }
+    groups.sort_by_key(|g| (g.severity, std::cmp::Reverse(g.findings.len())));
+
+    let errors = count(&report.findings, Severity::Error);
+    let warnings = count(&report.findings, Severity::Warning);
+    let files = distinct_files(report.findings.iter());
+    let mut out = vec![format!(
+        "{} in {} ({} {})",
+        [(errors, "error"), (warnings, "warning")]
+            .iter()
+            .filter(|(n, _)| *n > 0)
+            .map(|(n, label)| plural(*n, label))
+            .collect::<Vec<_>>()
+            .join(", "),
+        plural(files.len(), "file"),
+        groups.len(),
+        if groups.len() == 1 { "code" } else { "codes" },
+    )];
+
+    let mut budget = max_lines.saturating_sub(1);
+    let mut section = None;
+    for group in &groups {
+        if section != Some(group.severity) {
+            section = Some(group.severity);
+            out.push(match group.severity {
+                Severity::Error => "ERRORS:".to_string(),
+                Severity::Warning => "WARNINGS:".to_string(),
+            });
+        }
+        let lines = render_group(group);
+        // Always keep the header with the first occurrence.
+        let take = lines.len().min(budget).max(1);
+        budget = budget.saturating_sub(take);
+        out.extend(lines.into_iter().take(take));
+    }
+
+    if !report.unparsed.is_empty() {
+        out.push("OTHER:".to_string());
+        out.extend(report.unparsed);
+    }
+    out.extend(report.notes);
+    Some(out.join("\n"))
+}
+
+/// Header with the first occurrence, then its detail, a different message
+/// of the same code, and the files with their counts.
+fn render_group(group: &Group) -> Vec<String> {
+    let first = group.findings[0];
+    let label = if group.code.is_empty() {
+        String::new()
+    } else {
+        format!("{} ", group.code)
+    };
+    if group.findings.len() == 1 {
+        let mut lines = vec![format!("{}{} — {}", label, first.message, first.location())];
+        lines.extend(
+            first
+                .detail
+                .iter()
+                .take(MAX_DETAIL_LINES)
+                .map(|d| format!("    {d}")),
+        );
+        return lines;
+    }
+
+    let files = distinct_files(group.findings.iter().copied());
+    let mut lines = vec![format!(
+        "{}×{} in {}: {} — {}",
+        label,
+        group.findings.len(),
+        plural(files.len(), "file"),
+        first.message,
+        first.location()
+    )];
+    lines.extend(
+        first
+            .detail
+            .iter()
+            .take(MAX_DETAIL_LINES)
+            .map(|d| format!("    {d}")),
+    );
+
+    let mut messages = vec![&first.message];
+    for finding in &group.findings[1..] {
+        if messages.len() >= REPRESENTATIVE_MESSAGES {
+            break;
+        }
+        if !messages.contains(&&finding.message) {
+            messages.push(&finding.message);
+            lines.push(format!(
+                "  also: {} — {}",
+                finding.message,
+                finding.location()
+            ));
+        }
+    }
+
+    if files.len() > 1 {
+        let mut listed: Vec<String> = files
+            .iter()
+            .take(MAX_FILES_PER_GROUP)
+            .map(|(file, n)| {
+                if *n == 1 {
+                    file.to_string()
+                } else {
+                    format!("{file} ×{n}")
+                }
+            })
+            .collect();
+        if files.len() > MAX_FILES_PER_GROUP {
+            listed.push(format!("+{} more", files.len() - MAX_FILES_PER_GROUP));
+        }
+        lines.push(format!("  {}", listed.join(", ")));
+    }
+    lines
+}
+
+/// Files with their finding counts, most findings first.
+fn distinct_files<'a>(findings: impl Iterator<Item = &'a Finding>) -> Vec<(&'a str, usize)> {
+    let mut files: Vec<(&str, usize)> = Vec::new();
+    for finding in findings {
+        match files.iter_mut().find(|(f, _)| *f == finding.file) {
+            Some((_, n)) => *n += 1,
+            None => files.push((&finding.file, 1)),
+        }
+    }
+    files.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+    files
+}
+
+fn count(findings: &[Finding], severity: Severity) -> usize {
+    findings.iter().filter(|f| f.severity == severity).count()
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{n} {noun}s")
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_tsc_errors_by_code_then_file() {
+        let mut input = String::new();
+        for i in 0..40 {
+            input.push_str(&format!(
+                "src/file{}.ts({},5): error TS2345: Argument of type 'string' is not assignable to parameter of type 'number'.\n",
+                i % 8,
+                i + 1
+            ));
+        }
+        input.push_str(
+            "src/file3.ts(99,1): error TS2345: Argument of type 'Foo' is not assignable to parameter of type 'Bar'.\n\
+             \x20 Property 'id' is missing in type 'Foo' but required in type 'Bar'.\n\
+             src/other.ts(3,1): error TS2304: Cannot find name 'foo'.\n\
+             \n\
+             Found 42 errors in 9 files.\n",
+        );
+
+        let result = compact_diagnostics(&input, 80).unwrap();
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(lines[0], "42 errors in 9 files (2 codes)");
+        assert_eq!(lines[1], "ERRORS:");
+        assert_eq!(
+            lines[2],
+            "TS2345 ×41 in 8 files: Argument of type 'string' is not assignable to parameter of type 'number'. — src/file0.ts:1:5"
+        );
+        // Identical messages are not repeated.
+        assert_eq!(
+            lines[3],
+            "  also: Argument of type 'Foo' is not assignable to parameter of type 'Bar'. — src/file3.ts:99:1"
+        );
+        assert_eq!(
+            lines[4],
+            "  src/file3.ts ×6, src/file0.ts ×5, src/file1.ts ×5, src/file2.ts ×5, src/file4.ts ×5, +3 more"
+        );
+        assert_eq!(
+            lines[5],
+            "TS2304 Cannot find name 'foo'. — src/other.ts:3:1"
+        );
+        assert_eq!(lines.len(), 6);
+    }
+
+    #[test]
+    fn keeps_tsc_message_continuation_for_first_occurrence() {
+        let input = "\
+src/a.ts:4:7 - error TS2322: Type '{ a: number; }' is not assignable to type 'B'.
+  Object literal may only specify known properties.
+
+4 const x: B = { a: 1 };
+        ~
+
+Found 1 error in src/a.ts:4";
+        let result = compact_diagnostics(input, 80).unwrap();
+        assert_eq!(
+            result,
+            "1 error in 1 file (1 code)\n\
+             ERRORS:\n\
+             TS2322 Type '{ a: number; }' is not assignable to type 'B'. — src/a.ts:4:7\n\
+             \x20   Object literal may only specify known properties."
+        );
+    }
+
+    #[test]
+    fn parses_eslint_stylish() {
+        let input = "\
+/repo/src/a.js
+  1:10  error    'foo' is defined but never used  no-unused-vars
+  2:1   warning  Unexpected console statement     no-console
+  7:3   warning  Unexpected console statement     no-console
+
+/repo/src/b.js
+  3:1  error  'bar' is defined but never used  no-unused-vars
+  9:1  error  Parsing error: Unexpected token
+
+✖ 5 problems (3 errors, 2 warnings)
+  1 error and 0 warnings potentially fixable with the `--fix` option.";
+        let result = compact_diagnostics(input, 80).unwrap();
+        assert_eq!(
+            result,
+            "3 errors, 2 warnings in 2 files (3 codes)\n\
+             ERRORS:\n\
+             no-unused-vars ×2 in 2 files: 'foo' is defined but never used — /repo/src/a.js:1:10\n\
+             \x20 also: 'bar' is defined but never used — /repo/src/b.js:3:1\n\
+             \x20 /repo/src/a.js, /repo/src/b.js\n\
+             Parsing error: Unexpected token — /repo/src/b.js:9:1\n\
+             WARNINGS:\n\
+             no-console ×2 in 1 file: Unexpected console statement — /repo/src/a.js:2:1\n\
+             1 error and 0 warnings potentially fixable with the `--fix` option."
+        );
+    }
+
+    #[test]
+    fn parses_eslint_compact_and_unix() {
+        let input = "\
+/repo/a.js: line 1, col 10, Error - 'foo' is defined but never used (no-unused-vars)
+/repo/b.js:2:1: Unexpected console statement [Warning/no-console]";
+        let report = parse(input);
+        assert_eq!(report.findings.len(), 2);
+        assert_eq!(report.findings[0].code, "no-unused-vars");
+        assert_eq!(
+            report.findings[0].message,
+            "'foo' is defined but never used"
+        );
+        assert_eq!(report.findings[1].code, "no-console");
+        assert_eq!(report.findings[1].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn parses_ruff_concise_and_full() {
+        let concise = "\
+app/a.py:1:8: F401 [*] `os` imported but unused
+app/b.py:1:8: F401 [*] `sys` imported but unused
+app/b.py:4:5: E741 Ambiguous variable name: `l`
+Found 3 errors.
+[*] 2 fixable with the `--fix` option.";
+        let full = "\
+F401 [*] `os` imported but unused
+ --> app/a.py:1:8
+  |
+1 | import os
+  |        ^^
+  |
+help: Remove unused import: `os`
+
+F401 [*] `sys` imported but unused
+ --> app/b.py:1:8
+  |
+1 | import sys
+  |        ^^^
+
+E741 Ambiguous variable name: `l`
+ --> app/b.py:4:5
+  |
+4 |     l = 1
+  |     ^
+
+Found 3 errors.
+[*] 2 fixable with the `--fix` option.";
+        let expected = "\
+3 errors in 2 files (2 codes)
+ERRORS:
+F401 ×2 in 2 files: `os` imported but unused — app/a.py:1:8
+  also: `sys` imported but unused — app/b.py:1:8
+  app/a.py, app/b.py
+E741 Ambiguous variable name: `l` — app/b.py:4:5
+[*] 2 fixable with the `--fix` option.";
+        assert_eq!(compact_diagnostics(concise, 80).unwrap(), expected);
+        assert_eq!(compact_diagnostics(full, 80).unwrap(), expected);
+    }
+
+    #[test]
+    fn budget_keeps_first_occurrence_of_every_code() {
+        let input = "\
+a.py:1:1: F401 `os` imported but unused
+b.py:1:1: F401 `re` imported but unused
+a.py:2:1: E501 Line too long (120 > 88)
+a.py:3:1: E711 Comparison to `None` should be `cond is None`";
+        let result = compact_diagnostics(input, 3).unwrap();
+        assert_eq!(
+            result,
+            "4 errors in 2 files (3 codes)\n\
+             ERRORS:\n\
+             F401 ×2 in 2 files: `os` imported but unused — a.py:1:1\n\
+             \x20 also: `re` imported but unused — b.py:1:1\n\
+             E501 Line too long (120 > 88) — a.py:2:1\n\
+             E711 Comparison to `None` should be `cond is None` — a.py:3:1"
+        );
+    }
+
+    #[test]
+    fn keeps_unparsed_errors_verbatim() {
+        let input = "\
+error TS5083: Cannot read file '/repo/tsconfig.base.json'.
+src/a.ts(3,1): error TS2304: Cannot find name 'foo'.
+Found 2 errors in 1 file.";
+        assert_eq!(
+            compact_diagnostics(input, 80).unwrap(),
+            "1 error in 1 file (1 code)\n\
+             ERRORS:\n\
+             TS2304 Cannot find name 'foo'. — src/a.ts:3:1\n\
+             OTHER:\n\
+             error TS5083: Cannot read file '/repo/tsconfig.base.json'."
+        );
+
+        let make = "\
+cc -c src/main.c
+src/main.c:7:5: error: 'x' undeclared (first use in this function)
+src/util.py:3:1: E302 expected 2 blank lines, found 1
+make: *** [Makefile:4: main.o] Error 1
+src/errors.c";
+        let result = compact_diagnostics(make, 80).unwrap();
+        assert!(result.ends_with(
+            "OTHER:\n\
+             src/main.c:7:5: error: 'x' undeclared (first use in this function)\n\
+             make: *** [Makefile:4: main.o] Error 1"
+        ));
+    }
+
+    #[test]
+    fn unrecognized_output() {
+        assert!(compact_diagnostics("Build succeeded\n", 80).is_none());
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
 
 use crate::config::schema::DockerOptimizerConfig;
+use crate::optimizers::json::{JsonLimits, summarize_json};
 use crate::optimizers::{CommandContext, OptimizedOutput, Optimizer};
 use crate::utils::token_counter::estimate_tokens;
 
@@ -69,17 +70,6 @@ fn classify(lower: &str) -> Option<DockerCommand> {
     None
 }
 
-// ---------------------------------------------------------------------------
-// Flag helpers
-// ---------------------------------------------------------------------------
-
-/// Check if the command already has a format flag.
-fn has_format_flag(lower: &str) -> bool {
-    lower
-        .split_whitespace()
-        .any(|w| w == "--format" || w.starts_with("--format=") || w == "-f")
-}
-
 // ---------------------------------------------------------------------------
 // Optimizer
 // ---------------------------------------------------------------------------
@@ -90,6 +80,8 @@ pub struct DockerOptimizer {
     logs_max_tail: usize,
     logs_max_errors: usize,
     inspect_max_lines: usize,
+    /// JSON summarization for `docker inspect`; `None` keeps the line cap only.
+    inspect_json: Option<JsonLimits>,
     compose_max_rows: usize,
     resource_max_rows: usize,
 }
@@ -113,10 +105,19 @@ impl DockerOptimizer {
             logs_max_tail: cfg.logs_max_tail,
             logs_max_errors: cfg.logs_max_errors,
             inspect_max_lines: cfg.inspect_max_lines,
+            inspect_json: Some(JsonLimits::default()),
             compose_max_rows: cfg.compose_max_rows,
             resource_max_rows: cfg.resource_max_rows,
         }
     }
+
+    /// Use `limits` (from `[optimizers.json]`) to summarize `docker inspect`.
+    pub fn with_json_limits(self, limits: Option<JsonLimits>) -> Self {
+        Self {
+            inspect_json: limits,
+            ..self
+        }
+    }
 }
 
 impl Optimizer for DockerOptimizer {
@@ -132,7 +133,7 @@ impl Optimizer for DockerOptimizer {
 
         match cmd {
             // Skip if user already has a custom format
-            DockerCommand::Ps | DockerCommand::Images => !has_format_flag(&lower),
+            DockerCommand::Ps | DockerCommand::Images => !ctx.explicit.format,
             _ => true,
         }
     }
@@ -142,13 +143,20 @@ impl Optimizer for DockerOptimizer {
         let cmd = classify(&lower).unwrap_or(DockerCommand::Ps);
 
         let optimized = match cmd {
-            DockerCommand::Ps => compact_docker_ps(raw_output, self.ps_max_rows),
-            DockerCommand::Images => compact_docker_images(raw_output, self.images_max_rows),
-            DockerCommand::Logs => {
-                compact_docker_logs(raw_output, self.logs_max_tail, self.logs_max_errors)
+            DockerCommand::Ps => compact_docker_ps(raw_output, ctx.line_cap(self.ps_max_rows)),
+            DockerCommand::Images => {
+                compact_docker_images(raw_output, ctx.line_cap(self.images_max_rows))
             }
+            DockerCommand::Logs => compact_docker_logs(
+                raw_output,
+                ctx.line_cap(self.logs_max_tail),
+                self.logs_max_errors,
+            ),
             DockerCommand::ComposePs => compact_compose_ps(raw_output, self.compose_max_rows),
-            DockerCommand::Inspect => compact_docker_inspect(raw_output, self.inspect_max_lines),
+            DockerCommand::Inspect => self
+                .inspect_json
+                .and_then(|limits| summarize_json(raw_output, &limits))
+                .unwrap_or_else(|| compact_docker_inspect(raw_output, self.inspect_max_lines)),
             DockerCommand::Build => compact_docker_build(raw_output),
             DockerCommand::PullPush => compact_docker_pull_push(raw_output),
             DockerCommand::ListResource => {
@@ -298,7 +306,7 @@ fn compact_docker_images(raw_output: &str, max_images: usize) -> String {
 // ---------------------------------------------------------------------------
 
 /// Compact `docker logs` output: keep errors/warnings + last N lines.
-fn compact_docker_logs(raw_output: &str, max_tail: usize, max_errors: usize) -> String {
+pub(crate) fn compact_docker_logs(raw_output: &str, max_tail: usize, max_errors: usize) -> String {
     let trimmed = raw_output.trim();
     if trimmed.is_empty() {
         return "No logs".to_string();
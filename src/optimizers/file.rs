@@ -1,7 +1,10 @@
 use anyhow::Result;
 
 use crate::config::schema::FileOptimizerConfig;
+use crate::matching::{self, shell};
+use crate::optimizers::outline;
 use crate::optimizers::{CommandContext, OptimizedOutput, Optimizer};
+use crate::utils::process::OutputStream;
 use crate::utils::token_counter::estimate_tokens;
 
 // ---------------------------------------------------------------------------
@@ -59,9 +62,48 @@ fn has_compact_flag(lower: &str, cmd: FileCommand) -> bool {
     }
 }
 
-/// Check if any whitespace-delimited token matches a flag exactly.
+/// The file shown whole by a plain `cat FILE` (`type`, `Get-Content -Path`).
+///
+/// `None` for ranges (`head`, `tail`, `Get-Content -TotalCount`), other
+/// flags (`cat -n`), several files, and output piped through another
+/// command (`cat f | sed -n 5,9p`), none of which is the file as written.
+fn whole_file_path(ctx: &CommandContext) -> Option<String> {
+    let words = shell::split_words(ctx.core);
+    let (program, args) = words.split_first()?;
+    if !matches!(
+        matching::program_name(program).as_str(),
+        "cat" | "type" | "get-content" | "gc"
+    ) {
+        return None;
+    }
+    let operands: Vec<&String> = args
+        .iter()
+        .filter(|a| !a.eq_ignore_ascii_case("-path") && !a.eq_ignore_ascii_case("-literalpath"))
+        .collect();
+    let [path] = operands.as_slice() else {
+        return None;
+    };
+    if path.starts_with('-') || is_piped(ctx.original) {
+        return None;
+    }
+    Some(path.to_string())
+}
+
+/// Whether the command line contains a pipeline.
+fn is_piped(command: &str) -> bool {
+    shell::parse(command).is_ok_and(|script| {
+        script
+            .items
+            .iter()
+            .any(|item| item.pipeline.commands.len() > 1)
+    })
+}
+
+/// Check if any shell word matches a flag exactly.
 fn has_any_flag(text: &str, flags: &[&str]) -> bool {
-    text.split_whitespace().any(|word| flags.contains(&word))
+    shell::split_words(text)
+        .iter()
+        .any(|word| flags.contains(&word.as_str()))
 }
 
 // ---------------------------------------------------------------------------
@@ -75,6 +117,8 @@ pub struct FileOptimizer {
     cat_max_lines: usize,
     cat_head_lines: usize,
     cat_tail_lines: usize,
+    cat_outline: bool,
+    cat_fold_lines: usize,
     wc_max_lines: usize,
     tree_max_lines: usize,
     tree_noise_dirs: Vec<String>,
@@ -100,6 +144,8 @@ impl FileOptimizer {
             cat_max_lines: cfg.cat_max_lines,
             cat_head_lines: cfg.cat_head_lines,
             cat_tail_lines: cfg.cat_tail_lines,
+            cat_outline: cfg.cat_outline,
+            cat_fold_lines: cfg.cat_fold_lines,
             wc_max_lines: cfg.wc_max_lines,
             tree_max_lines: cfg.tree_max_lines,
             tree_noise_dirs: cfg.tree_noise_dirs.clone(),
@@ -107,6 +153,32 @@ impl FileOptimizer {
     }
 }
 
+impl FileOptimizer {
+    /// Outline a whole file shown with `cat`, or keep the head and tail.
+    fn compact_file_content(&self, ctx: &CommandContext, raw_output: &str) -> String {
+        let outline = whole_file_path(ctx)
+            .filter(|_| self.cat_outline)
+            .and_then(|path| {
+                // Outlines number lines, so they need the file as written
+                // rather than preprocessed text with lines dropped.
+                let content = if ctx.output.is_empty() {
+                    raw_output.to_string()
+                } else {
+                    ctx.stream_text(OutputStream::Stdout)
+                };
+                outline::summarize_file(&path, &content, self.cat_max_lines, self.cat_fold_lines)
+            });
+        outline.unwrap_or_else(|| {
+            compact_cat(
+                raw_output,
+                ctx.line_cap(self.cat_max_lines),
+                self.cat_head_lines,
+                self.cat_tail_lines,
+            )
+        })
+    }
+}
+
 impl Optimizer for FileOptimizer {
     fn name(&self) -> &'static str {
         "file"
@@ -124,23 +196,24 @@ impl Optimizer for FileOptimizer {
         }
     }
 
-    fn optimize_output(&self, _ctx: &CommandContext, raw_output: &str) -> Result<OptimizedOutput> {
-        let lower = _ctx.core.to_ascii_lowercase();
+    fn optimize_output(&self, ctx: &CommandContext, raw_output: &str) -> Result<OptimizedOutput> {
+        let lower = ctx.core.to_ascii_lowercase();
         let cmd = classify(&lower).unwrap_or(FileCommand::Ls);
 
         let optimized = match cmd {
-            FileCommand::Ls => compact_ls(raw_output, self.ls_max_entries, self.ls_max_items),
-            FileCommand::Find => compact_find(raw_output, self.find_max_results),
-            FileCommand::CatHeadTail => compact_cat(
+            FileCommand::Ls => compact_ls(
                 raw_output,
-                self.cat_max_lines,
-                self.cat_head_lines,
-                self.cat_tail_lines,
+                ctx.line_cap(self.ls_max_entries),
+                ctx.line_cap(self.ls_max_items),
+            ),
+            FileCommand::Find => compact_find(raw_output, ctx.line_cap(self.find_max_results)),
+            FileCommand::CatHeadTail => self.compact_file_content(ctx, raw_output),
+            FileCommand::Wc => compact_wc(raw_output, ctx.line_cap(self.wc_max_lines)),
+            FileCommand::Tree => compact_tree(
+                raw_output,
+                ctx.line_cap(self.tree_max_lines),
+                &self.tree_noise_dirs,
             ),
-            FileCommand::Wc => compact_wc(raw_output, self.wc_max_lines),
-            FileCommand::Tree => {
-                compact_tree(raw_output, self.tree_max_lines, &self.tree_noise_dirs)
-            }
         };
 
         Ok(OptimizedOutput {
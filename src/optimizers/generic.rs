@@ -1,7 +1,11 @@
+use std::collections::HashSet;
+
 use anyhow::Result;
 
 use crate::config::schema::GenericOptimizerConfig;
 use crate::optimizers::{CommandContext, OptimizedOutput, Optimizer};
+use crate::preprocessing::noise::strip_ansi;
+use crate::utils::process::OutputStream;
 use crate::utils::token_counter::estimate_tokens;
 
 // ---------------------------------------------------------------------------
@@ -15,7 +19,8 @@ use crate::utils::token_counter::estimate_tokens;
 /// - Collapse 3+ consecutive blank lines to max 2
 /// - Trim trailing whitespace from each line
 /// - Remove trailing blank lines from overall output
-/// - Cap total line count with head/tail preservation
+/// - Cap total line count with head/tail preservation, keeping stderr lines
+///   (warnings, errors) from the omitted middle when stdout is also present
 ///
 /// It is registered last in the optimizer registry so that it only fires
 /// when no specialized optimizer (git, file, build, docker) matches.
@@ -66,7 +71,7 @@ impl Optimizer for GenericOptimizer {
         true
     }
 
-    fn optimize_output(&self, _ctx: &CommandContext, raw_output: &str) -> Result<OptimizedOutput> {
+    fn optimize_output(&self, ctx: &CommandContext, raw_output: &str) -> Result<OptimizedOutput> {
         // Skip tiny outputs — not worth optimizing
         if raw_output.len() < self.min_size {
             return Ok(OptimizedOutput {
@@ -76,7 +81,23 @@ impl Optimizer for GenericOptimizer {
             });
         }
 
-        let optimized = cleanup_whitespace(raw_output, self.max_lines);
+        // When the output mixes both streams, stderr lines are the ones
+        // most likely to matter — don't let the line cap drop them.
+        let stdout = ctx.stream_text(OutputStream::Stdout);
+        let stderr = ctx.stream_text(OutputStream::Stderr);
+        let stderr_lines: HashSet<String> = if stdout.trim().is_empty() {
+            HashSet::new()
+        } else {
+            stderr
+                .lines()
+                .map(|l| strip_ansi(l).trim().to_string())
+                .filter(|l| !l.is_empty())
+                .collect()
+        };
+        let optimized =
+            cleanup_whitespace_keeping(raw_output, ctx.line_cap(self.max_lines), |line| {
+                stderr_lines.contains(line.trim())
+            });
 
         Ok(OptimizedOutput {
             optimized_tokens: estimate_tokens(&optimized),
@@ -90,13 +111,26 @@ impl Optimizer for GenericOptimizer {
 // Whitespace cleanup
 // ---------------------------------------------------------------------------
 
+/// [`cleanup_whitespace_keeping`] that keeps no lines from the omitted
+/// middle.
+#[cfg(test)]
+pub fn cleanup_whitespace(text: &str, max_lines: usize) -> String {
+    cleanup_whitespace_keeping(text, max_lines, |_| false)
+}
+
 /// Apply universal whitespace cleanup to output text.
 ///
 /// 1. Trim trailing whitespace from each line
 /// 2. Collapse 3+ consecutive blank lines to max 2
 /// 3. Remove trailing blank lines
-/// 4. Cap total lines with head/tail preservation
-pub fn cleanup_whitespace(text: &str, max_lines: usize) -> String {
+/// 4. Cap total lines with head/tail preservation; lines in the omitted
+///    middle for which `keep` returns true are retained after the gap
+///    marker (up to a quarter of `max_lines`)
+pub fn cleanup_whitespace_keeping(
+    text: &str,
+    max_lines: usize,
+    keep: impl Fn(&str) -> bool,
+) -> String {
     let mut result = Vec::new();
     let mut consecutive_blanks = 0u32;
     let max_consecutive_blanks = 2u32;
@@ -126,13 +160,27 @@ pub fn cleanup_whitespace(text: &str, max_lines: usize) -> String {
         let head_count = max_lines * 2 / 3; // ~67% head
         let tail_count = max_lines - head_count - 1; // rest for tail + gap line
 
-        let mut capped = Vec::with_capacity(max_lines);
+        let kept: Vec<&String> = result[head_count..total - tail_count]
+            .iter()
+            .filter(|line| keep(line))
+            .take(max_lines / 4)
+            .collect();
+
+        let mut capped = Vec::with_capacity(max_lines + kept.len());
         capped.extend_from_slice(&result[..head_count]);
-        capped.push(format!(
-            "\n... ({} lines omitted, {} total) ...\n",
-            total - head_count - tail_count,
-            total
-        ));
+        let omitted = total - head_count - tail_count - kept.len();
+        if kept.is_empty() {
+            capped.push(format!(
+                "\n... ({omitted} lines omitted, {total} total) ..."
+            ));
+        } else {
+            capped.push(format!(
+                "\n... ({omitted} lines omitted, {total} total; stderr lines kept) ..."
+            ));
+            capped.extend(kept.into_iter().cloned());
+            capped.push("...".to_string());
+        }
+        capped.push(String::new());
         capped.extend_from_slice(&result[total - tail_count..]);
         return capped.join("\n");
     }
@@ -198,6 +246,34 @@ mod tests {
         assert!(result.contains("line 299")); // tail preserved
     }
 
+    #[test]
+    fn keeps_stderr_lines_from_omitted_middle() {
+        use crate::utils::process::OutputChunk;
+
+        let mut lines: Vec<String> = (0..300).map(|i| format!("line {i}")).collect();
+        lines[150] = "warning: deprecated flag".to_string();
+        let input = lines.join("\n");
+        let chunks = [
+            OutputChunk {
+                stream: OutputStream::Stdout,
+                text: "line 0\n".to_string(),
+            },
+            OutputChunk {
+                stream: OutputStream::Stderr,
+                text: "warning: deprecated flag\n".to_string(),
+            },
+        ];
+        let opt = GenericOptimizer::new();
+        let ctx = CommandContext::new("some-tool").with_output(&chunks);
+        let result = opt.optimize_output(&ctx, &input).unwrap().output;
+        assert!(result.contains("stderr lines kept"));
+        assert!(result.contains("warning: deprecated flag"));
+
+        // Without stream information nothing is kept.
+        let plain = cleanup_whitespace(&input, 200);
+        assert!(!plain.contains("warning: deprecated flag"));
+    }
+
     #[test]
     fn small_output_passthrough() {
         let opt = GenericOptimizer::new();
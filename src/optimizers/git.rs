@@ -1,6 +1,10 @@
+use std::sync::LazyLock;
+
 use anyhow::{Result, anyhow};
+use regex::Regex;
 
 use crate::config::schema::GitOptimizerConfig;
+use crate::matching::shell;
 use crate::optimizers::{CommandContext, OptimizedOutput, Optimizer};
 use crate::utils::process::run_shell_command;
 use crate::utils::token_counter::estimate_tokens;
@@ -61,27 +65,17 @@ fn classify(lower: &str) -> Option<GitSubcommand> {
 // Argument inspection helpers
 // ---------------------------------------------------------------------------
 
-/// Check if any whitespace-delimited token matches a flag exactly, or starts
-/// with `flag=` for long options like `--pretty=format:...`.
+/// Check if any shell word matches a flag exactly, or starts with `flag=`
+/// for long options like `--pretty=format:...`. Quoted arguments such as
+/// `-m "fix --amend"` are single words and never match.
 fn has_flag(text: &str, flags: &[&str]) -> bool {
-    text.split_whitespace().any(|word| {
+    shell::split_words(text).iter().any(|word| {
         flags
             .iter()
             .any(|&f| word == f || (f.starts_with("--") && word.starts_with(&format!("{f}="))))
     })
 }
 
-/// Check if the command contains a numeric limit argument like `-10` or `-n`.
-fn has_numeric_limit(text: &str) -> bool {
-    text.split_whitespace().any(|arg| {
-        arg == "-n"
-            || (arg.starts_with('-')
-                && arg.len() > 1
-                && arg.as_bytes()[1].is_ascii_digit()
-                && arg[1..].bytes().all(|b| b.is_ascii_digit()))
-    })
-}
-
 /// Extract the stash sub-subcommand (list, show, pop, apply, drop, push).
 fn stash_subcommand(lower: &str) -> &str {
     lower
@@ -150,7 +144,7 @@ impl Optimizer for GitOptimizer {
             // Skip destructive / rename / copy branch operations.
             GitSubcommand::Branch => !has_flag(&lower, &["-d", "-D", "-m", "-M", "-c", "-C"]),
             // Skip if user specified a custom display format.
-            GitSubcommand::Show => !has_flag(&lower, &["--stat", "--format", "--pretty"]),
+            GitSubcommand::Show => !ctx.explicit.format && !has_flag(&lower, &["--stat"]),
             // Skip worktree action commands (add, remove, prune, etc.).
             GitSubcommand::Worktree => !has_flag(
                 &lower,
@@ -166,10 +160,9 @@ impl Optimizer for GitOptimizer {
             classify(&lower).ok_or_else(|| anyhow!("git command not supported by optimizer"))?;
 
         let optimized = match subcommand {
-            GitSubcommand::Status => optimize_status(ctx)?,
+            GitSubcommand::Status => optimize_status(raw_output)?,
             GitSubcommand::Log => optimize_log(
                 ctx,
-                &lower,
                 raw_output,
                 self.log_max_entries,
                 self.log_default_limit,
@@ -207,92 +200,357 @@ impl Optimizer for GitOptimizer {
 }
 
 // ---------------------------------------------------------------------------
-// Status — structured porcelain display
+// Status — parse the captured human-readable output
 // ---------------------------------------------------------------------------
 
-/// Run `git status --porcelain -b` and format into a structured display.
-fn optimize_status(ctx: &CommandContext) -> Result<String> {
-    let output =
-        optimize_with_substitution(ctx.original, "git status", "git status --porcelain -b")?;
-    Ok(format_porcelain_status(&output))
+/// Section of human `git status` output currently being parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusSection {
+    Staged,
+    Unstaged,
+    Unmerged,
+    Untracked,
+    /// Ignored files or any other section whose entries are dropped.
+    Other,
 }
 
-/// Parse porcelain output into a structured, token-efficient display.
+/// One repository status block parsed from human `git status` output.
 ///
-/// Input format:
-/// ```text
-/// ## main...origin/main [ahead 1]
-///  M src/main.rs
-/// A  new_file.rs
-/// ?? untracked.txt
-/// ```
-fn format_porcelain_status(porcelain: &str) -> String {
-    let lines: Vec<&str> = porcelain.lines().collect();
-    if lines.is_empty() {
-        return "clean".to_string();
-    }
+/// A command chain such as `git status && cd sub && git status` produces
+/// several blocks; each is parsed and rendered independently.
+#[derive(Debug, Default)]
+struct StatusReport {
+    /// Branch name, or `HEAD (detached at <rev>)`.
+    branch: Option<String>,
+    /// Upstream branch and ahead/behind/gone summary.
+    upstream: Option<String>,
+    tracking: Vec<String>,
+    /// In-progress operation, e.g. `rebase in progress (onto abc123)`.
+    state: Option<String>,
+    staged: StatusFiles,
+    unstaged: StatusFiles,
+    conflicts: StatusFiles,
+    untracked: StatusFiles,
+}
 
-    let mut output = String::new();
+/// Entries of one status section.
+#[derive(Debug, Default)]
+struct StatusFiles {
+    entries: Vec<String>,
+    /// Entries hidden behind a preprocessing dedup marker
+    /// (`[... N more similar line(s) matching "..."]`).
+    collapsed: usize,
+}
 
-    // Parse branch info from ## line
-    if let Some(branch_line) = lines.first()
-        && let Some(branch) = branch_line.strip_prefix("## ")
-    {
-        output.push_str(&format!("branch: {branch}\n"));
+impl StatusFiles {
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.collapsed == 0
     }
 
-    let mut staged: Vec<&str> = Vec::new();
-    let mut modified: Vec<&str> = Vec::new();
-    let mut untracked: Vec<&str> = Vec::new();
-    let mut conflicts = 0usize;
+    fn len(&self) -> usize {
+        self.entries.len() + self.collapsed
+    }
+}
 
-    for line in lines.iter().skip(1) {
-        if line.len() < 3 {
+/// Count of lines collapsed by the dedup stage, e.g.
+/// `[... 58 more similar line(s) matching "f1"...]`.
+static DEDUP_MARKER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[\.\.\. (\d+) more similar line").unwrap());
+
+/// Change kinds git prints before a path (`\tmodified:   src/main.rs`).
+const STATUS_KINDS: &[&str] = &[
+    "new file",
+    "modified",
+    "deleted",
+    "renamed",
+    "copied",
+    "typechange",
+    "both modified",
+    "both added",
+    "both deleted",
+    "added by us",
+    "added by them",
+    "deleted by us",
+    "deleted by them",
+    "unmerged",
+];
+
+/// Compact the human `git status` output that was already captured.
+///
+/// The command is never re-executed: a second run doubles latency, can
+/// observe a different working tree, and cannot be substituted reliably
+/// into command chains. Returns an error when the output does not look like
+/// `git status` (e.g. `fatal: not a git repository`, or a localized git),
+/// so the registry falls through to the next optimizer.
+fn optimize_status(raw_output: &str) -> Result<String> {
+    let reports = parse_human_status(raw_output);
+    if reports.is_empty() {
+        return Err(anyhow!("unrecognized git status output"));
+    }
+    Ok(reports
+        .iter()
+        .map(format_status_report)
+        .collect::<Vec<_>>()
+        .join("\n\n"))
+}
+
+/// Parse human `git status` output into one report per repository block.
+fn parse_human_status(raw_output: &str) -> Vec<StatusReport> {
+    let mut reports: Vec<StatusReport> = Vec::new();
+    let mut current: Option<StatusReport> = None;
+    let mut section: Option<StatusSection> = None;
+
+    for line in raw_output.lines() {
+        let trimmed = line.trim();
+
+        // Block headers — a new header starts a new report.
+        let branch = if let Some(name) = trimmed.strip_prefix("On branch ") {
+            Some(name.to_string())
+        } else if trimmed.starts_with("HEAD detached ") {
+            Some(format!("HEAD ({})", trimmed.trim_start_matches("HEAD ")))
+        } else if trimmed == "Not currently on any branch." {
+            Some("HEAD (detached)".to_string())
+        } else {
+            None
+        };
+        if let Some(branch) = branch {
+            if let Some(done) = current.take() {
+                reports.push(done);
+            }
+            current = Some(StatusReport {
+                branch: Some(branch),
+                ..StatusReport::default()
+            });
+            section = None;
             continue;
         }
-        let status = line.as_bytes();
-        let file = &line[3..];
-
-        // First byte = index (staging area)
-        match status[0] {
-            b'M' | b'A' | b'D' | b'R' | b'C' => staged.push(file),
-            b'U' => conflicts += 1,
-            _ => {}
+
+        // Section headers can also open a block when the branch line is
+        // missing (e.g. output trimmed by preprocessing).
+        let new_section = match trimmed {
+            "Changes to be committed:" => Some(StatusSection::Staged),
+            "Changes not staged for commit:" => Some(StatusSection::Unstaged),
+            "Unmerged paths:" => Some(StatusSection::Unmerged),
+            "Untracked files:" => Some(StatusSection::Untracked),
+            "Ignored files:" => Some(StatusSection::Other),
+            _ => None,
+        };
+        if let Some(new_section) = new_section {
+            section = Some(new_section);
+            current.get_or_insert_with(StatusReport::default);
+            continue;
         }
 
-        // Second byte = worktree (working directory)
-        match status[1] {
-            b'M' | b'D' => modified.push(file),
-            _ => {}
+        let Some(report) = current.as_mut() else {
+            // During a rebase git prints the operation before any branch
+            // line; a recognized state line opens the block.
+            let mut report = StatusReport::default();
+            parse_status_info_line(&mut report, trimmed);
+            if report.state.is_some() {
+                current = Some(report);
+            }
+            continue;
+        };
+
+        if trimmed.is_empty() {
+            section = None;
+            continue;
+        }
+        // Hints such as `(use "git add <file>..." to update ...)`.
+        if trimmed.starts_with('(') {
+            continue;
         }
 
-        if status[0] == b'?' && status[1] == b'?' {
-            untracked.push(file);
+        if let Some(sec) = section {
+            // Section entries are tab-indented.
+            if line.starts_with('\t') {
+                push_status_entry(report, sec, trimmed);
+                continue;
+            }
+            if let Some(caps) = DEDUP_MARKER_RE.captures(trimmed) {
+                let hidden = caps[1].parse().unwrap_or(0);
+                if let Some(files) = section_files(report, sec) {
+                    files.collapsed += hidden;
+                }
+                continue;
+            }
         }
+
+        parse_status_info_line(report, trimmed);
     }
 
-    if staged.is_empty() && modified.is_empty() && untracked.is_empty() && conflicts == 0 {
-        output.push_str("clean");
-        return output.trim_end().to_string();
+    if let Some(done) = current {
+        reports.push(done);
+    }
+    reports
+}
+
+/// Record a tab-indented file entry in its section.
+fn push_status_entry(report: &mut StatusReport, section: StatusSection, entry: &str) {
+    let (kind, path) = match entry.split_once(':') {
+        Some((kind, path)) if STATUS_KINDS.contains(&kind) => (Some(kind), path.trim()),
+        _ => (None, entry),
+    };
+
+    let entry = match (section, kind) {
+        (StatusSection::Unmerged, Some(kind)) => format!("{path} ({kind})"),
+        (StatusSection::Staged | StatusSection::Unstaged, _) => describe_entry(kind, path),
+        _ => path.to_string(),
+    };
+    if let Some(files) = section_files(report, section) {
+        files.entries.push(entry);
     }
+}
 
-    if !staged.is_empty() {
-        output.push_str(&format!("staged ({}): ", staged.len()));
-        append_file_list(&mut output, &staged, 5);
-        output.push('\n');
+/// The entry list for a section, or `None` for sections that are dropped.
+fn section_files(report: &mut StatusReport, section: StatusSection) -> Option<&mut StatusFiles> {
+    match section {
+        StatusSection::Staged => Some(&mut report.staged),
+        StatusSection::Unstaged => Some(&mut report.unstaged),
+        StatusSection::Unmerged => Some(&mut report.conflicts),
+        StatusSection::Untracked => Some(&mut report.untracked),
+        StatusSection::Other => None,
     }
-    if !modified.is_empty() {
-        output.push_str(&format!("modified ({}): ", modified.len()));
-        append_file_list(&mut output, &modified, 5);
-        output.push('\n');
+}
+
+/// Annotate a path with its change kind unless it is a plain modification.
+fn describe_entry(kind: Option<&str>, path: &str) -> String {
+    match kind {
+        Some("new file") => format!("{path} (new)"),
+        Some("deleted") => format!("{path} (deleted)"),
+        Some("typechange") => format!("{path} (typechange)"),
+        // Renames and copies already read `old -> new`.
+        _ => path.to_string(),
+    }
+}
+
+/// Parse the free-form lines between the branch header and the sections:
+/// upstream tracking and in-progress operations.
+fn parse_status_info_line(report: &mut StatusReport, line: &str) {
+    if let Some(rest) = line.strip_prefix("Your branch is ") {
+        let upstream = quoted(rest);
+        if rest.starts_with("up to date") {
+            report.upstream = upstream;
+        } else if rest.starts_with("ahead") {
+            report.upstream = upstream;
+            if let Some(n) = count_after(rest, " by ") {
+                report.tracking.push(format!("ahead {n}"));
+            }
+        } else if rest.starts_with("behind") {
+            report.upstream = upstream;
+            if let Some(n) = count_after(rest, " by ") {
+                report.tracking.push(format!("behind {n}"));
+            }
+        } else if rest.starts_with("based on") && rest.contains("upstream is gone") {
+            report.upstream = upstream;
+            report.tracking.push("gone".to_string());
+        }
+    } else if let Some(rest) = line.strip_prefix("Your branch and ") {
+        // Your branch and 'origin/main' have diverged,
+        report.upstream = quoted(rest);
+    } else if let Some(rest) = line.strip_prefix("and have ") {
+        // and have 2 and 3 different commits each, respectively.
+        let mut counts = rest
+            .split_whitespace()
+            .filter_map(|w| w.parse::<usize>().ok());
+        if let (Some(ahead), Some(behind)) = (counts.next(), counts.next()) {
+            report.tracking.push(format!("ahead {ahead}"));
+            report.tracking.push(format!("behind {behind}"));
+        }
+    } else if line.starts_with("interactive rebase in progress")
+        || line.starts_with("rebase in progress")
+    {
+        let onto = line
+            .split_once("; onto ")
+            .map(|(_, rev)| format!(" (onto {rev})"))
+            .unwrap_or_default();
+        report.state = Some(format!("rebase in progress{onto}"));
+    } else if line.starts_with("You are currently rebasing") {
+        report
+            .state
+            .get_or_insert_with(|| "rebase in progress".to_string());
+    } else if line == "You have unmerged paths."
+        || line.starts_with("All conflicts fixed but you are still merging")
+    {
+        report.state = Some("merge in progress".to_string());
+    } else if line.starts_with("You are currently cherry-picking") {
+        report.state = Some("cherry-pick in progress".to_string());
+    } else if line.starts_with("You are currently reverting") {
+        report.state = Some("revert in progress".to_string());
+    } else if line.starts_with("You are currently bisecting") {
+        report.state = Some("bisect in progress".to_string());
+    } else if line.starts_with("You are in the middle of an am session") {
+        report.state = Some("am in progress".to_string());
     }
-    if !untracked.is_empty() {
-        output.push_str(&format!("untracked ({}): ", untracked.len()));
-        append_file_list(&mut output, &untracked, 3);
+}
+
+/// Extract the first single-quoted name: `... of 'origin/main' by 2` → `origin/main`.
+fn quoted(text: &str) -> Option<String> {
+    let start = text.find('\'')? + 1;
+    let len = text[start..].find('\'')?;
+    Some(text[start..start + len].to_string())
+}
+
+/// Parse the number following `marker`: `... by 2 commits.` → `2`.
+fn count_after(text: &str, marker: &str) -> Option<usize> {
+    let (_, rest) = text.rsplit_once(marker)?;
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+/// Render a parsed status block into a structured, token-efficient display.
+///
+/// ```text
+/// branch: main...origin/main [ahead 1]
+/// staged (2): src/lib.rs, src/new.rs (new)
+/// modified (1): README.md
+/// untracked (1): notes.txt
+/// ```
+fn format_status_report(report: &StatusReport) -> String {
+    let mut output = String::new();
+
+    if let Some(branch) = &report.branch {
+        output.push_str(&format!("branch: {branch}"));
+        if let Some(upstream) = &report.upstream {
+            output.push_str(&format!("...{upstream}"));
+        }
+        if !report.tracking.is_empty() {
+            output.push_str(&format!(" [{}]", report.tracking.join(", ")));
+        }
         output.push('\n');
     }
-    if conflicts > 0 {
-        output.push_str(&format!("conflicts: {conflicts}\n"));
+    if let Some(state) = &report.state {
+        output.push_str(&format!("state: {state}\n"));
+    }
+
+    if report.staged.is_empty()
+        && report.unstaged.is_empty()
+        && report.untracked.is_empty()
+        && report.conflicts.is_empty()
+    {
+        output.push_str("clean");
+        return output.trim_end().to_string();
+    }
+
+    let sections = [
+        ("conflicts", &report.conflicts, 10),
+        ("staged", &report.staged, 5),
+        ("modified", &report.unstaged, 5),
+        ("untracked", &report.untracked, 3),
+    ];
+    for (label, files, max) in sections {
+        if files.is_empty() {
+            continue;
+        }
+        let shown: Vec<&str> = files.entries.iter().take(max).map(String::as_str).collect();
+        output.push_str(&format!("{label} ({}): ", files.len()));
+        append_file_list(&mut output, &shown, max);
+        // Entries past `max` and those collapsed by dedup share one counter.
+        let hidden = files.len() - shown.len();
+        if hidden > 0 {
+            let sep = if shown.is_empty() { "" } else { ", " };
+            output.push_str(&format!("{sep}+{hidden} more"));
+        }
+        output.push('\n');
     }
 
     output.trim_end().to_string()
@@ -317,25 +575,25 @@ fn append_file_list(output: &mut String, files: &[&str], max: usize) {
 
 /// Apply smart defaults for `git log`, respecting user-provided flags.
 ///
-/// - Adds `--oneline` only if the user hasn't specified `--oneline`, `--pretty`,
-///   or `--format`.
-/// - Adds `-n 20` only if the user hasn't specified a numeric limit.
+/// - Adds `--oneline` only if the user hasn't asked for a format
+///   (`--oneline`, `--pretty`, `--format`).
+/// - Adds `-n 20` only if the command has no explicit limit (`-n 5`, `-10`,
+///   `| head`).
 fn optimize_log(
     ctx: &CommandContext,
-    core: &str,
     raw_text: &str,
     log_max_entries: usize,
     log_default_limit: usize,
     log_line_max_chars: usize,
 ) -> Result<String> {
-    let has_format = has_flag(core, &["--oneline", "--pretty", "--format"]);
-    let has_limit = has_numeric_limit(core) || has_flag(core, &["-n"]);
+    let has_format = ctx.explicit.format;
+    let has_limit = ctx.explicit.lines.is_some();
 
     if has_format && has_limit {
         // User already specified both — just filter the raw output for length.
         return Ok(filter_log_output(
             raw_text,
-            log_max_entries,
+            ctx.line_cap(log_max_entries),
             log_line_max_chars,
         ));
     }
@@ -829,7 +1087,7 @@ mod tests {
         assert!(opt.can_handle(&CommandContext::new("git show abc1234")));
     }
 
-    // has_flag / has_numeric_limit helpers --------------------------------
+    // has_flag helper ---------------------------------------------------
 
     #[test]
     fn has_flag_detects_exact_and_prefix() {
@@ -840,52 +1098,196 @@ mod tests {
         assert!(!has_flag("git diff", &["--stat"]));
     }
 
+    // optimize_status --------------------------------------------------
+
+    #[test]
+    fn status_clean_tree() {
+        let raw = "\
+On branch main
+Your branch is up to date with 'origin/main'.
+
+nothing to commit, working tree clean
+";
+        assert_eq!(
+            optimize_status(raw).unwrap(),
+            "branch: main...origin/main\nclean"
+        );
+    }
+
+    #[test]
+    fn status_rejects_non_status_output() {
+        assert!(optimize_status("").is_err());
+        assert!(
+            optimize_status("fatal: not a git repository (or any of the parent directories): .git")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn status_mixed_changes() {
+        let raw = "\
+On branch main
+Your branch is ahead of 'origin/main' by 2 commits.
+  (use \"git push\" to publish your local commits)
+
+Changes to be committed:
+  (use \"git restore --staged <file>...\" to unstage)
+\tmodified:   staged.rs
+\tnew file:   added.rs
+\trenamed:    old.rs -> new.rs
+
+Changes not staged for commit:
+  (use \"git add <file>...\" to update what will be committed)
+  (use \"git restore <file>...\" to discard changes in working directory)
+\tmodified:   modified.rs
+\tdeleted:    gone.rs
+
+Untracked files:
+  (use \"git add <file>...\" to include in what will be committed)
+\tuntracked.txt
+
+";
+        let result = optimize_status(raw).unwrap();
+        assert!(result.contains("branch: main...origin/main [ahead 2]"));
+        assert!(result.contains("staged (3): staged.rs, added.rs (new), old.rs -> new.rs"));
+        assert!(result.contains("modified (2): modified.rs, gone.rs (deleted)"));
+        assert!(result.contains("untracked (1): untracked.txt"));
+        assert!(!result.contains("use \"git"));
+    }
+
     #[test]
-    fn has_numeric_limit_detects_limits() {
-        assert!(has_numeric_limit("git log -10"));
-        assert!(has_numeric_limit("git log -n 20"));
-        assert!(has_numeric_limit("git log -5 --oneline"));
-        assert!(!has_numeric_limit("git log --oneline"));
-        assert!(!has_numeric_limit("git log -p")); // -p is not numeric
+    fn status_behind_and_diverged() {
+        let behind = "\
+On branch dev
+Your branch is behind 'origin/dev' by 3 commits, and can be fast-forwarded.
+
+nothing to commit, working tree clean
+";
+        assert!(
+            optimize_status(behind)
+                .unwrap()
+                .starts_with("branch: dev...origin/dev [behind 3]")
+        );
+
+        let diverged = "\
+On branch dev
+Your branch and 'origin/dev' have diverged,
+and have 2 and 5 different commits each, respectively.
+
+nothing to commit, working tree clean
+";
+        assert!(
+            optimize_status(diverged)
+                .unwrap()
+                .starts_with("branch: dev...origin/dev [ahead 2, behind 5]")
+        );
+    }
+
+    #[test]
+    fn status_upstream_gone() {
+        let raw = "\
+On branch feature
+Your branch is based on 'origin/feature', but the upstream is gone.
+  (use \"git branch --unset-upstream\" to fixup)
+
+nothing to commit, working tree clean
+";
+        assert!(
+            optimize_status(raw)
+                .unwrap()
+                .starts_with("branch: feature...origin/feature [gone]")
+        );
+    }
+
+    #[test]
+    fn status_detached_head() {
+        let raw = "HEAD detached at 1a2b3c4\nnothing to commit, working tree clean\n";
+        assert_eq!(
+            optimize_status(raw).unwrap(),
+            "branch: HEAD (detached at 1a2b3c4)\nclean"
+        );
     }
 
-    // format_porcelain_status -------------------------------------------
+    #[test]
+    fn status_merge_conflicts() {
+        let raw = "\
+On branch main
+You have unmerged paths.
+  (fix conflicts and run \"git commit\")
+  (use \"git merge --abort\" to abort the merge)
+
+Unmerged paths:
+  (use \"git add <file>...\" to mark resolution)
+\tboth modified:   src/lib.rs
+\tdeleted by them: src/old.rs
+
+no changes added to commit (use \"git add\" and/or \"git commit -a\")
+";
+        let result = optimize_status(raw).unwrap();
+        assert!(result.contains("state: merge in progress"));
+        assert!(
+            result.contains(
+                "conflicts (2): src/lib.rs (both modified), src/old.rs (deleted by them)"
+            )
+        );
+    }
 
     #[test]
-    fn porcelain_clean_tree() {
-        let porcelain = "## main...origin/main\n";
-        let result = format_porcelain_status(porcelain);
-        assert!(result.contains("branch: main...origin/main"));
-        assert!(result.contains("clean"));
+    fn status_rebase_in_progress() {
+        let raw = "\
+interactive rebase in progress; onto 9f8e7d6
+Last command done (1 command done):
+   pick 1234567 Add feature
+No commands remaining.
+You are currently rebasing branch 'feature' on '9f8e7d6'.
+  (all conflicts fixed: run \"git rebase --continue\")
+
+Changes to be committed:
+\tmodified:   src/main.rs
+
+";
+        let result = optimize_status(raw).unwrap();
+        assert!(result.contains("state: rebase in progress (onto 9f8e7d6)"));
+        assert!(result.contains("staged (1): src/main.rs"));
+        assert!(!result.contains("pick 1234567"));
     }
 
     #[test]
-    fn porcelain_empty() {
-        let result = format_porcelain_status("");
-        assert_eq!(result, "clean");
+    fn status_chained_invocations_render_each_block() {
+        let raw = "\
+On branch main
+nothing to commit, working tree clean
+On branch sub-main
+Untracked files:
+\tnew.txt
+
+";
+        let result = optimize_status(raw).unwrap();
+        assert!(result.contains("branch: main\nclean"));
+        assert!(result.contains("branch: sub-main\nuntracked (1): new.txt"));
     }
 
     #[test]
-    fn porcelain_mixed_changes() {
-        let porcelain = "## main\nM  staged.rs\n M modified.rs\nA  added.rs\n?? untracked.txt\n";
-        let result = format_porcelain_status(porcelain);
-        assert!(result.contains("branch: main"));
-        assert!(result.contains("staged (2):"));
-        assert!(result.contains("staged.rs"));
-        assert!(result.contains("added.rs"));
-        assert!(result.contains("modified (1):"));
-        assert!(result.contains("modified.rs"));
-        assert!(result.contains("untracked (1):"));
-        assert!(result.contains("untracked.txt"));
+    fn status_counts_entries_collapsed_by_dedup() {
+        let raw = "\
+On branch main
+Untracked files:
+\tf1
+\tf2
+[... 58 more similar line(s) matching \"f1...\"]
+
+";
+        let result = optimize_status(raw).unwrap();
+        assert!(result.contains("untracked (60): f1, f2, +58 more"));
     }
 
     #[test]
-    fn porcelain_truncates_long_lists() {
-        let mut porcelain = String::from("## main\n");
+    fn status_truncates_long_lists() {
+        let mut raw = String::from("On branch main\nUntracked files:\n");
         for i in 0..8 {
-            porcelain.push_str(&format!("?? file{i}.txt\n"));
+            raw.push_str(&format!("\tfile{i}.txt\n"));
         }
-        let result = format_porcelain_status(&porcelain);
+        let result = optimize_status(&raw).unwrap();
         assert!(result.contains("untracked (8):"));
         assert!(result.contains("+5 more"));
     }
@@ -0,0 +1,524 @@
+use std::sync::LazyLock;
+
+use anyhow::{Result, bail};
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::config::schema::JsonOptimizerConfig;
+use crate::matching;
+use crate::optimizers::{CommandContext, OptimizedOutput, Optimizer};
+use crate::utils::token_counter::estimate_tokens;
+
+// ---------------------------------------------------------------------------
+// Limits
+// ---------------------------------------------------------------------------
+
+/// How aggressively [`summarize_json`] compacts a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonLimits {
+    /// Array elements (and NDJSON records) kept; the rest are counted.
+    pub max_array_items: usize,
+    /// Strings longer than this many characters are cut.
+    pub max_string_length: usize,
+    /// Drop `null`, `""`, `[]` and `{}` fields below the top level.
+    pub drop_empty: bool,
+}
+
+impl Default for JsonLimits {
+    fn default() -> Self {
+        let cfg = JsonOptimizerConfig::default();
+        Self {
+            max_array_items: cfg.max_array_items,
+            max_string_length: cfg.max_string_length,
+            drop_empty: cfg.drop_empty,
+        }
+    }
+}
+
+/// Programs whose JSON output is file content (`cat package.json`) or an
+/// explicit transform (`jq`). Cutting arrays or dropping empty values would
+/// silently change what the user asked to see, so they are only summarized
+/// when a `[[optimizers.json.commands]]` override enables them.
+const VERBATIM_PROGRAMS: &[&str] = &[
+    "cat",
+    "head",
+    "tail",
+    "less",
+    "more",
+    "bat",
+    "type",
+    "get-content",
+    "gc",
+    "jq",
+    "yq",
+];
+
+/// Limits for `command` from `[optimizers.json]` and the first matching
+/// `[[optimizers.json.commands]]` override, or `None` when JSON
+/// summarization is disabled for it.
+pub fn limits_for(cfg: &JsonOptimizerConfig, command: &str) -> Option<JsonLimits> {
+    let words: Vec<String> = command
+        .split_whitespace()
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let verbatim = words
+        .first()
+        .is_some_and(|w| VERBATIM_PROGRAMS.contains(&matching::program_name(w).as_str()));
+    let matched = cfg.commands.iter().find(|o| {
+        let prefix: Vec<String> = o
+            .command
+            .split_whitespace()
+            .map(str::to_ascii_lowercase)
+            .collect();
+        !prefix.is_empty() && words.starts_with(&prefix)
+    });
+
+    if !matched
+        .and_then(|o| o.enabled)
+        .unwrap_or(cfg.enabled && !verbatim)
+    {
+        return None;
+    }
+    Some(JsonLimits {
+        max_array_items: matched
+            .and_then(|o| o.max_array_items)
+            .unwrap_or(cfg.max_array_items),
+        max_string_length: matched
+            .and_then(|o| o.max_string_length)
+            .unwrap_or(cfg.max_string_length),
+        drop_empty: matched.and_then(|o| o.drop_empty).unwrap_or(cfg.drop_empty),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Optimizer
+// ---------------------------------------------------------------------------
+
+/// Summarizes JSON and NDJSON output from any command no specialized
+/// optimizer claimed (`gh api`, `curl`, `kubectl get -o json`,
+/// `cargo metadata`, ...).
+///
+/// It accepts every command; output that is not JSON is rejected from
+/// `optimize_output` so the registry falls through to the generic optimizer.
+pub struct JsonOptimizer {
+    cfg: JsonOptimizerConfig,
+}
+
+impl Default for JsonOptimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonOptimizer {
+    pub fn new() -> Self {
+        Self::from_config(&JsonOptimizerConfig::default())
+    }
+
+    /// Create a `JsonOptimizer` from the configuration.
+    pub fn from_config(cfg: &JsonOptimizerConfig) -> Self {
+        Self { cfg: cfg.clone() }
+    }
+}
+
+impl Optimizer for JsonOptimizer {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn can_handle(&self, ctx: &CommandContext) -> bool {
+        limits_for(&self.cfg, ctx.core).is_some()
+    }
+
+    fn optimize_output(&self, ctx: &CommandContext, raw_output: &str) -> Result<OptimizedOutput> {
+        let Some(limits) = limits_for(&self.cfg, ctx.core) else {
+            bail!("JSON summarization disabled for this command");
+        };
+        let Some(optimized) = summarize_json(raw_output, &limits) else {
+            bail!("output is not JSON");
+        };
+
+        Ok(OptimizedOutput {
+            optimized_tokens: estimate_tokens(&optimized),
+            output: optimized,
+            optimizer_used: self.name().to_string(),
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Summarization
+// ---------------------------------------------------------------------------
+
+/// Keys listed in an array schema before the rest are counted.
+const MAX_SCHEMA_KEYS: usize = 15;
+
+/// Marker heading an array that was already collapsed.
+static MARKER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[\d+ \w+, showing \d+(?:; schema .*)?\]$").unwrap());
+
+/// Suffix of a string that was already cut.
+static ELIDED_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"… \[\+\d+ chars\]$").unwrap());
+
+/// Summarize JSON or NDJSON output.
+///
+/// Long arrays keep their first elements behind a marker with the element
+/// count and, for arrays of objects, the schema of all elements. Long
+/// strings are cut and empty fields dropped, except top-level keys (those
+/// of the root object or of the objects in a root array), which are always
+/// kept. The result is still valid JSON; NDJSON stays one record per line.
+///
+/// Summarizing is idempotent, so output that preprocessing already
+/// summarized comes back unchanged rather than collapsed again.
+///
+/// Returns `None` when the output is neither, or when summarizing would
+/// make it larger.
+pub fn summarize_json(raw_output: &str, limits: &JsonLimits) -> Option<String> {
+    summarize_json_document(raw_output, limits)
+        .or_else(|| summarize_ndjson(raw_output.trim(), limits))
+}
+
+/// Summarize output that is a single JSON document, leaving NDJSON alone.
+pub fn summarize_json_document(raw_output: &str, limits: &JsonLimits) -> Option<String> {
+    let trimmed = raw_output.trim();
+    if !trimmed.starts_with(['{', '[']) {
+        return None;
+    }
+    let value = serde_json::from_str::<Value>(trimmed).ok()?;
+    let summary = serde_json::to_string_pretty(&summarize_value(value, limits, true)).ok()?;
+    (summary.len() <= trimmed.len()).then_some(summary)
+}
+
+/// One compact line per record, with a header giving the record count and
+/// schema when records are dropped.
+fn summarize_ndjson(trimmed: &str, limits: &JsonLimits) -> Option<String> {
+    if !trimmed.starts_with(['{', '[']) {
+        return None;
+    }
+    let records: Vec<Value> = trimmed
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str::<Value>(l).ok().filter(is_container))
+        .collect::<Option<_>>()?;
+    if records.len() < 2 {
+        return None;
+    }
+
+    let mut out = Vec::new();
+    if records.len() > limits.max_array_items {
+        out.push(format!(
+            "[{} records, showing {}{}]",
+            records.len(),
+            limits.max_array_items,
+            schema_suffix(&records)
+        ));
+    }
+    for record in records.into_iter().take(limits.max_array_items) {
+        out.push(serde_json::to_string(&summarize_value(record, limits, true)).ok()?);
+    }
+    let summary = out.join("\n");
+    (summary.len() <= trimmed.len()).then_some(summary)
+}
+
+/// Summarize one value; `top` marks the root and the elements of a root
+/// array, whose keys are kept even when empty.
+fn summarize_value(value: Value, limits: &JsonLimits, top: bool) -> Value {
+    match value {
+        Value::String(s) => Value::String(elide(s, limits.max_string_length)),
+        Value::Array(items) => {
+            let total = items.len();
+            if total <= limits.max_array_items || items.first().is_some_and(is_marker) {
+                return Value::Array(
+                    items
+                        .into_iter()
+                        .map(|v| summarize_value(v, limits, top))
+                        .collect(),
+                );
+            }
+            let marker = format!(
+                "[{total} {}, showing {}{}]",
+                element_noun(&items),
+                limits.max_array_items,
+                schema_suffix(&items)
+            );
+            let mut kept = vec![Value::String(marker)];
+            kept.extend(
+                items
+                    .into_iter()
+                    .take(limits.max_array_items)
+                    .map(|v| summarize_value(v, limits, top)),
+            );
+            Value::Array(kept)
+        }
+        Value::Object(fields) => {
+            let mut kept = Map::new();
+            for (key, value) in fields {
+                let value = summarize_value(value, limits, false);
+                if top || !limits.drop_empty || !is_empty(&value) {
+                    kept.insert(key, value);
+                }
+            }
+            Value::Object(kept)
+        }
+        scalar => scalar,
+    }
+}
+
+fn elide(s: String, max_chars: usize) -> String {
+    let total = s.chars().count();
+    if total <= max_chars || ELIDED_RE.is_match(&s) || MARKER_RE.is_match(&s) {
+        return s;
+    }
+    let kept: String = s.chars().take(max_chars).collect();
+    format!("{kept}… [+{} chars]", total - max_chars)
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        Value::Array(a) => a.is_empty(),
+        Value::Object(o) => o.is_empty(),
+        _ => false,
+    }
+}
+
+fn is_marker(value: &Value) -> bool {
+    value.as_str().is_some_and(|s| MARKER_RE.is_match(s))
+}
+
+fn is_container(value: &Value) -> bool {
+    value.is_object() || value.is_array()
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// `objects`, `strings`, ... when all elements share a type, else `items`.
+fn element_noun(items: &[Value]) -> &'static str {
+    let first = items.first().map(type_name);
+    if items.iter().any(|v| Some(type_name(v)) != first) {
+        return "items";
+    }
+    match first {
+        Some("object") => "objects",
+        Some("string") => "strings",
+        Some("number") => "numbers",
+        Some("bool") => "bools",
+        Some("array") => "arrays",
+        _ => "items",
+    }
+}
+
+/// `; schema {name: string, tags?: array, ...}` for arrays of objects:
+/// every key seen in any element with its types, `?` when some elements
+/// lack it.
+fn schema_suffix(items: &[Value]) -> String {
+    let objects: Vec<&Map<String, Value>> = items.iter().filter_map(Value::as_object).collect();
+    if objects.is_empty() {
+        return String::new();
+    }
+
+    // (key, types seen, elements having it), in order of first appearance.
+    let mut keys: Vec<(&str, Vec<&'static str>, usize)> = Vec::new();
+    for object in &objects {
+        for (key, value) in *object {
+            let ty = type_name(value);
+            match keys.iter_mut().find(|(k, _, _)| k == key) {
+                Some((_, types, seen)) => {
+                    *seen += 1;
+                    if !types.contains(&ty) {
+                        types.push(ty);
+                    }
+                }
+                None => keys.push((key, vec![ty], 1)),
+            }
+        }
+    }
+
+    let mut fields: Vec<String> = keys
+        .iter()
+        .take(MAX_SCHEMA_KEYS)
+        .map(|(key, types, seen)| {
+            let optional = if *seen < objects.len() { "?" } else { "" };
+            format!("{key}{optional}: {}", types.join("|"))
+        })
+        .collect();
+    if keys.len() > MAX_SCHEMA_KEYS {
+        fields.push(format!("+{} more keys", keys.len() - MAX_SCHEMA_KEYS));
+    }
+    format!("; schema {{{}}}", fields.join(", "))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::JsonCommandConfig;
+
+    fn limits(max_array_items: usize, max_string_length: usize) -> JsonLimits {
+        JsonLimits {
+            max_array_items,
+            max_string_length,
+            drop_empty: true,
+        }
+    }
+
+    #[test]
+    fn collapses_arrays_with_schema_and_count() {
+        let users: Vec<String> = (0..50)
+            .map(|i| {
+                if i % 2 == 0 {
+                    format!(r#"{{"id": {i}, "name": "user{i}", "email": null}}"#)
+                } else {
+                    format!(r#"{{"id": {i}, "name": "user{i}", "admin": true}}"#)
+                }
+            })
+            .collect();
+        let input = format!(r#"{{"total": 50, "users": [{}]}}"#, users.join(", "));
+
+        let result = summarize_json(&input, &limits(2, 200)).unwrap();
+        let value: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(value["total"], 50);
+        let kept = value["users"].as_array().unwrap();
+        assert_eq!(
+            kept[0],
+            "[50 objects, showing 2; schema {id: number, name: string, email?: null, admin?: bool}]"
+        );
+        assert_eq!(kept.len(), 3);
+        // The null email is dropped; the first element keeps its other keys.
+        assert_eq!(kept[1], serde_json::json!({"id": 0, "name": "user0"}));
+        assert_eq!(kept[2]["admin"], true);
+    }
+
+    #[test]
+    fn keeps_top_level_keys_and_drops_nested_empties() {
+        let input = format!(
+            r#"{{"Id": "abc", "Name": "", "Labels": {{}}, "Config": {{"Env": [], "Cmd": null, "Image": "nginx", "Entrypoint": "{}"}}}}"#,
+            "x".repeat(500)
+        );
+        let result = summarize_json(&input, &limits(3, 20)).unwrap();
+        let value: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(value["Name"], "");
+        assert_eq!(value["Labels"], serde_json::json!({}));
+        assert_eq!(
+            value["Config"],
+            serde_json::json!({
+                "Image": "nginx",
+                "Entrypoint": format!("{}… [+480 chars]", "x".repeat(20)),
+            })
+        );
+        // Key order is preserved.
+        assert!(result.find("\"Id\"").unwrap() < result.find("\"Config\"").unwrap());
+    }
+
+    #[test]
+    fn root_array_elements_are_top_level() {
+        let input = format!(
+            r#"[{{"Id": "abc", "Mounts": [], "Log": "{}"}}]"#,
+            "y".repeat(300)
+        );
+        let result = summarize_json(&input, &limits(3, 10)).unwrap();
+        let value: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(value[0]["Mounts"], serde_json::json!([]));
+        assert_eq!(value[0]["Log"], "yyyyyyyyyy… [+290 chars]");
+    }
+
+    #[test]
+    fn summarizes_ndjson() {
+        let input: Vec<String> = (0..20)
+            .map(|i| format!(r#"{{"level": "info", "msg": "request {i}", "trace": null}}"#))
+            .collect();
+        let result = summarize_json(&input.join("\n"), &limits(2, 200)).unwrap();
+        assert_eq!(
+            result,
+            "[20 records, showing 2; schema {level: string, msg: string, trace: null}]\n\
+             {\"level\":\"info\",\"msg\":\"request 0\",\"trace\":null}\n\
+             {\"level\":\"info\",\"msg\":\"request 1\",\"trace\":null}"
+        );
+    }
+
+    #[test]
+    fn summarizing_twice_is_stable() {
+        let items: Vec<String> = (0..20)
+            .map(|i| format!(r#"{{"id": {i}, "note": "{}"}}"#, "x".repeat(300)))
+            .collect();
+        let input = format!("[{}]", items.join(", "));
+
+        let once = summarize_json(&input, &limits(3, 50)).unwrap();
+        let twice = summarize_json(&once, &limits(3, 50)).unwrap();
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn rejects_non_json_and_small_documents() {
+        assert!(summarize_json("On branch main", &JsonLimits::default()).is_none());
+        assert!(summarize_json("[not json", &JsonLimits::default()).is_none());
+        assert!(summarize_json(r#"{"a": 1}"#, &JsonLimits::default()).is_none());
+        // A single NDJSON-looking line that is not valid JSON either.
+        assert!(summarize_json("{\"a\": 1}\nplain text", &JsonLimits::default()).is_none());
+    }
+
+    #[test]
+    fn per_command_overrides() {
+        let cfg = JsonOptimizerConfig {
+            commands: vec![
+                JsonCommandConfig {
+                    command: "gh api".to_string(),
+                    max_array_items: Some(10),
+                    ..JsonCommandConfig::default()
+                },
+                JsonCommandConfig {
+                    command: "cargo metadata".to_string(),
+                    enabled: Some(false),
+                    ..JsonCommandConfig::default()
+                },
+            ],
+            ..JsonOptimizerConfig::default()
+        };
+        let gh = limits_for(&cfg, "gh api repos/o/r/pulls").unwrap();
+        assert_eq!(gh.max_array_items, 10);
+        assert_eq!(gh.max_string_length, 200);
+        assert_eq!(
+            limits_for(&cfg, "curl localhost:8080").unwrap(),
+            JsonLimits::default()
+        );
+        assert!(limits_for(&cfg, "cargo metadata --format-version 1").is_none());
+        // Whole words only.
+        assert_eq!(limits_for(&cfg, "gh apis").unwrap().max_array_items, 3);
+    }
+
+    #[test]
+    fn file_reads_and_jq_are_left_verbatim() {
+        let mut cfg = JsonOptimizerConfig::default();
+        assert!(limits_for(&cfg, "cat package.json").is_none());
+        assert!(limits_for(&cfg, "/usr/bin/jq .items data.json").is_none());
+        assert!(limits_for(&cfg, "curl -s localhost:8080").is_some());
+
+        cfg.commands.push(JsonCommandConfig {
+            command: "jq".to_string(),
+            enabled: Some(true),
+            ..JsonCommandConfig::default()
+        });
+        assert!(limits_for(&cfg, "jq . big.json").is_some());
+    }
+
+    #[test]
+    fn optimizer_falls_through_on_plain_text() {
+        let opt = JsonOptimizer::new();
+        let ctx = CommandContext::new("curl localhost:8080/health");
+        assert!(opt.can_handle(&ctx));
+        assert!(opt.optimize_output(&ctx, "OK").is_err());
+    }
+}
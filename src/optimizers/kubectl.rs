@@ -0,0 +1,1084 @@
+use anyhow::Result;
+
+use crate::config::schema::KubectlOptimizerConfig;
+use crate::matching::shell;
+use crate::optimizers::docker::compact_docker_logs;
+use crate::optimizers::{CommandContext, OptimizedOutput, Optimizer};
+use crate::utils::token_counter::estimate_tokens;
+
+// ---------------------------------------------------------------------------
+// Subcommand classification
+// ---------------------------------------------------------------------------
+
+/// Recognized kubectl / helm commands that terse can optimize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KubeCommand {
+    /// kubectl get — resource tables
+    Get,
+    /// kubectl describe — object details and events
+    Describe,
+    /// kubectl logs — container logs
+    Logs,
+    /// kubectl rollout status — rollout progress
+    RolloutStatus,
+    /// helm list / helm ls — release table
+    HelmList,
+    /// helm status — release summary and notes
+    HelmStatus,
+    /// helm history — revision table
+    HelmHistory,
+}
+
+/// kubectl and helm options that take a separate value word.
+const VALUE_OPTIONS: &[&str] = &[
+    "-n",
+    "--namespace",
+    "--context",
+    "--kube-context",
+    "--cluster",
+    "--kubeconfig",
+    "--user",
+    "-l",
+    "--selector",
+    "--field-selector",
+    "-o",
+    "--output",
+    "-c",
+    "--container",
+    "--sort-by",
+    "-L",
+    "--label-columns",
+    "--since",
+    "--tail",
+    "--max",
+    "--filter",
+];
+
+/// Program name and positional words of a command, skipping options and
+/// their values (`kubectl -n prod get pods` → `kubectl`, `[get, pods]`).
+fn positionals(words: &[String]) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut i = 1;
+    while let Some(word) = words.get(i) {
+        if word.starts_with('-') {
+            i += if VALUE_OPTIONS.contains(&word.as_str()) {
+                2
+            } else {
+                1
+            };
+            continue;
+        }
+        out.push(word.as_str());
+        i += 1;
+    }
+    out
+}
+
+/// Classify the core command into a [`KubeCommand`].
+fn classify(core: &str) -> Option<KubeCommand> {
+    let words = shell::split_words(&core.to_ascii_lowercase());
+    let program = words.first()?;
+    let args = positionals(&words);
+    match (program.as_str(), args.as_slice()) {
+        ("kubectl", ["get", ..]) => Some(KubeCommand::Get),
+        ("kubectl", ["describe", ..]) => Some(KubeCommand::Describe),
+        ("kubectl", ["logs", ..]) => Some(KubeCommand::Logs),
+        ("kubectl", ["rollout", "status", ..]) => Some(KubeCommand::RolloutStatus),
+        ("helm", ["list" | "ls", ..]) => Some(KubeCommand::HelmList),
+        ("helm", ["status", ..]) => Some(KubeCommand::HelmStatus),
+        ("helm", ["history" | "hist", ..]) => Some(KubeCommand::HelmHistory),
+        _ => None,
+    }
+}
+
+/// The `-o` / `--output` format, if one was requested.
+fn output_format(core: &str) -> Option<String> {
+    let words = shell::split_words(&core.to_ascii_lowercase());
+    words.iter().enumerate().find_map(|(i, word)| {
+        if word == "-o" || word == "--output" {
+            return Some(words.get(i + 1).cloned().unwrap_or_default());
+        }
+        word.strip_prefix("--output=")
+            .or_else(|| word.strip_prefix("-o="))
+            .or_else(|| word.strip_prefix("-o"))
+            .map(str::to_string)
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Optimizer
+// ---------------------------------------------------------------------------
+
+pub struct KubectlOptimizer {
+    get_max_rows: usize,
+    describe_max_events: usize,
+    logs_max_tail: usize,
+    logs_max_errors: usize,
+    helm_max_rows: usize,
+    helm_history_max_rows: usize,
+    helm_notes_max_lines: usize,
+}
+
+impl Default for KubectlOptimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KubectlOptimizer {
+    pub fn new() -> Self {
+        Self::from_config(&KubectlOptimizerConfig::default())
+    }
+
+    /// Create a `KubectlOptimizer` from the configuration.
+    pub fn from_config(cfg: &KubectlOptimizerConfig) -> Self {
+        Self {
+            get_max_rows: cfg.get_max_rows,
+            describe_max_events: cfg.describe_max_events,
+            logs_max_tail: cfg.logs_max_tail,
+            logs_max_errors: cfg.logs_max_errors,
+            helm_max_rows: cfg.helm_max_rows,
+            helm_history_max_rows: cfg.helm_history_max_rows,
+            helm_notes_max_lines: cfg.helm_notes_max_lines,
+        }
+    }
+}
+
+impl Optimizer for KubectlOptimizer {
+    fn name(&self) -> &'static str {
+        "kubectl"
+    }
+
+    fn can_handle(&self, ctx: &CommandContext) -> bool {
+        let Some(cmd) = classify(ctx.core) else {
+            return false;
+        };
+        match cmd {
+            // Machine-readable formats are for scripts — leave them alone.
+            KubeCommand::Get | KubeCommand::HelmList | KubeCommand::HelmHistory => {
+                output_format(ctx.core).is_none_or(|f| f == "wide" || f == "table")
+            }
+            _ => true,
+        }
+    }
+
+    fn optimize_output(&self, ctx: &CommandContext, raw_output: &str) -> Result<OptimizedOutput> {
+        let cmd = classify(ctx.core).unwrap_or(KubeCommand::Get);
+
+        let optimized = match cmd {
+            KubeCommand::Get => compact_get(raw_output, ctx.line_cap(self.get_max_rows)),
+            KubeCommand::Describe => compact_describe(raw_output, self.describe_max_events),
+            KubeCommand::Logs => compact_docker_logs(
+                raw_output,
+                ctx.line_cap(self.logs_max_tail),
+                self.logs_max_errors,
+            ),
+            KubeCommand::RolloutStatus => compact_rollout_status(raw_output),
+            KubeCommand::HelmList => compact_helm_table(
+                raw_output,
+                &[
+                    "NAME",
+                    "NAMESPACE",
+                    "REVISION",
+                    "STATUS",
+                    "CHART",
+                    "APP VERSION",
+                ],
+                TableEnd::Head(ctx.line_cap(self.helm_max_rows)),
+            ),
+            KubeCommand::HelmHistory => compact_helm_table(
+                raw_output,
+                &["REVISION", "STATUS", "CHART", "APP VERSION", "DESCRIPTION"],
+                TableEnd::Tail(ctx.line_cap(self.helm_history_max_rows)),
+            ),
+            KubeCommand::HelmStatus => compact_helm_status(raw_output, self.helm_notes_max_lines),
+        };
+
+        Ok(OptimizedOutput {
+            optimized_tokens: estimate_tokens(&optimized),
+            output: optimized,
+            optimizer_used: self.name().to_string(),
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Aligned tables
+// ---------------------------------------------------------------------------
+
+/// A column-aligned table as printed by kubectl: each column starts where
+/// its header starts. Header names may contain single spaces
+/// (`NOMINATED NODE`), so columns are separated by two or more spaces.
+struct Columns {
+    names: Vec<String>,
+    starts: Vec<usize>,
+}
+
+impl Columns {
+    fn parse(header: &str) -> Self {
+        let bytes = header.as_bytes();
+        let mut names = Vec::new();
+        let mut starts = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b' ' {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            // A column name ends at two consecutive spaces or end of line.
+            while i < bytes.len()
+                && !(bytes[i] == b' ' && bytes.get(i + 1).is_none_or(|&b| b == b' '))
+            {
+                i += 1;
+            }
+            starts.push(start);
+            names.push(header[start..i].trim().to_string());
+        }
+        Self { names, starts }
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    /// Cell text of column `idx` in `row`.
+    fn cell<'a>(&self, row: &'a str, idx: usize) -> &'a str {
+        let start = floor_char_boundary(row, self.starts[idx]);
+        let end = self
+            .starts
+            .get(idx + 1)
+            .map_or(row.len(), |&e| floor_char_boundary(row, e));
+        if start >= end {
+            return "";
+        }
+        row[start..end].trim()
+    }
+}
+
+fn floor_char_boundary(s: &str, mut i: usize) -> usize {
+    i = i.min(s.len());
+    while !s.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+// ---------------------------------------------------------------------------
+// kubectl get — drop healthy rows, group the rest by status
+// ---------------------------------------------------------------------------
+
+/// Statuses that mean "fine" for resources without a READY column.
+const HEALTHY_STATUSES: &[&str] = &[
+    "Running",
+    "Completed",
+    "Succeeded",
+    "Complete",
+    "Ready",
+    "Bound",
+    "Active",
+    "Available",
+    "Deployed",
+];
+
+/// Compact `kubectl get` output.
+///
+/// Tables with a STATUS and/or READY column are health-aware: healthy rows
+/// (Running and fully ready with no restarts, Completed jobs, Ready nodes,
+/// fully available deployments) are counted instead of listed, and the
+/// remaining rows are grouped by status. Other tables are capped at
+/// `max_rows`. `kubectl get all` prints several tables; each is compacted
+/// separately.
+fn compact_get(raw_output: &str, max_rows: usize) -> String {
+    let trimmed = raw_output.trim();
+    if trimmed.is_empty() {
+        return "No resources found".to_string();
+    }
+
+    let blocks: Vec<String> = trimmed
+        .split("\n\n")
+        .map(|block| compact_get_table(block.trim_matches('\n'), max_rows))
+        .collect();
+    blocks.join("\n\n")
+}
+
+fn compact_get_table(block: &str, max_rows: usize) -> String {
+    let mut lines = block.lines();
+    let Some(header) = lines.next() else {
+        return String::new();
+    };
+    let rows: Vec<&str> = lines.filter(|l| !l.trim().is_empty()).collect();
+    let columns = Columns::parse(header);
+    let status_col = columns.index("STATUS");
+    let ready_col = columns.index("READY");
+    if (status_col.is_none() && ready_col.is_none()) || rows.is_empty() {
+        return cap_rows(header, &rows, max_rows);
+    }
+    let restarts_col = columns.index("RESTARTS");
+
+    // (label, row) for rows needing attention; healthy counts by status.
+    let mut attention: Vec<(String, &str)> = Vec::new();
+    let mut healthy: Vec<(String, usize)> = Vec::new();
+    for row in &rows {
+        let status = status_col.map(|c| columns.cell(row, c)).unwrap_or("");
+        let ready = ready_col.map(|c| columns.cell(row, c)).unwrap_or("");
+        let restarts = restarts_col.map(|c| columns.cell(row, c)).unwrap_or("0");
+
+        let fully_ready = ready.is_empty() || is_fully_ready(ready);
+        let restarted = !restarts.starts_with('0');
+        let status_ok = status.is_empty() || HEALTHY_STATUSES.contains(&status);
+        // Completed pods report 0/1 ready — that is expected.
+        let done = matches!(status, "Completed" | "Succeeded");
+
+        let label = if !status_ok {
+            status.to_string()
+        } else if !fully_ready && !done {
+            format!("{} not ready", display_status(status))
+        } else if restarted {
+            format!("{} with restarts", display_status(status))
+        } else {
+            let key = display_status(status).to_string();
+            match healthy.iter_mut().find(|(s, _)| *s == key) {
+                Some((_, n)) => *n += 1,
+                None => healthy.push((key, 1)),
+            }
+            continue;
+        };
+        attention.push((label, row));
+    }
+
+    let healthy_total: usize = healthy.iter().map(|(_, n)| n).sum();
+    let breakdown = healthy
+        .iter()
+        .map(|(s, n)| format!("{n} {s}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if attention.is_empty() {
+        return format!("All {healthy_total} healthy ({breakdown})");
+    }
+
+    // Group by label, in order of first appearance.
+    let mut groups: Vec<(&str, Vec<&str>)> = Vec::new();
+    for (label, row) in &attention {
+        match groups.iter_mut().find(|(l, _)| l == label) {
+            Some((_, rows)) => rows.push(row),
+            None => groups.push((label, vec![row])),
+        }
+    }
+
+    let mut out = vec![header.trim_end().to_string()];
+    let mut shown = 0usize;
+    for (label, rows) in &groups {
+        out.push(format!("{label} ({}):", rows.len()));
+        for row in rows {
+            if shown < max_rows {
+                out.push(row.trim_end().to_string());
+            }
+            shown += 1;
+        }
+    }
+    if shown > max_rows {
+        out.push(format!("...+{} more rows", shown - max_rows));
+    }
+    if healthy_total > 0 {
+        out.push(format!("[+{healthy_total} healthy: {breakdown}]"));
+    }
+    out.join("\n")
+}
+
+fn display_status(status: &str) -> &str {
+    if status.is_empty() { "ready" } else { status }
+}
+
+/// `3/3` style READY value with both sides equal (and non-zero desired).
+fn is_fully_ready(ready: &str) -> bool {
+    match ready.split_once('/') {
+        Some((have, want)) => have == want && want != "0",
+        // Replica sets print a bare number; nothing to compare against.
+        None => true,
+    }
+}
+
+fn cap_rows(header: &str, rows: &[&str], max_rows: usize) -> String {
+    let mut out = vec![header.trim_end()];
+    out.extend(rows.iter().take(max_rows).map(|r| r.trim_end()));
+    let mut text = out.join("\n");
+    if rows.len() > max_rows {
+        text.push_str(&format!(
+            "\n...+{} more rows ({} total)",
+            rows.len() - max_rows,
+            rows.len()
+        ));
+    }
+    text
+}
+
+// ---------------------------------------------------------------------------
+// kubectl describe — key fields, container state, conditions, events
+// ---------------------------------------------------------------------------
+
+/// Top-level fields that are rarely useful when diagnosing a resource.
+const DESCRIBE_DROP_FIELDS: &[&str] = &[
+    "Labels",
+    "Annotations",
+    "Priority",
+    "Priority Class Name",
+    "Start Time",
+    "IP",
+    "IPs",
+    "QoS Class",
+    "Node-Selectors",
+    "Tolerations",
+    "Service Account",
+    "Volumes",
+    "Selector",
+    "Pod Template",
+    "CreationTimestamp",
+    "Finalizers",
+];
+
+/// Container fields kept under `Containers:` / `Init Containers:`.
+const CONTAINER_KEEP_FIELDS: &[&str] = &[
+    "Image",
+    "State",
+    "Last State",
+    "Reason",
+    "Message",
+    "Exit Code",
+    "Ready",
+    "Restart Count",
+];
+
+/// Conditions whose healthy value is `False`.
+const PRESSURE_CONDITIONS: &[&str] = &[
+    "MemoryPressure",
+    "DiskPressure",
+    "PIDPressure",
+    "NetworkUnavailable",
+    "ReplicaFailure",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DescribeSection {
+    /// A top-level field we keep, with its continuation lines.
+    Keep,
+    /// A top-level field or section we drop.
+    Drop,
+    Containers,
+    Conditions,
+    Events,
+}
+
+/// Compact `kubectl describe` output.
+///
+/// Keeps identifying fields, container state, abnormal conditions, and
+/// events (all warnings, then the most recent normal events up to
+/// `max_events`). Labels, annotations, volumes, mounts, environment, and
+/// other configuration detail are dropped.
+fn compact_describe(raw_output: &str, max_events: usize) -> String {
+    let trimmed = raw_output.trim();
+    if trimmed.is_empty() {
+        return "No describe output".to_string();
+    }
+
+    let mut out: Vec<String> = Vec::new();
+    let mut section = DescribeSection::Drop;
+    let mut conditions: Vec<&str> = Vec::new();
+    let mut events: Vec<&str> = Vec::new();
+
+    let flush = |out: &mut Vec<String>, conditions: &mut Vec<&str>, events: &mut Vec<&str>| {
+        if !conditions.is_empty() {
+            out.extend(render_conditions(conditions));
+            conditions.clear();
+        }
+        if !events.is_empty() {
+            out.extend(render_events(events, max_events));
+            events.clear();
+        }
+    };
+
+    for line in trimmed.lines() {
+        let indent = line.len() - line.trim_start().len();
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if indent == 0 {
+            flush(&mut out, &mut conditions, &mut events);
+            let (key, value) = line.split_once(':').unwrap_or((line, ""));
+            let key = key.trim();
+            let value = value.trim();
+            // A new object in multi-object output.
+            if key == "Name" && !out.is_empty() {
+                out.push(String::new());
+            }
+            section = match key {
+                "Containers" | "Init Containers" => DescribeSection::Containers,
+                "Conditions" => DescribeSection::Conditions,
+                "Events" if value.is_empty() => DescribeSection::Events,
+                k if DESCRIBE_DROP_FIELDS.contains(&k) => DescribeSection::Drop,
+                // Other sections (Mounts, Volumes, ...) are configuration.
+                _ if value.is_empty() => DescribeSection::Drop,
+                _ => DescribeSection::Keep,
+            };
+            if matches!(
+                section,
+                DescribeSection::Keep | DescribeSection::Containers | DescribeSection::Conditions
+            ) || key == "Events"
+            {
+                out.push(line.trim_end().to_string());
+            }
+            continue;
+        }
+
+        match section {
+            DescribeSection::Keep => out.push(line.trim_end().to_string()),
+            DescribeSection::Drop => {}
+            DescribeSection::Containers => {
+                let text = line.trim();
+                let key = text.split(':').next().unwrap_or("").trim();
+                let is_container_name = indent == 2 && text.ends_with(':');
+                if is_container_name || CONTAINER_KEEP_FIELDS.contains(&key) {
+                    out.push(line.trim_end().to_string());
+                }
+            }
+            DescribeSection::Conditions => conditions.push(line),
+            DescribeSection::Events => events.push(line),
+        }
+    }
+    flush(&mut out, &mut conditions, &mut events);
+
+    out.join("\n")
+}
+
+/// Keep the condition table header and rows with an abnormal status.
+fn render_conditions(lines: &[&str]) -> Vec<String> {
+    let (header, rows) = lines.split_first().expect("non-empty conditions");
+    let columns = Columns::parse(header.trim_start());
+    let type_col = columns.index("Type");
+    let status_col = columns.index("Status");
+    let (Some(type_col), Some(status_col)) = (type_col, status_col) else {
+        return lines.iter().map(|l| l.trim_end().to_string()).collect();
+    };
+
+    let indent = header.len() - header.trim_start().len();
+    let abnormal: Vec<String> = rows
+        .iter()
+        .filter(|row| !row.trim_start().starts_with("----"))
+        .filter(|row| {
+            let row = row.get(indent..).unwrap_or("");
+            let kind = columns.cell(row, type_col);
+            let status = columns.cell(row, status_col);
+            let healthy = if PRESSURE_CONDITIONS.contains(&kind) {
+                "False"
+            } else {
+                "True"
+            };
+            status != healthy
+        })
+        .map(|row| row.trim_end().to_string())
+        .collect();
+
+    if abnormal.is_empty() {
+        return vec![format!("  (all {} normal)", rows.len())];
+    }
+    let mut out = vec![header.trim_end().to_string()];
+    out.extend(abnormal);
+    out
+}
+
+/// Keep every warning event and the most recent normal events, up to
+/// `max_events` rows in total, in their original order.
+fn render_events(lines: &[&str], max_events: usize) -> Vec<String> {
+    let (header, rows) = lines.split_first().expect("non-empty events");
+    let rows: Vec<&str> = rows
+        .iter()
+        .copied()
+        .filter(|row| !row.trim_start().starts_with("----"))
+        .collect();
+    let is_warning = |row: &str| row.trim_start().starts_with("Warning");
+
+    let warnings = rows.iter().filter(|r| is_warning(r)).count();
+    let normal_budget = max_events.saturating_sub(warnings);
+    let normal_total = rows.len() - warnings;
+    let skip_normal = normal_total.saturating_sub(normal_budget);
+
+    let mut out = vec![header.trim_end().to_string()];
+    let mut normal_seen = 0usize;
+    let mut warnings_shown = 0usize;
+    for row in &rows {
+        if is_warning(row) {
+            warnings_shown += 1;
+            if warnings_shown > max_events {
+                continue;
+            }
+        } else {
+            normal_seen += 1;
+            if normal_seen <= skip_normal {
+                continue;
+            }
+        }
+        out.push(row.trim_end().to_string());
+    }
+    let omitted = skip_normal.min(normal_total) + warnings.saturating_sub(max_events);
+    if omitted > 0 {
+        out.push(format!("  ...+{omitted} earlier events omitted"));
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// kubectl rollout status — collapse progress lines
+// ---------------------------------------------------------------------------
+
+/// Compact `kubectl rollout status` output: repeated "Waiting for ..."
+/// progress lines collapse to the first and last, everything else is kept.
+fn compact_rollout_status(raw_output: &str) -> String {
+    let trimmed = raw_output.trim();
+    if trimmed.is_empty() {
+        return "No rollout status".to_string();
+    }
+
+    let lines: Vec<&str> = trimmed.lines().map(str::trim_end).collect();
+    let waiting: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| l.starts_with("Waiting for"))
+        .map(|(i, _)| i)
+        .collect();
+    if waiting.len() <= 2 {
+        return lines.join("\n");
+    }
+
+    let first = waiting[0];
+    let last = waiting[waiting.len() - 1];
+    let mut out = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if waiting.contains(&i) && i != first && i != last {
+            if i == waiting[1] {
+                out.push(format!("[... {} progress updates]", waiting.len() - 2));
+            }
+            continue;
+        }
+        out.push(line.to_string());
+    }
+    out.join("\n")
+}
+
+// ---------------------------------------------------------------------------
+// helm list / history / status
+// ---------------------------------------------------------------------------
+
+/// Which end of a long table to keep.
+#[derive(Clone, Copy)]
+enum TableEnd {
+    /// Keep the first N rows (`helm list`).
+    Head(usize),
+    /// Keep the last N rows (`helm history` — newest revisions last).
+    Tail(usize),
+}
+
+/// Split a helm table row. Helm pads tab-separated cells with spaces.
+fn helm_cells(line: &str) -> Vec<String> {
+    line.split('\t').map(|c| c.trim().to_string()).collect()
+}
+
+/// Compact a helm table to the `keep` columns (e.g. dropping UPDATED
+/// timestamps) and cap its rows. Non-deployed releases are counted in a
+/// status summary.
+fn compact_helm_table(raw_output: &str, keep: &[&str], end: TableEnd) -> String {
+    let trimmed = raw_output.trim();
+    if trimmed.is_empty() {
+        return "No releases".to_string();
+    }
+
+    let mut lines = trimmed.lines().filter(|l| !l.trim().is_empty());
+    let Some(header) = lines.next() else {
+        return "No releases".to_string();
+    };
+    let rows: Vec<&str> = lines.collect();
+
+    // Tab-separated (helm's native format) or space-aligned.
+    let (names, cells): (Vec<String>, Vec<Vec<String>>) = if header.contains('\t') {
+        (
+            helm_cells(header),
+            rows.iter().map(|r| helm_cells(r)).collect(),
+        )
+    } else {
+        let columns = Columns::parse(header);
+        let cells = rows
+            .iter()
+            .map(|r| {
+                (0..columns.names.len())
+                    .map(|i| columns.cell(r, i).to_string())
+                    .collect()
+            })
+            .collect();
+        (columns.names, cells)
+    };
+
+    let indices: Vec<usize> = keep
+        .iter()
+        .filter_map(|k| names.iter().position(|n| n == k))
+        .collect();
+    if indices.is_empty() {
+        return trimmed.to_string();
+    }
+
+    let total = cells.len();
+    let (skip, take) = match end {
+        TableEnd::Head(n) => (0, n),
+        TableEnd::Tail(n) => (total.saturating_sub(n), n),
+    };
+
+    let mut out = vec![
+        indices
+            .iter()
+            .map(|&i| names[i].as_str())
+            .collect::<Vec<_>>()
+            .join(" | "),
+    ];
+    for row in cells.iter().skip(skip).take(take) {
+        out.push(
+            indices
+                .iter()
+                .map(|&i| row.get(i).map_or("-", String::as_str))
+                .collect::<Vec<_>>()
+                .join(" | "),
+        );
+    }
+    if total > take {
+        let hidden = total - take;
+        out.push(match end {
+            TableEnd::Head(_) => format!("...+{hidden} more rows ({total} total)"),
+            TableEnd::Tail(_) => format!("...{hidden} earlier rows omitted ({total} total)"),
+        });
+    }
+
+    if let Some(status) = names.iter().position(|n| n == "STATUS") {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for row in &cells {
+            let s = row.get(status).map_or("", String::as_str);
+            match counts.iter_mut().find(|(k, _)| *k == s) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((s, 1)),
+            }
+        }
+        if counts.len() > 1 {
+            let summary = counts
+                .iter()
+                .map(|(s, n)| format!("{n} {s}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            out.push(format!("[{total} rows: {summary}]"));
+        }
+    }
+
+    out.join("\n")
+}
+
+/// Compact `helm status`: keep the release summary, cap the NOTES section.
+fn compact_helm_status(raw_output: &str, max_notes: usize) -> String {
+    let trimmed = raw_output.trim();
+    if trimmed.is_empty() {
+        return "No status output".to_string();
+    }
+
+    let mut out = Vec::new();
+    let mut notes: Option<usize> = None;
+    let mut omitted = 0usize;
+    for line in trimmed.lines() {
+        if line.trim_end() == "NOTES:" {
+            notes = Some(0);
+            out.push(line.trim_end());
+            continue;
+        }
+        match notes.as_mut() {
+            Some(count) if *count >= max_notes => omitted += 1,
+            Some(count) => {
+                *count += 1;
+                out.push(line.trim_end());
+            }
+            None => out.push(line.trim_end()),
+        }
+    }
+
+    let mut text = out.join("\n");
+    if omitted > 0 {
+        text.push_str(&format!("\n...+{omitted} more NOTES lines"));
+    }
+    text
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // classify -----------------------------------------------------------
+
+    #[test]
+    fn classifies_kube_commands() {
+        assert_eq!(classify("kubectl get pods"), Some(KubeCommand::Get));
+        assert_eq!(classify("kubectl -n prod get pods"), Some(KubeCommand::Get));
+        assert_eq!(
+            classify("kubectl describe pod api"),
+            Some(KubeCommand::Describe)
+        );
+        assert_eq!(
+            classify("kubectl logs api -c app --tail 500"),
+            Some(KubeCommand::Logs)
+        );
+        assert_eq!(
+            classify("kubectl rollout status deploy/api"),
+            Some(KubeCommand::RolloutStatus)
+        );
+        assert_eq!(classify("helm ls -A"), Some(KubeCommand::HelmList));
+        assert_eq!(classify("helm status api"), Some(KubeCommand::HelmStatus));
+        assert_eq!(
+            classify("helm history api --max 20"),
+            Some(KubeCommand::HelmHistory)
+        );
+        assert_eq!(classify("kubectl apply -f x.yaml"), None);
+        assert_eq!(classify("helm install api ./chart"), None);
+    }
+
+    #[test]
+    fn skips_machine_readable_output() {
+        let opt = KubectlOptimizer::new();
+        assert!(opt.can_handle(&CommandContext::new("kubectl get pods -o wide")));
+        assert!(!opt.can_handle(&CommandContext::new("kubectl get pods -o yaml")));
+        assert!(!opt.can_handle(&CommandContext::new("kubectl get pods -ojson")));
+        assert!(!opt.can_handle(&CommandContext::new("helm list --output=json")));
+    }
+
+    // get ----------------------------------------------------------------
+
+    const PODS: &str = "\
+NAME                   READY   STATUS             RESTARTS      AGE
+api-7d9c-abcde         1/1     Running            0             2d
+api-7d9c-fghij         1/1     Running            0             2d
+worker-5f6b-klmno      0/1     CrashLoopBackOff   12 (3m ago)   1h
+worker-5f6b-pqrst      0/1     CrashLoopBackOff   11 (4m ago)   1h
+cache-0                1/1     Running            2 (1d ago)    5d
+web-6c7d-uvwxy         0/1     Running            0             30s
+migrate-x1y2z          0/1     Completed          0             3d
+new-8e9f-zzzzz         0/1     Pending            0             10s";
+
+    #[test]
+    fn get_pods_drops_healthy_and_groups_by_status() {
+        let result = compact_get(PODS, 30);
+        assert!(result.starts_with("NAME"));
+        assert!(!result.contains("api-7d9c"));
+        assert!(!result.contains("migrate-x1y2z"));
+        assert!(result.contains("CrashLoopBackOff (2):\nworker-5f6b-klmno"));
+        assert!(result.contains("Running with restarts (1):\ncache-0"));
+        assert!(result.contains("Running not ready (1):\nweb-6c7d-uvwxy"));
+        assert!(result.contains("Pending (1):\nnew-8e9f-zzzzz"));
+        assert!(result.ends_with("[+3 healthy: 2 Running, 1 Completed]"));
+    }
+
+    #[test]
+    fn get_all_healthy_is_one_line() {
+        let input = "\
+NAME          STATUS   ROLES           AGE   VERSION
+node-1        Ready    control-plane   90d   v1.29.2
+node-2        Ready    <none>          90d   v1.29.2";
+        assert_eq!(compact_get(input, 30), "All 2 healthy (2 Ready)");
+
+        let deployments = "\
+NAME     READY   UP-TO-DATE   AVAILABLE   AGE
+api      3/3     3            3           10d
+worker   1/2     2            1           10d";
+        let result = compact_get(deployments, 30);
+        assert!(result.contains("ready not ready (1):\nworker"));
+        assert!(result.contains("[+1 healthy: 1 ready]"));
+    }
+
+    #[test]
+    fn get_without_status_is_capped() {
+        let mut input = "NAME   TYPE        CLUSTER-IP   PORT(S)   AGE\n".to_string();
+        for i in 0..50 {
+            input.push_str(&format!(
+                "svc-{i}  ClusterIP   10.0.0.{i}     80/TCP    1d\n"
+            ));
+        }
+        let result = compact_get(&input, 10);
+        assert!(result.contains("svc-9"));
+        assert!(!result.contains("svc-10 "));
+        assert!(result.contains("...+40 more rows (50 total)"));
+    }
+
+    #[test]
+    fn columns_allow_single_spaces_in_names() {
+        let columns = Columns::parse("NAME   NOMINATED NODE   READINESS GATES");
+        assert_eq!(columns.names, ["NAME", "NOMINATED NODE", "READINESS GATES"]);
+    }
+
+    // describe -----------------------------------------------------------
+
+    const DESCRIBE_POD: &str = "\
+Name:             worker-5f6b-klmno
+Namespace:        default
+Priority:         0
+Node:             node-1/10.0.0.1
+Labels:           app=worker
+                  pod-template-hash=5f6b
+Annotations:      <none>
+Status:           Running
+IP:               10.1.2.3
+Controlled By:    ReplicaSet/worker-5f6b
+Containers:
+  worker:
+    Container ID:   containerd://abc
+    Image:          worker:1.2
+    Image ID:       docker.io/worker@sha256:def
+    Port:           <none>
+    State:          Waiting
+      Reason:       CrashLoopBackOff
+    Last State:     Terminated
+      Reason:       Error
+      Exit Code:    1
+    Ready:          False
+    Restart Count:  12
+    Environment:
+      LOG_LEVEL:  debug
+    Mounts:
+      /var/run/secrets from kube-api-access (ro)
+Conditions:
+  Type              Status
+  Initialized       True
+  Ready             False
+  ContainersReady   False
+  PodScheduled      True
+Volumes:
+  kube-api-access:
+    Type:                    Projected
+QoS Class:                   BestEffort
+Events:
+  Type     Reason     Age                  From               Message
+  ----     ------     ----                 ----               -------
+  Normal   Scheduled  1h                   default-scheduler  Successfully assigned default/worker to node-1
+  Normal   Pulled     1h                   kubelet            Container image pulled
+  Normal   Created    1h                   kubelet            Created container worker
+  Warning  BackOff    2m (x50 over 1h)     kubelet            Back-off restarting failed container";
+
+    #[test]
+    fn describe_keeps_state_conditions_and_events() {
+        let result = compact_describe(DESCRIBE_POD, 3);
+        assert!(result.contains("Name:             worker-5f6b-klmno"));
+        assert!(result.contains("Status:           Running"));
+        assert!(result.contains("  worker:"));
+        assert!(result.contains("Reason:       CrashLoopBackOff"));
+        assert!(result.contains("Exit Code:    1"));
+        assert!(result.contains("Restart Count:  12"));
+        assert!(result.contains("Ready             False"));
+        assert!(!result.contains("Initialized"));
+        assert!(result.contains("Back-off restarting failed container"));
+        assert!(!result.contains("Successfully assigned"));
+        assert!(result.contains("...+1 earlier events omitted"));
+
+        for dropped in [
+            "Labels",
+            "pod-template-hash",
+            "Image ID",
+            "LOG_LEVEL",
+            "Mounts",
+            "QoS",
+        ] {
+            assert!(!result.contains(dropped), "{dropped} should be dropped");
+        }
+    }
+
+    #[test]
+    fn describe_summarizes_normal_conditions() {
+        let input = "\
+Name:   node-1
+Conditions:
+  Type             Status
+  MemoryPressure   False
+  DiskPressure     True
+  Ready            True
+Events:           <none>";
+        let result = compact_describe(input, 10);
+        assert!(result.contains("DiskPressure     True"));
+        assert!(!result.contains("MemoryPressure"));
+        assert!(result.contains("Events:           <none>"));
+    }
+
+    // rollout / helm -----------------------------------------------------
+
+    #[test]
+    fn rollout_status_collapses_progress() {
+        let input = "\
+Waiting for deployment \"api\" rollout to finish: 0 of 3 updated replicas are available...
+Waiting for deployment \"api\" rollout to finish: 1 of 3 updated replicas are available...
+Waiting for deployment \"api\" rollout to finish: 1 of 3 updated replicas are available...
+Waiting for deployment \"api\" rollout to finish: 2 of 3 updated replicas are available...
+deployment \"api\" successfully rolled out";
+        let result = compact_rollout_status(input);
+        assert_eq!(result.lines().count(), 4);
+        assert!(result.contains("0 of 3"));
+        assert!(result.contains("[... 2 progress updates]"));
+        assert!(result.contains("2 of 3"));
+        assert!(result.ends_with("successfully rolled out"));
+    }
+
+    #[test]
+    fn helm_list_drops_updated_column() {
+        let input = "NAME\tNAMESPACE\tREVISION\tUPDATED                                \tSTATUS  \tCHART        \tAPP VERSION\n\
+api \tprod     \t12      \t2024-05-01 10:11:12.123456 +0000 UTC\tdeployed\tapi-1.4.0    \t1.4.0\n\
+jobs\tprod     \t3       \t2024-05-02 09:00:00.000000 +0000 UTC\tfailed  \tjobs-0.2.0   \t0.2.0";
+        let result = compact_helm_table(
+            input,
+            &[
+                "NAME",
+                "NAMESPACE",
+                "REVISION",
+                "STATUS",
+                "CHART",
+                "APP VERSION",
+            ],
+            TableEnd::Head(30),
+        );
+        assert!(result.starts_with("NAME | NAMESPACE | REVISION | STATUS | CHART | APP VERSION"));
+        assert!(result.contains("api | prod | 12 | deployed | api-1.4.0 | 1.4.0"));
+        assert!(!result.contains("UTC"));
+        assert!(result.ends_with("[2 rows: 1 deployed, 1 failed]"));
+    }
+
+    #[test]
+    fn helm_history_keeps_latest_revisions() {
+        let mut input = "REVISION\tUPDATED\tSTATUS\tCHART\tAPP VERSION\tDESCRIPTION\n".to_string();
+        for rev in 1..=15 {
+            input.push_str(&format!(
+                "{rev}\tMon May  1 10:00:00 2024\tsuperseded\tapi-1.{rev}.0\t1.{rev}.0\tUpgrade complete\n"
+            ));
+        }
+        let result = compact_helm_table(
+            &input,
+            &["REVISION", "STATUS", "CHART", "APP VERSION", "DESCRIPTION"],
+            TableEnd::Tail(5),
+        );
+        assert!(!result.contains("api-1.10.0"));
+        assert!(result.contains("15 | superseded | api-1.15.0"));
+        assert!(result.contains("...10 earlier rows omitted (15 total)"));
+    }
+
+    #[test]
+    fn helm_status_caps_notes() {
+        let mut input = "NAME: api\nLAST DEPLOYED: Mon May  1 10:00:00 2024\nNAMESPACE: prod\nSTATUS: deployed\nREVISION: 12\nNOTES:\n".to_string();
+        for i in 0..20 {
+            input.push_str(&format!("note line {i}\n"));
+        }
+        let result = compact_helm_status(&input, 5);
+        assert!(result.contains("STATUS: deployed"));
+        assert!(result.contains("note line 4"));
+        assert!(!result.contains("note line 5"));
+        assert!(result.ends_with("...+15 more NOTES lines"));
+    }
+}
@@ -2,18 +2,30 @@ use anyhow::Result;
 
 use crate::config::schema::OptimizersConfig;
 use crate::matching;
+use crate::matching::limits::{self, ExplicitLimits};
+use crate::utils::process::{OutputChunk, OutputStream};
 
 pub mod build;
+mod diagnostics;
 pub mod docker;
 pub mod file;
 pub mod generic;
 pub mod git;
+pub mod json;
+pub mod kubectl;
+mod outline;
+mod rustc;
+pub mod search;
+mod test_report;
 
 pub use build::BuildOptimizer;
 pub use docker::DockerOptimizer;
 pub use file::FileOptimizer;
 pub use generic::GenericOptimizer;
 pub use git::GitOptimizer;
+pub use json::JsonOptimizer;
+pub use kubectl::KubectlOptimizer;
+pub use search::SearchOptimizer;
 
 /// Output produced by an optimizer after post-processing raw command output.
 #[derive(Debug, Clone)]
@@ -37,6 +49,14 @@ pub struct CommandContext<'a> {
     /// The core command extracted by the matching engine.
     /// Used for matching/routing (e.g., `"git status"`).
     pub core: &'a str,
+
+    /// The raw output as time-ordered chunks tagged with their stream.
+    /// Empty when only merged text is available (e.g. in unit tests).
+    pub output: &'a [OutputChunk],
+
+    /// Output limit and format the command already asked for
+    /// (`head -n 200`, `git log --oneline -20`, `find . | head`).
+    pub explicit: ExplicitLimits,
 }
 
 impl<'a> CommandContext<'a> {
@@ -45,8 +65,32 @@ impl<'a> CommandContext<'a> {
         Self {
             original: command,
             core: matching::extract_core_command(command),
+            output: &[],
+            explicit: limits::explicit_limits(command),
         }
     }
+
+    /// Attach the tagged raw output.
+    pub fn with_output(self, output: &'a [OutputChunk]) -> Self {
+        Self { output, ..self }
+    }
+
+    /// A configured line or entry cap, raised to the command's explicit
+    /// limit so optimizers never cut output the user already bounded.
+    pub fn line_cap(&self, configured: usize) -> usize {
+        self.explicit
+            .lines
+            .map_or(configured, |n| n.max(configured))
+    }
+
+    /// Raw text written to one stream.
+    pub fn stream_text(&self, stream: OutputStream) -> String {
+        self.output
+            .iter()
+            .filter(|chunk| chunk.stream == stream)
+            .map(|chunk| chunk.text.as_str())
+            .collect()
+    }
 }
 
 /// Trait for command optimizers.
@@ -54,7 +98,10 @@ impl<'a> CommandContext<'a> {
 /// Each optimizer handles a set of commands. The router runs the command
 /// first, then passes the raw output to the optimizer for post-processing.
 /// Optimizers transform the output into a more compact representation.
-pub trait Optimizer {
+///
+/// Optimizers must be `Send`: the router runs them on a worker thread so
+/// it can abandon one that exceeds `fast_path.timeout_ms`.
+pub trait Optimizer: Send {
     fn name(&self) -> &'static str;
 
     /// Check whether this optimizer can handle the command.
@@ -62,6 +109,13 @@ pub trait Optimizer {
     /// Use `ctx.core` for prefix matching (already lowered/extracted).
     fn can_handle(&self, ctx: &CommandContext) -> bool;
 
+    /// Command to run in place of the original, e.g. to request a more
+    /// compact output format. Consulted before execution, only when
+    /// optimization is allowed. The default runs the command unchanged.
+    fn rewrite_command(&self, _ctx: &CommandContext) -> Option<String> {
+        None
+    }
+
     /// Post-process raw command output into a compact, token-efficient form.
     ///
     /// The router has already executed the command and passes the raw output.
@@ -100,11 +154,25 @@ impl OptimizerRegistry {
         if cfg.file.enabled {
             optimizers.push(Box::new(FileOptimizer::from_config(&cfg.file)));
         }
+        if cfg.search.enabled {
+            optimizers.push(Box::new(SearchOptimizer::from_config(&cfg.search)));
+        }
         if cfg.build.enabled {
             optimizers.push(Box::new(BuildOptimizer::from_config(&cfg.build)));
         }
         if cfg.docker.enabled {
-            optimizers.push(Box::new(DockerOptimizer::from_config(&cfg.docker)));
+            optimizers.push(Box::new(
+                DockerOptimizer::from_config(&cfg.docker)
+                    .with_json_limits(json::limits_for(&cfg.json, "docker inspect")),
+            ));
+        }
+        if cfg.kubectl.enabled {
+            optimizers.push(Box::new(KubectlOptimizer::from_config(&cfg.kubectl)));
+        }
+        // JSON summarizer: accepts any command, but declines output that
+        // is not JSON so the generic fallback still sees it.
+        if cfg.json.enabled {
+            optimizers.push(Box::new(JsonOptimizer::from_config(&cfg.json)));
         }
         // Generic fallback (tried last — catches everything)
         if cfg.generic.enabled {
@@ -123,11 +191,29 @@ impl OptimizerRegistry {
         self.optimizers.iter().any(|o| o.can_handle(&ctx))
     }
 
+    /// Rewritten command from the first optimizer that can handle `command`,
+    /// or `None` to run it as written.
+    pub fn rewrite_command(&self, command: &str) -> Option<String> {
+        let ctx = CommandContext::new(command);
+        self.optimizers
+            .iter()
+            .find(|o| o.can_handle(&ctx))?
+            .rewrite_command(&ctx)
+    }
+
     /// Find the first optimizer that can handle the command, post-process the
     /// raw output, and return the optimized result. Returns `None` if no
     /// optimizer matches or all matching optimizers fail.
-    pub fn optimize_first(&self, command: &str, raw_output: &str) -> Option<OptimizedOutput> {
-        let ctx = CommandContext::new(command);
+    ///
+    /// `output` is the tagged raw output, available to optimizers through
+    /// [`CommandContext::output`]; it may be empty.
+    pub fn optimize_first_with_output(
+        &self,
+        command: &str,
+        raw_output: &str,
+        output: &[OutputChunk],
+    ) -> Option<OptimizedOutput> {
+        let ctx = CommandContext::new(command).with_output(output);
 
         for optimizer in &self.optimizers {
             if !optimizer.can_handle(&ctx) {
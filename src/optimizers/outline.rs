@@ -0,0 +1,880 @@
+//! Structural summaries of whole files shown with `cat`.
+//!
+//! Keeping the head and tail of a 2000-line source file hides exactly the
+//! functions an agent is looking for. For common languages this module
+//! instead keeps every line outside function bodies — imports, types,
+//! signatures, `impl` and class blocks — with its line number, and folds
+//! bodies longer than a threshold. Lockfiles and minified files are
+//! summarized on their own, and a leading license header collapses to one
+//! line. Matching is line-based with comments and strings masked out; it
+//! does not parse the language.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::Value;
+
+// ---------------------------------------------------------------------------
+// Entry point
+// ---------------------------------------------------------------------------
+
+/// Summarize the content of the file at `path`, or `None` to fall back to
+/// head/tail truncation.
+///
+/// Files of at most `max_lines` lines are left alone unless minified.
+/// Function bodies longer than `fold_lines` are folded; when that still
+/// leaves more than `max_lines` lines, every body of two or more lines is.
+pub(crate) fn summarize_file(
+    path: &str,
+    content: &str,
+    max_lines: usize,
+    fold_lines: usize,
+) -> Option<String> {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    if is_minified(name, content) {
+        return Some(summarize_minified(name, content));
+    }
+
+    let lines: Vec<&str> = content.trim_end().lines().collect();
+    if lines.len() <= max_lines {
+        return None;
+    }
+    if let Some(summary) = summarize_lockfile(name, content, lines.len()) {
+        return Some(summary);
+    }
+
+    let language = Language::from_name(name)?;
+    let outline = Outline::new(&lines, language);
+    let mut pieces = outline.fold(fold_lines);
+    if pieces.len() > max_lines {
+        pieces = outline.fold(1);
+    }
+    // An outline that keeps most of the file (data tables, long scripts)
+    // is worse than the head and tail.
+    if pieces.len() > lines.len() / 2 {
+        return None;
+    }
+    Some(render(&lines, &pieces))
+}
+
+// ---------------------------------------------------------------------------
+// Languages and masking
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Rust,
+    Python,
+    /// JavaScript and TypeScript.
+    JavaScript,
+    Go,
+    Java,
+    CSharp,
+}
+
+impl Language {
+    fn from_name(name: &str) -> Option<Self> {
+        let (_, ext) = name.rsplit_once('.')?;
+        match ext.to_ascii_lowercase().as_str() {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => Some(Self::JavaScript),
+            "go" => Some(Self::Go),
+            "java" => Some(Self::Java),
+            "cs" => Some(Self::CSharp),
+            _ => None,
+        }
+    }
+
+    /// Delimiters that open a string, longest first.
+    fn string_delimiters(self) -> &'static [&'static str] {
+        match self {
+            Self::Python => &["\"\"\"", "'''", "\"", "'"],
+            Self::JavaScript | Self::Go => &["\"", "'", "`"],
+            // Rust `'` is a lifetime unless it closes a char literal.
+            Self::Rust => &["\""],
+            Self::Java | Self::CSharp => &["\"", "'"],
+        }
+    }
+}
+
+/// What a line ends inside of, carried to the next line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Open {
+    Code,
+    Comment,
+    Str(&'static str),
+}
+
+/// The code of `line` with comments and string contents removed, so that
+/// braces, parentheses and keywords in them are not counted.
+fn mask_line(line: &str, language: Language, open: &mut Open) -> String {
+    let bytes = line.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match *open {
+            Open::Comment => {
+                if bytes[i..].starts_with(b"*/") {
+                    *open = Open::Code;
+                    i += 2;
+                } else {
+                    i += 1;
+                }
+            }
+            Open::Str(delim) => {
+                if bytes[i] == b'\\' && delim != "`" {
+                    i += 2;
+                } else if bytes[i..].starts_with(delim.as_bytes()) {
+                    out.extend_from_slice(delim.as_bytes());
+                    *open = Open::Code;
+                    i += delim.len();
+                } else {
+                    i += 1;
+                }
+            }
+            Open::Code => {
+                let rest = &bytes[i..];
+                if language == Language::Python {
+                    if rest[0] == b'#' {
+                        break;
+                    }
+                } else if rest.starts_with(b"//") {
+                    break;
+                } else if rest.starts_with(b"/*") {
+                    *open = Open::Comment;
+                    i += 2;
+                    continue;
+                }
+                if language == Language::Rust
+                    && rest[0] == b'\''
+                    && let Some(len) = rust_char_literal(rest)
+                {
+                    out.extend_from_slice(b"''");
+                    i += len;
+                    continue;
+                }
+                if let Some(delim) = language
+                    .string_delimiters()
+                    .iter()
+                    .find(|d| rest.starts_with(d.as_bytes()))
+                {
+                    out.extend_from_slice(delim.as_bytes());
+                    *open = Open::Str(delim);
+                    i += delim.len();
+                    continue;
+                }
+                out.push(rest[0]);
+                i += 1;
+            }
+        }
+    }
+    // Ordinary strings do not span lines (Rust and Python triple-quoted
+    // strings, template literals and raw strings do).
+    if let Open::Str(delim) = *open
+        && matches!(delim, "\"" | "'")
+        && language != Language::Rust
+        && !line.ends_with('\\')
+    {
+        *open = Open::Code;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Length of a Rust char literal (`'x'`, `'\n'`, `'\u{1F600}'`) at the
+/// start of `rest`, or `None` for a lifetime.
+fn rust_char_literal(rest: &[u8]) -> Option<usize> {
+    if rest.get(1) == Some(&b'\\') {
+        let close = rest[2..].iter().position(|&b| b == b'\'')?;
+        return Some(close + 3);
+    }
+    let ch = std::str::from_utf8(&rest[1..rest.len().min(5)])
+        .ok()
+        .or_else(|| {
+            // Cut mid-character: retry on the longest valid prefix.
+            (2..rest.len().min(5))
+                .rev()
+                .find_map(|end| std::str::from_utf8(&rest[1..end]).ok())
+        })?
+        .chars()
+        .next()?;
+    let len = 1 + ch.len_utf8();
+    (rest.get(len) == Some(&b'\'')).then_some(len + 1)
+}
+
+// ---------------------------------------------------------------------------
+// Outline
+// ---------------------------------------------------------------------------
+
+/// `pub(crate)`, `pub(super)`: visibility, not a parameter list.
+static VISIBILITY_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bpub\s*\([^)]*\)").unwrap());
+
+/// Attributes and annotations: `#[derive(Debug)]`, `@Test`, `[Fact]`.
+static ATTRIBUTE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"#!?\[[^\]]*\]|@\w+(?:\([^)]*\))?|^\s*\[[^\]]*\]").unwrap());
+
+/// Blocks whose contents are outlined rather than folded.
+static CONTAINER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b(?:impl|trait|mod|struct|enum|union|class|interface|namespace|record|extern|object)\b",
+    )
+    .unwrap()
+});
+
+/// Blocks that are statements, not definitions, when they start a signature.
+const CONTROL_KEYWORDS: &[&str] = &[
+    "if", "else", "for", "foreach", "while", "do", "loop", "switch", "match", "select", "try",
+    "catch", "finally", "using", "lock", "return", "defer", "go",
+];
+
+/// One line of output: a kept source line, a folded body, or a note
+/// standing in for a run of lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Line(usize),
+    Fold { indent: String, count: usize },
+    Note { line: usize, text: String },
+}
+
+struct Outline<'a> {
+    lines: &'a [&'a str],
+    language: Language,
+    /// Masked code of each line.
+    code: Vec<String>,
+    /// Whether each line starts inside a multi-line string.
+    in_string: Vec<bool>,
+    /// Brace depth before each line, plus the depth after the last.
+    depth: Vec<usize>,
+    /// Lines taken by a leading license header, and its summary.
+    license: Option<(usize, String)>,
+}
+
+impl<'a> Outline<'a> {
+    fn new(lines: &'a [&'a str], language: Language) -> Self {
+        let mut open = Open::Code;
+        let mut code = Vec::with_capacity(lines.len());
+        let mut in_string = Vec::with_capacity(lines.len());
+        let mut depth = vec![0usize];
+        for line in lines {
+            in_string.push(matches!(open, Open::Str(_)));
+            let masked = mask_line(line, language, &mut open);
+            let mut d = *depth.last().unwrap_or(&0);
+            for b in masked.bytes() {
+                match b {
+                    b'{' => d += 1,
+                    b'}' => d = d.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            depth.push(d);
+            code.push(masked);
+        }
+        let license = license_header(lines, &code);
+        Self {
+            lines,
+            language,
+            code,
+            in_string,
+            depth,
+            license,
+        }
+    }
+
+    /// Outline with bodies longer than `fold_lines` folded.
+    fn fold(&self, fold_lines: usize) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        if let Some((end, text)) = &self.license {
+            pieces.push(Piece::Note {
+                line: 0,
+                text: text.clone(),
+            });
+            start = *end;
+        }
+        if self.language == Language::Python {
+            self.fold_indented(start, fold_lines, &mut pieces);
+        } else {
+            self.fold_braces(start, fold_lines, &mut pieces);
+        }
+        pieces
+    }
+
+    fn fold_braces(&self, start: usize, fold_lines: usize, pieces: &mut Vec<Piece>) {
+        let n = self.lines.len();
+        let mut statement: Option<usize> = None;
+        let mut i = start;
+        while i < n {
+            pieces.push(Piece::Line(i));
+            let before = self.depth[i];
+            let trimmed = self.code[i].trim();
+
+            if self.depth[i + 1] > before {
+                let first = statement.unwrap_or(i);
+                let signature = self.code[first..=i].join(" ");
+                statement = None;
+                if is_function(&signature)
+                    && let Some(close) = (i + 1..n).find(|&j| self.depth[j + 1] <= before)
+                {
+                    self.push_body(i + 1, close, fold_lines, pieces);
+                    pieces.push(Piece::Line(close));
+                    i = close + 1;
+                    continue;
+                }
+            } else if trimmed.is_empty() || ATTRIBUTE_RE.replace_all(trimmed, "").trim().is_empty()
+            {
+                // Comments, blank lines and attributes do not start a
+                // signature.
+            } else if trimmed.ends_with([';', '}', ',']) {
+                statement = None;
+            } else if statement.is_none() {
+                statement = Some(i);
+            }
+            i += 1;
+        }
+    }
+
+    fn fold_indented(&self, start: usize, fold_lines: usize, pieces: &mut Vec<Piece>) {
+        let n = self.lines.len();
+        let mut i = start;
+        while i < n {
+            pieces.push(Piece::Line(i));
+            let trimmed = self.code[i].trim_start();
+            let is_def = !self.in_string[i]
+                && (trimmed.starts_with("def ") || trimmed.starts_with("async def "));
+            let Some(colon) = is_def.then(|| self.signature_end(i)).flatten() else {
+                i += 1;
+                continue;
+            };
+            for j in i + 1..=colon {
+                pieces.push(Piece::Line(j));
+            }
+
+            let indent = indent_width(self.lines[i]);
+            let mut end = colon;
+            for j in colon + 1..n {
+                if self.in_string[j] || self.lines[j].trim().is_empty() {
+                    continue;
+                }
+                if indent_width(self.lines[j]) <= indent {
+                    break;
+                }
+                end = j;
+            }
+
+            let mut body_start = colon + 1;
+            // Keep a one-line docstring: it says what the function does.
+            if body_start <= end && is_one_line_docstring(self.lines[body_start]) {
+                pieces.push(Piece::Line(body_start));
+                body_start += 1;
+            }
+            self.push_body(body_start, end + 1, fold_lines, pieces);
+            i = end + 1;
+        }
+    }
+
+    /// Last line of a `def` signature: where its parentheses close and the
+    /// line ends with `:`. `None` for one-line definitions.
+    fn signature_end(&self, def: usize) -> Option<usize> {
+        let mut parens = 0i32;
+        for j in def..self.lines.len().min(def + 50) {
+            for b in self.code[j].bytes() {
+                match b {
+                    b'(' | b'[' | b'{' => parens += 1,
+                    b')' | b']' | b'}' => parens -= 1,
+                    _ => {}
+                }
+            }
+            if parens <= 0 {
+                return self.code[j].trim_end().ends_with(':').then_some(j);
+            }
+        }
+        None
+    }
+
+    /// Lines `start..end` as a fold when there are more than `fold_lines`,
+    /// otherwise as they are.
+    fn push_body(&self, start: usize, end: usize, fold_lines: usize, pieces: &mut Vec<Piece>) {
+        let count = end.saturating_sub(start);
+        if count > fold_lines.max(1) {
+            let indent = self.lines[start..end]
+                .iter()
+                .find(|l| !l.trim().is_empty())
+                .map(|l| l[..l.len() - l.trim_start().len()].to_string())
+                .unwrap_or_default();
+            pieces.push(Piece::Fold { indent, count });
+        } else {
+            pieces.extend((start..end).map(Piece::Line));
+        }
+    }
+}
+
+/// Whether a block with this masked signature is a function (or closure)
+/// rather than a container such as an `impl`, class or namespace.
+fn is_function(signature: &str) -> bool {
+    let signature = ATTRIBUTE_RE.replace_all(signature, "");
+    let signature = VISIBILITY_RE.replace_all(&signature, "");
+    let Some(paren) = signature.find('(') else {
+        return false;
+    };
+    let first = signature
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .find(|w| !w.is_empty())
+        .unwrap_or("");
+    !CONTROL_KEYWORDS.contains(&first) && !CONTAINER_RE.is_match(&signature[..paren])
+}
+
+fn indent_width(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_one_line_docstring(line: &str) -> bool {
+    let t = line.trim();
+    ["\"\"\"", "'''"]
+        .iter()
+        .any(|q| t.len() >= 6 && t.starts_with(q) && t.ends_with(q))
+}
+
+fn render(lines: &[&str], pieces: &[Piece]) -> String {
+    let width = lines.len().to_string().len();
+    let folded = pieces
+        .iter()
+        .filter(|p| matches!(p, Piece::Fold { .. }))
+        .count();
+    let mut out = vec![format!(
+        "[outline of {} lines, {folded} bodies folded; view a range with sed -n 'START,ENDp']",
+        lines.len()
+    )];
+    for piece in pieces {
+        let line = match piece {
+            Piece::Line(i) => format!("{:>width$}  {}", i + 1, lines[*i]),
+            Piece::Fold { indent, count } => format!("{:width$}  {indent}... {count} lines", ""),
+            Piece::Note { line, text } => format!("{:>width$}  {text}", line + 1),
+        };
+        out.push(line.trim_end().to_string());
+    }
+    out.join("\n")
+}
+
+// ---------------------------------------------------------------------------
+// License headers
+// ---------------------------------------------------------------------------
+
+/// Comment lines before the first line of code that make up a license.
+const MIN_LICENSE_LINES: usize = 5;
+
+/// A leading comment block that mentions a license or copyright: the line
+/// after it and a one-line summary naming the license when recognizable.
+fn license_header(lines: &[&str], code: &[String]) -> Option<(usize, String)> {
+    let start = usize::from(lines.first().is_some_and(|l| l.starts_with("#!")));
+    let mut end = start;
+    while end < lines.len() && code[end].trim().is_empty() {
+        end += 1;
+    }
+    while end > start && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    if end - start < MIN_LICENSE_LINES {
+        return None;
+    }
+
+    let text = lines[start..end].join("\n").to_ascii_lowercase();
+    if !text.contains("license") && !text.contains("copyright") {
+        return None;
+    }
+    let name = lines[start..end]
+        .iter()
+        .find_map(|l| {
+            l.split_once("SPDX-License-Identifier:")
+                .map(|(_, id)| id.trim().trim_end_matches("*/").trim().to_string())
+        })
+        .or_else(|| {
+            [
+                ("apache license", "Apache"),
+                ("permission is hereby granted, free of charge", "MIT"),
+                ("mit license", "MIT"),
+                ("gnu lesser general public license", "LGPL"),
+                ("gnu affero general public license", "AGPL"),
+                ("gnu general public license", "GPL"),
+                ("mozilla public license", "MPL"),
+                ("redistribution and use in source and binary forms", "BSD"),
+            ]
+            .iter()
+            .find(|(needle, _)| text.contains(needle))
+            .map(|(_, name)| name.to_string())
+        });
+    let summary = match name {
+        Some(name) => format!("[license header, lines {}-{end}: {name}]", start + 1),
+        None => format!("[license header, lines {}-{end}]", start + 1),
+    };
+    // A shebang stays as line 1.
+    (start == 0).then_some((end, summary))
+}
+
+// ---------------------------------------------------------------------------
+// Lockfiles
+// ---------------------------------------------------------------------------
+
+/// Packages named in a lockfile summary before the rest are counted.
+const MAX_LOCK_PACKAGES: usize = 20;
+
+/// `name version` of every package in a known lockfile, as one summary.
+fn summarize_lockfile(name: &str, content: &str, total_lines: usize) -> Option<String> {
+    let packages = match name.to_ascii_lowercase().as_str() {
+        "cargo.lock" | "poetry.lock" | "uv.lock" | "pdm.lock" => toml_packages(content),
+        "package-lock.json" | "npm-shrinkwrap.json" => npm_packages(content)?,
+        "composer.lock" => composer_packages(content)?,
+        "yarn.lock" => yarn_packages(content),
+        "pnpm-lock.yaml" => pnpm_packages(content),
+        "go.sum" => go_sum_modules(content),
+        "gemfile.lock" => gemfile_packages(content),
+        _ => return None,
+    };
+
+    let mut out = vec![format!(
+        "[lockfile {name}: {} packages, {total_lines} lines]",
+        packages.len()
+    )];
+    if !packages.is_empty() {
+        let mut listed = packages
+            .iter()
+            .take(MAX_LOCK_PACKAGES)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ");
+        if packages.len() > MAX_LOCK_PACKAGES {
+            listed.push_str(&format!(
+                ", ... +{} more",
+                packages.len() - MAX_LOCK_PACKAGES
+            ));
+        }
+        out.push(listed);
+    }
+    Some(out.join("\n"))
+}
+
+/// `[[package]]` tables with `name` and `version` keys.
+fn toml_packages(content: &str) -> Vec<String> {
+    let mut packages = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            packages.extend(current.take().map(|(n, v)| format!("{n} {v}")));
+            if line == "[[package]]" {
+                current = Some(Default::default());
+            }
+        } else if let Some((n, v)) = current.as_mut()
+            && let Some((key, value)) = line.split_once('=')
+        {
+            let value = value.trim().trim_matches('"').to_string();
+            match key.trim() {
+                "name" => *n = value,
+                "version" => *v = value,
+                _ => {}
+            }
+        }
+    }
+    packages.extend(current.map(|(n, v)| format!("{n} {v}")));
+    packages
+}
+
+fn npm_packages(content: &str) -> Option<Vec<String>> {
+    let value: Value = serde_json::from_str(content).ok()?;
+    let map = value
+        .get("packages")
+        .or_else(|| value.get("dependencies"))?
+        .as_object()?;
+    Some(
+        map.iter()
+            .filter(|(key, _)| !key.is_empty())
+            .map(|(key, entry)| {
+                let name = key.rsplit("node_modules/").next().unwrap_or(key);
+                let version = entry.get("version").and_then(Value::as_str).unwrap_or("");
+                format!("{name} {version}").trim_end().to_string()
+            })
+            .collect(),
+    )
+}
+
+fn composer_packages(content: &str) -> Option<Vec<String>> {
+    let value: Value = serde_json::from_str(content).ok()?;
+    Some(
+        ["packages", "packages-dev"]
+            .iter()
+            .filter_map(|key| value.get(key)?.as_array())
+            .flatten()
+            .filter_map(|p| {
+                let name = p.get("name")?.as_str()?;
+                let version = p.get("version").and_then(Value::as_str).unwrap_or("");
+                Some(format!("{name} {version}").trim_end().to_string())
+            })
+            .collect(),
+    )
+}
+
+/// Unindented `"name@^1.0.0", "name@^1.1.0":` entries and their `version`.
+fn yarn_packages(content: &str) -> Vec<String> {
+    let mut packages = Vec::new();
+    let mut current: Option<String> = None;
+    for line in content.lines() {
+        if !line.starts_with([' ', '#']) && line.ends_with(':') {
+            let spec = line.trim_end_matches(':').split(',').next().unwrap_or("");
+            let spec = spec.trim().trim_matches('"');
+            current = Some(split_at_version(spec).0.to_string());
+        } else if let Some(name) = &current
+            && let Some(version) = line.trim().strip_prefix("version")
+        {
+            let version = version.trim_start_matches(':').trim().trim_matches('"');
+            packages.push(format!("{name} {version}"));
+            current = None;
+        }
+    }
+    packages
+}
+
+/// Keys under `packages:` such as `/name@1.0.0:` or `'@scope/x@1.0.0':`.
+fn pnpm_packages(content: &str) -> Vec<String> {
+    let mut packages = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        if !line.starts_with(' ') && !line.is_empty() {
+            in_packages = line == "packages:";
+            continue;
+        }
+        if in_packages && indent_width(line) == 2 && line.ends_with(':') {
+            let key = line.trim().trim_end_matches(':').trim_matches('\'');
+            let (name, version) = split_at_version(key.trim_start_matches('/'));
+            packages.push(format!("{name} {version}").trim_end().to_string());
+        }
+    }
+    packages
+}
+
+/// `name@version`, allowing a leading `@` scope.
+fn split_at_version(spec: &str) -> (&str, &str) {
+    match spec[1.min(spec.len())..].find('@') {
+        Some(at) => (&spec[..at + 1], &spec[at + 2..]),
+        None => (spec, ""),
+    }
+}
+
+/// `module version hash` lines, once per module version.
+fn go_sum_modules(content: &str) -> Vec<String> {
+    let mut modules: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let mut words = line.split_whitespace();
+        let (Some(module), Some(version)) = (words.next(), words.next()) else {
+            continue;
+        };
+        let entry = format!("{module} {}", version.trim_end_matches("/go.mod"));
+        if !modules.contains(&entry) {
+            modules.push(entry);
+        }
+    }
+    modules
+}
+
+/// `    name (1.2.3)` lines under `specs:`.
+fn gemfile_packages(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter(|l| indent_width(l) == 4)
+        .filter_map(|l| {
+            let (name, version) = l.trim().split_once(" (")?;
+            Some(format!("{name} {}", version.trim_end_matches(')')))
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Minified files
+// ---------------------------------------------------------------------------
+
+/// Lines at least this long mark a bundled or minified file.
+const MINIFIED_LINE_LENGTH: usize = 1000;
+
+/// Characters of a minified file shown in its summary.
+const MINIFIED_PREVIEW_CHARS: usize = 200;
+
+fn is_minified(name: &str, content: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    if lower.contains(".min.") {
+        return true;
+    }
+    let bundled = [".js", ".mjs", ".cjs", ".css", ".map"]
+        .iter()
+        .any(|ext| lower.ends_with(ext));
+    let lines = content.lines().count().max(1);
+    bundled
+        && content.len() / lines >= MINIFIED_LINE_LENGTH / 2
+        && content.lines().any(|l| l.len() >= MINIFIED_LINE_LENGTH)
+}
+
+fn summarize_minified(name: &str, content: &str) -> String {
+    let preview: String = content
+        .trim()
+        .chars()
+        .take(MINIFIED_PREVIEW_CHARS)
+        .collect();
+    format!(
+        "[minified {name}: {} bytes on {} lines]\n{preview}...",
+        content.len(),
+        content.lines().count()
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(n: usize, indent: &str) -> String {
+        (0..n)
+            .map(|i| format!("{indent}let x{i} = {i};\n"))
+            .collect()
+    }
+
+    #[test]
+    fn rust_outline_folds_long_bodies_and_keeps_signatures() {
+        let source = format!(
+            "use std::fmt;\n\n#[derive(Debug)]\npub(crate) struct Point {{\n    x: i32,\n}}\n\n\
+             impl Point {{\n    pub fn new() -> Self {{\n{}        Self {{ x: 0 }}\n    }}\n\n    \
+             fn short(&self) -> i32 {{\n        self.x\n    }}\n}}\n\n\
+             fn main() {{\n{}}}\n",
+            body(30, "        "),
+            body(40, "    ")
+        );
+        let result = summarize_file("src/point.rs", &source, 20, 10).unwrap();
+
+        assert!(result.starts_with("[outline of 89 lines, 2 bodies folded"));
+        assert!(result.contains(" 1  use std::fmt;"));
+        assert!(result.contains(" 4  pub(crate) struct Point {"));
+        assert!(result.contains(" 5      x: i32,"));
+        assert!(result.contains(" 9      pub fn new() -> Self {"));
+        assert!(result.contains("            ... 31 lines"));
+        assert!(result.contains("41      }"));
+        // Short bodies are kept.
+        assert!(result.contains("44          self.x"));
+        assert!(result.contains("48  fn main() {"));
+        assert!(result.contains("        ... 40 lines"));
+        assert!(!result.contains("let x5 = 5"));
+    }
+
+    #[test]
+    fn braces_in_strings_and_comments_are_ignored() {
+        let source = format!(
+            "fn a() {{\n    let s = \"{{{{\"; // }}\n    let c = '{{';\n{}}}\n\nfn b<'a>(x: &'a str) {{\n{}}}\n",
+            body(20, "    "),
+            body(20, "    ")
+        );
+        let result = summarize_file("x.rs", &source, 10, 5).unwrap();
+        assert!(result.contains("    ... 22 lines"));
+        assert!(result.contains("fn b<'a>(x: &'a str) {"));
+        assert!(result.contains("    ... 20 lines"));
+    }
+
+    #[test]
+    fn python_outline_keeps_classes_decorators_and_docstrings() {
+        let lines: String = (0..25).map(|i| format!("        y = {i}\n")).collect();
+        let source = format!(
+            "import os\n\n\nclass Store:\n    \"\"\"A store.\"\"\"\n\n    @property\n    \
+             def path(self,\n             other):\n        \"\"\"Where it lives.\"\"\"\n{lines}\n    \
+             def name(self):\n        return 'x'\n\n\ndef main():\n    text = \"\"\"\nnot code\n\"\"\"\n{}",
+            lines.replace("        ", "    ")
+        );
+        let result = summarize_file("store.py", &source, 20, 5).unwrap();
+
+        assert!(result.contains(" 4  class Store:"));
+        assert!(result.contains(" 7      @property"));
+        assert!(result.contains(" 9               other):"));
+        assert!(result.contains("10          \"\"\"Where it lives.\"\"\""));
+        assert!(result.contains("            ... 25 lines"));
+        assert!(result.contains("    def name(self):"));
+        assert!(result.contains("        return 'x'"));
+        assert!(result.contains("def main():"));
+        assert!(result.contains("    ... 28 lines"));
+        assert!(!result.contains("not code"));
+    }
+
+    #[test]
+    fn typescript_and_csharp_signatures() {
+        let ts = format!(
+            "import {{ a }} from './a';\n\nexport class Service {{\n  constructor(private db: Db) {{}}\n\n  \
+             async load(id: string): Promise<Item> {{\n{}  }}\n}}\n\nexport const handler = async (req) => {{\n{}}};\n",
+            body(20, "    "),
+            body(20, "  ")
+        );
+        let result = summarize_file("service.ts", &ts, 10, 5).unwrap();
+        assert!(result.contains("export class Service {"));
+        assert!(result.contains("async load(id: string): Promise<Item> {"));
+        assert!(result.contains("export const handler = async (req) => {"));
+        assert_eq!(result.matches("... 20 lines").count(), 2);
+
+        let cs = format!(
+            "namespace App\n{{\n    [Serializable]\n    public class Job\n    {{\n        public void Run()\n        {{\n{}        }}\n    }}\n}}\n",
+            body(30, "            ")
+        );
+        let result = summarize_file("Job.cs", &cs, 10, 5).unwrap();
+        assert!(result.contains("public class Job"));
+        assert!(result.contains("public void Run()"));
+        assert!(result.contains("... 30 lines"));
+    }
+
+    #[test]
+    fn license_header_collapses_to_one_line() {
+        let header = "// Copyright 2024 Example Corp.\n//\n// Licensed under the Apache License, Version 2.0\n\
+                      // you may not use this file except in compliance.\n// See LICENSE.\n\n";
+        let source = format!(
+            "{header}package main\n\nfunc main() {{\n{}}}\n",
+            body(40, "\t")
+        );
+        let result = summarize_file("main.go", &source, 20, 10).unwrap();
+        assert!(result.contains(" 1  [license header, lines 1-5: Apache]"));
+        assert!(!result.contains("Example Corp"));
+        assert!(result.contains(" 7  package main"));
+        assert!(result.contains("func main() {"));
+    }
+
+    #[test]
+    fn lockfiles_list_packages() {
+        let mut lock = String::from("version = 3\n\n");
+        for i in 0..30 {
+            lock.push_str(&format!(
+                "[[package]]\nname = \"crate{i}\"\nversion = \"1.0.{i}\"\nsource = \"registry\"\n\n"
+            ));
+        }
+        let result = summarize_file("Cargo.lock", &lock, 50, 10).unwrap();
+        assert!(result.starts_with("[lockfile Cargo.lock: 30 packages, 151 lines]"));
+        assert!(result.contains("crate0 1.0.0, crate1 1.0.1"));
+        assert!(result.ends_with(", ... +10 more"));
+
+        let yarn: String = (0..30)
+            .map(|i| {
+                format!("\"@scope/pkg{i}@^1.0.0\":\n  version \"1.0.{i}\"\n  resolved \"x\"\n\n")
+            })
+            .collect();
+        let result = summarize_file("yarn.lock", &yarn, 50, 10).unwrap();
+        assert!(result.contains("@scope/pkg0 1.0.0"));
+    }
+
+    #[test]
+    fn minified_files_are_summarized_whatever_their_length() {
+        let bundle = "var a=1;".repeat(500);
+        let result = summarize_file("dist/app.js", &bundle, 100, 10).unwrap();
+        assert!(result.starts_with("[minified app.js: 4000 bytes on 1 lines]"));
+        assert!(result.len() < 300);
+    }
+
+    #[test]
+    fn short_unknown_and_data_files_fall_back() {
+        assert!(summarize_file("main.rs", "fn main() {}\n", 100, 10).is_none());
+        let text = "line\n".repeat(200);
+        assert!(summarize_file("notes.txt", &text, 100, 10).is_none());
+        // Nothing to fold: a long table keeps most of the file.
+        let table = format!("const T: [u8; 200] = [\n{}];\n", "    1,\n".repeat(200));
+        assert!(summarize_file("table.rs", &table, 100, 10).is_none());
+    }
+}
@@ -0,0 +1,601 @@
+//! Structured compaction of rustc / cargo diagnostics.
+//!
+//! Line heuristics cut multi-line rustc diagnostics apart. This module parses
+//! whole diagnostics — header, `-->` primary span, snippet, notes and help —
+//! from human, `--message-format=short` and `--message-format=json` output,
+//! then renders errors in full and warnings summarized by lint.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+// ---------------------------------------------------------------------------
+// Diagnostic model
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Error,
+    Warning,
+}
+
+/// One complete compiler diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Diagnostic {
+    level: Level,
+    /// Error code or lint name (`E0308`, `unused_variables`, `clippy::x`).
+    code: Option<String>,
+    message: String,
+    /// Primary span as `file:line:col`.
+    location: Option<String>,
+    /// Every line of the rendered diagnostic.
+    body: Vec<String>,
+}
+
+/// Everything recognized in a cargo run.
+#[derive(Debug, Default)]
+struct CargoReport {
+    diagnostics: Vec<Diagnostic>,
+    /// Diagnostics dropped because an identical one was already seen.
+    duplicates: usize,
+    /// `Compiling` / `Checking` / download progress lines.
+    steps: usize,
+    /// `Finished ...` / `error: could not compile ...` lines.
+    summary: Vec<String>,
+}
+
+impl CargoReport {
+    fn push(&mut self, diagnostic: Diagnostic) {
+        // The same source compiled for several targets (lib, bin, tests)
+        // reports the same diagnostic once per target.
+        if self.diagnostics.iter().any(|d| {
+            d.level == diagnostic.level
+                && d.message == diagnostic.message
+                && d.location == diagnostic.location
+                && (d.location.is_some() || d.body == diagnostic.body)
+        }) {
+            self.duplicates += 1;
+        } else {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Parsing
+// ---------------------------------------------------------------------------
+
+static HEADER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(error|warning)(?:\[([A-Za-z0-9_:]+)\])?: (.+)$").unwrap());
+
+static SHORT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\S.*?):(\d+):(\d+): (error|warning)(?:\[([A-Za-z0-9_:]+)\])?: (.+)$").unwrap()
+});
+
+static LOCATION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*--> (\S+:\d+:\d+)").unwrap());
+
+/// `#[warn(unused_variables)]` notes and clippy lint links name the lint.
+static LINT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"#\[(?:warn|deny|forbid)\(([\w:]+)\)\]|rust-clippy/[^#\s]*#(\w+)").unwrap()
+});
+
+static BACKTICK_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`[^`]*`").unwrap());
+
+/// Cargo progress lines, counted as build steps.
+const STEP_PREFIXES: &[&str] = &[
+    "Compiling ",
+    "Checking ",
+    "Documenting ",
+    "Downloading ",
+    "Downloaded ",
+    "Updating ",
+    "Locking ",
+    "Adding ",
+    "Fresh ",
+    "Installing ",
+    "Replacing ",
+    "Blocking ",
+    "Packaging ",
+    "Verifying ",
+];
+
+/// Header-shaped lines that are cargo summaries, not diagnostics.
+fn is_summary_header(line: &str) -> bool {
+    line.starts_with("error: could not compile")
+        || line.starts_with("error: aborting due to")
+        || line.starts_with("warning: build failed")
+        || (line.starts_with("warning: ")
+            && line.contains(" generated ")
+            && line.contains("warning"))
+}
+
+/// Parse cargo output. JSON message lines (`--message-format=json*`) and
+/// human-readable lines may be mixed, as with `json-render-diagnostics`.
+fn parse(raw_output: &str) -> CargoReport {
+    let mut report = CargoReport::default();
+    let mut current: Option<Diagnostic> = None;
+
+    let finish = |report: &mut CargoReport, current: &mut Option<Diagnostic>| {
+        if let Some(mut diagnostic) = current.take() {
+            if diagnostic.code.is_none() && diagnostic.level == Level::Warning {
+                diagnostic.code = lint_name(&diagnostic.body);
+            }
+            report.push(diagnostic);
+        }
+    };
+
+    for line in raw_output.lines() {
+        let line = line.trim_end();
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with('{')
+            && let Ok(value) = serde_json::from_str::<serde_json::Value>(trimmed)
+        {
+            finish(&mut report, &mut current);
+            parse_json_message(&value, &mut report);
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            finish(&mut report, &mut current);
+            continue;
+        }
+
+        if STEP_PREFIXES.iter().any(|p| trimmed.starts_with(p)) {
+            finish(&mut report, &mut current);
+            report.steps += 1;
+            continue;
+        }
+        if trimmed.starts_with("Finished ") {
+            finish(&mut report, &mut current);
+            report.summary.push(trimmed.to_string());
+            continue;
+        }
+
+        if is_summary_header(line) {
+            finish(&mut report, &mut current);
+            if line.starts_with("error: could not compile") {
+                report.summary.push(line.to_string());
+            }
+            continue;
+        }
+
+        if let Some(caps) = SHORT_RE.captures(line) {
+            finish(&mut report, &mut current);
+            report.push(Diagnostic {
+                level: parse_level(&caps[4]),
+                code: caps.get(5).map(|m| m.as_str().to_string()),
+                message: caps[6].to_string(),
+                location: Some(format!("{}:{}:{}", &caps[1], &caps[2], &caps[3])),
+                body: vec![line.to_string()],
+            });
+            continue;
+        }
+
+        if let Some(caps) = HEADER_RE.captures(line) {
+            finish(&mut report, &mut current);
+            current = Some(Diagnostic {
+                level: parse_level(&caps[1]),
+                code: caps.get(2).map(|m| m.as_str().to_string()),
+                message: caps[3].to_string(),
+                location: None,
+                body: vec![line.to_string()],
+            });
+            continue;
+        }
+
+        if let Some(diagnostic) = current.as_mut() {
+            if diagnostic.location.is_none()
+                && let Some(caps) = LOCATION_RE.captures(line)
+            {
+                diagnostic.location = Some(caps[1].to_string());
+            }
+            diagnostic.body.push(line.to_string());
+        }
+        // Anything else (`For more information ...`, build script chatter)
+        // is dropped.
+    }
+    finish(&mut report, &mut current);
+    report
+}
+
+fn parse_level(level: &str) -> Level {
+    if level == "error" {
+        Level::Error
+    } else {
+        Level::Warning
+    }
+}
+
+/// Lint name from a warning's notes, if rustc printed it.
+fn lint_name(body: &[String]) -> Option<String> {
+    body.iter().find_map(|line| {
+        let caps = LINT_RE.captures(line)?;
+        match (caps.get(1), caps.get(2)) {
+            (Some(lint), _) => Some(lint.as_str().to_string()),
+            (None, Some(lint)) => Some(format!("clippy::{}", lint.as_str())),
+            _ => None,
+        }
+    })
+}
+
+/// Handle one `--message-format=json` line.
+fn parse_json_message(value: &serde_json::Value, report: &mut CargoReport) {
+    match value["reason"].as_str() {
+        Some("compiler-message") => {}
+        Some("compiler-artifact" | "build-script-executed") => {
+            report.steps += 1;
+            return;
+        }
+        Some("build-finished") => {
+            let status = if value["success"].as_bool() == Some(true) {
+                "Build finished successfully"
+            } else {
+                "Build failed"
+            };
+            report.summary.push(status.to_string());
+            return;
+        }
+        _ => return,
+    }
+
+    let message = &value["message"];
+    let level = match message["level"].as_str() {
+        Some("error") => Level::Error,
+        Some("warning") => Level::Warning,
+        _ => return,
+    };
+    let text = message["message"].as_str().unwrap_or_default().to_string();
+    if text.starts_with("aborting due to") || text.ends_with("warnings emitted") {
+        return;
+    }
+
+    let location = message["spans"].as_array().and_then(|spans| {
+        spans
+            .iter()
+            .find(|s| s["is_primary"].as_bool() == Some(true))
+            .map(|s| {
+                format!(
+                    "{}:{}:{}",
+                    s["file_name"].as_str().unwrap_or("?"),
+                    s["line_start"],
+                    s["column_start"]
+                )
+            })
+    });
+    let code = message["code"]["code"].as_str().map(str::to_string);
+    let body: Vec<String> = match message["rendered"].as_str() {
+        Some(rendered) => rendered.trim_end().lines().map(str::to_string).collect(),
+        None => {
+            let header = match &code {
+                Some(code) => format!("{}[{code}]: {text}", level_name(level)),
+                None => format!("{}: {text}", level_name(level)),
+            };
+            let mut body = vec![header];
+            body.extend(location.iter().map(|l| format!(" --> {l}")));
+            body
+        }
+    };
+
+    report.push(Diagnostic {
+        level,
+        code,
+        message: text,
+        location,
+        body,
+    });
+}
+
+fn level_name(level: Level) -> &'static str {
+    match level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Rendering
+// ---------------------------------------------------------------------------
+
+/// Locations listed per warning group before the rest are counted.
+const MAX_GROUP_LOCATIONS: usize = 3;
+
+/// A set of warnings sharing a lint (or, without one, a message shape).
+struct WarningGroup<'a> {
+    label: String,
+    key: String,
+    example: &'a str,
+    locations: Vec<&'a str>,
+    count: usize,
+}
+
+/// Compact cargo output by whole diagnostics.
+///
+/// Errors are kept complete (snippet, notes, help) until `max_error_lines`
+/// is used; later errors still get their header and primary span. Warnings
+/// are deduplicated and summarized by lint code, at most `max_warnings`
+/// groups. Returns `None` when the output holds no rustc diagnostics or
+/// cargo summary, so the caller can fall back to line heuristics.
+pub(crate) fn compact_cargo_output(
+    raw_output: &str,
+    max_error_lines: usize,
+    max_warnings: usize,
+) -> Option<String> {
+    let report = parse(raw_output);
+    if report.diagnostics.is_empty() && report.summary.is_empty() {
+        return None;
+    }
+
+    let mut out: Vec<String> = Vec::new();
+    if report.steps > 0 {
+        out.push(format!("[{} build steps]", report.steps));
+    }
+
+    let errors: Vec<&Diagnostic> = report
+        .diagnostics
+        .iter()
+        .filter(|d| d.level == Level::Error)
+        .collect();
+    if !errors.is_empty() {
+        out.push(format!("ERRORS ({}):", errors.len()));
+        let mut used = 0usize;
+        let mut condensed = false;
+        for error in &errors {
+            if !condensed && (used == 0 || used + error.body.len() <= max_error_lines) {
+                used += error.body.len();
+                out.extend(error.body.iter().cloned());
+                continue;
+            }
+            if !condensed {
+                out.push("[remaining errors condensed]".to_string());
+                condensed = true;
+            }
+            out.push(match &error.location {
+                Some(location) => format!("{} --> {location}", error.body[0]),
+                None => error.body[0].clone(),
+            });
+        }
+    }
+
+    let warnings: Vec<&Diagnostic> = report
+        .diagnostics
+        .iter()
+        .filter(|d| d.level == Level::Warning)
+        .collect();
+    if !warnings.is_empty() {
+        let groups = group_warnings(&warnings);
+        out.push(format!(
+            "WARNINGS ({}, {} kinds):",
+            warnings.len(),
+            groups.len()
+        ));
+        for group in groups.iter().take(max_warnings) {
+            out.push(render_group(group));
+        }
+        if groups.len() > max_warnings {
+            let rest = &groups[max_warnings..];
+            out.push(format!(
+                "...+{} more warning kinds ({} warnings)",
+                rest.len(),
+                rest.iter().map(|g| g.count).sum::<usize>()
+            ));
+        }
+    }
+
+    if report.duplicates > 0 {
+        out.push(format!(
+            "[{} duplicate diagnostics removed]",
+            report.duplicates
+        ));
+    }
+    out.extend(report.summary);
+    Some(out.join("\n"))
+}
+
+/// Group warnings by lint, largest group first.
+fn group_warnings<'a>(warnings: &[&'a Diagnostic]) -> Vec<WarningGroup<'a>> {
+    // rustc only names the lint on its first occurrence, so learn the lint
+    // for each message shape and apply it to the rest.
+    let shape = |d: &Diagnostic| BACKTICK_RE.replace_all(&d.message, "`_`").into_owned();
+    let learned: Vec<(String, &str)> = warnings
+        .iter()
+        .filter_map(|d| Some((shape(d), d.code.as_deref()?)))
+        .collect();
+
+    let mut groups: Vec<WarningGroup> = Vec::new();
+    for warning in warnings {
+        let message_shape = shape(warning);
+        let code = warning.code.as_deref().or_else(|| {
+            learned
+                .iter()
+                .find(|(s, _)| *s == message_shape)
+                .map(|(_, c)| *c)
+        });
+        let key = code.map_or(message_shape, str::to_string);
+        match groups.iter_mut().find(|g| g.key == key) {
+            Some(group) => {
+                group.count += 1;
+                group.locations.extend(warning.location.as_deref());
+            }
+            None => groups.push(WarningGroup {
+                label: code.unwrap_or("warning").to_string(),
+                key,
+                example: &warning.message,
+                locations: warning.location.as_deref().into_iter().collect(),
+                count: 1,
+            }),
+        }
+    }
+    groups.sort_by_key(|g| std::cmp::Reverse(g.count));
+    groups
+}
+
+fn render_group(group: &WarningGroup) -> String {
+    let mut line = if group.count > 1 {
+        format!("{} \u{d7}{}: {}", group.label, group.count, group.example)
+    } else {
+        format!("{}: {}", group.label, group.example)
+    };
+    if !group.locations.is_empty() {
+        let shown: Vec<&str> = group
+            .locations
+            .iter()
+            .take(MAX_GROUP_LOCATIONS)
+            .copied()
+            .collect();
+        line.push_str(&format!(" \u{2014} {}", shown.join(", ")));
+        if group.locations.len() > MAX_GROUP_LOCATIONS {
+            line.push_str(&format!(
+                ", +{} more",
+                group.locations.len() - MAX_GROUP_LOCATIONS
+            ));
+        }
+    }
+    line
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HUMAN: &str = "\
+   Compiling demo v0.1.0 (/tmp/demo)
+warning: unused variable: `x`
+ --> src/main.rs:2:9
+  |
+2 |     let x = 5;
+  |         ^ help: if this is intentional, prefix it with an underscore: `_x`
+  |
+  = note: `#[warn(unused_variables)]` on by default
+
+warning: unused variable: `y`
+ --> src/main.rs:3:9
+  |
+3 |     let y = 6;
+  |         ^ help: if this is intentional, prefix it with an underscore: `_y`
+
+error[E0308]: mismatched types
+ --> src/main.rs:4:18
+  |
+4 |     let z: u32 = \"a\";
+  |            ---   ^^^ expected `u32`, found `&str`
+  |            |
+  |            expected due to this
+
+For more information about this error, try `rustc --explain E0308`.
+warning: `demo` (bin \"demo\") generated 2 warnings
+error: could not compile `demo` (bin \"demo\") due to 1 previous error; 2 warnings emitted";
+
+    #[test]
+    fn parses_whole_human_diagnostics() {
+        let report = parse(HUMAN);
+        assert_eq!(report.steps, 1);
+        assert_eq!(report.diagnostics.len(), 3);
+        let error = &report.diagnostics[2];
+        assert_eq!(error.level, Level::Error);
+        assert_eq!(error.code.as_deref(), Some("E0308"));
+        assert_eq!(error.location.as_deref(), Some("src/main.rs:4:18"));
+        assert_eq!(error.body.len(), 7);
+        assert_eq!(
+            report.diagnostics[0].code.as_deref(),
+            Some("unused_variables")
+        );
+        assert_eq!(report.diagnostics[1].code, None);
+    }
+
+    #[test]
+    fn keeps_errors_complete_and_groups_warnings_by_lint() {
+        let result = compact_cargo_output(HUMAN, 60, 10).unwrap();
+        assert!(result.starts_with("[1 build steps]\nERRORS (1):\nerror[E0308]: mismatched types"));
+        assert!(result.contains("  |            expected due to this"));
+        assert!(result.contains(
+            "WARNINGS (2, 1 kinds):\nunused_variables \u{d7}2: unused variable: `x` \u{2014} src/main.rs:2:9, src/main.rs:3:9"
+        ));
+        assert!(!result.contains("For more information"));
+        assert!(!result.contains("generated 2 warnings"));
+        assert!(result.ends_with("error: could not compile `demo` (bin \"demo\") due to 1 previous error; 2 warnings emitted"));
+    }
+
+    #[test]
+    fn condenses_errors_past_the_line_budget() {
+        let mut input = String::new();
+        for i in 1..=5 {
+            input.push_str(&format!(
+                "error[E0425]: cannot find value `v{i}` in this scope\n --> src/lib.rs:{i}:5\n  |\n{i} |     v{i}\n  |     ^^ not found in this scope\n\n"
+            ));
+        }
+        let result = compact_cargo_output(&input, 12, 10).unwrap();
+        assert!(result.contains("ERRORS (5):"));
+        assert!(result.contains("  |     ^^ not found in this scope"));
+        assert!(result.contains("[remaining errors condensed]"));
+        assert!(
+            result
+                .contains("error[E0425]: cannot find value `v5` in this scope --> src/lib.rs:5:5")
+        );
+    }
+
+    #[test]
+    fn deduplicates_across_targets() {
+        let warning = "warning: unused import: `std::fmt`\n --> src/lib.rs:1:5\n  |\n1 | use std::fmt;\n  |     ^^^^^^^^\n\n";
+        let input = format!("{warning}{warning}    Finished `dev` profile in 0.5s");
+        let result = compact_cargo_output(&input, 60, 10).unwrap();
+        assert!(result.contains("WARNINGS (1, 1 kinds):"));
+        assert!(result.contains("[1 duplicate diagnostics removed]"));
+        assert!(result.ends_with("Finished `dev` profile in 0.5s"));
+    }
+
+    #[test]
+    fn clippy_lints_are_named_from_their_links() {
+        let input = "\
+warning: unneeded `return` statement
+ --> src/lib.rs:3:5
+  |
+3 |     return 1;
+  |     ^^^^^^^^
+  |
+  = help: for further information visit https://rust-lang.github.io/rust-clippy/master/index.html#needless_return
+";
+        let result = compact_cargo_output(input, 60, 10).unwrap();
+        assert!(result.contains("clippy::needless_return: unneeded `return` statement"));
+    }
+
+    #[test]
+    fn parses_short_format() {
+        let input = "\
+src/main.rs:2:9: warning: unused variable: `x`
+src/main.rs:3:9: warning: unused variable: `y`
+src/main.rs:4:18: error[E0308]: mismatched types: expected `u32`, found `&str`
+error: could not compile `demo` (bin \"demo\") due to 1 previous error";
+        let result = compact_cargo_output(input, 60, 10).unwrap();
+        assert!(result.contains(
+            "ERRORS (1):\nsrc/main.rs:4:18: error[E0308]: mismatched types: expected `u32`, found `&str`"
+        ));
+        assert!(result.contains("warning \u{d7}2: unused variable: `x`"));
+    }
+
+    #[test]
+    fn parses_json_format() {
+        let input = r#"{"reason":"compiler-artifact","package_id":"dep 1.0.0","target":{"name":"dep"}}
+{"reason":"compiler-message","package_id":"demo","message":{"message":"unused variable: `x`","code":{"code":"unused_variables","explanation":null},"level":"warning","spans":[{"file_name":"src/main.rs","line_start":2,"column_start":9,"is_primary":true}],"rendered":"warning: unused variable: `x`\n --> src/main.rs:2:9\n"}}
+{"reason":"compiler-message","package_id":"demo","message":{"message":"aborting due to 1 previous error","code":null,"level":"error","spans":[],"rendered":"error: aborting due to 1 previous error\n"}}
+{"reason":"compiler-message","package_id":"demo","message":{"message":"mismatched types","code":{"code":"E0308"},"level":"error","spans":[{"file_name":"src/main.rs","line_start":4,"column_start":18,"is_primary":true}],"rendered":null}}
+{"reason":"build-finished","success":false}"#;
+        let result = compact_cargo_output(input, 60, 10).unwrap();
+        assert!(result.starts_with(
+            "[1 build steps]\nERRORS (1):\nerror[E0308]: mismatched types\n --> src/main.rs:4:18"
+        ));
+        assert!(result.contains("unused_variables: unused variable: `x` \u{2014} src/main.rs:2:9"));
+        assert!(!result.contains("aborting"));
+        assert!(result.ends_with("Build failed"));
+    }
+
+    #[test]
+    fn returns_none_without_diagnostics() {
+        assert!(compact_cargo_output("Diff in src/main.rs at line 1:", 60, 10).is_none());
+    }
+}
@@ -0,0 +1,1215 @@
+use anyhow::Result;
+use regex::{Regex, RegexBuilder};
+
+use crate::config::schema::SearchOptimizerConfig;
+use crate::matching::shell;
+use crate::optimizers::{CommandContext, OptimizedOutput, Optimizer};
+use crate::preprocessing::path_filter;
+use crate::utils::token_counter::estimate_tokens;
+
+// ---------------------------------------------------------------------------
+// Command parsing
+// ---------------------------------------------------------------------------
+
+/// Recognized search tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchTool {
+    /// grep / egrep / fgrep
+    Grep,
+    /// ripgrep
+    Rg,
+    /// the silver searcher
+    Ag,
+    /// git grep
+    GitGrep,
+}
+
+/// Shape of the search output, chosen by flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchMode {
+    /// `path:line:text` match lines (the default)
+    Lines,
+    /// `-c` / `--count` — `path:count` per file
+    Count,
+    /// `-l` / `-L` / `--files` — one path per line
+    FilesOnly,
+    /// `rg --json` — NDJSON match events
+    Json,
+}
+
+/// What the search command asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SearchCommand {
+    tool: SearchTool,
+    mode: SearchMode,
+    /// The search pattern, used to find the match column in long lines.
+    pattern: Option<String>,
+    fixed_strings: bool,
+    ignore_case: bool,
+    /// `rg --heading` style output: a path line followed by its matches.
+    heading: bool,
+    /// Whether output lines are expected to start with a file path.
+    with_filename: bool,
+}
+
+/// Options that take a separate value word, per tool. Short options listed
+/// here also take the rest of a bundle as their value (`-A3`, `-efoo`).
+fn value_options(tool: SearchTool) -> &'static [&'static str] {
+    match tool {
+        SearchTool::Grep => &[
+            "-e",
+            "-f",
+            "-m",
+            "-A",
+            "-B",
+            "-C",
+            "-d",
+            "-D",
+            "--regexp",
+            "--file",
+            "--max-count",
+            "--after-context",
+            "--before-context",
+            "--context",
+            "--include",
+            "--exclude",
+            "--exclude-dir",
+            "--label",
+        ],
+        SearchTool::Rg => &[
+            "-e",
+            "-f",
+            "-g",
+            "-t",
+            "-T",
+            "-m",
+            "-A",
+            "-B",
+            "-C",
+            "-M",
+            "-E",
+            "-j",
+            "-r",
+            "-d",
+            "--regexp",
+            "--file",
+            "--glob",
+            "--iglob",
+            "--type",
+            "--type-not",
+            "--type-add",
+            "--max-count",
+            "--after-context",
+            "--before-context",
+            "--context",
+            "--max-columns",
+            "--encoding",
+            "--threads",
+            "--replace",
+            "--max-depth",
+            "--sort",
+            "--sortr",
+            "--pre",
+            "--path-separator",
+            "--context-separator",
+        ],
+        SearchTool::Ag => &[
+            "-G",
+            "-g",
+            "-m",
+            "-A",
+            "-B",
+            "-C",
+            "-p",
+            "--file-search-regex",
+            "--max-count",
+            "--after",
+            "--before",
+            "--context",
+            "--ignore",
+            "--ignore-dir",
+            "--path-to-ignore",
+            "--depth",
+        ],
+        SearchTool::GitGrep => &[
+            "-e",
+            "-f",
+            "-m",
+            "-A",
+            "-B",
+            "-C",
+            "-O",
+            "--max-count",
+            "--after-context",
+            "--before-context",
+            "--context",
+            "--max-depth",
+            "--open-files-in-pager",
+        ],
+    }
+}
+
+/// Parse a search command, or `None` if it is not one we handle.
+fn parse_command(core: &str) -> Option<SearchCommand> {
+    let words = shell::split_words(core);
+    let program = words.first()?.to_ascii_lowercase();
+    let program = program.rsplit(['/', '\\']).next().unwrap_or(&program);
+    let (tool, args) = match program {
+        "grep" | "egrep" | "fgrep" => (SearchTool::Grep, &words[1..]),
+        "rg" => (SearchTool::Rg, &words[1..]),
+        "ag" => (SearchTool::Ag, &words[1..]),
+        "git" if words.get(1).is_some_and(|w| w == "grep") => (SearchTool::GitGrep, &words[2..]),
+        _ => return None,
+    };
+    let values = value_options(tool);
+
+    let mut cmd = SearchCommand {
+        tool,
+        mode: SearchMode::Lines,
+        pattern: None,
+        fixed_strings: program == "fgrep",
+        ignore_case: false,
+        heading: false,
+        with_filename: tool != SearchTool::Grep,
+    };
+    let mut filename_flag: Option<bool> = None;
+    let mut recursive = false;
+    let mut positionals: Vec<&str> = Vec::new();
+    let mut explicit_pattern = false;
+
+    let mut i = 0;
+    let mut options_done = false;
+    while let Some(word) = args.get(i) {
+        i += 1;
+        if options_done || !word.starts_with('-') || word == "-" {
+            positionals.push(word);
+            continue;
+        }
+        if word == "--" {
+            options_done = true;
+            continue;
+        }
+
+        // Collect (flag, inline value) pairs from long options and bundles.
+        let mut flags: Vec<(String, Option<String>)> = Vec::new();
+        if word.starts_with("--") {
+            match word.split_once('=') {
+                Some((flag, value)) => flags.push((flag.to_string(), Some(value.to_string()))),
+                None => flags.push((word.to_string(), None)),
+            }
+        } else {
+            let chars: Vec<char> = word[1..].chars().collect();
+            for (pos, c) in chars.iter().enumerate() {
+                let flag = format!("-{c}");
+                if values.contains(&flag.as_str()) {
+                    let rest: String = chars[pos + 1..].iter().collect();
+                    flags.push((flag, (!rest.is_empty()).then_some(rest)));
+                    break;
+                }
+                flags.push((flag, None));
+            }
+        }
+
+        for (flag, inline) in flags {
+            let value = if values.contains(&flag.as_str()) {
+                match inline {
+                    Some(v) => Some(v),
+                    None => {
+                        i += 1;
+                        args.get(i - 1).cloned()
+                    }
+                }
+            } else {
+                None
+            };
+            apply_flag(&mut cmd, &flag, value, &mut filename_flag, &mut recursive);
+            // `-e` gives the pattern; `rg --files` takes none.
+            if matches!(flag.as_str(), "-e" | "--regexp") && tool != SearchTool::Ag
+                || flag == "--files" && tool == SearchTool::Rg
+            {
+                explicit_pattern = true;
+            }
+        }
+    }
+
+    // Without `-e`, the first positional word is the pattern.
+    let operands = if explicit_pattern {
+        &positionals[..]
+    } else {
+        if let Some(first) = positionals.first() {
+            cmd.pattern = Some(first.to_string());
+        }
+        positionals.get(1..).unwrap_or(&[])
+    };
+
+    cmd.with_filename = filename_flag.unwrap_or(match tool {
+        SearchTool::Grep => {
+            recursive || operands.len() > 1 || operands.iter().any(|o| o.contains(['*', '?']))
+        }
+        // rg and ag omit the path when searching exactly one file.
+        SearchTool::Rg | SearchTool::Ag => !(operands.len() == 1 && looks_like_file(operands[0])),
+        SearchTool::GitGrep => true,
+    });
+    Some(cmd)
+}
+
+/// Apply one parsed flag to the command description.
+fn apply_flag(
+    cmd: &mut SearchCommand,
+    flag: &str,
+    value: Option<String>,
+    filename_flag: &mut Option<bool>,
+    recursive: &mut bool,
+) {
+    let tool = cmd.tool;
+    match flag {
+        "-e" | "--regexp" if tool != SearchTool::Ag => cmd.pattern = value,
+        "-c" | "--count" | "--count-matches" => cmd.mode = SearchMode::Count,
+        "-l"
+        | "-L"
+        | "--files-with-matches"
+        | "--files-without-match"
+        | "--name-only"
+        | "--files" => cmd.mode = SearchMode::FilesOnly,
+        "--json" if tool == SearchTool::Rg => cmd.mode = SearchMode::Json,
+        "-i" | "--ignore-case" | "-S" | "--smart-case" => cmd.ignore_case = true,
+        "-F" | "--fixed-strings" | "-Q" | "--literal" => cmd.fixed_strings = true,
+        "--heading" | "-p" | "--pretty" if tool == SearchTool::Rg => cmd.heading = true,
+        "--group" if tool == SearchTool::Ag => cmd.heading = true,
+        "-H" | "--with-filename" => *filename_flag = Some(true),
+        "-h" | "--no-filename" if tool != SearchTool::Rg || flag == "--no-filename" => {
+            *filename_flag = Some(false)
+        }
+        "-I" if tool == SearchTool::Rg => *filename_flag = Some(false),
+        "-r" | "-R" | "--recursive" | "--dereference-recursive" if tool == SearchTool::Grep => {
+            *recursive = true
+        }
+        _ => {}
+    }
+}
+
+/// Whether a path operand names a single file rather than a directory.
+fn looks_like_file(operand: &str) -> bool {
+    let last = operand.trim_end_matches(['/', '\\']);
+    if last.len() != operand.len() || last == "." || last == ".." {
+        return false;
+    }
+    let name = last.rsplit(['/', '\\']).next().unwrap_or(last);
+    name.contains('.') && !name.starts_with('.')
+}
+
+// ---------------------------------------------------------------------------
+// Optimizer
+// ---------------------------------------------------------------------------
+
+pub struct SearchOptimizer {
+    max_files: usize,
+    max_matches_per_file: usize,
+    max_line_length: usize,
+    max_listed_files: usize,
+}
+
+impl Default for SearchOptimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SearchOptimizer {
+    pub fn new() -> Self {
+        Self::from_config(&SearchOptimizerConfig::default())
+    }
+
+    /// Create a `SearchOptimizer` from the configuration.
+    pub fn from_config(cfg: &SearchOptimizerConfig) -> Self {
+        Self {
+            max_files: cfg.max_files,
+            max_matches_per_file: cfg.max_matches_per_file,
+            max_line_length: cfg.max_line_length,
+            max_listed_files: cfg.max_listed_files,
+        }
+    }
+
+    /// Limits raised to the command's explicit limit (`rg foo | head -50`).
+    fn capped(&self, ctx: &CommandContext) -> Self {
+        Self {
+            max_files: ctx.line_cap(self.max_files),
+            max_matches_per_file: ctx.line_cap(self.max_matches_per_file),
+            max_line_length: self.max_line_length,
+            max_listed_files: ctx.line_cap(self.max_listed_files),
+        }
+    }
+}
+
+impl Optimizer for SearchOptimizer {
+    fn name(&self) -> &'static str {
+        "search"
+    }
+
+    fn can_handle(&self, ctx: &CommandContext) -> bool {
+        parse_command(ctx.core).is_some()
+    }
+
+    fn optimize_output(&self, ctx: &CommandContext, raw_output: &str) -> Result<OptimizedOutput> {
+        let Some(cmd) = parse_command(ctx.core) else {
+            return Ok(OptimizedOutput {
+                optimized_tokens: estimate_tokens(raw_output),
+                output: raw_output.to_string(),
+                optimizer_used: self.name().to_string(),
+            });
+        };
+
+        let this = self.capped(ctx);
+        let optimized = match cmd.mode {
+            SearchMode::Lines => {
+                let results = parse_match_lines(raw_output, &cmd);
+                this.render_matches(&results, &cmd)
+            }
+            SearchMode::Json => {
+                let results = parse_json(raw_output);
+                this.render_matches(&results, &cmd)
+            }
+            SearchMode::Count => this.render_counts(raw_output),
+            SearchMode::FilesOnly => this.render_file_list(raw_output),
+        };
+
+        Ok(OptimizedOutput {
+            optimized_tokens: estimate_tokens(&optimized),
+            output: optimized,
+            optimizer_used: self.name().to_string(),
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Match parsing
+// ---------------------------------------------------------------------------
+
+/// One output line belonging to a file: a match or a context line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MatchLine {
+    line: Option<u64>,
+    /// 1-based byte column of the match, when the tool reported it.
+    column: Option<usize>,
+    text: String,
+    context: bool,
+}
+
+/// Matches grouped under one path (empty for single-file searches).
+#[derive(Debug, Default)]
+struct FileMatches {
+    path: String,
+    lines: Vec<MatchLine>,
+}
+
+impl FileMatches {
+    fn match_count(&self) -> usize {
+        self.lines.iter().filter(|l| !l.context).count()
+    }
+}
+
+/// Parsed search output.
+#[derive(Debug, Default)]
+struct SearchResults {
+    files: Vec<FileMatches>,
+    /// Matches already collapsed by path filtering during preprocessing.
+    filtered: usize,
+    /// Lines that are not matches (errors, "Binary file ... matches").
+    other: Vec<String>,
+}
+
+impl SearchResults {
+    fn push(&mut self, path: &str, entry: MatchLine) {
+        match self.files.last_mut() {
+            Some(file) if file.path == path => file.lines.push(entry),
+            _ => self.files.push(FileMatches {
+                path: path.to_string(),
+                lines: vec![entry],
+            }),
+        }
+    }
+}
+
+/// Split `line` as `<path><sep><digits><sep><rest>`, trying `known` paths
+/// first so file names containing `-12-` are not split in the middle.
+fn split_numbered<'a>(
+    line: &'a str,
+    sep: char,
+    known: Option<&str>,
+) -> Option<(&'a str, u64, &'a str)> {
+    let parse_tail = |tail: &'a str| -> Option<(u64, &'a str)> {
+        let digits = tail.find(|c: char| !c.is_ascii_digit())?;
+        if digits == 0 || !tail[digits..].starts_with(sep) {
+            return None;
+        }
+        Some((tail[..digits].parse().ok()?, &tail[digits + 1..]))
+    };
+
+    if let Some(path) = known
+        && let Some(tail) = line.strip_prefix(path).and_then(|t| t.strip_prefix(sep))
+        && let Some((n, rest)) = parse_tail(tail)
+    {
+        return Some((&line[..path.len()], n, rest));
+    }
+
+    for (idx, c) in line.char_indices() {
+        if c != sep || idx == 0 || is_drive_prefix(line, idx) {
+            continue;
+        }
+        if let Some((n, rest)) = parse_tail(&line[idx + 1..]) {
+            return Some((&line[..idx], n, rest));
+        }
+    }
+    None
+}
+
+/// `C:` at the start of a Windows path is not a separator.
+fn is_drive_prefix(line: &str, idx: usize) -> bool {
+    idx == 1
+        && line.as_bytes()[0].is_ascii_alphabetic()
+        && matches!(line.as_bytes().get(2), Some(b'\\' | b'/'))
+}
+
+/// A prefix that plausibly names a file rather than a word of text.
+fn looks_like_path(candidate: &str) -> bool {
+    !candidate.is_empty()
+        && !candidate.starts_with(char::is_whitespace)
+        && !candidate.contains("  ")
+        && !candidate.contains('\t')
+        && (candidate.contains(['/', '\\', '.']))
+}
+
+/// Leading `N:` column after the line number (`--column` / `--vimgrep`).
+fn split_column(rest: &str) -> (Option<usize>, &str) {
+    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(0);
+    if digits > 0 && rest[digits..].starts_with(':') {
+        (rest[..digits].parse().ok(), &rest[digits + 1..])
+    } else {
+        (None, rest)
+    }
+}
+
+/// Parse line-oriented grep / rg / ag / git grep output.
+fn parse_match_lines(raw_output: &str, cmd: &SearchCommand) -> SearchResults {
+    let mut results = SearchResults::default();
+    let lines: Vec<&str> = raw_output.lines().collect();
+
+    // Confirm the filename prefix against the content: rg drops it when the
+    // input is piped, and grep adds it for globbed operands.
+    let with_filename = cmd.heading
+        || (cmd.with_filename && {
+            let candidates = lines
+                .iter()
+                .filter(|l| !l.trim().is_empty() && *l != &"--")
+                .filter(|l| path_filter::filtered_count(l).is_none())
+                .count();
+            let prefixed = lines
+                .iter()
+                .filter(|l| parse_prefixed(l, None).is_some() || is_context_line(l))
+                .count();
+            candidates > 0 && prefixed * 2 >= candidates
+        });
+
+    let mut heading: Option<String> = None;
+    let mut pending: Vec<&str> = Vec::new();
+    for line in lines {
+        if line.trim().is_empty() {
+            heading = None;
+            continue;
+        }
+        if line == "--" {
+            continue;
+        }
+        if let Some(n) = path_filter::filtered_count(line) {
+            results.filtered += n;
+            continue;
+        }
+
+        if cmd.heading {
+            match (&heading, split_unprefixed(line)) {
+                (Some(path), Some(entry)) => {
+                    let path = path.clone();
+                    results.push(&path, entry);
+                }
+                _ => heading = Some(line.to_string()),
+            }
+            continue;
+        }
+
+        if !with_filename {
+            match split_unprefixed(line) {
+                Some(entry) => results.push("", entry),
+                None => results.push(
+                    "",
+                    MatchLine {
+                        line: None,
+                        column: None,
+                        text: line.to_string(),
+                        context: false,
+                    },
+                ),
+            }
+            continue;
+        }
+
+        let known = results.files.last().map(|f| f.path.as_str());
+        match parse_prefixed(line, known) {
+            Some((path, entry)) => {
+                let path = path.to_string();
+                // Leading context of a new file can only be split once the
+                // match line has told us the path.
+                for held in pending.drain(..) {
+                    match parse_prefixed(held, Some(&path)) {
+                        Some((p, ctx)) if p == path && ctx.context => results.push(&path, ctx),
+                        _ => results.other.push(held.to_string()),
+                    }
+                }
+                results.push(&path, entry);
+            }
+            None if is_context_line(line) => pending.push(line),
+            None => results.other.push(line.to_string()),
+        }
+    }
+    results
+        .other
+        .extend(pending.into_iter().map(str::to_string));
+    results
+}
+
+/// `path-N-text` as printed for context lines by `-A` / `-B` / `-C`.
+fn is_context_line(line: &str) -> bool {
+    split_numbered(line, '-', None).is_some_and(|(path, _, _)| looks_like_path(path))
+}
+
+/// Parse `path:N:text`, `path-N-text` (context), or `path:text`.
+fn parse_prefixed<'a>(line: &'a str, known: Option<&str>) -> Option<(&'a str, MatchLine)> {
+    if let Some((path, n, rest)) = split_numbered(line, ':', known)
+        && looks_like_path(path)
+    {
+        let (column, text) = split_column(rest);
+        return Some((
+            path,
+            MatchLine {
+                line: Some(n),
+                column,
+                text: text.to_string(),
+                context: false,
+            },
+        ));
+    }
+    // Context lines are only attributed to the file being read.
+    if let Some(path) = known
+        && let Some((p, n, rest)) = split_numbered(line, '-', Some(path))
+        && p == path
+    {
+        return Some((
+            p,
+            MatchLine {
+                line: Some(n),
+                column: None,
+                text: rest.to_string(),
+                context: true,
+            },
+        ));
+    }
+    if let Some(path) = known
+        && let Some(rest) = line.strip_prefix(path)
+        && let Some(sep) = rest.chars().next()
+        && (sep == ':' || sep == '-')
+    {
+        return Some((
+            &line[..path.len()],
+            MatchLine {
+                line: None,
+                column: None,
+                text: rest[1..].to_string(),
+                context: sep == '-',
+            },
+        ));
+    }
+    let (path, text) = line.split_once(':')?;
+    if !looks_like_path(path) || path.contains(' ') {
+        return None;
+    }
+    Some((
+        path,
+        MatchLine {
+            line: None,
+            column: None,
+            text: text.to_string(),
+            context: false,
+        },
+    ))
+}
+
+/// Parse `N:text` / `N-text` from a single-file or heading-style search.
+fn split_unprefixed(line: &str) -> Option<MatchLine> {
+    let digits = line.find(|c: char| !c.is_ascii_digit())?;
+    if digits == 0 {
+        return None;
+    }
+    let sep = line[digits..].chars().next()?;
+    if sep != ':' && sep != '-' {
+        return None;
+    }
+    let (column, text) = split_column(&line[digits + 1..]);
+    Some(MatchLine {
+        line: line[..digits].parse().ok(),
+        column,
+        text: text.to_string(),
+        context: sep == '-',
+    })
+}
+
+/// Parse `rg --json` output (one JSON event per line).
+fn parse_json(raw_output: &str) -> SearchResults {
+    let mut results = SearchResults::default();
+    for line in raw_output.lines() {
+        if let Some(n) = path_filter::filtered_count(line) {
+            results.filtered += n;
+            continue;
+        }
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+            if !line.trim().is_empty() {
+                results.other.push(line.to_string());
+            }
+            continue;
+        };
+        let kind = event["type"].as_str().unwrap_or("");
+        if kind != "match" && kind != "context" {
+            continue;
+        }
+        let data = &event["data"];
+        let path = data["path"]["text"].as_str().unwrap_or("<binary path>");
+        let text = data["lines"]["text"]
+            .as_str()
+            .unwrap_or("<binary line>")
+            .trim_end_matches(['\n', '\r']);
+        let column = data["submatches"][0]["start"]
+            .as_u64()
+            .map(|start| start as usize + 1);
+        results.push(
+            path,
+            MatchLine {
+                line: data["line_number"].as_u64(),
+                column,
+                text: text.to_string(),
+                context: kind == "context",
+            },
+        );
+    }
+    results
+}
+
+// ---------------------------------------------------------------------------
+// Rendering
+// ---------------------------------------------------------------------------
+
+fn noun(n: usize, one: &'static str, many: &'static str) -> &'static str {
+    if n == 1 { one } else { many }
+}
+
+fn matches(n: usize) -> String {
+    format!("{n} {}", noun(n, "match", "matches"))
+}
+
+fn files(n: usize) -> String {
+    format!("{n} {}", noun(n, "file", "files"))
+}
+
+fn more_files(n: usize) -> String {
+    format!("...+{n} more {}", noun(n, "file", "files"))
+}
+
+/// Summary line for matches under vendored / generated directories.
+fn vendored_summary(match_count: usize, file_count: usize, segments: &[&str]) -> Option<String> {
+    if match_count == 0 {
+        return None;
+    }
+    let mut line = format!("[+{} in vendored paths", matches(match_count));
+    if file_count > 0 {
+        line.push_str(&format!(", {}", files(file_count)));
+    }
+    if !segments.is_empty() {
+        line.push_str(&format!(": {}", segments.join(", ")));
+    }
+    line.push(']');
+    Some(line)
+}
+
+/// Compile the search pattern to locate matches inside long lines.
+fn pattern_regex(cmd: &SearchCommand) -> Option<Regex> {
+    let pattern = cmd.pattern.as_deref()?;
+    let source = if cmd.fixed_strings {
+        regex::escape(pattern)
+    } else {
+        pattern.to_string()
+    };
+    RegexBuilder::new(&source)
+        .case_insensitive(cmd.ignore_case)
+        .build()
+        .or_else(|_| {
+            // Basic regex syntax (grep without -E) — fall back to a literal.
+            RegexBuilder::new(&regex::escape(pattern))
+                .case_insensitive(cmd.ignore_case)
+                .build()
+        })
+        .ok()
+}
+
+/// Cut a long line to about `max_len` bytes centred on the match.
+fn truncate_around(text: &str, match_start: Option<usize>, max_len: usize) -> String {
+    if text.len() <= max_len || max_len == 0 {
+        return text.to_string();
+    }
+    let anchor = match_start.unwrap_or(0).min(text.len());
+    let mut start = anchor.saturating_sub(max_len / 3);
+    let mut end = (start + max_len).min(text.len());
+    start = end.saturating_sub(max_len).min(start);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+    let prefix = if start > 0 { "..." } else { "" };
+    let suffix = if end < text.len() { "..." } else { "" };
+    format!("{prefix}{}{suffix}", &text[start..end])
+}
+
+impl SearchOptimizer {
+    fn render_line(&self, entry: &MatchLine, regex: Option<&Regex>) -> String {
+        let text = entry.text.trim();
+        let text = if text.len() > self.max_line_length {
+            let offset = entry.text.len() - entry.text.trim_start().len();
+            let anchor = entry
+                .column
+                .map(|c| c.saturating_sub(1 + offset))
+                .or_else(|| regex.and_then(|re| re.find(text)).map(|m| m.start()));
+            truncate_around(text, anchor, self.max_line_length)
+        } else {
+            text.to_string()
+        };
+        let sep = if entry.context { '-' } else { ':' };
+        let rendered = match entry.line {
+            Some(n) => format!("  {n}{sep} {text}"),
+            None if entry.context => format!("  - {text}"),
+            None => format!("  {text}"),
+        };
+        rendered.trim_end().to_string()
+    }
+
+    /// Group matches by file with per-file counts and caps.
+    fn render_matches(&self, results: &SearchResults, cmd: &SearchCommand) -> String {
+        let regex = pattern_regex(cmd);
+        let mut out: Vec<String> = Vec::new();
+
+        let (vendored, files_kept): (Vec<&FileMatches>, Vec<&FileMatches>) = results
+            .files
+            .iter()
+            .partition(|f| !f.path.is_empty() && path_filter::noise_segment(&f.path).is_some());
+
+        // Single-file search: no grouping, just cap the lines.
+        if files_kept.len() == 1 && files_kept[0].path.is_empty() {
+            let file = files_kept[0];
+            let total = file.match_count();
+            out.push(matches(total));
+            let limit = self.max_matches_per_file * self.max_files.max(1);
+            out.extend(self.render_entries(file, limit, regex.as_ref()));
+        } else {
+            let total: usize = results.files.iter().map(FileMatches::match_count).sum();
+            let total = total + results.filtered;
+            let file_total = results.files.len();
+            if total == 0 && results.other.is_empty() {
+                return "No matches".to_string();
+            }
+            out.push(format!("{} in {}", matches(total), files(file_total)));
+
+            for file in files_kept.iter().take(self.max_files) {
+                out.push(format!("{} ({})", file.path, file.match_count()));
+                out.extend(self.render_entries(file, self.max_matches_per_file, regex.as_ref()));
+            }
+            if files_kept.len() > self.max_files {
+                let rest = &files_kept[self.max_files..];
+                let hidden: usize = rest.iter().map(|f| f.match_count()).sum();
+                out.push(format!("{} ({})", more_files(rest.len()), matches(hidden)));
+            }
+        }
+
+        let mut segments: Vec<&str> = Vec::new();
+        for file in &vendored {
+            if let Some(seg) = path_filter::noise_segment(&file.path)
+                && !segments.contains(&seg)
+            {
+                segments.push(seg);
+            }
+        }
+        let vendored_matches: usize =
+            vendored.iter().map(|f| f.match_count()).sum::<usize>() + results.filtered;
+        out.extend(vendored_summary(
+            vendored_matches,
+            vendored.len(),
+            &segments,
+        ));
+
+        out.extend(render_other(&results.other));
+        out.join("\n")
+    }
+
+    /// Up to `limit` matches of a file, with the context lines between them.
+    fn render_entries(
+        &self,
+        file: &FileMatches,
+        limit: usize,
+        regex: Option<&Regex>,
+    ) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut shown = 0usize;
+        for entry in &file.lines {
+            if !entry.context {
+                if shown == limit {
+                    break;
+                }
+                shown += 1;
+            }
+            out.push(self.render_line(entry, regex));
+        }
+        let total = file.match_count();
+        if total > shown {
+            let hidden = total - shown;
+            out.push(format!(
+                "  ...+{hidden} more {}",
+                noun(hidden, "match", "matches")
+            ));
+        }
+        out
+    }
+
+    /// Compact `-c` output: drop zero counts, collapse vendored paths.
+    fn render_counts(&self, raw_output: &str) -> String {
+        let mut entries: Vec<(&str, usize)> = Vec::new();
+        let mut vendored = (0usize, 0usize);
+        let mut segments: Vec<&str> = Vec::new();
+        let mut zero = 0usize;
+        let mut filtered = 0usize;
+        let mut other: Vec<String> = Vec::new();
+
+        for line in raw_output.lines().filter(|l| !l.trim().is_empty()) {
+            if let Some(n) = path_filter::filtered_count(line) {
+                filtered += n;
+                continue;
+            }
+            let (path, count) = match line.rsplit_once(':') {
+                Some((path, count)) => (path, count.trim()),
+                None => ("", line.trim()),
+            };
+            let Ok(count) = count.parse::<usize>() else {
+                other.push(line.to_string());
+                continue;
+            };
+            if count == 0 {
+                zero += 1;
+            } else if let Some(seg) = path_filter::noise_segment(path) {
+                vendored.0 += count;
+                vendored.1 += 1;
+                if !segments.contains(&seg) {
+                    segments.push(seg);
+                }
+            } else {
+                entries.push((path, count));
+            }
+        }
+
+        let total: usize = entries.iter().map(|(_, n)| n).sum::<usize>() + vendored.0;
+        let file_total = entries.len() + vendored.1;
+        let mut out = vec![format!("{} in {}", matches(total), files(file_total))];
+        for (path, count) in entries.iter().take(self.max_listed_files) {
+            out.push(if path.is_empty() {
+                count.to_string()
+            } else {
+                format!("{path}: {count}")
+            });
+        }
+        if entries.len() > self.max_listed_files {
+            let rest = &entries[self.max_listed_files..];
+            let hidden = rest.iter().map(|(_, n)| n).sum();
+            out.push(format!("{} ({})", more_files(rest.len()), matches(hidden)));
+        }
+        out.extend(vendored_summary(vendored.0, vendored.1, &segments));
+        if filtered > 0 {
+            out.push(format!("[+{} in vendored paths]", files(filtered)));
+        }
+        if zero > 0 {
+            out.push(format!("[{} with no matches]", files(zero)));
+        }
+        out.extend(render_other(&other));
+        out.join("\n")
+    }
+
+    /// Compact `-l` / `--files` output: cap the list, collapse vendored paths.
+    fn render_file_list(&self, raw_output: &str) -> String {
+        let mut paths: Vec<&str> = Vec::new();
+        let mut vendored = 0usize;
+        let mut segments: Vec<&str> = Vec::new();
+
+        for line in raw_output.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some(n) = path_filter::filtered_count(line) {
+                vendored += n;
+            } else if let Some(seg) = path_filter::noise_segment(line) {
+                vendored += 1;
+                if !segments.contains(&seg) {
+                    segments.push(seg);
+                }
+            } else {
+                paths.push(line);
+            }
+        }
+
+        let mut out = vec![files(paths.len() + vendored)];
+        out.extend(
+            paths
+                .iter()
+                .take(self.max_listed_files)
+                .map(|p| p.to_string()),
+        );
+        if paths.len() > self.max_listed_files {
+            out.push(more_files(paths.len() - self.max_listed_files));
+        }
+        if vendored > 0 {
+            let mut line = format!("[+{} in vendored paths", files(vendored));
+            if !segments.is_empty() {
+                line.push_str(&format!(": {}", segments.join(", ")));
+            }
+            line.push(']');
+            out.push(line);
+        }
+        out.join("\n")
+    }
+}
+
+/// Non-match lines (errors, binary file notices), capped.
+fn render_other(other: &[String]) -> Vec<String> {
+    const MAX_OTHER: usize = 5;
+    let mut out: Vec<String> = other.iter().take(MAX_OTHER).cloned().collect();
+    if other.len() > MAX_OTHER {
+        out.push(format!("...+{} more lines", other.len() - MAX_OTHER));
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn optimize(command: &str, input: &str) -> String {
+        let opt = SearchOptimizer::new();
+        let ctx = CommandContext::new(command);
+        assert!(opt.can_handle(&ctx), "{command} should be handled");
+        opt.optimize_output(&ctx, input).unwrap().output
+    }
+
+    // parse_command ------------------------------------------------------
+
+    #[test]
+    fn parses_tools_modes_and_patterns() {
+        let cmd = parse_command("grep -rn 'fn main' src").unwrap();
+        assert_eq!(cmd.tool, SearchTool::Grep);
+        assert_eq!(cmd.mode, SearchMode::Lines);
+        assert_eq!(cmd.pattern.as_deref(), Some("fn main"));
+        assert!(cmd.with_filename);
+
+        let cmd = parse_command("rg -A3 -g '*.rs' -e todo -i").unwrap();
+        assert_eq!(cmd.pattern.as_deref(), Some("todo"));
+        assert!(cmd.ignore_case);
+
+        assert_eq!(
+            parse_command("rg --json foo").unwrap().mode,
+            SearchMode::Json
+        );
+        assert_eq!(
+            parse_command("git grep -c foo").unwrap().mode,
+            SearchMode::Count
+        );
+        assert_eq!(
+            parse_command("ag -l foo").unwrap().mode,
+            SearchMode::FilesOnly
+        );
+        assert_eq!(
+            parse_command("git grep --name-only foo").unwrap().tool,
+            SearchTool::GitGrep
+        );
+        assert!(parse_command("git log --grep foo").is_none());
+        assert!(parse_command("cargo test grep").is_none());
+    }
+
+    #[test]
+    fn filename_expectation_follows_operands() {
+        assert!(!parse_command("grep foo file.txt").unwrap().with_filename);
+        assert!(parse_command("grep foo a.txt b.txt").unwrap().with_filename);
+        assert!(parse_command("grep -H foo a.txt").unwrap().with_filename);
+        assert!(!parse_command("rg foo src/main.rs").unwrap().with_filename);
+        assert!(parse_command("rg foo src").unwrap().with_filename);
+        assert!(!parse_command("rg -I foo").unwrap().with_filename);
+    }
+
+    // line output --------------------------------------------------------
+
+    #[test]
+    fn groups_matches_by_file_with_caps() {
+        let mut input = String::new();
+        for i in 1..=12 {
+            input.push_str(&format!("src/app.rs:{i}:    let x{i} = todo!();\n"));
+        }
+        input.push_str("src/lib.rs:3:// todo: docs\n");
+        let result = optimize("grep -rn todo src", &input);
+        assert!(result.starts_with("13 matches in 2 files"));
+        assert!(result.contains("src/app.rs (12)\n  1: let x1 = todo!();"));
+        assert!(result.contains("  5: let x5 = todo!();\n  ...+7 more matches"));
+        assert!(!result.contains("x6 "));
+        assert!(result.contains("src/lib.rs (1)\n  3: // todo: docs"));
+    }
+
+    #[test]
+    fn caps_number_of_files() {
+        let mut input = String::new();
+        for i in 0..30 {
+            input.push_str(&format!("src/m{i}.rs:1:use foo;\nsrc/m{i}.rs:2:foo();\n"));
+        }
+        let result = optimize("rg -n foo", &input);
+        assert!(result.starts_with("60 matches in 30 files"));
+        assert!(result.contains("src/m19.rs (2)"));
+        assert!(!result.contains("src/m20.rs"));
+        assert!(result.contains("...+10 more files (20 matches)"));
+    }
+
+    #[test]
+    fn keeps_context_lines_and_hyphenated_paths() {
+        let input = "\
+src/my-2-file.rs-9-fn before() {}
+src/my-2-file.rs:10:fn target() {}
+src/my-2-file.rs-11-fn after() {}
+--
+src/other.rs:4:fn target() {}";
+        let result = optimize("grep -rn -C1 target src", input);
+        assert!(result.starts_with("2 matches in 2 files"));
+        assert!(result.contains("src/my-2-file.rs (1)\n  9- fn before() {}\n  10: fn target() {}"));
+        assert!(result.contains("  11- fn after() {}"));
+    }
+
+    #[test]
+    fn truncates_long_lines_around_match() {
+        let line = format!("{}NEEDLE{}", "a".repeat(500), "b".repeat(500));
+        let input = format!("dist.js:1:{line}\nsrc/x.js:2:NEEDLE\n");
+        let result = optimize("grep -rn NEEDLE .", &input);
+        let long = result.lines().find(|l| l.contains("aNEEDLEb")).unwrap();
+        assert!(long.len() < 200, "{long}");
+        assert!(long.starts_with("  1: ...a"));
+        assert!(long.ends_with("b..."));
+    }
+
+    #[test]
+    fn truncates_at_reported_column() {
+        let line = format!("{}x{}", "a".repeat(300), "b".repeat(300));
+        let input = format!("a.txt:1:302:{line}\nb.txt:1:1:x\n");
+        let result = optimize("rg --vimgrep x", &input);
+        assert!(result.contains("ax"));
+    }
+
+    #[test]
+    fn collapses_vendored_paths() {
+        let input = "\
+src/main.rs:3:use serde;
+node_modules/serde/index.js:1:serde
+node_modules/serde/index.js:2:serde
+[4 path(s) in noise directories filtered]
+target/debug/build/x.rs:5:serde";
+        let result = optimize("grep -rn serde .", input);
+        assert!(result.starts_with("8 matches in 3 files"));
+        assert!(result.contains("src/main.rs (1)"));
+        assert!(!result.contains("index.js"));
+        assert!(
+            result.contains(
+                "[+7 matches in vendored paths, 2 files: node_modules, target/debug/build]"
+            )
+        );
+    }
+
+    #[test]
+    fn single_file_search_has_no_grouping() {
+        let mut input = String::new();
+        for i in 1..=150 {
+            input.push_str(&format!("{i}:error: thing {i}\n"));
+        }
+        let result = optimize("grep -n error log.txt", &input);
+        assert!(result.starts_with("150 matches\n  1: error: thing 1"));
+        assert!(result.contains("...+50 more matches"));
+    }
+
+    #[test]
+    fn rg_heading_output() {
+        let input = "\
+src/a.rs
+3:let foo = 1;
+9:foo();
+
+src/b.rs
+1:use foo;";
+        let result = optimize("rg --heading -n foo", input);
+        assert!(result.starts_with("3 matches in 2 files"));
+        assert!(result.contains("src/a.rs (2)\n  3: let foo = 1;\n  9: foo();"));
+    }
+
+    #[test]
+    fn keeps_errors_and_binary_notices() {
+        let input = "\
+src/a.rs:1:foo
+grep: secret: Permission denied
+Binary file target/app matches";
+        let result = optimize("grep -rn foo .", input);
+        assert!(result.contains("grep: secret: Permission denied"));
+        assert!(result.contains("Binary file target/app matches"));
+    }
+
+    // -c / -l / --json ---------------------------------------------------
+
+    #[test]
+    fn count_output_drops_zeros() {
+        let input = "\
+src/a.rs:3
+src/b.rs:0
+node_modules/x.js:40
+src/c.rs:1";
+        let result = optimize("grep -rc foo .", input);
+        assert!(result.starts_with("44 matches in 3 files"));
+        assert!(result.contains("src/a.rs: 3\nsrc/c.rs: 1"));
+        assert!(result.contains("[+40 matches in vendored paths, 1 file: node_modules]"));
+        assert!(result.contains("[1 file with no matches]"));
+    }
+
+    #[test]
+    fn file_list_is_capped() {
+        let mut input = String::new();
+        for i in 0..80 {
+            input.push_str(&format!("src/f{i}.rs\n"));
+        }
+        input.push_str("node_modules/a.js\n");
+        let result = optimize("rg -l foo", &input);
+        assert!(result.starts_with("81 files\nsrc/f0.rs"));
+        assert!(result.contains("...+30 more files"));
+        assert!(result.contains("[+1 file in vendored paths: node_modules]"));
+    }
+
+    #[test]
+    fn json_output_is_grouped() {
+        let input = r#"{"type":"begin","data":{"path":{"text":"src/a.rs"}}}
+{"type":"match","data":{"path":{"text":"src/a.rs"},"lines":{"text":"fn foo() {}\n"},"line_number":4,"absolute_offset":10,"submatches":[{"match":{"text":"foo"},"start":3,"end":6}]}}
+{"type":"end","data":{"path":{"text":"src/a.rs"},"stats":{}}}
+{"type":"summary","data":{"stats":{"matches":1}}}"#;
+        let result = optimize("rg --json foo", input);
+        assert_eq!(result, "1 match in 1 file\nsrc/a.rs (1)\n  4: fn foo() {}");
+    }
+}
@@ -0,0 +1,459 @@
+//! `go test` output, plain and `-json`.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::{TestFailure, TestReport, trim_block};
+
+/// `--- FAIL: TestName (0.00s)`, indented for subtests.
+static RESULT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*--- (PASS|FAIL|SKIP): (\S+) \(([\d.]+s)\)").unwrap());
+
+/// `    foo_test.go:12: message` from `t.Errorf` and friends.
+static LOCATION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(\S+\.go:\d+): ").unwrap());
+
+/// `ok  example.com/pkg  0.012s` and `FAIL  example.com/pkg  0.020s` (tab separated).
+static PACKAGE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(ok|FAIL)\s+(\S+)\s+(?:([\d.]+s)|\[[^\]]+\])").unwrap());
+
+/// Goroutine frames kept from a panic besides the message.
+const MAX_PANIC_LINES: usize = 6;
+
+// ---------------------------------------------------------------------------
+// Plain output
+// ---------------------------------------------------------------------------
+
+/// Parse `go test` / `go test -v` output.
+pub(super) fn parse_text(raw_output: &str) -> Option<TestReport> {
+    let mut report = TestReport::default();
+    let mut outputs: HashMap<String, Vec<&str>> = HashMap::new();
+    let mut results: Vec<(String, String)> = Vec::new();
+    let mut packages = (0usize, 0usize);
+    let mut durations = Vec::new();
+    let mut current: Option<String> = None;
+    let mut build_errors: Vec<&str> = Vec::new();
+    let mut in_build_error = false;
+    let mut verbose = false;
+
+    for line in raw_output.lines() {
+        let line = line.trim_end();
+        if let Some(caps) = RESULT_RE.captures(line) {
+            let name = caps[2].to_string();
+            results.push((caps[1].to_string(), name.clone()));
+            current = Some(name);
+            in_build_error = false;
+            continue;
+        }
+        if let Some(caps) = PACKAGE_RE.captures(line) {
+            if &caps[1] == "ok" {
+                packages.0 += 1;
+            } else {
+                packages.1 += 1;
+            }
+            durations.extend(caps.get(3).map(|m| m.as_str().to_string()));
+            current = None;
+            in_build_error = false;
+            continue;
+        }
+        if line.starts_with("=== ") || line == "PASS" || line == "FAIL" {
+            // `=== RUN`, `=== PAUSE`, `=== CONT` and the bare verdicts.
+            if let Some(name) = line.strip_prefix("=== RUN").map(str::trim) {
+                current = Some(name.to_string());
+                verbose = true;
+            }
+            continue;
+        }
+        if line.starts_with("# ") {
+            in_build_error = true;
+            build_errors.push(line);
+            continue;
+        }
+        if in_build_error {
+            build_errors.push(line);
+            continue;
+        }
+        if line.starts_with("panic: ") {
+            // Panics abort the binary before `--- FAIL` for the running test.
+            current.get_or_insert_with(|| "panic".to_string());
+        }
+        if let Some(name) = &current {
+            outputs.entry(name.clone()).or_default().push(line);
+        }
+    }
+
+    // Without `-v` a passing run prints only package lines; there is no
+    // per-test result to report.
+    let panicked = outputs
+        .values()
+        .any(|lines| lines.iter().any(|l| l.starts_with("panic: ")));
+    if results.is_empty() && !panicked && build_errors.is_empty() {
+        return None;
+    }
+
+    // Without `-v` only failures are printed, so passing tests are unknown.
+    report.passed_unknown = !verbose;
+    // Parents of failed subtests fail too; count and list only leaves.
+    let parents: HashSet<&str> = results
+        .iter()
+        .flat_map(|(_, name)| parent_names(name))
+        .collect();
+    for (status, name) in &results {
+        if parents.contains(name.as_str()) {
+            continue;
+        }
+        match status.as_str() {
+            "PASS" => report.passed += 1,
+            "SKIP" => report.skipped += 1,
+            _ => {
+                report.failed += 1;
+                report.failures.push(failure_from_output(
+                    name,
+                    outputs.get(name).map_or(&[][..], |v| v),
+                ));
+            }
+        }
+    }
+    // A panic kills the test binary before it prints `--- FAIL`.
+    let mut panicked: Vec<(&String, &Vec<&str>)> = outputs
+        .iter()
+        .filter(|(name, lines)| {
+            lines.iter().any(|l| l.starts_with("panic: "))
+                && !report.failures.iter().any(|f| &f.name == *name)
+        })
+        .collect();
+    panicked.sort();
+    for (name, lines) in panicked {
+        report.failed += 1;
+        report.failures.push(failure_from_output(name, lines));
+    }
+
+    if durations.len() == 1 {
+        report.duration = durations.pop();
+    }
+    if packages.0 + packages.1 > 1 {
+        report.notes.push(format!(
+            "Packages: {} ok, {} failed",
+            packages.0, packages.1
+        ));
+    }
+    report.notes.extend(trim_block(&build_errors));
+    Some(report)
+}
+
+// ---------------------------------------------------------------------------
+// JSON output
+// ---------------------------------------------------------------------------
+
+/// Parse `go test -json` events.
+pub(super) fn parse_json(raw_output: &str) -> Option<TestReport> {
+    let mut report = TestReport::default();
+    let mut outputs: HashMap<(String, String), Vec<String>> = HashMap::new();
+    let mut results: Vec<(String, String, String)> = Vec::new();
+    let mut packages = (0usize, 0usize);
+    let mut durations = Vec::new();
+    let mut seen_event = false;
+
+    for line in raw_output.lines() {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let Some(action) = event.get("Action").and_then(|a| a.as_str()) else {
+            continue;
+        };
+        seen_event = true;
+        let package = event
+            .get("Package")
+            .and_then(|p| p.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let test = event.get("Test").and_then(|t| t.as_str());
+        match (action, test) {
+            ("output", _) => {
+                let output = event
+                    .get("Output")
+                    .and_then(|o| o.as_str())
+                    .unwrap_or_default();
+                outputs
+                    .entry((package, test.unwrap_or_default().to_string()))
+                    .or_default()
+                    .push(output.trim_end_matches('\n').to_string());
+            }
+            ("pass" | "fail" | "skip", Some(test)) => {
+                results.push((action.to_string(), package, test.to_string()));
+            }
+            ("pass", None) => {
+                packages.0 += 1;
+                durations.extend(elapsed(&event));
+            }
+            ("fail", None) => {
+                packages.1 += 1;
+                durations.extend(elapsed(&event));
+                // A package failing without failing tests did not build or
+                // panicked outside a test.
+                if !results.iter().any(|(a, p, _)| a == "fail" && *p == package) {
+                    report.notes.push(format!("FAIL {package}"));
+                    let lines = outputs
+                        .remove(&(package, String::new()))
+                        .unwrap_or_default();
+                    let lines: Vec<&str> = lines
+                        .iter()
+                        .map(String::as_str)
+                        .filter(|l| !is_verdict(l))
+                        .collect();
+                    report.notes.extend(trim_block(&lines));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !seen_event {
+        return None;
+    }
+
+    let parents: HashSet<(&str, &str)> = results
+        .iter()
+        .flat_map(|(_, package, test)| parent_names(test).map(move |p| (package.as_str(), p)))
+        .collect();
+    for (action, package, test) in &results {
+        if parents.contains(&(package.as_str(), test.as_str())) {
+            continue;
+        }
+        match action.as_str() {
+            "pass" => report.passed += 1,
+            "skip" => report.skipped += 1,
+            _ => {
+                report.failed += 1;
+                let lines: Vec<&str> = outputs
+                    .get(&(package.clone(), test.clone()))
+                    .map(|v| v.iter().map(String::as_str).collect())
+                    .unwrap_or_default();
+                report.failures.push(failure_from_output(test, &lines));
+            }
+        }
+    }
+
+    if durations.len() == 1 {
+        report.duration = durations.pop();
+    }
+    if packages.0 + packages.1 > 1 {
+        report.notes.insert(
+            0,
+            format!("Packages: {} ok, {} failed", packages.0, packages.1),
+        );
+    }
+    Some(report)
+}
+
+/// Names of the tests enclosing subtest `name`: `A` and `A/b` for `A/b/c`.
+fn parent_names(name: &str) -> impl Iterator<Item = &str> {
+    name.match_indices('/').map(|(i, _)| &name[..i])
+}
+
+fn elapsed(event: &serde_json::Value) -> Option<String> {
+    event
+        .get("Elapsed")
+        .and_then(|e| e.as_f64())
+        .map(|e| format!("{e:.3}s"))
+}
+
+fn is_verdict(line: &str) -> bool {
+    line == "PASS" || line == "FAIL" || PACKAGE_RE.is_match(line)
+}
+
+// ---------------------------------------------------------------------------
+// Failures
+// ---------------------------------------------------------------------------
+
+/// The test's log lines with the location of the first one, or the panic
+/// message with the first goroutine frames.
+fn failure_from_output(name: &str, lines: &[&str]) -> TestFailure {
+    let mut failure = TestFailure::new(name);
+    let lines: Vec<&str> = lines
+        .iter()
+        .copied()
+        .filter(|l| !l.starts_with("=== ") && !RESULT_RE.is_match(l) && !is_verdict(l))
+        .collect();
+
+    if let Some(panic) = lines.iter().position(|l| l.starts_with("panic: ")) {
+        failure.details.push(lines[panic].to_string());
+        let mut frames = 0usize;
+        let mut iter = lines[panic + 1..].iter();
+        while let Some(line) = iter.next() {
+            // Frames are `pkg.Func(...)` followed by `\tfile.go:N +0x..`;
+            // skip the runtime and testing harness.
+            let trimmed = line.trim();
+            if trimmed.starts_with("goroutine ") || trimmed.is_empty() {
+                continue;
+            }
+            let at = iter.next().map(|l| l.trim()).unwrap_or_default();
+            if trimmed.starts_with("runtime.")
+                || trimmed.starts_with("testing.")
+                || trimmed.starts_with("panic(")
+                || trimmed.starts_with("created by ")
+            {
+                continue;
+            }
+            if failure.location.is_none() {
+                failure.location = at.split_whitespace().next().map(str::to_string);
+            }
+            failure.details.push(format!("  {trimmed}"));
+            frames += 1;
+            if frames * 2 >= MAX_PANIC_LINES {
+                break;
+            }
+        }
+        return failure;
+    }
+
+    failure.location = lines
+        .iter()
+        .find_map(|l| LOCATION_RE.captures(l).map(|caps| caps[1].to_string()));
+    let trimmed: Vec<&str> = lines.iter().map(|l| l.trim()).collect();
+    failure.details = trim_block(&trimmed);
+    failure
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbose_text_output() {
+        let input = "\
+=== RUN   TestAdd
+--- PASS: TestAdd (0.00s)
+=== RUN   TestDivide
+=== RUN   TestDivide/by_zero
+    math_test.go:21: expected error, got nil
+=== RUN   TestDivide/exact
+--- FAIL: TestDivide (0.00s)
+    --- FAIL: TestDivide/by_zero (0.00s)
+    --- PASS: TestDivide/exact (0.00s)
+=== RUN   TestSlow
+    math_test.go:30: skipping in short mode
+--- SKIP: TestSlow (0.00s)
+FAIL
+FAIL	example.com/math	0.004s
+FAIL";
+        let report = parse_text(input).unwrap();
+        assert_eq!((report.passed, report.failed, report.skipped), (2, 1, 1));
+        assert_eq!(report.duration.as_deref(), Some("0.004s"));
+        assert_eq!(
+            report.failures,
+            [TestFailure {
+                name: "TestDivide/by_zero".to_string(),
+                location: Some("math_test.go:21".to_string()),
+                details: vec!["math_test.go:21: expected error, got nil".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn text_output_with_panic_and_build_error() {
+        let input = "\
+# example.com/broken
+broken/x.go:3:2: undefined: foo
+FAIL	example.com/broken [build failed]
+=== RUN   TestIndex
+panic: runtime error: index out of range [3] with length 2 [recovered]
+
+goroutine 7 [running]:
+testing.tRunner.func1.2({0x1, 0x2})
+	/usr/lib/go/src/testing/testing.go:1545 +0x238
+panic({0x1, 0x2})
+	/usr/lib/go/src/runtime/panic.go:914 +0x21f
+example.com/math.Index(...)
+	/tmp/math/math.go:12 +0x1d
+example.com/math.TestIndex(0x0?)
+	/tmp/math/math_test.go:40 +0x25
+FAIL	example.com/math	0.006s";
+        let report = parse_text(input).unwrap();
+        assert_eq!(report.failed, 1);
+        let failure = &report.failures[0];
+        assert_eq!(failure.name, "TestIndex");
+        assert_eq!(failure.location.as_deref(), Some("/tmp/math/math.go:12"));
+        assert_eq!(
+            failure.details,
+            [
+                "panic: runtime error: index out of range [3] with length 2 [recovered]",
+                "  example.com/math.Index(...)",
+                "  example.com/math.TestIndex(0x0?)",
+            ]
+        );
+        assert_eq!(
+            report.notes,
+            [
+                "Packages: 0 ok, 2 failed",
+                "# example.com/broken",
+                "broken/x.go:3:2: undefined: foo"
+            ]
+        );
+    }
+
+    #[test]
+    fn quiet_text_output_omits_passed_count() {
+        let input = "\
+--- FAIL: TestDivide (0.00s)
+    math_test.go:21: expected error, got nil
+FAIL
+FAIL	example.com/math	0.004s
+ok  	example.com/strings	0.002s";
+        let report = parse_text(input).unwrap();
+        assert!(report.passed_unknown);
+        assert!(
+            report
+                .render(80)
+                .starts_with("Tests: 1 failed\nFAILED TestDivide (math_test.go:21)")
+        );
+        assert_eq!(report.notes, ["Packages: 1 ok, 1 failed"]);
+    }
+
+    #[test]
+    fn json_output() {
+        let input = r##"{"Action":"start","Package":"example.com/math"}
+{"Action":"run","Package":"example.com/math","Test":"TestAdd"}
+{"Action":"output","Package":"example.com/math","Test":"TestAdd","Output":"=== RUN   TestAdd\n"}
+{"Action":"pass","Package":"example.com/math","Test":"TestAdd","Elapsed":0}
+{"Action":"run","Package":"example.com/math","Test":"TestDivide"}
+{"Action":"output","Package":"example.com/math","Test":"TestDivide","Output":"=== RUN   TestDivide\n"}
+{"Action":"output","Package":"example.com/math","Test":"TestDivide","Output":"    math_test.go:21: got 2, want 3\n"}
+{"Action":"output","Package":"example.com/math","Test":"TestDivide","Output":"--- FAIL: TestDivide (0.00s)\n"}
+{"Action":"fail","Package":"example.com/math","Test":"TestDivide","Elapsed":0}
+{"Action":"skip","Package":"example.com/math","Test":"TestSlow","Elapsed":0}
+{"Action":"output","Package":"example.com/math","Output":"FAIL\n"}
+{"Action":"fail","Package":"example.com/math","Elapsed":0.005}
+{"Action":"output","Package":"example.com/broken","Output":"# example.com/broken\n"}
+{"Action":"output","Package":"example.com/broken","Output":"broken/x.go:3:2: undefined: foo\n"}
+{"Action":"output","Package":"example.com/broken","Output":"FAIL\texample.com/broken [build failed]\n"}
+{"Action":"fail","Package":"example.com/broken","Elapsed":0}"##;
+        let report = parse_json(input).unwrap();
+        assert_eq!((report.passed, report.failed, report.skipped), (1, 1, 1));
+        assert_eq!(
+            report.failures,
+            [TestFailure {
+                name: "TestDivide".to_string(),
+                location: Some("math_test.go:21".to_string()),
+                details: vec!["math_test.go:21: got 2, want 3".to_string()],
+            }]
+        );
+        assert_eq!(
+            report.notes,
+            [
+                "Packages: 0 ok, 2 failed",
+                "FAIL example.com/broken",
+                "# example.com/broken",
+                "broken/x.go:3:2: undefined: foo",
+            ]
+        );
+    }
+
+    #[test]
+    fn unrelated_output() {
+        assert!(parse_text("go: no such tool").is_none());
+        assert!(parse_text("ok  \texample.com/math\t0.004s").is_none());
+        assert!(parse_json("not json").is_none());
+    }
+}
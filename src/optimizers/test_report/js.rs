@@ -0,0 +1,321 @@
+//! Jest and Vitest output.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::{TestFailure, TestReport, counts};
+
+/// `    at Object.<anonymous> (src/sum.test.js:5:17)` or `at src/x.js:5:17`.
+static JEST_FRAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*at (?:.*\()?([^()\s]+:\d+:\d+)\)?$").unwrap());
+
+/// ` FAIL  src/sum.test.ts > sum > adds` opening a Vitest failure.
+static VITEST_FAIL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*FAIL\s+(\S+ > .+?)\s*$").unwrap());
+
+/// ` ❯ src/sum.test.ts:5:17` pointing at the failing assertion.
+static VITEST_LOCATION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*❯ (\S+:\d+:\d+)").unwrap());
+
+/// Lines of the failing assertion kept besides the message.
+const MAX_CONTEXT_LINES: usize = 6;
+
+// ---------------------------------------------------------------------------
+// Jest
+// ---------------------------------------------------------------------------
+
+/// Parse Jest output. Failures are `● Suite › test` blocks; totals come
+/// from the `Tests:` line.
+pub(super) fn parse_jest(raw_output: &str) -> Option<TestReport> {
+    let mut report = TestReport::default();
+    let mut seen_totals = false;
+    let mut blocks: Vec<(String, Vec<&str>)> = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+
+    for line in raw_output.lines() {
+        let line = line.trim_end();
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("Tests:") {
+            seen_totals = true;
+            blocks.extend(current.take());
+            for (n, label) in counts(rest) {
+                match label.as_str() {
+                    "passed" => report.passed = n,
+                    "failed" => report.failed = n,
+                    "skipped" | "todo" => report.skipped += n,
+                    _ => {}
+                }
+            }
+            continue;
+        }
+        if let Some(rest) = trimmed.strip_prefix("Time:") {
+            report.duration = Some(rest.trim().replace(' ', ""));
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix("● ") {
+            blocks.extend(current.take());
+            // `● Test suite failed to run` has no test name, but is a
+            // failure all the same.
+            current = Some((name.trim().to_string(), Vec::new()));
+            continue;
+        }
+        if trimmed.starts_with("PASS ")
+            || trimmed.starts_with("FAIL ")
+            || trimmed.starts_with("Test Suites:")
+        {
+            blocks.extend(current.take());
+            continue;
+        }
+        if let Some((_, lines)) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    blocks.extend(current);
+
+    if !seen_totals {
+        return None;
+    }
+    report.failures = blocks
+        .iter()
+        .map(|(name, lines)| jest_failure(name, lines))
+        .collect();
+    Some(report)
+}
+
+/// Message lines up to the code frame, the `>` line of the frame, and the
+/// first stack frame outside `node_modules`.
+fn jest_failure(name: &str, lines: &[&str]) -> TestFailure {
+    let mut failure = TestFailure::new(name);
+    failure.location = lines.iter().find_map(|l| {
+        JEST_FRAME_RE
+            .captures(l)
+            .map(|caps| caps[1].to_string())
+            .filter(|loc| !loc.contains("node_modules") && !loc.starts_with("node:"))
+    });
+
+    let mut kept = 0usize;
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.starts_with("at ") {
+            break;
+        }
+        if trimmed.is_empty() {
+            continue;
+        }
+        // Code frame: `  4 |   expect(x)` and `> 5 |   expect(sum(1, 2))`;
+        // keep only the failing line.
+        if trimmed.contains(" | ") || trimmed.starts_with('|') {
+            if trimmed.starts_with('>') {
+                failure.details.push(trimmed.to_string());
+            }
+            continue;
+        }
+        if kept < MAX_CONTEXT_LINES {
+            kept += 1;
+            failure.details.push(trimmed.to_string());
+        }
+    }
+    failure
+}
+
+// ---------------------------------------------------------------------------
+// Vitest
+// ---------------------------------------------------------------------------
+
+/// Parse Vitest output. Failures are ` FAIL  file > test` blocks separated
+/// by `⎯` rules; totals come from the `Tests` line.
+pub(super) fn parse_vitest(raw_output: &str) -> Option<TestReport> {
+    let mut report = TestReport::default();
+    let mut seen_totals = false;
+    let mut blocks: Vec<(String, Vec<&str>)> = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+
+    for line in raw_output.lines() {
+        let line = line.trim_end();
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("Tests ") {
+            seen_totals = true;
+            blocks.extend(current.take());
+            // `Tests  1 failed | 3 passed | 1 skipped (5)`: the trailing
+            // total is not a label of its own.
+            let rest = rest.split('(').next().unwrap_or(rest);
+            for (n, label) in counts(rest) {
+                match label.as_str() {
+                    "passed" => report.passed = n,
+                    "failed" => report.failed = n,
+                    "skipped" | "todo" => report.skipped += n,
+                    _ => {}
+                }
+            }
+            continue;
+        }
+        if let Some(rest) = trimmed.strip_prefix("Duration ") {
+            let duration = rest.split_whitespace().next().unwrap_or(rest);
+            report.duration = Some(duration.to_string());
+            continue;
+        }
+        if let Some(caps) = VITEST_FAIL_RE.captures(line) {
+            blocks.extend(current.take());
+            current = Some((caps[1].to_string(), Vec::new()));
+            continue;
+        }
+        if trimmed.starts_with('⎯') {
+            blocks.extend(current.take());
+            continue;
+        }
+        if let Some((_, lines)) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    blocks.extend(current);
+
+    if !seen_totals {
+        return None;
+    }
+    report.failures = blocks
+        .iter()
+        .map(|(name, lines)| vitest_failure(name, lines))
+        .collect();
+    Some(report)
+}
+
+/// Message and diff lines, and the `❯` location.
+fn vitest_failure(name: &str, lines: &[&str]) -> TestFailure {
+    let mut failure = TestFailure::new(name);
+    let mut kept = 0usize;
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Some(caps) = VITEST_LOCATION_RE.captures(line) {
+            if failure.location.is_none() && !caps[1].contains("node_modules") {
+                failure.location = Some(caps[1].to_string());
+            }
+            continue;
+        }
+        // The code frame adds nothing the location does not point at.
+        if trimmed
+            .split_once('|')
+            .is_some_and(|(gutter, _)| is_frame_gutter(gutter))
+        {
+            continue;
+        }
+        if kept < MAX_CONTEXT_LINES {
+            kept += 1;
+            failure.details.push(trimmed.to_string());
+        }
+    }
+    failure
+}
+
+/// The `  5` gutter of a code frame line (blank under the caret line).
+fn is_frame_gutter(gutter: &str) -> bool {
+    let gutter = gutter.trim();
+    gutter.is_empty() || gutter.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jest_report() {
+        let input = "\
+PASS src/util.test.js
+FAIL src/sum.test.js
+  ● sum › adds numbers
+
+    expect(received).toBe(expected) // Object.is equality
+
+    Expected: 4
+    Received: 3
+
+      3 | test('adds numbers', () => {
+    > 4 |   expect(sum(1, 2)).toBe(4);
+        |                     ^
+      5 | });
+
+      at Object.toBe (src/sum.test.js:4:21)
+      at Promise.then.completed (node_modules/jest-circus/build/utils.js:298:28)
+
+Test Suites: 1 failed, 1 passed, 2 total
+Tests:       1 failed, 1 skipped, 5 passed, 7 total
+Snapshots:   0 total
+Time:        1.234 s
+Ran all test suites.";
+        let report = parse_jest(input).unwrap();
+        assert_eq!((report.passed, report.failed, report.skipped), (5, 1, 1));
+        assert_eq!(report.duration.as_deref(), Some("1.234s"));
+        assert_eq!(
+            report.failures,
+            [TestFailure {
+                name: "sum › adds numbers".to_string(),
+                location: Some("src/sum.test.js:4:21".to_string()),
+                details: vec![
+                    "expect(received).toBe(expected) // Object.is equality".to_string(),
+                    "Expected: 4".to_string(),
+                    "Received: 3".to_string(),
+                    "> 4 |   expect(sum(1, 2)).toBe(4);".to_string(),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn vitest_report() {
+        let input = "\
+ ✓ src/util.test.ts (2 tests) 3ms
+ ❯ src/sum.test.ts (3 tests | 1 failed) 6ms
+   × sum > adds numbers
+
+⎯⎯⎯⎯⎯⎯⎯ Failed Tests 1 ⎯⎯⎯⎯⎯⎯⎯
+
+ FAIL  src/sum.test.ts > sum > adds numbers
+AssertionError: expected 3 to be 4 // Object.is equality
+
+- Expected
++ Received
+
+- 4
++ 3
+
+ ❯ src/sum.test.ts:5:23
+      3| describe('sum', () => {
+      4|   it('adds numbers', () => {
+      5|     expect(sum(1, 2)).toBe(4)
+       |                       ^
+      6|   })
+
+⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯[1/1]⎯
+
+ Test Files  1 failed | 1 passed (2)
+      Tests  1 failed | 3 passed | 1 skipped (5)
+   Start at  10:00:00
+   Duration  412ms (transform 20ms, setup 0ms, collect 30ms, tests 6ms)";
+        let report = parse_vitest(input).unwrap();
+        assert_eq!((report.passed, report.failed, report.skipped), (3, 1, 1));
+        assert_eq!(report.duration.as_deref(), Some("412ms"));
+        assert_eq!(report.failures.len(), 1);
+        let failure = &report.failures[0];
+        assert_eq!(failure.name, "src/sum.test.ts > sum > adds numbers");
+        assert_eq!(failure.location.as_deref(), Some("src/sum.test.ts:5:23"));
+        assert_eq!(
+            failure.details,
+            [
+                "AssertionError: expected 3 to be 4 // Object.is equality",
+                "- Expected",
+                "+ Received",
+                "- 4",
+                "+ 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn jest_output_is_not_vitest() {
+        assert!(parse_vitest("Tests:       2 passed, 2 total").is_none());
+        assert!(parse_jest("Tests:       2 passed, 2 total").is_some());
+    }
+}
@@ -0,0 +1,253 @@
+//! Structured test results.
+//!
+//! Each supported framework gets a parser that turns its output into a
+//! shared [`TestReport`] — totals, failed test names, the assertion message
+//! and location of every failure, and a trimmed panic or traceback — which
+//! is then rendered in one compact format regardless of the framework.
+
+mod go;
+mod js;
+mod pytest;
+mod rust;
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+// ---------------------------------------------------------------------------
+// Report model
+// ---------------------------------------------------------------------------
+
+/// Result of a test run, independent of the framework that produced it.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct TestReport {
+    pub passed: usize,
+    /// The output does not list passing tests (`go test` without `-v`), so
+    /// `passed` is not a count and is left out of the totals.
+    pub passed_unknown: bool,
+    pub failed: usize,
+    /// Errors outside of test bodies (pytest setup/collection errors).
+    pub errors: usize,
+    /// Ignored, skipped and todo tests.
+    pub skipped: usize,
+    /// Wall-clock time reported by the framework.
+    pub duration: Option<String>,
+    pub failures: Vec<TestFailure>,
+    /// Framework lines worth keeping verbatim (package results, build errors).
+    pub notes: Vec<String>,
+}
+
+/// One failed test.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct TestFailure {
+    pub name: String,
+    /// `file:line[:col]` of the failing assertion, when reported.
+    pub location: Option<String>,
+    /// Assertion message followed by a trimmed panic or traceback.
+    pub details: Vec<String>,
+}
+
+/// Detail lines kept per failure before the rest are counted.
+const MAX_DETAIL_LINES: usize = 12;
+
+/// Failures listed by name once the detail budget is spent.
+const MAX_NAMED_FAILURES: usize = 30;
+
+impl TestFailure {
+    fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    fn header(&self) -> String {
+        match &self.location {
+            Some(location) => format!("FAILED {} ({location})", self.name),
+            None => format!("FAILED {}", self.name),
+        }
+    }
+}
+
+impl TestReport {
+    /// Render the report: a totals line, then each failure with its details
+    /// until `max_failure_lines` detail lines have been used, then failure
+    /// names only.
+    pub fn render(&self, max_failure_lines: usize) -> String {
+        let mut out = vec![self.totals()];
+
+        let mut budget = max_failure_lines;
+        let mut named = 0usize;
+        for (index, failure) in self.failures.iter().enumerate() {
+            let details = failure.details.len().min(MAX_DETAIL_LINES);
+            if details <= budget {
+                budget -= details;
+                out.push(failure.header());
+                out.extend(
+                    failure
+                        .details
+                        .iter()
+                        .take(MAX_DETAIL_LINES)
+                        .map(|line| format!("  {line}")),
+                );
+                if failure.details.len() > MAX_DETAIL_LINES {
+                    out.push(format!(
+                        "  ...+{} more lines",
+                        failure.details.len() - MAX_DETAIL_LINES
+                    ));
+                }
+            } else if named < MAX_NAMED_FAILURES {
+                budget = 0;
+                named += 1;
+                out.push(failure.header());
+            } else {
+                out.push(format!("...+{} more failures", self.failures.len() - index));
+                break;
+            }
+        }
+
+        out.extend(self.notes.iter().cloned());
+        out.join("\n")
+    }
+
+    /// `Tests: 2 failed, 140 passed, 3 skipped (1.23s)`.
+    fn totals(&self) -> String {
+        let mut parts = Vec::new();
+        if self.failed > 0 {
+            parts.push(format!("{} failed", self.failed));
+        }
+        if self.errors > 0 {
+            parts.push(format!(
+                "{} {}",
+                self.errors,
+                if self.errors == 1 { "error" } else { "errors" }
+            ));
+        }
+        if !self.passed_unknown {
+            parts.push(format!("{} passed", self.passed));
+        }
+        if self.skipped > 0 {
+            parts.push(format!("{} skipped", self.skipped));
+        }
+        if parts.is_empty() {
+            parts.push("no results".to_string());
+        }
+        let mut line = format!("Tests: {}", parts.join(", "));
+        if let Some(duration) = &self.duration {
+            line.push_str(&format!(" ({duration})"));
+        }
+        line
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Dispatch
+// ---------------------------------------------------------------------------
+
+/// Parse test output with the parser for the command's framework.
+///
+/// Returns `None` for unsupported frameworks and for output the parser does
+/// not recognize (e.g. a build failure before any test ran), so the caller
+/// can fall back to line heuristics.
+pub(crate) fn parse_test_output(lower: &str, raw_output: &str) -> Option<TestReport> {
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    match words.as_slice() {
+        ["cargo", "nextest", ..] => rust::parse_nextest(raw_output),
+        ["cargo", "test", ..] => rust::parse_libtest(raw_output),
+        ["go", "test", rest @ ..] if rest.contains(&"-json") => go::parse_json(raw_output),
+        ["go", "test", ..] => go::parse_text(raw_output),
+        ["pytest", ..] | ["python" | "python3", "-m", "pytest", ..] => pytest::parse(raw_output),
+        ["npx" | "vitest" | "jest", ..] | ["npm" | "yarn" | "pnpm" | "bun", "test" | "run", ..] => {
+            js::parse_vitest(raw_output).or_else(|| js::parse_jest(raw_output))
+        }
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Shared helpers
+// ---------------------------------------------------------------------------
+
+static COUNT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d+) ([a-z]+(?: [a-z]+)?)").unwrap());
+
+/// `(count, label)` pairs from a summary such as `3 passed, 1 failed`.
+fn counts(line: &str) -> Vec<(usize, String)> {
+    COUNT_RE
+        .captures_iter(line)
+        .filter_map(|caps| Some((caps[1].parse().ok()?, caps[2].to_string())))
+        .collect()
+}
+
+/// Drop blank lines at both ends and trailing whitespace on each line.
+fn trim_block(lines: &[&str]) -> Vec<String> {
+    let start = lines.iter().position(|l| !l.trim().is_empty());
+    let end = lines.iter().rposition(|l| !l.trim().is_empty());
+    match (start, end) {
+        (Some(start), Some(end)) => lines[start..=end]
+            .iter()
+            .map(|l| l.trim_end().to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(name: &str, details: usize) -> TestFailure {
+        TestFailure {
+            name: name.to_string(),
+            location: Some("src/lib.rs:1:1".to_string()),
+            details: (0..details).map(|i| format!("line {i}")).collect(),
+        }
+    }
+
+    #[test]
+    fn renders_totals_failures_and_notes() {
+        let report = TestReport {
+            passed: 10,
+            failed: 1,
+            skipped: 2,
+            duration: Some("0.50s".to_string()),
+            failures: vec![failure("tests::a", 2)],
+            notes: vec!["FAIL pkg".to_string()],
+            ..TestReport::default()
+        };
+        assert_eq!(
+            report.render(80),
+            "Tests: 1 failed, 10 passed, 2 skipped (0.50s)\n\
+             FAILED tests::a (src/lib.rs:1:1)\n  line 0\n  line 1\nFAIL pkg"
+        );
+    }
+
+    #[test]
+    fn failures_past_the_budget_keep_their_names() {
+        let report = TestReport {
+            failed: 3,
+            failures: vec![failure("a", 20), failure("b", 5), failure("c", 5)],
+            ..TestReport::default()
+        };
+        let result = report.render(15);
+        assert!(result.contains("FAILED a (src/lib.rs:1:1)\n  line 0"));
+        assert!(result.contains("  line 11\n  ...+8 more lines"));
+        assert!(result.ends_with("FAILED b (src/lib.rs:1:1)\nFAILED c (src/lib.rs:1:1)"));
+    }
+
+    #[test]
+    fn dispatches_by_command() {
+        assert!(parse_test_output("ls", "test result: ok.").is_none());
+        assert!(parse_test_output("cargo test", "garbage").is_none());
+        let report = parse_test_output(
+            "cargo test --lib",
+            "test result: ok. 2 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.01s",
+        )
+        .unwrap();
+        assert_eq!(report.passed, 2);
+    }
+}
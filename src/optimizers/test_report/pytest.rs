@@ -0,0 +1,254 @@
+//! pytest output.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::{TestFailure, TestReport, counts};
+
+/// `=== FAILURES ===`, `=== ERRORS ===` and the other `=`-framed headers.
+static SECTION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^={3,} (.+?) ={3,}$").unwrap());
+
+/// `___ test_name ___` opening one failure inside a section.
+static BLOCK_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^_{3,} (.+?) _{3,}$").unwrap());
+
+/// `tests/test_x.py:12: AssertionError` closing a traceback.
+static LOCATION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\S+\.py:\d+): \w").unwrap());
+
+/// `FAILED tests/test_x.py::test_a - assert 1 == 2` in the short summary.
+static SUMMARY_ENTRY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(FAILED|ERROR) (\S+)(?: - (.*))?$").unwrap());
+
+/// Final totals: `=== 1 failed, 3 passed in 0.12s ===`, or without the
+/// frame under `-q`.
+static TOTALS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^=*\s*((?:\d+ \w+(?:, )?)+) in ([\d.]+s)(?: \([^)]*\))?\s*=*$").unwrap()
+});
+
+/// Detail lines kept per traceback besides the message.
+const MAX_CODE_LINES: usize = 6;
+
+/// Parse pytest output.
+pub(super) fn parse(raw_output: &str) -> Option<TestReport> {
+    let mut report = TestReport::default();
+    let mut seen_totals = false;
+
+    let mut section = String::new();
+    let mut block: Option<(String, Vec<&str>)> = None;
+    let mut blocks: Vec<(String, Vec<&str>)> = Vec::new();
+    let mut summary: Vec<(String, Option<String>)> = Vec::new();
+
+    for line in raw_output.lines() {
+        let line = line.trim_end();
+        if let Some(caps) = TOTALS_RE.captures(line) {
+            seen_totals = true;
+            report.duration = Some(caps[2].to_string());
+            for (n, label) in counts(&caps[1]) {
+                match label.as_str() {
+                    "passed" => report.passed = n,
+                    "failed" => report.failed = n,
+                    "error" | "errors" => report.errors = n,
+                    "skipped" | "xfailed" | "deselected" => report.skipped += n,
+                    _ => {}
+                }
+            }
+            continue;
+        }
+        if let Some(caps) = SECTION_RE.captures(line) {
+            blocks.extend(block.take());
+            section = caps[1].to_string();
+            continue;
+        }
+        if let Some(caps) = SUMMARY_ENTRY_RE.captures(line) {
+            summary.push((
+                caps[2].to_string(),
+                caps.get(3).map(|m| m.as_str().to_string()),
+            ));
+            continue;
+        }
+        if section != "FAILURES" && section != "ERRORS" {
+            continue;
+        }
+        if let Some(caps) = BLOCK_RE.captures(line) {
+            blocks.extend(block.take());
+            block = Some((caps[1].to_string(), Vec::new()));
+        } else if let Some((_, lines)) = block.as_mut() {
+            lines.push(line);
+        }
+    }
+    blocks.extend(block);
+
+    if !seen_totals {
+        return None;
+    }
+
+    for (title, lines) in &blocks {
+        let mut failure = failure_from_traceback(title, lines);
+        // The short summary has the full node id; the block title only the
+        // test name (`ERROR at setup of test_x` for errors), with a class
+        // written `TestMath.test_div` where the node has `TestMath::test_div`.
+        let short = title.rsplit(' ').next().unwrap_or(title);
+        let (name, params) = short.split_at(short.find('[').unwrap_or(short.len()));
+        let suffix = format!("::{}{params}", name.replace('.', "::"));
+        if let Some((node, _)) = summary.iter().find(|(node, _)| node.ends_with(&suffix)) {
+            failure.name = node.clone();
+        }
+        report.failures.push(failure);
+    }
+    // `-rf` without tracebacks (`--tb=no`): names and messages only.
+    for (node, message) in summary {
+        if !report.failures.iter().any(|f| f.name == node) {
+            let mut failure = TestFailure::new(node);
+            failure.details.extend(message);
+            report.failures.push(failure);
+        }
+    }
+    Some(report)
+}
+
+/// The `E` message lines, the failing source line (`>`), and the location
+/// from the last `path.py:N: Error` line of the traceback.
+fn failure_from_traceback(title: &str, lines: &[&str]) -> TestFailure {
+    let mut failure = TestFailure::new(title);
+    failure.location = lines
+        .iter()
+        .rev()
+        .find_map(|l| LOCATION_RE.captures(l).map(|caps| caps[1].to_string()));
+
+    let mut code = 0usize;
+    for line in lines {
+        if line.starts_with("E ") {
+            failure.details.push(line[1..].trim().to_string());
+        } else if line.starts_with("> ") && code < MAX_CODE_LINES {
+            code += 1;
+            failure.details.push(line.to_string());
+        }
+    }
+    if failure.details.is_empty() {
+        // Captured output or a bare error: keep the last meaningful line.
+        if let Some(last) = lines.iter().rev().find(|l| {
+            let l = l.trim();
+            !l.is_empty() && !l.starts_with('-')
+        }) {
+            failure.details.push(last.trim().to_string());
+        }
+    }
+    failure
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+============================= test session starts ==============================
+platform linux -- Python 3.12.1, pytest-8.0.0, pluggy-1.4.0
+rootdir: /tmp/demo
+collected 6 items
+
+tests/test_math.py .F.s                                                  [ 66%]
+tests/test_io.py .E                                                      [100%]
+
+==================================== ERRORS ====================================
+_____________________ ERROR at setup of test_reads_file ________________________
+
+    @pytest.fixture
+    def data_file():
+>       raise FileNotFoundError(\"data.csv\")
+E       FileNotFoundError: data.csv
+
+tests/test_io.py:8: FileNotFoundError
+=================================== FAILURES ===================================
+_________________________________ test_divides _________________________________
+
+    def test_divides():
+        result = divide(6, 3)
+>       assert result == 3.5
+E       assert 2.0 == 3.5
+
+tests/test_math.py:14: AssertionError
+----------------------------- Captured stdout call -----------------------------
+dividing 6 by 3
+=========================== short test summary info ============================
+FAILED tests/test_math.py::test_divides - assert 2.0 == 3.5
+ERROR tests/test_io.py::test_reads_file - FileNotFoundError: data.csv
+============= 1 failed, 3 passed, 1 skipped, 1 error in 0.12s ==============";
+
+    #[test]
+    fn parses_failures_errors_and_totals() {
+        let report = parse(OUTPUT).unwrap();
+        assert_eq!(
+            (report.passed, report.failed, report.errors, report.skipped),
+            (3, 1, 1, 1)
+        );
+        assert_eq!(report.duration.as_deref(), Some("0.12s"));
+        assert_eq!(report.failures.len(), 2);
+
+        let error = &report.failures[0];
+        assert_eq!(error.name, "tests/test_io.py::test_reads_file");
+        assert_eq!(error.location.as_deref(), Some("tests/test_io.py:8"));
+        assert_eq!(
+            error.details,
+            [
+                ">       raise FileNotFoundError(\"data.csv\")",
+                "FileNotFoundError: data.csv"
+            ]
+        );
+
+        let failed = &report.failures[1];
+        assert_eq!(failed.name, "tests/test_math.py::test_divides");
+        assert_eq!(failed.location.as_deref(), Some("tests/test_math.py:14"));
+        assert_eq!(
+            failed.details,
+            [">       assert result == 3.5", "assert 2.0 == 3.5"]
+        );
+    }
+
+    #[test]
+    fn class_and_parametrized_titles_match_their_node() {
+        let input = "\
+=================================== FAILURES ===================================
+______________________________ TestMath.test_div _______________________________
+
+>       assert 1 / 2 == 1
+E       assert 0.5 == 1
+
+tests/test_math.py:9: AssertionError
+_______________________________ test_round[1.5] ________________________________
+
+>       assert round(x) == 1
+E       assert 2 == 1
+
+tests/test_math.py:20: AssertionError
+=========================== short test summary info ============================
+FAILED tests/test_math.py::TestMath::test_div - assert 0.5 == 1
+FAILED tests/test_math.py::test_round[1.5] - assert 2 == 1
+============================== 2 failed in 0.03s ===============================";
+        let report = parse(input).unwrap();
+        let names: Vec<&str> = report.failures.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "tests/test_math.py::TestMath::test_div",
+                "tests/test_math.py::test_round[1.5]"
+            ]
+        );
+    }
+
+    #[test]
+    fn quiet_output_without_tracebacks() {
+        let input = "\
+..F.
+FAILED tests/test_a.py::test_x - assert False
+1 failed, 3 passed in 0.05s";
+        let report = parse(input).unwrap();
+        assert_eq!((report.passed, report.failed), (3, 1));
+        assert_eq!(report.failures[0].name, "tests/test_a.py::test_x");
+        assert_eq!(report.failures[0].details, ["assert False"]);
+    }
+
+    #[test]
+    fn requires_totals() {
+        assert!(parse("ImportError: No module named pytest").is_none());
+    }
+}
@@ -0,0 +1,419 @@
+//! `cargo test` (libtest) and `cargo nextest` output.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::{TestFailure, TestReport, counts, trim_block};
+
+static LIBTEST_RESULT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^test result: \w+\. (\d+) passed; (\d+) failed; (\d+) ignored(?:;.*?finished in ([\d.]+)s)?",
+    )
+    .unwrap()
+});
+
+/// `test name ... FAILED`; `#[should_panic]` tests add ` - should panic`
+/// to the name, which their `---- name stdout ----` section does not.
+static LIBTEST_STATUS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^test (.+?)(?: - should panic)? \.\.\. FAILED$").unwrap());
+
+static SECTION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^---- (.+) stdout ----$").unwrap());
+
+static NEXTEST_STATUS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(PASS|FAIL|SKIP|SIGSEGV|SIGABRT|SIGKILL|TIMEOUT|ABORT|LEAK|FLAKY)\s+\[[^\]]*\]\s+(.+)$")
+        .unwrap()
+});
+
+static NEXTEST_OUTPUT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:-{3}|─+) STD(?:OUT|ERR):\s+(.+?)(?:\s+(?:-{3}|─+))?\s*$").unwrap()
+});
+
+static NEXTEST_SUMMARY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*Summary \[\s*([\d.]+s)\s*\] (.*)$").unwrap());
+
+/// New panic format: `thread 'x' panicked at src/lib.rs:10:9:`.
+static PANIC_AT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"panicked at (\S+:\d+:\d+):$").unwrap());
+
+/// Pre-1.73 panic format: `thread 'x' panicked at 'msg', src/lib.rs:10:9`.
+static PANIC_OLD_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"panicked at '(.*)'(?:, (\S+:\d+:\d+))?$").unwrap());
+
+static FRAME_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*\d+: (.+)$").unwrap());
+
+/// Backtrace frames from the runtime rather than the code under test.
+const RUNTIME_FRAME_PREFIXES: &[&str] = &[
+    "std::",
+    "core::",
+    "alloc::",
+    "test::",
+    "<",
+    "__rust",
+    "rust_begin_unwind",
+    "_start",
+    "__libc",
+    "start_thread",
+    "clone",
+];
+
+/// Backtrace frames kept from the code under test.
+const MAX_FRAMES: usize = 4;
+
+// ---------------------------------------------------------------------------
+// libtest
+// ---------------------------------------------------------------------------
+
+/// Parse `cargo test` output (one or more libtest binaries and doctests).
+pub(super) fn parse_libtest(raw_output: &str) -> Option<TestReport> {
+    let mut report = TestReport::default();
+    let mut seen_result = false;
+    let mut seconds: Option<f64> = None;
+    let mut failed_names: Vec<String> = Vec::new();
+
+    let lines: Vec<&str> = raw_output.lines().collect();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim_end();
+        if let Some(caps) = LIBTEST_RESULT_RE.captures(line) {
+            seen_result = true;
+            report.passed += caps[1].parse::<usize>().unwrap_or(0);
+            report.failed += caps[2].parse::<usize>().unwrap_or(0);
+            report.skipped += caps[3].parse::<usize>().unwrap_or(0);
+            if let Some(finished) = caps.get(4).and_then(|m| m.as_str().parse::<f64>().ok()) {
+                *seconds.get_or_insert(0.0) += finished;
+            }
+        } else if let Some(caps) = LIBTEST_STATUS_RE.captures(line) {
+            failed_names.push(caps[1].to_string());
+        } else if let Some(caps) = SECTION_RE.captures(line) {
+            let start = i + 1;
+            let mut end = start;
+            while end < lines.len()
+                && !lines[end].starts_with("---- ")
+                && lines[end].trim_end() != "failures:"
+                && !lines[end].starts_with("test result:")
+            {
+                end += 1;
+            }
+            report
+                .failures
+                .push(failure_from_output(&caps[1], &lines[start..end]));
+            i = end;
+            continue;
+        }
+        i += 1;
+    }
+
+    if !seen_result {
+        return None;
+    }
+    // Tests that failed without captured output (e.g. `--nocapture`).
+    for name in failed_names {
+        if !report.failures.iter().any(|f| f.name == name) {
+            report.failures.push(TestFailure::new(name));
+        }
+    }
+    report.duration = seconds.map(|s| format!("{s:.2}s"));
+    Some(report)
+}
+
+// ---------------------------------------------------------------------------
+// nextest
+// ---------------------------------------------------------------------------
+
+/// Parse `cargo nextest run` output.
+pub(super) fn parse_nextest(raw_output: &str) -> Option<TestReport> {
+    let mut report = TestReport::default();
+    let mut seen_summary = false;
+    // (test name, captured output lines) for failed tests.
+    let mut outputs: Vec<(String, Vec<&str>)> = Vec::new();
+    let mut current: Option<usize> = None;
+
+    for line in raw_output.lines() {
+        if let Some(caps) = NEXTEST_SUMMARY_RE.captures(line) {
+            seen_summary = true;
+            report.duration = Some(caps[1].to_string());
+            for (n, label) in counts(&caps[2]) {
+                match label.as_str() {
+                    "passed" => report.passed = n,
+                    "failed" | "timed out" => report.failed += n,
+                    "skipped" => report.skipped = n,
+                    _ => {}
+                }
+            }
+            current = None;
+            continue;
+        }
+        // The summary repeats every failed test; those lines add nothing.
+        if seen_summary {
+            continue;
+        }
+        if let Some(caps) = NEXTEST_OUTPUT_RE.captures(line) {
+            let name = caps[1].to_string();
+            current = Some(match outputs.iter().position(|(n, _)| *n == name) {
+                Some(index) => index,
+                None => {
+                    outputs.push((name, Vec::new()));
+                    outputs.len() - 1
+                }
+            });
+            continue;
+        }
+        if let Some(caps) = NEXTEST_STATUS_RE.captures(line) {
+            current = None;
+            if !matches!(&caps[1], "PASS" | "SKIP" | "FLAKY") {
+                let name = caps[2].trim().to_string();
+                if !outputs.iter().any(|(n, _)| *n == name) {
+                    outputs.push((name, Vec::new()));
+                }
+            }
+            continue;
+        }
+        if line.trim_start().starts_with("------------") {
+            current = None;
+            continue;
+        }
+        if let Some(index) = current {
+            outputs[index].1.push(line);
+        }
+    }
+
+    if !seen_summary {
+        return None;
+    }
+    report.failures = outputs
+        .iter()
+        .map(|(name, lines)| failure_from_output(name, lines))
+        .collect();
+    Some(report)
+}
+
+// ---------------------------------------------------------------------------
+// Panics
+// ---------------------------------------------------------------------------
+
+/// Build a failure from a test's captured output: the panic message and
+/// location, plus the backtrace frames from the code under test.
+fn failure_from_output(name: &str, lines: &[&str]) -> TestFailure {
+    let mut failure = TestFailure::new(name);
+
+    let Some(panic) = lines.iter().position(|l| l.contains("panicked at ")) else {
+        // No panic: a test returning `Err`, or a timeout. Keep the output
+        // minus libtest's own chatter.
+        let kept: Vec<&str> = lines
+            .iter()
+            .copied()
+            .filter(|l| {
+                !l.starts_with("running ")
+                    && !l.starts_with("test ")
+                    && !l.starts_with("note: run with")
+            })
+            .collect();
+        failure.details = trim_block(&kept);
+        return failure;
+    };
+
+    let panic_line = lines[panic].trim_end();
+    let mut message: Vec<&str> = Vec::new();
+    if let Some(caps) = PANIC_OLD_RE.captures(panic_line) {
+        failure.location = caps.get(2).map(|m| m.as_str().to_string());
+        message.push(caps.get(1).map_or("", |m| m.as_str()));
+    } else if let Some(caps) = PANIC_AT_RE.captures(panic_line) {
+        failure.location = Some(caps[1].to_string());
+    }
+
+    let rest = &lines[panic + 1..];
+    let message_end = rest
+        .iter()
+        .position(|l| l.starts_with("note: ") || l.starts_with("stack backtrace:"))
+        .unwrap_or(rest.len());
+    message.extend(&rest[..message_end]);
+    failure.details = trim_block(&message);
+
+    if let Some(backtrace) = rest.iter().position(|l| l.starts_with("stack backtrace:")) {
+        failure.details.extend(user_frames(&rest[backtrace + 1..]));
+    }
+    failure
+}
+
+/// Backtrace frames outside the standard library and test harness, each
+/// with its `at file:line` line.
+fn user_frames(lines: &[&str]) -> Vec<String> {
+    let mut frames = Vec::new();
+    let mut i = 0;
+    while i < lines.len() && frames.len() < MAX_FRAMES * 2 {
+        let Some(caps) = FRAME_RE.captures(lines[i]) else {
+            i += 1;
+            continue;
+        };
+        let symbol = caps[1].trim();
+        let at = lines
+            .get(i + 1)
+            .map(|l| l.trim())
+            .filter(|l| l.starts_with("at "));
+        // Test closures repeat the frame of the test function itself.
+        if !RUNTIME_FRAME_PREFIXES.iter().any(|p| symbol.starts_with(p))
+            && !symbol.ends_with("{{closure}}")
+            && at.is_none_or(|at| !at.contains("/rustc/"))
+        {
+            frames.push(format!("  {symbol}"));
+            if let Some(at) = at {
+                frames.push(format!("    {at}"));
+            }
+        }
+        i += if at.is_some() { 2 } else { 1 };
+    }
+    frames
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBTEST: &str = "\
+   Compiling demo v0.1.0 (/tmp/demo)
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.40s
+     Running unittests src/lib.rs (target/debug/deps/demo-1234)
+
+running 4 tests
+test tests::adds ... ok
+test tests::compares ... FAILED
+test tests::old_style ... FAILED
+test tests::slow ... ignored
+
+failures:
+
+---- tests::compares stdout ----
+debug output from the test
+thread 'tests::compares' panicked at src/lib.rs:20:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+---- tests::old_style stdout ----
+thread 'tests::old_style' panicked at 'boom', src/lib.rs:30:5
+stack backtrace:
+   0: rust_begin_unwind
+             at /rustc/abc/library/std/src/panicking.rs:645:5
+   1: core::panicking::panic_fmt
+             at /rustc/abc/library/core/src/panicking.rs:72:14
+   2: demo::tests::helper
+             at ./src/lib.rs:40:9
+   3: demo::tests::old_style
+             at ./src/lib.rs:30:5
+   4: core::ops::function::FnOnce::call_once
+             at /rustc/abc/library/core/src/ops/function.rs:250:5
+note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.
+
+
+failures:
+    tests::compares
+    tests::old_style
+
+test result: FAILED. 1 passed; 2 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s
+
+error: test failed, to rerun pass `--lib`";
+
+    #[test]
+    fn libtest_report() {
+        let report = parse_libtest(LIBTEST).unwrap();
+        assert_eq!((report.passed, report.failed, report.skipped), (1, 2, 1));
+        assert_eq!(report.duration.as_deref(), Some("0.01s"));
+        assert_eq!(report.failures.len(), 2);
+
+        let compares = &report.failures[0];
+        assert_eq!(compares.name, "tests::compares");
+        assert_eq!(compares.location.as_deref(), Some("src/lib.rs:20:9"));
+        assert_eq!(
+            compares.details,
+            ["assertion `left == right` failed", "  left: 1", " right: 2"]
+        );
+
+        let old = &report.failures[1];
+        assert_eq!(old.location.as_deref(), Some("src/lib.rs:30:5"));
+        assert_eq!(
+            old.details,
+            [
+                "boom",
+                "  demo::tests::helper",
+                "    at ./src/lib.rs:40:9",
+                "  demo::tests::old_style",
+                "    at ./src/lib.rs:30:5",
+            ]
+        );
+    }
+
+    #[test]
+    fn libtest_should_panic_failure_is_reported_once() {
+        let input = "\
+running 1 test
+test tests::sp - should panic ... FAILED
+
+failures:
+
+---- tests::sp stdout ----
+note: test did not panic as expected at src/lib.rs:8:5
+
+failures:
+    tests::sp
+
+test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s";
+        let report = parse_libtest(input).unwrap();
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].name, "tests::sp");
+    }
+
+    #[test]
+    fn libtest_sums_binaries() {
+        let input = "\
+test result: ok. 3 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.10s
+test result: ok. 2 passed; 0 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.25s";
+        let report = parse_libtest(input).unwrap();
+        assert_eq!(report.render(80), "Tests: 5 passed, 1 skipped (0.35s)");
+    }
+
+    #[test]
+    fn libtest_requires_a_result_line() {
+        assert!(parse_libtest("error[E0425]: cannot find value `x`").is_none());
+    }
+
+    #[test]
+    fn nextest_report() {
+        let input = "\
+    Starting 3 tests across 1 binary (1 test skipped)
+        PASS [   0.004s] demo tests::adds
+        FAIL [   0.005s] demo tests::compares
+--- STDOUT:              demo tests::compares ---
+
+running 1 test
+test tests::compares ... FAILED
+
+--- STDERR:              demo tests::compares ---
+thread 'tests::compares' panicked at src/lib.rs:20:9:
+assertion failed: a == b
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+        PASS [   0.006s] demo tests::other
+------------
+     Summary [   0.012s] 3 tests run: 2 passed, 1 failed, 1 skipped
+        FAIL [   0.005s] demo tests::compares
+error: test run failed";
+        let report = parse_nextest(input).unwrap();
+        assert_eq!((report.passed, report.failed, report.skipped), (2, 1, 1));
+        assert_eq!(report.duration.as_deref(), Some("0.012s"));
+        assert_eq!(
+            report.failures,
+            [TestFailure {
+                name: "demo tests::compares".to_string(),
+                location: Some("src/lib.rs:20:9".to_string()),
+                details: vec!["assertion failed: a == b".to_string()],
+            }]
+        );
+    }
+}
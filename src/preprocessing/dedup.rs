@@ -4,8 +4,19 @@
 //! linters often emit hundreds of structurally identical lines (e.g.
 //! `test foo::bar ... ok`). This module detects consecutive runs of similar
 //! lines and collapses them into a counted summary.
+//!
+//! Parallel builds, interleaved test output and repeated deprecation
+//! warnings repeat the same lines far apart, where runs never form. For
+//! command categories configured for it, a global pass first drops every
+//! later exact copy of a line and annotates the first one with `(×N)`.
 
 use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::config::schema::PreprocessingConfig;
+use crate::llm::prompts::classify_command;
 
 // ---------------------------------------------------------------------------
 // Configuration
@@ -18,6 +29,38 @@ const MIN_RUN_LENGTH: usize = 3;
 /// The first and last representative lines are always kept.
 const REPRESENTATIVE_LINES: usize = 2;
 
+/// Minimum trimmed length for a line to take part in global
+/// deduplication; shorter lines (`}`, `|`, `^^^`) are structure.
+const MIN_GLOBAL_KEY_LEN: usize = 8;
+
+/// Lines listed in the optional frequency summary block.
+const SUMMARY_PATTERNS: usize = 5;
+
+/// Settings for one deduplication run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DedupOptions {
+    /// Also collapse non-consecutive duplicates.
+    pub global: bool,
+    /// Append a block listing the most frequent lines (global mode).
+    pub summary: bool,
+}
+
+/// Deduplication options for a core command.
+///
+/// Global mode applies when `preprocessing.dedup_mode` is `"global"` or the
+/// command's category (`build_test`, `logs`, ...) is listed in
+/// `preprocessing.global_dedup_categories`.
+pub fn options_for(cfg: &PreprocessingConfig, core: &str) -> DedupOptions {
+    let global = cfg.dedup_mode == "global" || {
+        let category = classify_command(core).to_string();
+        cfg.global_dedup_categories.contains(&category)
+    };
+    DedupOptions {
+        global,
+        summary: global && cfg.dedup_summary,
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Line similarity
 // ---------------------------------------------------------------------------
@@ -30,7 +73,7 @@ const REPRESENTATIVE_LINES: usize = 2;
 /// Examples:
 /// - `"test foo::bar_123 ... ok"` → `"test ...::... ... ok"`
 /// - `"  PASS src/tests/test_01.rs"` → `"PASS src/tests/....rs"`
-fn pattern_key(line: &str) -> String {
+pub(crate) fn pattern_key(line: &str) -> String {
     let trimmed = line.trim();
     if trimmed.is_empty() {
         return String::new();
@@ -167,6 +210,100 @@ pub fn deduplicate(text: &str) -> String {
     result
 }
 
+// ---------------------------------------------------------------------------
+// Global deduplication
+// ---------------------------------------------------------------------------
+
+/// Lines that belong to a diagnostic or failure report: headers
+/// (`error[E0308]: ...`), failure markers, panics and assertion values.
+/// Identical copies of them describe different failures, so they are never
+/// merged.
+static DIAGNOSTIC_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^\s*(?:error|warning)(?:\[\w+\])?:|\bFAILED\b|\bpanicked\b|^\s*(?:left|right|expected|actual)\s*:|\bassert|^-{3,} ",
+    )
+    .expect("diagnostic regex must compile")
+});
+
+/// Number of leading whitespace characters.
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Grouping key for global deduplication: the line itself, or `None` for
+/// lines that always pass through (blank or short structural lines,
+/// pipeline markers, diagnostic lines, and headers of an indented block).
+fn global_key<'a>(line: &'a str, next: Option<&str>) -> Option<&'a str> {
+    let key = line.trim_end();
+    if key.trim_start().len() < MIN_GLOBAL_KEY_LEN
+        || is_marker_line(line)
+        || DIAGNOSTIC_RE.is_match(line)
+        || next.is_some_and(|next| !next.trim().is_empty() && indent(next) > indent(line))
+    {
+        return None;
+    }
+    Some(key)
+}
+
+/// Collapse non-consecutive exact duplicates across the whole text.
+///
+/// Later copies of a line are removed; the first occurrence keeps its
+/// position and gains an `(×N)` annotation. Only exact copies merge: lines
+/// that differ in a number or name (`case_3` vs `case_17`) are different
+/// facts. With `summary`, a block listing the most frequent lines is
+/// appended.
+pub fn deduplicate_global(text: &str, summary: bool) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let keys: Vec<Option<&str>> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| global_key(line, lines.get(i + 1).copied()))
+        .collect();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for &key in keys.iter().flatten() {
+        *counts.entry(key).or_default() += 1;
+    }
+    if counts.values().all(|&n| n < 2) {
+        return text.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    // (count, line index) of every repeated line, for the summary.
+    let mut repeated: Vec<(usize, usize)> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(key) = keys[i] else {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        };
+        if seen.insert(key, i).is_some() {
+            continue;
+        }
+        let count = counts[key];
+        if count > 1 {
+            result.push_str(line.trim_end());
+            result.push_str(&format!(" (×{count})\n"));
+            repeated.push((count, i));
+        } else {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+
+    if summary && !repeated.is_empty() {
+        repeated.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        let shown = repeated.len().min(SUMMARY_PATTERNS);
+        result.push_str(&format!("\n[most frequent lines: top {shown}]\n"));
+        for &(count, i) in repeated.iter().take(shown) {
+            result.push_str(&format!("  (×{count}) {}\n", lines[i].trim()));
+        }
+    }
+
+    result
+}
+
 /// Extract a short prefix from a line for display in summaries.
 fn common_prefix(line: &str) -> String {
     let max_len = 40;
@@ -280,6 +417,113 @@ mod tests {
         assert_eq!(k1, k2);
     }
 
+    #[test]
+    fn global_collapses_distant_duplicates() {
+        let input = "\
+npm WARN deprecated request@2.88.2: request has been deprecated
+added 12 packages
+npm WARN deprecated request@2.88.2: request has been deprecated
+added 3 packages
+npm WARN deprecated request@2.88.2: request has been deprecated
+done
+";
+        let result = deduplicate_global(input, false);
+        assert_eq!(
+            result,
+            "npm WARN deprecated request@2.88.2: request has been deprecated (×3)\n\
+             added 12 packages\nadded 3 packages\ndone\n"
+        );
+    }
+
+    #[test]
+    fn global_merges_only_exact_copies() {
+        let mut input = String::new();
+        for i in 0..10 {
+            input.push_str(&format!("[worker {i}] fetching index page\n"));
+            input.push_str("[pool] waiting for workers\n");
+        }
+        let result = deduplicate_global(&input, false);
+        assert!(result.contains("[pool] waiting for workers (×10)"));
+        assert!(result.contains("[worker 0] fetching index page\n"));
+        assert!(result.contains("[worker 9] fetching index page\n"));
+    }
+
+    #[test]
+    fn global_keeps_diagnostic_blocks_whole() {
+        let input = "\
+error[E0308]: mismatched types
+  --> src/lib.rs:10:5
+   |
+error[E0308]: mismatched types
+  --> src/lib.rs:20:9
+   |
+";
+        assert_eq!(deduplicate_global(input, false), input);
+    }
+
+    #[test]
+    fn global_keeps_each_test_failure() {
+        let mut input = String::new();
+        for i in 0..40 {
+            let status = if [3, 17, 28].contains(&i) {
+                "FAILED"
+            } else {
+                "ok"
+            };
+            input.push_str(&format!("test tests::case_{i} ... {status}\n"));
+        }
+        input.push_str("\nfailures:\n\n");
+        for i in [3, 17, 28] {
+            input.push_str(&format!(
+                "---- tests::case_{i} stdout ----\n\
+                 thread 'tests::case_{i}' panicked at src/lib.rs:{i}:9:\n\
+                 assertion `left == right` failed\n  left: 1\n right: 2\n\n"
+            ));
+        }
+        let result = deduplicate_global(&input, false);
+        for i in [3, 17, 28] {
+            assert!(result.contains(&format!("test tests::case_{i} ... FAILED\n")));
+            assert!(result.contains(&format!("---- tests::case_{i} stdout ----\n")));
+        }
+        assert_eq!(result.matches(" right: 2\n").count(), 3);
+        assert!(!result.contains('×'));
+    }
+
+    #[test]
+    fn global_keeps_per_suite_totals() {
+        let input = "\
+test result: ok. 12 passed; 0 failed
+running 3 tests
+test result: ok. 3 passed; 0 failed
+";
+        let result = deduplicate_global(input, false);
+        assert!(result.contains("12 passed"));
+        assert!(result.contains("3 passed"));
+    }
+
+    #[test]
+    fn global_summary_lists_most_frequent() {
+        let input =
+            "alpha beta gamma\ndelta epsilon\nalpha beta gamma\nalpha beta gamma\ndelta epsilon\n";
+        let result = deduplicate_global(input, true);
+        let summary = result.split("[most frequent lines: top 2]").nth(1).unwrap();
+        assert_eq!(
+            summary.trim(),
+            "(×3) alpha beta gamma\n  (×2) delta epsilon"
+        );
+    }
+
+    #[test]
+    fn global_mode_per_category() {
+        let mut cfg = PreprocessingConfig::default();
+        assert!(!options_for(&cfg, "cargo build").global);
+        cfg.global_dedup_categories = vec!["build_test".to_string()];
+        assert!(options_for(&cfg, "cargo build").global);
+        assert!(!options_for(&cfg, "git status").global);
+        cfg.dedup_mode = "global".to_string();
+        assert!(options_for(&cfg, "git status").global);
+    }
+
     #[test]
     fn frequency_summary_counts() {
         let input = "ok\nok\nok\nfail\nok\n";
@@ -0,0 +1,418 @@
+//! Log clustering — collapse interleaved log lines into learned templates.
+//!
+//! Stage 3a of the preprocessing pipeline, run before deduplication for log
+//! commands (`docker logs`, `kubectl logs`, `journalctl`, `tail -f`).
+//! [`dedup`](super::dedup) only sees *consecutive* repeats; real logs
+//! interleave a few dozen message templates thousands of times. This stage
+//! learns those templates across the whole output, Drain-style:
+//!
+//! 1. Strip the timestamp and split the line into tokens; tokens holding
+//!    digits become wildcards.
+//! 2. Look up candidate clusters by token count and first token.
+//! 3. Join the most similar candidate (at least [`SIMILARITY_THRESHOLD`] of
+//!    tokens equal) and widen its template where tokens differ, or start a
+//!    new cluster. A group holds at most [`MAX_CLUSTERS_PER_GROUP`]
+//!    clusters, like Drain's `max_children`; lines that would open another
+//!    one stay verbatim, so output of distinct lines costs linear time.
+//!
+//! Each template seen at least [`MIN_CLUSTER_LINES`] times is emitted once,
+//! at its first occurrence, with its count, time range and two concrete
+//! examples. WARN/ERROR lines and lines of rare templates stay verbatim and
+//! in order.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::config::schema::PreprocessingConfig;
+use crate::llm::prompts::{CommandCategory, classify_command};
+
+// ---------------------------------------------------------------------------
+// Configuration
+// ---------------------------------------------------------------------------
+
+/// Fraction of a template's tokens a line must match to join its cluster.
+const SIMILARITY_THRESHOLD: f64 = 0.5;
+
+/// Minimum number of lines sharing a template before it is collapsed.
+const MIN_CLUSTER_LINES: usize = 3;
+
+/// Clusters compared per (token count, first token) group.
+const MAX_CLUSTERS_PER_GROUP: usize = 64;
+
+/// Wildcard standing in for the variable tokens of a template.
+const WILDCARD: &str = "<*>";
+
+/// Settings for one clustering run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClusterOptions {
+    /// Outputs with fewer lines are left alone.
+    pub min_lines: usize,
+}
+
+/// Clustering options for a core command, or `None` when clustering is off
+/// for it.
+///
+/// `preprocessing.log_clustering` is `"auto"` (log and container commands
+/// only), `"always"` or `"off"`.
+pub fn options_for(cfg: &PreprocessingConfig, core: &str) -> Option<ClusterOptions> {
+    let enabled = match cfg.log_clustering.as_str() {
+        "always" => true,
+        "off" => false,
+        _ => matches!(
+            classify_command(core),
+            CommandCategory::Logs | CommandCategory::ContainerTools
+        ),
+    };
+    enabled.then_some(ClusterOptions {
+        min_lines: cfg.log_clustering_min_lines,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Line parsing
+// ---------------------------------------------------------------------------
+
+/// Timestamps near the start of a line: ISO 8601 / RFC 3339, syslog
+/// (`Oct 17 10:00:01`) and bare clock times (`10:00:01.123`).
+static TIMESTAMP_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?|[A-Z][a-z]{2} +\d{1,2} \d{2}:\d{2}:\d{2}|\b\d{2}:\d{2}:\d{2}(?:[.,]\d+)?",
+    )
+    .unwrap()
+});
+
+/// Severity words that keep a line out of clustering.
+static SALIENT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(?:warn(?:ing)?|error|err|fatal|crit(?:ical)?|panic|exception|traceback)\b")
+        .unwrap()
+});
+
+/// How far into a line a timestamp is looked for.
+const TIMESTAMP_SEARCH_BYTES: usize = 48;
+
+/// A clusterable line: its timestamp and masked tokens.
+struct ParsedLine<'a> {
+    timestamp: Option<&'a str>,
+    tokens: Vec<String>,
+}
+
+fn parse_line(line: &str) -> ParsedLine<'_> {
+    let mut head_end = TIMESTAMP_SEARCH_BYTES.min(line.len());
+    while !line.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    let found = TIMESTAMP_RE.find(&line[..head_end]);
+    let timestamp = found.map(|m| m.as_str());
+    let message = match found {
+        Some(m) => format!("{} {}", &line[..m.start()], &line[m.end()..]),
+        None => line.to_string(),
+    };
+    let tokens = message
+        .split_whitespace()
+        .map(|token| {
+            if token.bytes().any(|b| b.is_ascii_digit()) {
+                WILDCARD.to_string()
+            } else {
+                token.to_string()
+            }
+        })
+        .collect();
+    ParsedLine { timestamp, tokens }
+}
+
+/// Lines that are never clustered: blank lines, pipeline markers and lines
+/// mentioning a warning or error.
+fn is_verbatim(line: &str) -> bool {
+    let t = line.trim();
+    t.is_empty() || (t.starts_with('[') && t.ends_with(']')) || SALIENT_RE.is_match(line)
+}
+
+// ---------------------------------------------------------------------------
+// Template learning
+// ---------------------------------------------------------------------------
+
+/// A learned template and the lines that matched it.
+struct Cluster<'a> {
+    template: Vec<String>,
+    count: usize,
+    first_line: usize,
+    first_timestamp: Option<&'a str>,
+    last_timestamp: Option<&'a str>,
+    examples: [&'a str; 2],
+}
+
+impl Cluster<'_> {
+    /// Fraction of template tokens equal to `tokens`; wildcards never count.
+    fn similarity(&self, tokens: &[String]) -> f64 {
+        let equal = self
+            .template
+            .iter()
+            .zip(tokens)
+            .filter(|(t, token)| t.as_str() != WILDCARD && t == token)
+            .count();
+        equal as f64 / self.template.len().max(1) as f64
+    }
+}
+
+/// Index of the cluster each line belongs to (`None` for verbatim lines),
+/// and the clusters themselves.
+fn learn<'a>(lines: &[&'a str]) -> (Vec<Option<usize>>, Vec<Cluster<'a>>) {
+    let mut clusters: Vec<Cluster<'a>> = Vec::new();
+    // (token count, first token) → candidate clusters.
+    let mut groups: HashMap<(usize, String), Vec<usize>> = HashMap::new();
+    let mut assignment = Vec::with_capacity(lines.len());
+
+    for (i, &line) in lines.iter().enumerate() {
+        if is_verbatim(line) {
+            assignment.push(None);
+            continue;
+        }
+        let parsed = parse_line(line);
+        if parsed.tokens.is_empty() {
+            assignment.push(None);
+            continue;
+        }
+        let key = (parsed.tokens.len(), parsed.tokens[0].clone());
+        let candidates = groups.entry(key).or_default();
+        let best = candidates
+            .iter()
+            .map(|&c| (c, clusters[c].similarity(&parsed.tokens)))
+            .filter(|&(_, sim)| sim >= SIMILARITY_THRESHOLD)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(c, _)| c);
+
+        let id = match best {
+            None if candidates.len() >= MAX_CLUSTERS_PER_GROUP => {
+                assignment.push(None);
+                continue;
+            }
+            Some(c) => {
+                let cluster = &mut clusters[c];
+                for (t, token) in cluster.template.iter_mut().zip(&parsed.tokens) {
+                    if t != token {
+                        *t = WILDCARD.to_string();
+                    }
+                }
+                cluster.count += 1;
+                cluster.first_timestamp = cluster.first_timestamp.or(parsed.timestamp);
+                cluster.last_timestamp = parsed.timestamp.or(cluster.last_timestamp);
+                cluster.examples[1] = line;
+                c
+            }
+            None => {
+                candidates.push(clusters.len());
+                clusters.push(Cluster {
+                    template: parsed.tokens,
+                    count: 1,
+                    first_line: i,
+                    first_timestamp: parsed.timestamp,
+                    last_timestamp: parsed.timestamp,
+                    examples: [line, line],
+                });
+                clusters.len() - 1
+            }
+        };
+        assignment.push(Some(id));
+    }
+
+    (assignment, clusters)
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Collapse frequent log templates into counted summaries.
+///
+/// Returns the text unchanged when it has fewer than `min_lines` lines or
+/// when no template repeats often enough to save space.
+pub fn cluster_logs(text: &str, options: &ClusterOptions) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() < options.min_lines {
+        return text.to_string();
+    }
+
+    let (assignment, clusters) = learn(&lines);
+    if !clusters.iter().any(|c| c.count >= MIN_CLUSTER_LINES) {
+        return text.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len() / 2);
+    for (i, line) in lines.iter().enumerate() {
+        match assignment[i].map(|c| &clusters[c]) {
+            Some(cluster) if cluster.count >= MIN_CLUSTER_LINES => {
+                if cluster.first_line == i {
+                    push_summary(&mut result, cluster);
+                }
+            }
+            _ => {
+                result.push_str(line);
+                result.push('\n');
+            }
+        }
+    }
+
+    if result.len() >= text.len() {
+        return text.to_string();
+    }
+    result
+}
+
+/// `[N lines like: TEMPLATE, FIRST to LAST]` followed by two examples.
+fn push_summary(out: &mut String, cluster: &Cluster) {
+    let template = cluster.template.join(" ");
+    let range = match (cluster.first_timestamp, cluster.last_timestamp) {
+        (Some(first), Some(last)) if first != last => format!(", {first} to {last}"),
+        (Some(at), _) | (None, Some(at)) => format!(", at {at}"),
+        (None, None) => String::new(),
+    };
+    out.push_str(&format!(
+        "[{} lines like: {template}{range}]\n",
+        cluster.count
+    ));
+    for example in cluster.examples {
+        out.push_str("  ");
+        out.push_str(example.trim_end());
+        out.push('\n');
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTIONS: ClusterOptions = ClusterOptions { min_lines: 10 };
+
+    fn interleaved_log() -> String {
+        let mut log = String::new();
+        for i in 0..300 {
+            let ts = format!("2024-10-17T10:{:02}:{:02}Z", i / 60, i % 60);
+            match i % 3 {
+                0 => log.push_str(&format!(
+                    "{ts} INFO GET /api/users/{i} 200 in {}ms\n",
+                    i % 40
+                )),
+                1 => log.push_str(&format!("{ts} DEBUG cache hit for session s{i}\n")),
+                _ => log.push_str(&format!("{ts} INFO worker {} heartbeat ok\n", i % 4)),
+            }
+            if i == 150 {
+                log.push_str(&format!("{ts} ERROR database connection reset\n"));
+            }
+        }
+        log.push_str("shutting down gracefully\n");
+        log
+    }
+
+    #[test]
+    fn learns_interleaved_templates() {
+        let input = interleaved_log();
+        let result = cluster_logs(&input, &OPTIONS);
+
+        assert!(
+            result.contains(
+                "[100 lines like: INFO GET <*> <*> in <*>, \
+                 2024-10-17T10:00:00Z to 2024-10-17T10:04:57Z]"
+            ),
+            "{result}"
+        );
+        assert!(result.contains("[100 lines like: DEBUG cache hit for session <*>"));
+        assert!(result.contains("[100 lines like: INFO worker <*> heartbeat ok"));
+        assert!(result.contains("  2024-10-17T10:00:00Z INFO GET /api/users/0 200 in 0ms"));
+        assert!(result.contains("  2024-10-17T10:04:57Z INFO GET /api/users/297 200 in 17ms"));
+        assert!(result.len() < input.len() / 5);
+    }
+
+    #[test]
+    fn errors_and_unique_lines_stay_in_order() {
+        let result = cluster_logs(&interleaved_log(), &OPTIONS);
+        let lines: Vec<&str> = result.lines().collect();
+
+        let error = lines
+            .iter()
+            .position(|l| l.contains("ERROR database connection reset"))
+            .unwrap();
+        let summary = lines
+            .iter()
+            .position(|l| l.starts_with("[100 lines"))
+            .unwrap();
+        assert!(summary < error);
+        assert_eq!(lines.last(), Some(&"shutting down gracefully"));
+    }
+
+    #[test]
+    fn short_or_unique_output_is_unchanged() {
+        let short = "a 1\na 2\na 3\n";
+        assert_eq!(cluster_logs(short, &OPTIONS), short);
+
+        let unique: String = (1..=20).map(|i| format!("{}\n", "x".repeat(i))).collect();
+        assert_eq!(cluster_logs(&unique, &OPTIONS), unique);
+    }
+
+    #[test]
+    fn distinct_lines_are_capped_per_group() {
+        let word = |mut n: usize| {
+            let mut w = String::new();
+            loop {
+                w.push((b'a' + (n % 26) as u8) as char);
+                n /= 26;
+                if n == 0 {
+                    return w;
+                }
+            }
+        };
+        let lines: Vec<String> = (0..20_000)
+            .map(|i| format!("event {} {} {}", word(i), word(i + 1), word(i + 2)))
+            .collect();
+        let refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let (assignment, clusters) = learn(&refs);
+        assert_eq!(clusters.len(), MAX_CLUSTERS_PER_GROUP);
+        assert!(
+            assignment[MAX_CLUSTERS_PER_GROUP..]
+                .iter()
+                .all(Option::is_none)
+        );
+
+        let input = lines.join("\n") + "\n";
+        assert_eq!(cluster_logs(&input, &OPTIONS), input);
+    }
+
+    #[test]
+    fn syslog_timestamps_form_the_range() {
+        let input: String = (0..12)
+            .map(|i| format!("Oct 17 10:00:{i:02} web nginx[42]: upstream ok id={i}\n"))
+            .collect();
+        let result = cluster_logs(&input, &OPTIONS);
+        assert!(
+            result.starts_with(
+                "[12 lines like: web <*> upstream ok <*>, Oct 17 10:00:00 to Oct 17 10:00:11]"
+            ),
+            "{result}"
+        );
+    }
+
+    #[test]
+    fn enabled_for_log_and_container_commands() {
+        let cfg = PreprocessingConfig::default();
+        assert!(options_for(&cfg, "docker logs api").is_some());
+        assert!(options_for(&cfg, "kubectl logs api").is_some());
+        assert!(options_for(&cfg, "journalctl -u nginx").is_some());
+        assert!(options_for(&cfg, "cargo build").is_none());
+
+        let always = PreprocessingConfig {
+            log_clustering: "always".to_string(),
+            ..PreprocessingConfig::default()
+        };
+        assert!(options_for(&always, "cargo build").is_some());
+        let off = PreprocessingConfig {
+            log_clustering: "off".to_string(),
+            ..PreprocessingConfig::default()
+        };
+        assert!(options_for(&off, "docker logs api").is_none());
+    }
+}
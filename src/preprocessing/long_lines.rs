@@ -0,0 +1,341 @@
+//! Long lines — elide pathological lines and summarize binary output.
+//!
+//! No other stage limits line *width*: a minified bundle, a base64 blob or
+//! a 200 KB single-line JSON response reaches truncation intact and is
+//! sliced at an arbitrary byte. This module, run in Stage 1 after noise
+//! removal:
+//!
+//! - shortens base64 and hex runs and data URIs embedded in ordinary lines
+//!   to their first and last characters,
+//! - keeps the start and end of lines still longer than
+//!   `preprocessing.max_line_chars`, with a marker naming what was elided.
+//!
+//! Lines that are complete JSON objects (NDJSON records such as cargo's
+//! `--message-format=json` messages) are left whole: optimizers parse them
+//! as records, and a cut record parses as nothing.
+//!
+//! Before any stage runs, [`describe_binary`] recognizes binary output
+//! (decoded lossily into replacement characters) so the pipeline can
+//! replace it with its size and detected type.
+
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+// ---------------------------------------------------------------------------
+// Configuration
+// ---------------------------------------------------------------------------
+
+/// Fraction of `max_line_chars` kept from the start of a long line.
+const HEAD_RATIO: f64 = 0.6;
+
+/// Fraction of `max_line_chars` kept from the end of a long line.
+const TAIL_RATIO: f64 = 0.2;
+
+/// Characters kept from the start of an embedded blob.
+const BLOB_HEAD_CHARS: usize = 16;
+
+/// Characters kept from the end of an embedded blob.
+const BLOB_TAIL_CHARS: usize = 8;
+
+/// Bytes inspected when deciding whether output is binary.
+const BINARY_SAMPLE_BYTES: usize = 8192;
+
+/// Fraction of undecodable or control characters that marks output as
+/// binary.
+const BINARY_THRESHOLD: f64 = 0.1;
+
+// ---------------------------------------------------------------------------
+// Binary output
+// ---------------------------------------------------------------------------
+
+/// Magic prefixes (as decoded by `from_utf8_lossy`) and the type they name.
+const MAGIC: &[(&str, &str)] = &[
+    ("\u{FFFD}PNG", "PNG image"),
+    ("GIF8", "GIF image"),
+    ("%PDF-", "PDF document"),
+    ("\x7fELF", "ELF executable"),
+    ("MZ", "Windows executable"),
+    ("PK\x03\x04", "ZIP archive"),
+    ("\x1f\u{FFFD}", "gzip archive"),
+    ("BZh", "bzip2 archive"),
+    ("\u{FFFD}7zXZ", "xz archive"),
+    ("7z\u{FFFD}", "7-Zip archive"),
+    ("RIFF", "RIFF media (WAV/AVI/WebP)"),
+    ("\0asm", "WebAssembly module"),
+    ("SQLite format 3", "SQLite database"),
+];
+
+/// A one-line summary of `text` if it is binary data, else `None`.
+///
+/// `text` is the lossily decoded output: undecodable bytes appear as U+FFFD.
+/// The reported size is approximate because a replacement character may
+/// stand for up to three original bytes.
+pub fn describe_binary(text: &str) -> Option<String> {
+    let mut end = text.len().min(BINARY_SAMPLE_BYTES);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let sample = &text[..end];
+
+    let mut total = 0usize;
+    let mut suspicious = 0usize;
+    for c in sample.chars() {
+        total += 1;
+        if c == '\u{FFFD}' || (c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b'))
+        {
+            suspicious += 1;
+        }
+    }
+    if total == 0 || (suspicious as f64) < total as f64 * BINARY_THRESHOLD {
+        return None;
+    }
+
+    let kind = MAGIC
+        .iter()
+        .find(|(magic, _)| text.starts_with(magic))
+        .map(|(_, kind)| *kind)
+        .or_else(|| {
+            let head: String = text.chars().take(16).collect();
+            (head.starts_with("\u{FFFD}\u{FFFD}\u{FFFD}")
+                && (head.contains("JFIF") || head.contains("Exif")))
+            .then_some("JPEG image")
+        })
+        .unwrap_or("binary data");
+    let replaced = text.matches('\u{FFFD}').count();
+    let bytes = text.len() - replaced * 2;
+    Some(format!("[binary output: {kind}, ~{bytes} bytes]"))
+}
+
+// ---------------------------------------------------------------------------
+// Embedded blobs
+// ---------------------------------------------------------------------------
+
+/// `data:` URIs with a base64 payload; group 1 is the payload.
+static DATA_URI_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"data:[\w.+-]+/[\w.+-]+(?:;[\w=.-]+)*;base64,([A-Za-z0-9+/]{64,}={0,2})")
+        .expect("data URI regex must compile")
+});
+
+/// Long base64 runs.
+static BASE64_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-z0-9+/]{100,}={0,2}").expect("base64 regex must compile"));
+
+/// Long hex runs.
+static HEX_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[0-9a-fA-F]{100,}\b").expect("hex regex must compile"));
+
+/// Base64 rather than a long path or identifier: mixed case, digits and
+/// few slashes.
+fn looks_like_base64(run: &str) -> bool {
+    let has = |pred: fn(&u8) -> bool| run.bytes().any(|b| pred(&b));
+    has(u8::is_ascii_uppercase)
+        && has(u8::is_ascii_lowercase)
+        && has(u8::is_ascii_digit)
+        && run.bytes().filter(|&b| b == b'/').count() <= run.len() / 16
+}
+
+/// Keep the ends of an ASCII blob around an elision marker.
+fn shorten_blob(blob: &str, kind: &str) -> String {
+    let elided = blob.len() - BLOB_HEAD_CHARS - BLOB_TAIL_CHARS;
+    format!(
+        "{}[... {elided} chars of {kind} elided ...]{}",
+        &blob[..BLOB_HEAD_CHARS],
+        &blob[blob.len() - BLOB_TAIL_CHARS..]
+    )
+}
+
+/// Shorten data URIs, hex runs and base64 runs inside one line.
+fn shorten_blobs(line: &str) -> Cow<'_, str> {
+    let line = DATA_URI_RE.replace_all(line, |caps: &Captures| {
+        let payload = &caps[1];
+        let prefix = &caps[0][..caps[0].len() - payload.len()];
+        format!("{prefix}{}", shorten_blob(payload, "data URI"))
+    });
+    let line = match HEX_RE.replace_all(&line, |caps: &Captures| shorten_blob(&caps[0], "hex")) {
+        Cow::Owned(s) => Cow::Owned(s),
+        Cow::Borrowed(_) => line,
+    };
+    match BASE64_RE.replace_all(&line, |caps: &Captures| {
+        let run = &caps[0];
+        if looks_like_base64(run) {
+            shorten_blob(run, "base64")
+        } else {
+            run.to_string()
+        }
+    }) {
+        Cow::Owned(s) => Cow::Owned(s),
+        Cow::Borrowed(_) => line,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Long lines
+// ---------------------------------------------------------------------------
+
+/// What a long line most likely holds, for the elision marker.
+fn classify_line(line: &str) -> &'static str {
+    let trimmed = line.trim_start();
+    let len = line.len().max(1) as f64;
+    let spaces = line.bytes().filter(u8::is_ascii_whitespace).count() as f64;
+    let code_punct = line.bytes().filter(|b| b";{}(),=".contains(b)).count() as f64;
+    if (trimmed.starts_with('{') || trimmed.starts_with('[')) && line.contains("\":") {
+        "JSON"
+    } else if spaces / len < 0.02 && code_punct / len > 0.05 {
+        "minified code"
+    } else {
+        "text"
+    }
+}
+
+/// Byte offset of the `n`th character of `s` (or `s.len()`).
+fn char_offset(s: &str, n: usize) -> usize {
+    s.char_indices().nth(n).map_or(s.len(), |(i, _)| i)
+}
+
+/// A complete JSON object on one line, e.g. an NDJSON record.
+fn is_json_record(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with('{')
+        && trimmed.ends_with('}')
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok_and(|v| v.is_object())
+}
+
+/// Keep the start and end of a line longer than `max_chars` characters.
+fn elide_line(line: &str, max_chars: usize) -> Cow<'_, str> {
+    if line.len() <= max_chars {
+        return Cow::Borrowed(line);
+    }
+    let chars = line.chars().count();
+    if chars <= max_chars {
+        return Cow::Borrowed(line);
+    }
+    let head = (max_chars as f64 * HEAD_RATIO) as usize;
+    let tail = (max_chars as f64 * TAIL_RATIO) as usize;
+    let head_end = char_offset(line, head);
+    let tail_start = char_offset(line, chars - tail);
+    Cow::Owned(format!(
+        "{}[... {} chars of {} elided ...]{}",
+        &line[..head_end],
+        chars - head - tail,
+        classify_line(line),
+        &line[tail_start..]
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Shorten embedded blobs and elide the middle of lines longer than
+/// `max_chars` characters, leaving JSON records whole. `0` disables the
+/// stage.
+pub fn elide_long_lines(text: &str, max_chars: usize) -> String {
+    if max_chars == 0 {
+        return text.to_string();
+    }
+    let mut result = String::with_capacity(text.len());
+    for line in text.lines() {
+        if is_json_record(line) {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        }
+        let line = shorten_blobs(line);
+        result.push_str(&elide_line(&line, max_chars));
+        result.push('\n');
+    }
+    result
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elides_minified_line_keeping_ends() {
+        let bundle = format!(
+            "!function(e){{{}}}(window);",
+            "a=1;if(a){b(c,d)};".repeat(200)
+        );
+        let input = format!("dist/app.js:\n{bundle}\ndone\n");
+        let result = elide_long_lines(&input, 1000);
+
+        assert!(result.starts_with("dist/app.js:\n!function(e){"));
+        assert!(result.contains("chars of minified code elided ...]"));
+        assert!(result.contains("}(window);\ndone\n"));
+        assert!(result.lines().all(|l| l.chars().count() < 1100));
+    }
+
+    #[test]
+    fn shortens_embedded_base64_and_data_uri() {
+        let payload: String = (0..300)
+            .map(|i| ['Q', 'm', '9', 'x', 'Z', '3', 'J', 'h'][i % 8])
+            .collect();
+        let input = format!(
+            "  \"icon\": \"data:image/png;base64,{payload}\",\n  \"blob\": \"{payload}\"\n"
+        );
+        let result = elide_long_lines(&input, 1000);
+
+        assert!(result.contains(
+            "data:image/png;base64,Qm9xZ3JhQm9xZ3Jh[... 276 chars of data URI elided ...]"
+        ));
+        assert!(result.contains("chars of base64 elided ...]"));
+        assert!(!result.contains(&payload));
+    }
+
+    #[test]
+    fn shortens_hex_runs() {
+        let hex = "deadbeef0123456789".repeat(10);
+        let result = elide_long_lines(&format!("digest {hex}\n"), 1000);
+        assert!(result.starts_with("digest deadbeef01234567[... 156 chars of hex elided ...]"));
+    }
+
+    #[test]
+    fn leaves_ordinary_lines_alone() {
+        let path = format!("/home/user/{}/file.rs", "project/src".repeat(12));
+        let input = format!("error: failed\n{path}\n");
+        assert_eq!(elide_long_lines(&input, 1000), input);
+        assert_eq!(elide_long_lines(&input, 0), input);
+    }
+
+    #[test]
+    fn keeps_json_records_whole() {
+        let record = format!(
+            "{{\"reason\":\"compiler-message\",\"message\":{{\"rendered\":\"{}\"}}}}",
+            "error[E0308]: mismatched types ".repeat(100)
+        );
+        let input = format!("{record}\n{record}\n");
+        assert_eq!(elide_long_lines(&input, 1000), input);
+
+        let cut = format!("{}\n", &record[..record.len() - 1]);
+        assert!(elide_long_lines(&cut, 1000).contains("chars of JSON elided ...]"));
+    }
+
+    #[test]
+    fn elides_multibyte_lines_on_char_boundaries() {
+        let input = format!("{}\n", "é".repeat(2000));
+        let result = elide_long_lines(&input, 100);
+        assert!(result.contains("[... 1920 chars of text elided ...]"));
+    }
+
+    #[test]
+    fn recognizes_binary_output() {
+        let png = [
+            &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'][..],
+            &[0x00, 0xff, 0x13, 0x80, 0xfe, 0x02, 0x9c, 0x00].repeat(64),
+        ]
+        .concat();
+        let text = String::from_utf8_lossy(&png);
+        let summary = describe_binary(&text).unwrap();
+        assert!(summary.starts_with("[binary output: PNG image, ~"));
+
+        assert_eq!(describe_binary("plain text\nwith\ttabs\n"), None);
+        assert_eq!(describe_binary(""), None);
+    }
+}
@@ -7,27 +7,57 @@
 //!
 //! # Pipeline Stages
 //!
+//! Before any stage runs, the router masks credentials in the raw output
+//! streams (see [`redact`]), so no path ever sees them. Binary output is
+//! replaced by a one-line summary of its type and size.
+//!
+//! 0. **JSON summarization** — output that is a single JSON document is
+//!    summarized structurally (see [`crate::optimizers::json`]) instead of
+//!    going through the line-based stages, which would break it.
 //! 1. **Noise removal** — strip ANSI escape codes, progress bars, spinner
-//!    frames, and common boilerplate lines.
+//!    frames, and common boilerplate lines. Over-long lines (minified code,
+//!    base64 blobs) keep only their ends (see [`long_lines`]), and stack
+//!    traces are folded to their message and project frames (see
+//!    [`stack_trace`]).
 //! 2. **Path filtering** — collapse verbose directory listings (node_modules,
-//!    .git, target, etc.) into summary lines.
+//!    .git, target, etc.) into summary lines, then, when enabled, shorten
+//!    absolute paths under the working directory, repository root and home,
+//!    and factor out a directory shared by most lines (see [`path_prefix`]).
 //! 3. **Deduplication** — collapse repeated or near-identical consecutive
-//!    lines into counted summaries.
+//!    lines into counted summaries. For log and container commands, log
+//!    clustering first folds interleaved lines into learned templates (see
+//!    [`log_cluster`]); for opted-in command categories, later exact
+//!    duplicates anywhere in the output are dropped (see [`dedup`]).
 //! 4. **Truncation** — if output still exceeds a configurable max size, keep
-//!    the head and tail with a middle-truncation marker.
+//!    context around errors, warnings and summaries plus the head and tail,
+//!    marking each elided gap (see [`truncation`]). Skipped when the
+//!    command bounded its own output (`head -n 500`, `| tail -200`).
 //! 5. **Trim** — normalize whitespace: collapse runs of blank lines, strip
 //!    trailing whitespace, trim leading/trailing.
 
 pub mod dedup;
+pub mod log_cluster;
+pub mod long_lines;
 pub mod noise;
 pub mod path_filter;
+pub mod path_prefix;
+pub mod redact;
+pub mod stack_trace;
 pub mod trim;
 pub mod truncation;
 
 use std::borrow::Cow;
 use std::time::Instant;
 
+use crate::config::schema::PreprocessingConfig;
+use crate::matching;
+use crate::matching::limits;
+use crate::optimizers::json::{self, JsonLimits};
 use crate::utils::token_counter::estimate_tokens;
+use dedup::DedupOptions;
+use log_cluster::ClusterOptions;
+use path_prefix::PathRoots;
+use truncation::TruncationMode;
 
 // ---------------------------------------------------------------------------
 // Pipeline output
@@ -51,6 +81,10 @@ pub struct PreprocessedOutput {
     pub tokens_before: usize,
     /// Token count of the preprocessed output.
     pub tokens_after: usize,
+    /// The whole output was replaced by a summary (a JSON document's
+    /// structure, or binary data's type and size). Its size says nothing
+    /// about how large the command output was.
+    pub summarized: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -66,9 +100,13 @@ pub struct PreprocessedOutput {
 /// (`preprocessing.max_output_bytes`). If no config is present, the built-in
 /// default (128 KB) is used.
 ///
-/// `command` is provided for context-aware decisions (e.g. path filtering
-/// heuristics) but may be unused in early stages.
-pub fn preprocess(raw: &str, _command: &str) -> PreprocessedOutput {
+/// `command` selects the JSON summarization limits
+/// (`optimizers.json.commands`), log clustering
+/// (`preprocessing.log_clustering`) and the deduplication mode
+/// (`preprocessing.global_dedup_categories`), and may lift truncation (see
+/// [`truncation_budget`]). `preprocessing.truncation_mode` picks how lines
+/// are dropped.
+pub fn preprocess(raw: &str, command: &str) -> PreprocessedOutput {
     let cfg = crate::config::load();
 
     // If preprocessing is disabled, return the raw input unchanged with
@@ -83,22 +121,110 @@ pub fn preprocess(raw: &str, _command: &str) -> PreprocessedOutput {
             duration_ms: 0,
             tokens_before: tokens,
             tokens_after: tokens,
+            summarized: false,
         };
     }
 
-    let max_bytes = cfg.preprocessing.max_output_bytes;
-    preprocess_with_max(raw, _command, max_bytes)
+    let max_bytes = truncation_budget(raw, command, cfg.preprocessing.max_output_bytes);
+    let core = matching::extract_core_command(command);
+    let json_limits = json::limits_for(&cfg.optimizers.json, core);
+    let options = LineOptions::for_command(&cfg.preprocessing, core);
+    run_pipeline(raw, max_bytes, json_limits, options)
 }
 
-/// Run the preprocessing pipeline with a custom max output size (bytes).
-///
-/// Primarily used by tests.
-pub fn preprocess_with_max(raw: &str, _command: &str, max_bytes: usize) -> PreprocessedOutput {
+/// Run the preprocessing pipeline with a custom max output size (bytes)
+/// and default JSON limits and line-stage settings.
+#[cfg(test)]
+pub fn preprocess_with_max(raw: &str, command: &str, max_bytes: usize) -> PreprocessedOutput {
+    let max_bytes = truncation_budget(raw, command, max_bytes);
+    let core = matching::extract_core_command(command);
+    let json_limits =
+        json::limits_for(&crate::config::schema::JsonOptimizerConfig::default(), core);
+    let options = LineOptions::for_command(&PreprocessingConfig::default(), core);
+    run_pipeline(raw, max_bytes, json_limits, options)
+}
+
+/// Per-command settings for the line-based stages.
+#[derive(Debug, Clone)]
+struct LineOptions {
+    max_line_chars: usize,
+    path_roots: Option<PathRoots>,
+    clustering: Option<ClusterOptions>,
+    dedup: DedupOptions,
+    truncation: TruncationMode,
+}
+
+impl LineOptions {
+    fn for_command(cfg: &PreprocessingConfig, core: &str) -> Self {
+        Self {
+            max_line_chars: cfg.max_line_chars,
+            path_roots: path_prefix::roots_for(cfg, core),
+            clustering: log_cluster::options_for(cfg, core),
+            dedup: dedup::options_for(cfg, core),
+            truncation: TruncationMode::from_config(cfg),
+        }
+    }
+}
+
+/// `max_bytes`, or no limit when the command bounded its own output
+/// (`head -n 500`, `git log -n 50 --oneline`) and the output is within
+/// that bound: the user asked for exactly those lines.
+fn truncation_budget(raw: &str, command: &str, max_bytes: usize) -> usize {
+    match limits::explicit_limits(command).lines {
+        Some(lines) if raw.lines().count() <= lines => usize::MAX,
+        _ => max_bytes,
+    }
+}
+
+fn run_pipeline(
+    raw: &str,
+    max_bytes: usize,
+    json_limits: Option<JsonLimits>,
+    options: LineOptions,
+) -> PreprocessedOutput {
     let start = Instant::now();
     let original_bytes = raw.len();
     let tokens_before = estimate_tokens(raw);
 
-    // Stage 0: pre-truncation
+    // Binary output: replaced by its type and size, no stage can read it.
+    //
+    // Stage 0: JSON summarization
+    //
+    // Runs on the whole raw input: pre-truncation would leave it unparsable.
+    let summary = long_lines::describe_binary(raw).or_else(|| {
+        json_limits
+            .and_then(|limits| json::summarize_json_document(raw, &limits))
+            .map(|summary| truncation::truncate(&summary, max_bytes))
+    });
+    let summarized = summary.is_some();
+    let text = summary.unwrap_or_else(|| line_stages(raw, max_bytes, &options));
+
+    let duration_ms = start.elapsed().as_millis() as u64;
+    let tokens_after = estimate_tokens(&text);
+    let bytes_removed = original_bytes.saturating_sub(text.len());
+    let reduction_pct = if original_bytes == 0 {
+        0.0
+    } else {
+        (bytes_removed as f64 / original_bytes as f64) * 100.0
+    };
+
+    PreprocessedOutput {
+        text,
+        original_bytes,
+        bytes_removed,
+        reduction_pct,
+        duration_ms,
+        tokens_before,
+        tokens_after,
+        summarized,
+    }
+}
+
+/// Stages 1–5, preceded by a fast pre-truncation of very large input.
+fn line_stages(raw: &str, max_bytes: usize, options: &LineOptions) -> String {
+    let original_bytes = raw.len();
+
+    // Pre-truncation
     //
     // If the input vastly exceeds the final truncation budget, do a fast
     // byte-level head+tail cut *before* running expensive per-line stages.
@@ -113,39 +239,35 @@ pub fn preprocess_with_max(raw: &str, _command: &str, max_bytes: usize) -> Prepr
         Cow::Borrowed(raw)
     };
 
-    // Stage 1: noise removal
+    // Stage 1: noise removal, long-line elision, then stack trace folding
     let text = noise::strip_noise(&working_text);
+    let text = long_lines::elide_long_lines(&text, options.max_line_chars);
+    let text = stack_trace::fold_stack_traces(&text);
 
-    // Stage 2: path filtering
+    // Stage 2: path filtering, then path prefix shortening
     let text = path_filter::filter_paths(&text);
+    let text = match &options.path_roots {
+        Some(roots) => path_prefix::shorten_paths(&text, roots),
+        None => text,
+    };
 
-    // Stage 3: deduplication
+    // Stage 3: log clustering, then deduplication
+    let text = match &options.clustering {
+        Some(options) => log_cluster::cluster_logs(&text, options),
+        None => text,
+    };
+    let text = if options.dedup.global {
+        dedup::deduplicate_global(&text, options.dedup.summary)
+    } else {
+        text
+    };
     let text = dedup::deduplicate(&text);
 
     // Stage 4: truncation (only if still over budget)
-    let text = truncation::truncate(&text, max_bytes);
+    let text = truncation::truncate_with_mode(&text, max_bytes, options.truncation);
 
     // Stage 5: trim / whitespace normalization
-    let text = trim::normalize_whitespace(&text);
-
-    let duration_ms = start.elapsed().as_millis() as u64;
-    let tokens_after = estimate_tokens(&text);
-    let bytes_removed = original_bytes.saturating_sub(text.len());
-    let reduction_pct = if original_bytes == 0 {
-        0.0
-    } else {
-        (bytes_removed as f64 / original_bytes as f64) * 100.0
-    };
-
-    PreprocessedOutput {
-        text,
-        original_bytes,
-        bytes_removed,
-        reduction_pct,
-        duration_ms,
-        tokens_before,
-        tokens_after,
-    }
+    trim::normalize_whitespace(&text)
 }
 
 // ---------------------------------------------------------------------------
@@ -252,4 +374,82 @@ mod tests {
             "Summary line must survive preprocessing"
         );
     }
+
+    #[test]
+    fn explicit_line_limit_is_not_truncated() {
+        let words = [
+            "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf",
+        ];
+        let input: String = (0..500)
+            .map(|i| format!("{} request {i} took {}ms\n", words[i % 7], i * 3 % 17))
+            .collect();
+
+        let bounded = preprocess_with_max(&input, "tail -n 500 app.log", 1024);
+        assert!(bounded.text.contains("alpha request 0 took"));
+        assert!(bounded.text.contains("request 250 took"));
+
+        let unbounded = preprocess_with_max(&input, "cat app.log", 1024);
+        assert!(!unbounded.text.contains("request 250 took"));
+    }
+
+    #[test]
+    fn log_commands_are_clustered() {
+        let input: String = (0..300)
+            .map(|i| match i % 2 {
+                0 => format!("2024-10-17T10:00:{:02}Z GET /health {i}\n", i % 60),
+                _ => format!("2024-10-17T10:00:{:02}Z job {i} done\n", i % 60),
+            })
+            .collect();
+
+        let logs = preprocess_with_max(&input, "docker logs api", 128 * 1024);
+        assert!(logs.text.contains("[150 lines like: GET /health <*>"));
+
+        let other = preprocess_with_max(&input, "cargo run", 128 * 1024);
+        assert!(!other.text.contains("lines like:"));
+    }
+
+    #[test]
+    fn binary_output_is_summarized() {
+        let bytes = [
+            b"\x7fELF\x02\x01\x01\0".as_slice(),
+            &[0u8, 0xff, 0x80, 3].repeat(500),
+        ]
+        .concat();
+        let input = String::from_utf8_lossy(&bytes);
+
+        let result = preprocess_with_max(&input, "cat ./app", 128 * 1024);
+        assert!(result.text.starts_with("[binary output: ELF executable, ~"));
+    }
+
+    #[test]
+    fn minified_lines_keep_their_ends() {
+        let input = format!("built dist/app.min.js\n{}\n", "x=f(a,b);".repeat(2000));
+
+        let result = preprocess_with_max(&input, "cat dist/app.min.js", 128 * 1024);
+        assert!(result.text.contains("chars of minified code elided ...]"));
+        assert!(result.text.len() < 2000);
+    }
+
+    #[test]
+    fn large_json_is_summarized_not_truncated() {
+        let items: Vec<String> = (0..5000)
+            .map(|i| format!(r#"{{"id": {i}, "path": "node_modules/pkg{i}/index.js"}}"#))
+            .collect();
+        let input = format!(r#"{{"packages": [{}]}}"#, items.join(",\n"));
+
+        let result = preprocess_with_max(&input, "cargo metadata", 16 * 1024);
+        assert!(result.summarized);
+        let value: serde_json::Value = serde_json::from_str(&result.text).unwrap();
+        assert_eq!(
+            value["packages"][0],
+            "[5000 objects, showing 3; schema {id: number, path: string}]"
+        );
+
+        // File contents keep every element.
+        let items: Vec<String> = (0..50).map(|i| format!(r#"{{"id": {i}}}"#)).collect();
+        let file = format!(r#"{{"items": [{}]}}"#, items.join(",\n"));
+        let read = preprocess_with_max(&file, "cat items.json", 16 * 1024);
+        assert!(!read.summarized);
+        assert!(read.text.contains(r#"{"id": 49}"#));
+    }
 }
@@ -24,6 +24,15 @@ static ANSI_RE: LazyLock<Regex> = LazyLock::new(|| {
         .expect("ANSI regex must compile")
 });
 
+/// Remove ANSI escape sequences from a line.
+pub fn strip_ansi(line: &str) -> Cow<'_, str> {
+    if line.contains('\x1b') {
+        ANSI_RE.replace_all(line, "")
+    } else {
+        Cow::Borrowed(line)
+    }
+}
+
 /// Matches common progress indicators:
 /// - Percentage patterns like `73%`, `[====>   ]`, `[####    ]`
 /// - Spinner characters
@@ -129,11 +138,7 @@ pub fn strip_noise(raw: &str) -> String {
         // log, build output) the vast majority of lines contain no ANSI
         // codes, and a byte scan is orders of magnitude cheaper than
         // executing the regex automaton.
-        let clean: Cow<'_, str> = if line.contains('\x1b') {
-            ANSI_RE.replace_all(line, "")
-        } else {
-            Cow::Borrowed(line)
-        };
+        let clean = strip_ansi(line);
         let trimmed = clean.trim_start();
 
         // Step 2: skip boilerplate lines
@@ -65,22 +65,43 @@ fn is_noise_path_line(line: &str) -> bool {
         return false;
     }
 
+    noise_segment(trimmed).is_some()
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// The noise directory segment a path runs through, if any.
+///
+/// Backslashes are normalized so Windows paths match the same segments.
+pub fn noise_segment(path: &str) -> Option<&'static str> {
     // Normalize backslashes for cross-platform matching only when
     // backslashes are actually present — avoids a `String` allocation
     // on the overwhelmingly common forward-slash / no-slash case.
-    if trimmed.contains('\\') {
-        let normalized = trimmed.replace('\\', "/");
+    if path.contains('\\') {
+        let normalized = path.replace('\\', "/");
         NOISE_DIR_SEGMENTS
             .iter()
-            .any(|seg| normalized.contains(seg))
+            .copied()
+            .find(|seg| normalized.contains(seg))
     } else {
-        NOISE_DIR_SEGMENTS.iter().any(|seg| trimmed.contains(seg))
+        NOISE_DIR_SEGMENTS
+            .iter()
+            .copied()
+            .find(|seg| path.contains(seg))
     }
 }
 
-// ---------------------------------------------------------------------------
-// Public API
-// ---------------------------------------------------------------------------
+/// Number of lines collapsed into a summary line written by
+/// [`filter_paths`], or `None` if `line` is not such a summary.
+pub fn filtered_count(line: &str) -> Option<usize> {
+    line.trim()
+        .strip_prefix('[')?
+        .strip_suffix(" path(s) in noise directories filtered]")?
+        .parse()
+        .ok()
+}
 
 /// Filter noise directory paths from the output.
 ///
@@ -215,6 +236,21 @@ Cargo.toml
         assert!(!result.contains("node_modules/serde"));
     }
 
+    #[test]
+    fn noise_segment_and_summary_round_trip() {
+        assert_eq!(
+            noise_segment("node_modules/a/index.js:3:x"),
+            Some("node_modules")
+        );
+        assert_eq!(noise_segment("src\\.idea\\x.xml"), Some(".idea/"));
+        assert_eq!(noise_segment("src/main.rs"), None);
+
+        let filtered = filter_paths("node_modules/a\nnode_modules/b\nsrc/x\n");
+        let first = filtered.lines().next().unwrap();
+        assert_eq!(filtered_count(first), Some(2));
+        assert_eq!(filtered_count("src/x"), None);
+    }
+
     #[test]
     fn empty_input() {
         assert_eq!(filter_paths(""), "");
@@ -0,0 +1,352 @@
+//! Path prefixes — shorten absolute paths that repeat on every line.
+//!
+//! Runs in Stage 2 after path filtering. Compiler errors, `find` output,
+//! stack traces and test runners repeat long absolute paths such as
+//! `/home/me/work/monorepo/services/api/src/...` on every line. This module:
+//!
+//! 1. Rewrites paths under the working directory, the git repository root
+//!    and `$HOME` to `./`, `<repo>/` and `~/` (most specific root first).
+//! 2. Factors a directory shared by most lines out into `<dir>/`.
+//!
+//! Every placeholder other than `./` and `~/` is defined once in a header
+//! line (`[<repo> = /home/me/work/monorepo]`), so the agent can always
+//! rebuild the full path and open the file.
+//!
+//! The stage is opt-in (`preprocessing.path_shortening`) and never applies
+//! to file reads and diffs, whose paths are content rather than locations.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::{MAIN_SEPARATOR, Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::config::schema::PreprocessingConfig;
+use crate::matching;
+
+// ---------------------------------------------------------------------------
+// Configuration
+// ---------------------------------------------------------------------------
+
+/// Placeholder for the git repository root.
+const REPO: &str = "<repo>";
+
+/// Placeholder for the factored common directory.
+const DIR: &str = "<dir>";
+
+/// Minimum length of a common directory worth factoring out.
+const MIN_PREFIX_LEN: usize = 16;
+
+/// Minimum number of lines sharing a directory before it is factored out.
+const MIN_FACTOR_LINES: usize = 5;
+
+/// Fraction of non-blank lines that must share a directory.
+const FACTOR_SHARE: f64 = 0.5;
+
+/// Programs that print file contents or diffs.
+const CONTENT_PROGRAMS: &[&str] = &[
+    "cat",
+    "head",
+    "tail",
+    "less",
+    "more",
+    "bat",
+    "type",
+    "get-content",
+    "gc",
+    "diff",
+    "colordiff",
+];
+
+/// `git` subcommands that print file contents or patches.
+const GIT_CONTENT_SUBCOMMANDS: &[&str] = &["diff", "show", "blame", "format-patch"];
+
+// ---------------------------------------------------------------------------
+// Roots
+// ---------------------------------------------------------------------------
+
+/// Absolute directories that paths are rewritten against.
+#[derive(Debug, Clone, Default)]
+pub struct PathRoots {
+    /// `(absolute root, placeholder)`, longest root first.
+    prefixes: Vec<(String, &'static str)>,
+    /// Repository root, for the `<repo>` header.
+    repo: Option<String>,
+}
+
+impl PathRoots {
+    /// Roots for the current process: working directory, the nearest
+    /// ancestor holding `.git`, and the home directory.
+    pub fn detect() -> Self {
+        let cwd = std::env::current_dir().ok();
+        let repo = cwd.as_deref().and_then(|dir| {
+            dir.ancestors()
+                .find(|d| d.join(".git").exists())
+                .map(Path::to_path_buf)
+        });
+        Self::new(cwd, repo, dirs::home_dir())
+    }
+
+    /// Roots from explicit directories. Duplicates keep the most specific
+    /// placeholder (`./` over `<repo>/` over `~/`); `/` is ignored.
+    pub fn new(cwd: Option<PathBuf>, repo: Option<PathBuf>, home: Option<PathBuf>) -> Self {
+        let mut prefixes: Vec<(String, &'static str)> = Vec::new();
+        for (dir, placeholder) in [(cwd, "."), (repo, REPO), (home, "~")] {
+            let Some(dir) = dir.and_then(|d| d.to_str().map(str::to_string)) else {
+                continue;
+            };
+            let dir = dir.trim_end_matches(['/', MAIN_SEPARATOR]).to_string();
+            if dir.len() < 2 || prefixes.iter().any(|(d, _)| *d == dir) {
+                continue;
+            }
+            prefixes.push((dir, placeholder));
+        }
+        prefixes.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.len()));
+        let repo = prefixes
+            .iter()
+            .find(|(_, p)| *p == REPO)
+            .map(|(d, _)| d.clone());
+        Self { prefixes, repo }
+    }
+}
+
+/// Roots to shorten paths against for a core command, or `None` when path
+/// shortening is off or the command prints file contents or a diff.
+pub fn roots_for(cfg: &PreprocessingConfig, core: &str) -> Option<PathRoots> {
+    if !cfg.path_shortening {
+        return None;
+    }
+    let words: Vec<&str> = core.split_whitespace().collect();
+    let program = matching::program_name(words.first().copied().unwrap_or_default());
+    // The git subcommand is the first word that is neither an option nor
+    // the value of `-C <dir>` / `-c <key=value>`.
+    let git_subcommand = || {
+        (1..words.len())
+            .find(|&i| !words[i].starts_with('-') && !matches!(words[i - 1], "-C" | "-c"))
+            .map(|i| words[i])
+    };
+    let content = CONTENT_PROGRAMS.contains(&program.as_str())
+        || (program == "git"
+            && git_subcommand().is_some_and(|sub| GIT_CONTENT_SUBCOMMANDS.contains(&sub)));
+    (!content).then(PathRoots::detect)
+}
+
+// ---------------------------------------------------------------------------
+// Prefix replacement
+// ---------------------------------------------------------------------------
+
+/// Whether `c` may precede the start of a path: anything but a character
+/// that would make the match the middle of a longer path or word.
+fn is_boundary(c: char) -> bool {
+    !(c.is_alphanumeric() || matches!(c, '/' | '\\' | '.' | '_' | '-' | '~' | '>' | '@' | '+'))
+}
+
+/// Replace `prefix` followed by a path separator with `short` wherever it
+/// starts a path.
+fn replace_prefix<'a>(line: &'a str, prefix: &str, short: &str) -> Cow<'a, str> {
+    let mut result = String::new();
+    let mut copied = 0;
+    for (pos, _) in line.match_indices(prefix) {
+        if pos < copied {
+            continue;
+        }
+        let after = &line[pos + prefix.len()..];
+        let separated = after.starts_with('/') || after.starts_with(MAIN_SEPARATOR);
+        let bounded = line[..pos].chars().next_back().is_none_or(is_boundary);
+        if separated && bounded {
+            result.push_str(&line[copied..pos]);
+            result.push_str(short);
+            copied = pos + prefix.len();
+        }
+    }
+    if copied == 0 {
+        return Cow::Borrowed(line);
+    }
+    result.push_str(&line[copied..]);
+    Cow::Owned(result)
+}
+
+// ---------------------------------------------------------------------------
+// Common directory
+// ---------------------------------------------------------------------------
+
+/// Path tokens with at least two components, absolute or already
+/// shortened; group 1 is the path.
+static PATH_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?:^|[\s"'`(\[=,:;])((?:\.|~|<repo>)?/[\w.@+-]+(?:/[\w.@+-]+)+)"#)
+        .expect("path regex must compile")
+});
+
+/// The deepest directory shared by most non-blank lines, if any is long
+/// enough to be worth a header.
+fn common_directory(lines: &[String]) -> Option<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut non_blank = 0usize;
+    for line in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        non_blank += 1;
+        let mut dirs: HashSet<&str> = HashSet::new();
+        for caps in PATH_RE.captures_iter(line) {
+            let path = caps.get(1).map_or("", |m| m.as_str());
+            for (i, _) in path.match_indices('/').skip(1) {
+                dirs.insert(&path[..i]);
+            }
+        }
+        for dir in dirs {
+            *counts.entry(dir).or_default() += 1;
+        }
+    }
+
+    let needed = ((non_blank as f64 * FACTOR_SHARE).ceil() as usize).max(MIN_FACTOR_LINES);
+    counts
+        .into_iter()
+        .filter(|&(dir, n)| n >= needed && dir.len() >= MIN_PREFIX_LEN)
+        .max_by(|a, b| a.0.len().cmp(&b.0.len()).then(b.0.cmp(a.0)))
+        .map(|(dir, _)| dir.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Rewrite absolute paths against `roots`, then factor out a directory
+/// shared by most lines. Placeholders that need one get a header line.
+pub fn shorten_paths(text: &str, roots: &PathRoots) -> String {
+    let mut used_repo = false;
+    let mut lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            let mut line = Cow::Borrowed(line);
+            for (root, short) in &roots.prefixes {
+                if let Cow::Owned(s) = replace_prefix(&line, root, short) {
+                    used_repo |= *short == REPO;
+                    line = Cow::Owned(s);
+                }
+            }
+            line.into_owned()
+        })
+        .collect();
+
+    let mut header = String::new();
+    if used_repo && let Some(repo) = &roots.repo {
+        header.push_str(&format!("[{REPO} = {repo}]\n"));
+    }
+    if let Some(dir) = common_directory(&lines) {
+        for line in &mut lines {
+            if let Cow::Owned(s) = replace_prefix(line, &dir, DIR) {
+                *line = s;
+            }
+        }
+        header.push_str(&format!("[{DIR} = {dir}]\n"));
+    }
+
+    let mut result = String::with_capacity(text.len());
+    result.push_str(&header);
+    for line in &lines {
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roots() -> PathRoots {
+        PathRoots::new(
+            Some(PathBuf::from("/home/me/work/mono/services/api")),
+            Some(PathBuf::from("/home/me/work/mono")),
+            Some(PathBuf::from("/home/me")),
+        )
+    }
+
+    #[test]
+    fn rewrites_cwd_repo_and_home() {
+        let input = "\
+error: /home/me/work/mono/services/api/src/main.rs:10:5: mismatched types
+note: defined in /home/me/work/mono/libs/core/src/lib.rs:3
+config read from /home/me/.config/tool.toml
+";
+        let result = shorten_paths(input, &roots());
+        assert_eq!(
+            result,
+            "\
+[<repo> = /home/me/work/mono]
+error: ./src/main.rs:10:5: mismatched types
+note: defined in <repo>/libs/core/src/lib.rs:3
+config read from ~/.config/tool.toml
+"
+        );
+    }
+
+    #[test]
+    fn leaves_embedded_and_partial_matches() {
+        let input = "\
+/mnt/home/me/work/file.txt
+/home/meadow/file.txt
+file:///home/me/notes.md
+/home/me
+";
+        assert_eq!(shorten_paths(input, &roots()), input);
+    }
+
+    #[test]
+    fn factors_common_directory() {
+        let input: String = (0..8)
+            .map(|i| format!("/srv/build/pkgs/widget/src/mod_{i}.py:{i}: lint warning\n"))
+            .chain(["done\n".to_string()])
+            .collect();
+        let result = shorten_paths(&input, &PathRoots::default());
+        assert!(result.starts_with("[<dir> = /srv/build/pkgs/widget/src]\n"));
+        assert!(result.contains("\n<dir>/mod_3.py:3: lint warning\n"));
+        assert_eq!(result.matches("/srv/build").count(), 1);
+    }
+
+    #[test]
+    fn no_factoring_without_a_majority() {
+        let input = "\
+/srv/build/pkgs/widget/src/a.py
+/srv/build/pkgs/widget/src/b.py
+one
+two
+three
+four
+";
+        assert_eq!(shorten_paths(input, &PathRoots::default()), input);
+    }
+
+    #[test]
+    fn opt_in_and_skips_file_reads_and_diffs() {
+        let off = PreprocessingConfig::default();
+        assert!(roots_for(&off, "cargo build").is_none());
+
+        let on = PreprocessingConfig {
+            path_shortening: true,
+            ..PreprocessingConfig::default()
+        };
+        assert!(roots_for(&on, "cargo build").is_some());
+        assert!(roots_for(&on, "cat src/main.rs").is_none());
+        assert!(roots_for(&on, "git diff HEAD~1").is_none());
+        assert!(roots_for(&on, "git -C /repo show abc123").is_none());
+        assert!(roots_for(&on, "git status").is_some());
+    }
+
+    #[test]
+    fn duplicate_roots_keep_most_specific() {
+        let roots = PathRoots::new(
+            Some(PathBuf::from("/home/me/proj")),
+            Some(PathBuf::from("/home/me/proj")),
+            Some(PathBuf::from("/")),
+        );
+        let result = shorten_paths("/home/me/proj/src/lib.rs\n/etc/hosts\n", &roots);
+        assert_eq!(result, "./src/lib.rs\n/etc/hosts\n");
+    }
+}
@@ -0,0 +1,410 @@
+//! Stack trace folding — keep the frames that point at the project.
+//!
+//! Runs right after noise removal, before path filtering would collapse
+//! `node_modules` frames out of context. Failing tests and crashes print
+//! traces of dozens of frames, most of them in the standard library, the
+//! runtime or dependencies. Recognized traces:
+//!
+//! - Rust panics with `RUST_BACKTRACE=1` (`stack backtrace:` then
+//!   `  N: name` / `at path` pairs)
+//! - Python tracebacks (`File "...", line N, in f` plus the source line)
+//! - JVM, Node and .NET `at ...` frames, with `... N more` and
+//!   `--- End of stack trace ... ---` lines
+//!
+//! Messages (`panicked at`, `ValueError: ...`, `Caused by: ...`) are never
+//! frames and always stay. In each trace the first [`MAX_PROJECT_FRAMES`]
+//! project frames and the deepest project frame are kept; every other run
+//! of frames becomes a `[... N library frames ...]` marker.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+// ---------------------------------------------------------------------------
+// Configuration
+// ---------------------------------------------------------------------------
+
+/// Project frames kept from the top of each trace.
+const MAX_PROJECT_FRAMES: usize = 3;
+
+/// Traces with fewer frames are kept whole.
+const MIN_TRACE_FRAMES: usize = 5;
+
+// ---------------------------------------------------------------------------
+// Frame recognition
+// ---------------------------------------------------------------------------
+
+/// `   3: myapp::main` after a `stack backtrace:` line.
+static RUST_FRAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*\d+: \S").expect("Rust frame regex must compile"));
+
+/// `             at ./src/main.rs:9:5` under a Rust frame.
+static RUST_LOCATION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s+at \S").expect("Rust location regex must compile"));
+
+/// `  File "/app/main.py", line 10, in <module>`.
+static PYTHON_FRAME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^\s*File "[^"]+", line \d+"#).expect("Python frame regex must compile")
+});
+
+/// JVM `at a.B.c(B.java:12)`, Node `at f (/app/x.js:1:2)` and .NET
+/// `at A.B() in /src/B.cs:line 42`.
+static AT_FRAME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s+at \S.*(?:\)|:\d+(?::\d+)?|:line \d+)$").expect("frame regex must compile")
+});
+
+/// JVM `... 42 more`, frames shared with the enclosing trace.
+static MORE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s+\.\.\. (\d+) more$").expect("more regex must compile"));
+
+/// .NET `--- End of stack trace from previous location ---`.
+static END_OF_TRACE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*--- End of .* ---$").expect("end regex must compile"));
+
+/// Frames in the standard library, language runtimes, test harnesses and
+/// installed dependencies.
+static LIBRARY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        // Rust: std sources, cargo registry, name-only runtime frames
+        r"/rustc/[0-9a-f]+/|library/(?:std|core|alloc|test)/|[/\\]\.cargo[/\\](?:registry|git)[/\\]",
+        r"|^\s*\d+: <?(?:std|core|alloc|test)::",
+        r"|^\s*\d+: (?:rust_begin_unwind|__rust|_start|__libc_start|start_thread|clone|<unknown>)",
+        // Python
+        r"|site-packages|dist-packages|[/\\]lib[/\\]python\d|<frozen ",
+        // Node
+        r"|node_modules|\(node:|at node:|\(internal/|\(<anonymous>\)",
+        // JVM and .NET
+        r"|^\s+at (?:java|javax|jdk|sun|kotlin|kotlinx|scala|junit|org\.junit|org\.gradle|org\.apache\.maven)\.",
+        r"|^\s+at (?:System|Microsoft|Xunit|NUnit)\.",
+    ))
+    .expect("library frame regex must compile")
+});
+
+/// One frame: a range of lines.
+struct Frame {
+    start: usize,
+    end: usize,
+    library: bool,
+    /// Frames the lines stand for: 1, `N` for `... N more`, 0 for
+    /// `--- End of stack trace ---`.
+    weight: usize,
+    /// Python prints the innermost frame last.
+    innermost_last: bool,
+}
+
+/// Frame starting at line `i`, if any.
+///
+/// Rust frames are only recognized in a backtrace (`rust`), and the
+/// `... N more` and `--- End of ... ---` lines only continue a trace.
+fn frame_at(lines: &[&str], i: usize, rust: bool, in_trace: bool) -> Option<Frame> {
+    let line = lines[i];
+    let frame = |end: usize, weight: usize, innermost_last: bool| Frame {
+        start: i,
+        end,
+        library: lines[i..end].iter().any(|l| LIBRARY_RE.is_match(l)),
+        weight,
+        innermost_last,
+    };
+
+    if rust && RUST_FRAME_RE.is_match(line) {
+        let mut end = i + 1;
+        while end < lines.len() && RUST_LOCATION_RE.is_match(lines[end]) {
+            end += 1;
+        }
+        return Some(frame(end, 1, false));
+    }
+    if PYTHON_FRAME_RE.is_match(line) {
+        // The source line and `^^^^` markers are indented below `File`.
+        let indent = indentation(line);
+        let mut end = i + 1;
+        while end < lines.len()
+            && !lines[end].trim().is_empty()
+            && indentation(lines[end]) > indent
+            && !PYTHON_FRAME_RE.is_match(lines[end])
+        {
+            end += 1;
+        }
+        return Some(Frame {
+            // Only the `File` line names the module; the source may not.
+            library: LIBRARY_RE.is_match(line),
+            ..frame(end, 1, true)
+        });
+    }
+    if AT_FRAME_RE.is_match(line) {
+        return Some(frame(i + 1, 1, false));
+    }
+    if in_trace {
+        if let Some(caps) = MORE_RE.captures(line) {
+            let weight = caps[1].parse().unwrap_or(1);
+            return Some(Frame {
+                library: true,
+                ..frame(i + 1, weight, false)
+            });
+        }
+        if END_OF_TRACE_RE.is_match(line) {
+            return Some(Frame {
+                library: true,
+                ..frame(i + 1, 0, false)
+            });
+        }
+    }
+    None
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Fold the library frames of every stack trace in the text.
+///
+/// Text without traces, and traces shorter than [`MIN_TRACE_FRAMES`], pass
+/// through unchanged.
+pub fn fold_stack_traces(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut rust = false;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let mut frames = Vec::new();
+        let mut next = i;
+        while next < lines.len()
+            && let Some(frame) = frame_at(&lines, next, rust, !frames.is_empty())
+        {
+            next = frame.end;
+            frames.push(frame);
+        }
+
+        if frames.is_empty() {
+            rust = lines[i].trim() == "stack backtrace:";
+            push_lines(&mut result, &lines[i..i + 1]);
+            i += 1;
+            continue;
+        }
+        push_trace(&mut result, &lines, &frames);
+        rust = false;
+        i = next;
+    }
+
+    result
+}
+
+/// Emit a trace, folding runs of frames that are not kept.
+fn push_trace(out: &mut String, lines: &[&str], frames: &[Frame]) {
+    if frames.len() < MIN_TRACE_FRAMES {
+        push_lines(out, &lines[frames[0].start..frames[frames.len() - 1].end]);
+        return;
+    }
+
+    let project: Vec<usize> = (0..frames.len())
+        .filter(|&f| !frames[f].library && frames[f].weight > 0)
+        .collect();
+    let deepest = if frames[0].innermost_last {
+        project.last()
+    } else {
+        project.first()
+    };
+    let keep: Vec<bool> = (0..frames.len())
+        .map(|f| project.iter().take(MAX_PROJECT_FRAMES).any(|&p| p == f) || deepest == Some(&f))
+        .collect();
+
+    let mut f = 0;
+    while f < frames.len() {
+        if keep[f] {
+            push_lines(out, &lines[frames[f].start..frames[f].end]);
+            f += 1;
+            continue;
+        }
+        // A run of folded frames of the same kind.
+        let library = frames[f].library;
+        let run_end = (f..frames.len())
+            .find(|&g| keep[g] || frames[g].library != library)
+            .unwrap_or(frames.len());
+        let run = &frames[f..run_end];
+        if run.len() == 1 {
+            push_lines(out, &lines[run[0].start..run[0].end]);
+        } else {
+            let count: usize = run.iter().map(|frame| frame.weight).sum();
+            let kind = if library { "library" } else { "project" };
+            let plural = if count == 1 { "" } else { "s" };
+            let indent = &lines[run[0].start][..indentation(lines[run[0].start])];
+            out.push_str(&format!("{indent}[... {count} {kind} frame{plural} ...]\n"));
+        }
+        f = run_end;
+    }
+}
+
+fn push_lines(out: &mut String, lines: &[&str]) {
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rust_backtrace_keeps_project_frames() {
+        let input = "\
+thread 'main' panicked at src/main.rs:5:5:
+explicit panic
+stack backtrace:
+   0: rust_begin_unwind
+             at /rustc/90b35a6239c3d8bdabc530a6a0816f7ff89a0aaf/library/std/src/panicking.rs:645:5
+   1: core::panicking::panic_fmt
+             at /rustc/90b35a6239c3d8bdabc530a6a0816f7ff89a0aaf/library/core/src/panicking.rs:72:14
+   2: myapp::parse
+             at ./src/main.rs:5:5
+   3: myapp::main
+             at ./src/main.rs:9:5
+   4: core::ops::function::FnOnce::call_once
+             at /rustc/90b35a6239c3d8bdabc530a6a0816f7ff89a0aaf/library/core/src/ops/function.rs:250:5
+   5: std::rt::lang_start::{{closure}}
+             at /rustc/90b35a6239c3d8bdabc530a6a0816f7ff89a0aaf/library/std/src/rt.rs:166:18
+note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.
+";
+        let result = fold_stack_traces(input);
+        assert_eq!(
+            result,
+            "\
+thread 'main' panicked at src/main.rs:5:5:
+explicit panic
+stack backtrace:
+   [... 2 library frames ...]
+   2: myapp::parse
+             at ./src/main.rs:5:5
+   3: myapp::main
+             at ./src/main.rs:9:5
+   [... 2 library frames ...]
+note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.
+"
+        );
+    }
+
+    #[test]
+    fn python_traceback_keeps_deepest_user_frame() {
+        let mut input = String::from("Traceback (most recent call last):\n");
+        for (path, func) in [
+            ("/app/cli.py", "main"),
+            ("/app/commands.py", "run"),
+            ("/app/loader.py", "load"),
+            ("/app/config.py", "read"),
+            ("/app/schema.py", "validate"),
+            (
+                "/usr/lib/python3.12/site-packages/yaml/__init__.py",
+                "safe_load",
+            ),
+            (
+                "/usr/lib/python3.12/site-packages/yaml/loader.py",
+                "get_data",
+            ),
+            ("/app/hooks.py", "on_node"),
+            (
+                "/usr/lib/python3.12/site-packages/yaml/constructor.py",
+                "construct",
+            ),
+        ] {
+            input.push_str(&format!(
+                "  File \"{path}\", line 12, in {func}\n    {func}()\n"
+            ));
+        }
+        input.push_str("ValueError: bad node\n");
+
+        let result = fold_stack_traces(&input);
+        assert!(result.contains("File \"/app/cli.py\""));
+        assert!(result.contains("File \"/app/loader.py\""));
+        assert!(!result.contains("File \"/app/config.py\""));
+        assert!(result.contains("  [... 2 project frames ...]\n"));
+        assert!(result.contains("  [... 2 library frames ...]\n"));
+        assert!(result.contains("File \"/app/hooks.py\", line 12, in on_node\n    on_node()\n"));
+        assert!(
+            result.contains("  File \"/usr/lib/python3.12/site-packages/yaml/constructor.py\"")
+        );
+        assert!(result.ends_with("ValueError: bad node\n"));
+    }
+
+    #[test]
+    fn jvm_trace_folds_runtime_and_shared_frames() {
+        let input = "\
+Exception in thread \"main\" java.lang.IllegalStateException: boom
+\tat com.example.App.run(App.java:12)
+\tat com.example.App.main(App.java:5)
+\tat java.base/jdk.internal.reflect.NativeMethodAccessorImpl.invoke0(Native Method)
+\tat java.base/java.lang.reflect.Method.invoke(Method.java:568)
+\tat org.junit.runners.ParentRunner.run(ParentRunner.java:413)
+Caused by: java.io.IOException: disk full
+\tat com.example.Store.write(Store.java:40)
+\tat java.base/java.io.FileOutputStream.write(FileOutputStream.java:349)
+\tat java.base/java.io.BufferedOutputStream.flush(BufferedOutputStream.java:142)
+\tat java.base/java.io.Writer.close(Writer.java:20)
+\t... 42 more
+";
+        let result = fold_stack_traces(input);
+        assert_eq!(
+            result,
+            "\
+Exception in thread \"main\" java.lang.IllegalStateException: boom
+\tat com.example.App.run(App.java:12)
+\tat com.example.App.main(App.java:5)
+\t[... 3 library frames ...]
+Caused by: java.io.IOException: disk full
+\tat com.example.Store.write(Store.java:40)
+\t[... 45 library frames ...]
+"
+        );
+    }
+
+    #[test]
+    fn node_and_dotnet_frames() {
+        let node = "\
+TypeError: Cannot read properties of undefined (reading 'id')
+    at getUser (/app/src/users.js:14:22)
+    at /app/node_modules/express/lib/router/layer.js:95:5
+    at next (/app/node_modules/express/lib/router/route.js:149:13)
+    at Route.dispatch (/app/node_modules/express/lib/router/route.js:119:3)
+    at process.processTicksAndRejections (node:internal/process/task_queues:95:5)
+";
+        let result = fold_stack_traces(node);
+        assert!(result.contains("    at getUser (/app/src/users.js:14:22)\n"));
+        assert!(result.contains("    [... 4 library frames ...]\n"));
+
+        let dotnet = "\
+System.InvalidOperationException: Sequence contains no elements
+   at System.Linq.ThrowHelper.ThrowNoElementsException()
+   at System.Linq.Enumerable.First[TSource](IEnumerable`1 source)
+   at MyApp.Orders.Latest() in /src/MyApp/Orders.cs:line 42
+   --- End of stack trace from previous location ---
+   at System.Runtime.ExceptionServices.ExceptionDispatchInfo.Throw()
+   at MyApp.Tests.OrderTests.Latest_Works() in /src/MyApp.Tests/OrderTests.cs:line 17
+";
+        let result = fold_stack_traces(dotnet);
+        assert!(result.contains("   [... 2 library frames ...]\n"));
+        assert!(result.contains("at MyApp.Orders.Latest() in /src/MyApp/Orders.cs:line 42\n"));
+        assert!(result.contains("   [... 1 library frame ...]\n"));
+        assert!(result.contains("OrderTests.cs:line 17\n"));
+    }
+
+    #[test]
+    fn text_without_traces_is_unchanged() {
+        let input = "\
+Results:
+  1: parsed ok
+  2: parsed ok
+    at least 3 retries left
+short trace:
+    at f (/app/a.js:1:1)
+    at g (/app/node_modules/b.js:1:1)
+";
+        assert_eq!(fold_stack_traces(input), input);
+    }
+}
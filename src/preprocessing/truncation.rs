@@ -2,8 +2,23 @@
 //!
 //! Stage 4 of the preprocessing pipeline. If the output still exceeds a
 //! configurable maximum after noise removal, path filtering, and
-//! deduplication, this module truncates the middle while preserving the head
-//! (command start, errors at top) and tail (summary lines, final status).
+//! deduplication, this module drops lines until it fits. Two modes exist
+//! (`preprocessing.truncation_mode`):
+//!
+//! - **salience** (default) — score every line (error/warning keywords,
+//!   `file:line` references, exit summaries, uniqueness), keep windows of
+//!   context around the highest-scoring lines, then spend what is left of
+//!   the budget on the head and tail. Every elided gap gets its own marker.
+//! - **head_tail** — keep a fixed head and tail and drop the middle, where
+//!   the first error of a long build often sits.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::dedup::pattern_key;
+use crate::config::schema::PreprocessingConfig;
 
 // ---------------------------------------------------------------------------
 // Configuration
@@ -17,10 +32,61 @@ const TAIL_RATIO: f64 = 0.4;
 
 // The remaining 20% is reserved for the truncation marker itself.
 
+/// Lines kept before a salient line.
+const CONTEXT_BEFORE: usize = 2;
+
+/// Lines kept after a salient line (diagnostic details usually follow it).
+const CONTEXT_AFTER: usize = 4;
+
+/// Leading lines always kept in salience mode (command banner, first step).
+const HEAD_ANCHOR_LINES: usize = 3;
+
+/// Trailing lines always kept in salience mode (summary, final status).
+const TAIL_ANCHOR_LINES: usize = 5;
+
+/// Minimum score for a line to get a context window of its own.
+const SEED_SCORE: u32 = 4;
+
+/// Windows opened per distinct line pattern, so 500 copies of one warning
+/// don't crowd out everything else.
+const MAX_SEEDS_PER_PATTERN: usize = 2;
+
+/// Bytes reserved for each gap marker in salience mode.
+const MARKER_RESERVE: usize = 56;
+
+/// Which lines Stage 4 keeps when the output is over budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncationMode {
+    /// Fixed head and tail slices around a single middle gap.
+    HeadTail,
+    /// Context windows around salient lines, then head and tail.
+    #[default]
+    Salience,
+}
+
+impl TruncationMode {
+    /// Mode selected by `preprocessing.truncation_mode`: `"head_tail"`, or
+    /// salience for anything else.
+    pub fn from_config(cfg: &PreprocessingConfig) -> Self {
+        match cfg.truncation_mode.as_str() {
+            "head_tail" => Self::HeadTail,
+            _ => Self::Salience,
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Public API
 // ---------------------------------------------------------------------------
 
+/// Truncate text to fit within `max_bytes` using the given mode.
+pub fn truncate_with_mode(text: &str, max_bytes: usize, mode: TruncationMode) -> String {
+    match mode {
+        TruncationMode::HeadTail => truncate(text, max_bytes),
+        TruncationMode::Salience => salience_truncate(text, max_bytes),
+    }
+}
+
 /// Truncate text to fit within `max_bytes`, preserving head and tail.
 ///
 /// If text is already within budget, it is returned unchanged. Otherwise,
@@ -96,6 +162,209 @@ pub fn truncate(text: &str, max_bytes: usize) -> String {
     result
 }
 
+// ---------------------------------------------------------------------------
+// Salience scoring
+// ---------------------------------------------------------------------------
+
+/// Failure keywords.
+static ERROR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?:error|fail(?:ed|ure)?|fatal|panic(?:ked)?|exception|traceback|abort(?:ed)?|segmentation fault)\b|npm ERR!",
+    )
+    .expect("error regex must compile")
+});
+
+/// Warning keywords.
+static WARNING_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\bwarn(?:ing)?s?\b|\bdeprecated\b").expect("warning regex must compile")
+});
+
+/// Source locations: `src/main.rs:42`, `app.py:7:3`, `File "x.py", line 7`.
+static LOCATION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"[\w./\\-]+\.[A-Za-z]\w*:\d+|File "[^"]+", line \d+"#)
+        .expect("location regex must compile")
+});
+
+/// Exit and summary lines: test totals, build results, exit codes.
+static SUMMARY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^\W*(?:test result:|tests?:\s+\d|test suites?:|ran \d+ tests?|\d+ (?:passed|failed|tests?)\b|build (?:succeeded|successful|failed)|finished\b|done in\b|summary\b)|\bexit(?:ed)?(?: with)? (?:code|status)\b",
+    )
+    .expect("summary regex must compile")
+});
+
+/// Salience of one line; `unique` marks lines whose pattern occurs once.
+fn score_line(line: &str, unique: bool) -> u32 {
+    let mut score = 0;
+    if ERROR_RE.is_match(line) {
+        score += 8;
+    } else if WARNING_RE.is_match(line) {
+        score += 4;
+    }
+    if LOCATION_RE.is_match(line) {
+        score += 4;
+    }
+    if SUMMARY_RE.is_match(line) {
+        score += 6;
+    }
+    if unique && score > 0 {
+        score += 1;
+    }
+    score
+}
+
+// ---------------------------------------------------------------------------
+// Salience truncation
+// ---------------------------------------------------------------------------
+
+/// Lines chosen so far, and the bytes they (and their gap markers) cost.
+struct Selection<'a> {
+    lines: &'a [&'a str],
+    kept: Vec<bool>,
+    used: usize,
+    budget: usize,
+}
+
+impl<'a> Selection<'a> {
+    fn new(lines: &'a [&'a str], budget: usize) -> Self {
+        Self {
+            lines,
+            kept: vec![false; lines.len()],
+            // One marker is always needed once anything is dropped.
+            used: MARKER_RESERVE,
+            budget,
+        }
+    }
+
+    /// Keep `start..end` if it fits, reserving room for the extra gap
+    /// marker a new island may create. Returns whether it was kept.
+    fn keep_range(&mut self, start: usize, end: usize) -> bool {
+        let new: Vec<usize> = (start..end).filter(|&i| !self.kept[i]).collect();
+        if new.is_empty() {
+            return true;
+        }
+        let cost = new.iter().map(|&i| self.lines[i].len() + 1).sum::<usize>() + MARKER_RESERVE;
+        if self.used + cost > self.budget {
+            return false;
+        }
+        for i in new {
+            self.kept[i] = true;
+        }
+        self.used += cost;
+        true
+    }
+
+    /// Grow the kept head and tail one line at a time, alternating, until
+    /// the budget runs out.
+    fn fill_from_ends(&mut self) {
+        let mut head_open = true;
+        let mut tail_open = true;
+        while head_open || tail_open {
+            if head_open {
+                head_open = match self.kept.iter().position(|&k| !k) {
+                    Some(i) => self.keep_line(i),
+                    None => false,
+                };
+            }
+            if tail_open {
+                tail_open = match self.kept.iter().rposition(|&k| !k) {
+                    Some(i) => self.keep_line(i),
+                    None => false,
+                };
+            }
+        }
+    }
+
+    /// Keep one line adjacent to an existing region (no new marker).
+    fn keep_line(&mut self, i: usize) -> bool {
+        let cost = self.lines[i].len() + 1;
+        if self.used + cost > self.budget {
+            return false;
+        }
+        self.kept[i] = true;
+        self.used += cost;
+        true
+    }
+
+    fn render(&self) -> String {
+        let mut result = String::with_capacity(self.budget);
+        let mut i = 0;
+        while i < self.lines.len() {
+            if self.kept[i] {
+                result.push_str(self.lines[i]);
+                result.push('\n');
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < self.lines.len() && !self.kept[i] {
+                i += 1;
+            }
+            let omitted_bytes: usize = self.lines[start..i].iter().map(|l| l.len() + 1).sum();
+            result.push_str(&format!(
+                "[... {} lines ({omitted_bytes} bytes) truncated ...]\n",
+                i - start
+            ));
+        }
+        result
+    }
+}
+
+/// Keep context windows around the most salient lines, then fill the rest
+/// of the budget from the head and tail.
+fn salience_truncate(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() <= 6 {
+        return byte_truncate(text, max_bytes);
+    }
+
+    let keys: Vec<String> = lines.iter().map(|l| pattern_key(l)).collect();
+    let mut key_counts: HashMap<&str, usize> = HashMap::new();
+    for key in &keys {
+        *key_counts.entry(key.as_str()).or_default() += 1;
+    }
+
+    let mut seeds: Vec<(u32, usize)> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| (score_line(line, key_counts[keys[i].as_str()] == 1), i))
+        .filter(|&(score, _)| score >= SEED_SCORE)
+        .collect();
+    // Highest score first; earlier lines win ties (the first error is
+    // usually the cause).
+    seeds.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let len = lines.len();
+    let mut selection = Selection::new(&lines, max_bytes);
+    selection.keep_range(0, HEAD_ANCHOR_LINES.min(len));
+    selection.keep_range(len.saturating_sub(TAIL_ANCHOR_LINES), len);
+
+    let mut seeded: HashMap<&str, usize> = HashMap::new();
+    for (_, i) in seeds {
+        let count = seeded.entry(keys[i].as_str()).or_default();
+        if *count >= MAX_SEEDS_PER_PATTERN {
+            continue;
+        }
+        *count += 1;
+        let start = i.saturating_sub(CONTEXT_BEFORE);
+        let end = (i + CONTEXT_AFTER + 1).min(len);
+        if !selection.keep_range(start, end) {
+            selection.keep_range(i, i + 1);
+        }
+    }
+
+    selection.fill_from_ends();
+
+    if !selection.kept.iter().any(|&k| k) {
+        return byte_truncate(text, max_bytes);
+    }
+    selection.render()
+}
+
 /// Simple byte-level truncation for very short texts.
 fn byte_truncate(text: &str, max_bytes: usize) -> String {
     if text.len() <= max_bytes {
@@ -191,4 +460,95 @@ mod tests {
         assert!(result.contains("bytes"));
         assert!(result.contains("truncated"));
     }
+
+    fn build_log(error_at: &[usize]) -> String {
+        let mut input = String::from("   Compiling app v0.1.0\n");
+        for i in 0..1000 {
+            if error_at.contains(&i) {
+                input.push_str("error[E0308]: mismatched types\n");
+                input.push_str(&format!("  --> src/module_{i}.rs:12:5\n"));
+            } else {
+                input.push_str(&format!("   Compiling dependency_{i} v1.0.{i}\n"));
+            }
+        }
+        input.push_str("error: could not compile `app` due to previous error\n");
+        input
+    }
+
+    /// Sum of the line counts in every gap marker.
+    fn elided_lines(result: &str) -> usize {
+        result
+            .lines()
+            .filter_map(|l| l.strip_prefix("[... "))
+            .filter_map(|l| l.split(' ').next()?.parse::<usize>().ok())
+            .sum()
+    }
+
+    #[test]
+    fn salience_keeps_error_from_the_middle() {
+        let input = build_log(&[500]);
+        let result = truncate_with_mode(&input, 2048, TruncationMode::Salience);
+
+        assert!(result.len() <= 2048, "{} bytes", result.len());
+        assert!(result.contains("error[E0308]: mismatched types"));
+        assert!(result.contains("src/module_500.rs:12:5"));
+        assert!(result.contains("Compiling app v0.1.0"));
+        assert!(result.contains("could not compile `app`"));
+        // Head/tail drops the same error.
+        assert!(!truncate(&input, 2048).contains("mismatched types"));
+    }
+
+    #[test]
+    fn salience_marks_every_gap_with_line_count() {
+        let input = build_log(&[300, 700]);
+        let result = truncate_with_mode(&input, 2048, TruncationMode::Salience);
+
+        assert!(result.contains("src/module_300.rs"));
+        assert!(result.contains("src/module_700.rs"));
+        let markers = result.lines().filter(|l| l.starts_with("[... ")).count();
+        assert!(markers >= 3, "expected a marker per gap:\n{result}");
+        let kept = result.lines().count() - markers;
+        assert_eq!(kept + elided_lines(&result), input.lines().count());
+    }
+
+    #[test]
+    fn salience_without_signals_keeps_head_and_tail() {
+        let mut input = String::new();
+        for i in 0..1000 {
+            input.push_str(&format!("line {i}: some content here\n"));
+        }
+        let result = truncate_with_mode(&input, 2048, TruncationMode::Salience);
+
+        assert!(result.len() <= 2048);
+        assert!(result.contains("line 0:"));
+        assert!(result.contains("line 999:"));
+        assert_eq!(result.lines().filter(|l| l.starts_with("[... ")).count(), 1);
+    }
+
+    #[test]
+    fn salience_limits_windows_per_repeated_line() {
+        let mut input = String::new();
+        for i in 0..400 {
+            input.push_str(&format!("step {i} ok\n"));
+            if (100..300).contains(&i) && i % 10 == 0 {
+                input.push_str("warning: unused variable `x`\n");
+            }
+            if i == 250 {
+                input.push_str("error: linker failed\n");
+            }
+        }
+        let result = truncate_with_mode(&input, 1024, TruncationMode::Salience);
+
+        assert!(result.contains("error: linker failed"));
+        // Two warning windows, plus at most one inside the error's window.
+        assert!(result.matches("unused variable").count() <= 3, "{result}");
+    }
+
+    #[test]
+    fn mode_from_config() {
+        let mut cfg = PreprocessingConfig::default();
+        assert_eq!(TruncationMode::from_config(&cfg), TruncationMode::Salience);
+        cfg.truncation_mode = "head_tail".to_string();
+        assert_eq!(TruncationMode::from_config(&cfg), TruncationMode::HeadTail);
+    }
 }
@@ -22,21 +22,29 @@
 /// ```
 pub mod decision;
 
+use std::collections::HashSet;
+use std::sync::mpsc;
+use std::time::Duration;
+
 use anyhow::{Context, Result};
 
 use crate::config;
-use crate::config::schema::Mode;
+use crate::config::schema::{Mode, StderrConfig, StderrMode, TerseConfig};
 use crate::llm;
 use crate::llm::config::SmartPathConfig;
 use crate::matching;
-use crate::optimizers::OptimizerRegistry;
+use crate::optimizers::{OptimizedOutput, OptimizerRegistry};
 use crate::preprocessing;
+use crate::preprocessing::noise::strip_ansi;
+use crate::preprocessing::redact::Redactor;
 use crate::safety::circuit_breaker::{CircuitBreaker, PathId};
 use crate::safety::classifier::{self, CommandClass};
-use crate::utils::process::run_shell_command;
+use crate::safety::interactive;
+use crate::stash;
+use crate::utils::process::{OutputChunk, ProcessOutput, run_shell_command_with_timeout};
 use crate::utils::token_counter::estimate_tokens;
 
-pub use decision::{HookDecision, OptimizationPath, PassthroughReason};
+pub use decision::{DecisionCache, HookDecision, OptimizationPath, PassthroughReason};
 
 // ---------------------------------------------------------------------------
 // Output size thresholds — loaded from config at runtime
@@ -61,7 +69,8 @@ fn load_thresholds() -> (usize, usize) {
 pub struct ExecutionResult {
     /// The (possibly optimized) output text.
     pub output: String,
-    /// Stderr content (populated only on passthrough).
+    /// Text for terse's own stderr: the raw stderr on passthrough, and the
+    /// stderr-derived lines on optimized paths with `stderr.mode = "separate"`.
     pub stderr: String,
     /// Which optimization path was taken.
     pub path: OptimizationPath,
@@ -86,6 +95,11 @@ pub struct ExecutionResult {
     /// Diagnostic: error from a higher-priority path that was attempted but
     /// failed before falling through to the current path.
     pub fallback_reason: Option<String>,
+    /// Exit code of the wrapped command (`128 + signal` for signal kills on
+    /// Unix). `terse run` exits with this code on every path.
+    pub exit_code: Option<i32>,
+    /// Secrets masked in the output and the command string.
+    pub redactions: usize,
 }
 
 // ---------------------------------------------------------------------------
@@ -109,19 +123,80 @@ pub struct ExecutionResult {
 /// 3. Heredoc: structurally complex → passthrough
 /// 4. Classifier: destructive/editor command → passthrough
 /// 5. Otherwise → rewrite to `terse run`
+///
+/// This always evaluates every gate; the hook handlers use
+/// [`decide_hook_cached`] instead.
 pub fn decide_hook(command: &str) -> HookDecision {
-    // 0. Config gates
     let cfg = config::load();
-    if !cfg.general.enabled {
-        return HookDecision::Passthrough(PassthroughReason::NoPathAvailable);
+    if let Some(reason) = config_gate(&cfg) {
+        return HookDecision::Passthrough(reason);
     }
-    if cfg.general.mode == Mode::Passthrough {
-        return HookDecision::Passthrough(PassthroughReason::NoPathAvailable);
+    command_gates(command)
+}
+
+/// Hook decision annotated with whether it came from the decision cache.
+#[derive(Debug, Clone, Copy)]
+pub struct CachedHookDecision {
+    pub decision: HookDecision,
+    /// `Some(true)` on a cache hit, `Some(false)` on a miss, `None` when the
+    /// cache was not consulted (config gate or caching disabled).
+    pub cache_hit: Option<bool>,
+}
+
+/// [`decide_hook`] backed by the file-backed [`DecisionCache`].
+///
+/// Config gates are always evaluated fresh — they are cheap and a kill
+/// switch must take effect immediately. The command-level gates are cached
+/// per normalized command and working directory for
+/// `router.decision_cache_ttl_secs`, and invalidated whenever any config
+/// layer changes.
+pub fn decide_hook_cached(command: &str) -> CachedHookDecision {
+    let cfg = config::load();
+    if let Some(reason) = config_gate(&cfg) {
+        return CachedHookDecision {
+            decision: HookDecision::Passthrough(reason),
+            cache_hit: None,
+        };
     }
-    if cfg.general.safe_mode {
-        return HookDecision::Passthrough(PassthroughReason::NoPathAvailable);
+
+    let ttl = cfg.router.decision_cache_ttl_secs;
+    let cwd = std::env::current_dir().unwrap_or_default();
+    if ttl == 0 {
+        return CachedHookDecision {
+            decision: command_gates(command),
+            cache_hit: None,
+        };
     }
 
+    let key = decision::cache_key(command, &cwd);
+    let mut cache = DecisionCache::load(ttl, config::layer_fingerprint());
+    let (decision, hit) = match cache.get(&key) {
+        Some(decision) => (decision, true),
+        None => {
+            let decision = command_gates(command);
+            cache.insert(key, decision);
+            (decision, false)
+        }
+    };
+    cache.record_lookup(hit);
+    cache.save();
+
+    CachedHookDecision {
+        decision,
+        cache_hit: Some(hit),
+    }
+}
+
+/// Config-level gates: master switch, passthrough mode, safe mode.
+fn config_gate(cfg: &TerseConfig) -> Option<PassthroughReason> {
+    if !cfg.general.enabled || cfg.general.mode == Mode::Passthrough || cfg.general.safe_mode {
+        return Some(PassthroughReason::NoPathAvailable);
+    }
+    None
+}
+
+/// Command-level safety gates (loop guard, heredoc, classifier, interactive).
+fn command_gates(command: &str) -> HookDecision {
     // 1. Loop guard
     if matching::is_terse_invocation(command) {
         return HookDecision::Passthrough(PassthroughReason::TerseInvocation);
@@ -137,7 +212,12 @@ pub fn decide_hook(command: &str) -> HookDecision {
         return HookDecision::Passthrough(PassthroughReason::NeverOptimize);
     }
 
-    // 4. All safety gates passed — route through terse run.
+    // 4. Interactive / long-running: would hang or lose its UI under capture
+    if let Some(kind) = interactive::detect(command) {
+        return HookDecision::Passthrough(PassthroughReason::Interactive(kind));
+    }
+
+    // 5. All safety gates passed — route through terse run.
     //    The actual path is decided post-execution based on output size.
     HookDecision::Rewrite
 }
@@ -150,7 +230,9 @@ pub fn decide_hook(command: &str) -> HookDecision {
 ///
 /// Called from `terse run`. The pipeline is linear:
 ///
-/// 1. **Run** the original command
+/// 1. **Run** the command, rewritten first if its optimizer asks to be
+///    (see [`Optimizer::rewrite_command`](crate::optimizers::Optimizer::rewrite_command)),
+///    and redact secrets in its output (see [`Redactor`])
 /// 2. **Preprocess** the output (always — strip noise, dedup, truncate)
 /// 3. **Size-based routing** using preprocessed output size:
 ///    - Below passthrough threshold → passthrough
@@ -173,9 +255,35 @@ pub fn execute_run(command: &str) -> Result<ExecutionResult> {
     let config_allows_optimization =
         cfg.general.enabled && *mode != Mode::Passthrough && !cfg.general.safe_mode;
 
-    // --- Step 1: Run the original command ---
-    let raw_output = run_shell_command(command).context("failed executing command in router")?;
-    let raw_text = combine_stdout_stderr(&raw_output.stdout, &raw_output.stderr);
+    // --- Step 1: Run the command ---
+    //
+    // Optimizers may ask for a more compact output format up front
+    // (e.g. `cargo build --message-format=short`).
+    let rewritten = if config_allows_optimization {
+        registry.rewrite_command(command)
+    } else {
+        None
+    };
+    let deadline = match cfg.router.command_timeout_secs {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    };
+    let mut raw_output =
+        run_shell_command_with_timeout(rewritten.as_deref().unwrap_or(command), deadline)
+            .context("failed executing command in router")?;
+
+    // Mask credentials in the output every path sees, and in the command
+    // string that is stashed and sent to the LLM.
+    let redactor = Redactor::from_config(&cfg.preprocessing);
+    let mut redactions = 0;
+    raw_output.map_chunks(|text| {
+        let (text, count) = redactor.redact(text);
+        redactions += count;
+        text.into_owned()
+    });
+    let (shared_command, command_redactions) = redactor.redact(command);
+    redactions += command_redactions;
+    let raw_text = raw_output.interleaved();
     let raw_bytes = raw_text.len();
     let raw_tokens = estimate_tokens(&raw_text);
 
@@ -186,7 +294,13 @@ pub fn execute_run(command: &str) -> Result<ExecutionResult> {
     let pp_duration_ms = preprocessed.duration_ms;
     let pp_tokens_before = preprocessed.tokens_before;
     let pp_tokens_after = preprocessed.tokens_after;
-    let output_bytes = preprocessed.text.len();
+    // A summary replacing the whole output is small however large the
+    // output was; route on the original size so it is not passed through.
+    let output_bytes = if preprocessed.summarized {
+        raw_bytes
+    } else {
+        preprocessed.text.len()
+    };
 
     // --- Step 3: Size-based path decision ---
 
@@ -206,9 +320,25 @@ pub fn execute_run(command: &str) -> Result<ExecutionResult> {
             preprocessing_tokens_before: Some(pp_tokens_before),
             preprocessing_tokens_after: Some(pp_tokens_after),
             fallback_reason: None,
+            exit_code: raw_output.exit_code,
+            redactions,
         });
     }
 
+    // Optimized text → (stdout, stderr): present stderr-derived lines per
+    // `[stderr]`, stash and footer, then the exit header.
+    let finish_optimized = |text: &str| {
+        let (body, stderr) = split_stderr_lines(text, &raw_output, &cfg.stderr);
+        // Lines moved to stderr were not removed; keep them out of the footer.
+        let shown_raw_bytes = raw_bytes.saturating_sub(stderr.len());
+        let output = prepend_exit_header(
+            &finish_output(&body, shown_raw_bytes, &shared_command, &raw_output, &cfg),
+            raw_output.exit_code,
+            raw_output.timed_out,
+        );
+        (output, stderr)
+    };
+
     let smart_config = SmartPathConfig::load();
     let above_smart_threshold = output_bytes >= smart_path_threshold;
 
@@ -226,17 +356,17 @@ pub fn execute_run(command: &str) -> Result<ExecutionResult> {
         && cb.is_allowed(PathId::SmartPath)
         && smart_config.enabled
     {
-        match llm::optimize_with_llm(command, &preprocessed.text) {
+        match llm::optimize_with_llm(&shared_command, &preprocessed.text) {
             Ok(llm_result) => {
                 cb.record_success(PathId::SmartPath);
-                let output = append_truncation_footer(&llm_result.output, raw_bytes);
+                let (output, stderr) = finish_optimized(&llm_result.output);
                 return Ok(ExecutionResult {
                     original_tokens: raw_tokens,
                     optimized_tokens: llm_result.optimized_tokens,
                     path: OptimizationPath::SmartPath,
                     optimizer_name: format!("llm:{}", llm_result.model),
                     output,
-                    stderr: String::new(),
+                    stderr,
                     latency_ms: Some(llm_result.latency_ms),
                     preprocessing_bytes_removed: Some(pp_bytes_removed),
                     preprocessing_pct: Some(pp_pct),
@@ -244,6 +374,8 @@ pub fn execute_run(command: &str) -> Result<ExecutionResult> {
                     preprocessing_tokens_before: Some(pp_tokens_before),
                     preprocessing_tokens_after: Some(pp_tokens_after),
                     fallback_reason: None,
+                    exit_code: raw_output.exit_code,
+                    redactions,
                 });
             }
             Err(err) => {
@@ -266,17 +398,24 @@ pub fn execute_run(command: &str) -> Result<ExecutionResult> {
         && cb.is_allowed(PathId::FastPath)
         && registry.can_handle(command)
     {
-        match registry.optimize_first(command, &preprocessed.text) {
-            Some(result) => {
+        let timeout_ms = cfg.fast_path.timeout_ms;
+        match optimize_with_deadline(
+            registry,
+            command,
+            &preprocessed.text,
+            &raw_output.chunks,
+            timeout_ms,
+        ) {
+            FastPathOutcome::Optimized(result) => {
                 cb.record_success(PathId::FastPath);
-                let output = append_truncation_footer(&result.output, raw_bytes);
+                let (output, stderr) = finish_optimized(&result.output);
                 return Ok(ExecutionResult {
                     original_tokens: raw_tokens,
                     optimized_tokens: result.optimized_tokens,
                     path: OptimizationPath::FastPath,
                     optimizer_name: result.optimizer_used,
                     output,
-                    stderr: String::new(),
+                    stderr,
                     latency_ms: None,
                     preprocessing_bytes_removed: Some(pp_bytes_removed),
                     preprocessing_pct: Some(pp_pct),
@@ -284,9 +423,35 @@ pub fn execute_run(command: &str) -> Result<ExecutionResult> {
                     preprocessing_tokens_before: Some(pp_tokens_before),
                     preprocessing_tokens_after: Some(pp_tokens_after),
                     fallback_reason: smart_path_error,
+                    exit_code: raw_output.exit_code,
+                    redactions,
+                });
+            }
+            FastPathOutcome::TimedOut => {
+                // The optimizer is abandoned on its worker thread. The
+                // preprocessed text is already a safe, reduced form of the
+                // output, so return that instead of the raw output.
+                cb.record_failure(PathId::FastPath);
+                let (output, stderr) = finish_optimized(&preprocessed.text);
+                return Ok(ExecutionResult {
+                    original_tokens: raw_tokens,
+                    optimized_tokens: estimate_tokens(&preprocessed.text),
+                    path: OptimizationPath::Passthrough,
+                    optimizer_name: "passthrough".to_string(),
+                    output,
+                    stderr,
+                    latency_ms: None,
+                    preprocessing_bytes_removed: Some(pp_bytes_removed),
+                    preprocessing_pct: Some(pp_pct),
+                    preprocessing_duration_ms: Some(pp_duration_ms),
+                    preprocessing_tokens_before: Some(pp_tokens_before),
+                    preprocessing_tokens_after: Some(pp_tokens_after),
+                    fallback_reason: Some(format!("fast path timed out after {timeout_ms}ms")),
+                    exit_code: raw_output.exit_code,
+                    redactions,
                 });
             }
-            None => {
+            FastPathOutcome::Failed => {
                 cb.record_failure(PathId::FastPath);
                 // Fall through to passthrough.
             }
@@ -294,13 +459,14 @@ pub fn execute_run(command: &str) -> Result<ExecutionResult> {
     }
 
     // --- Passthrough (no optimizer matched, or paths failed/disabled) ---
-    let output = append_truncation_footer(&raw_output.stdout, raw_bytes);
+    // Both streams are shown as captured, so nothing was removed: there is
+    // no footer to add and nothing to stash.
     Ok(ExecutionResult {
         original_tokens: raw_tokens,
         optimized_tokens: raw_tokens,
         path: OptimizationPath::Passthrough,
         optimizer_name: "passthrough".to_string(),
-        output,
+        output: raw_output.stdout,
         stderr: raw_output.stderr,
         latency_ms: None,
         preprocessing_bytes_removed: Some(pp_bytes_removed),
@@ -309,6 +475,8 @@ pub fn execute_run(command: &str) -> Result<ExecutionResult> {
         preprocessing_tokens_before: Some(pp_tokens_before),
         preprocessing_tokens_after: Some(pp_tokens_after),
         fallback_reason: smart_path_error,
+        exit_code: raw_output.exit_code,
+        redactions,
     })
 }
 
@@ -348,12 +516,40 @@ pub fn preview(command: &str) -> Result<PreviewResult> {
 // Helpers
 // ---------------------------------------------------------------------------
 
+/// Stash the raw output when the final output was reduced, then append the
+/// truncation footer naming the stash id.
+///
+/// The stash is only written when there is something to retrieve, so runs
+/// that pass through unchanged cost no disk I/O.
+fn finish_output(
+    output: &str,
+    raw_bytes: usize,
+    command: &str,
+    raw_output: &ProcessOutput,
+    cfg: &TerseConfig,
+) -> String {
+    if raw_bytes == 0 || output.len() >= raw_bytes {
+        return output.to_string();
+    }
+    let stash_id = stash::save(
+        command,
+        &raw_output.stdout,
+        &raw_output.stderr,
+        raw_output.exit_code,
+        &cfg.stash,
+    );
+    append_truncation_footer(output, raw_bytes, stash_id.as_deref())
+}
+
 /// Append a truncation footer to output when the final output is
 /// significantly smaller than the raw command output. This is applied in the
 /// router *after* the optimizer produces its final output so Claude knows
 /// the output was reduced — regardless of whether preprocessing is enabled
 /// or which optimization path was taken.
-fn append_truncation_footer(output: &str, raw_bytes: usize) -> String {
+///
+/// When the raw output was stashed, the footer names the `terse raw` command
+/// that retrieves it.
+fn append_truncation_footer(output: &str, raw_bytes: usize, stash_id: Option<&str>) -> String {
     let output_bytes = output.len();
     if raw_bytes == 0 || output_bytes >= raw_bytes {
         return output.to_string();
@@ -364,18 +560,263 @@ fn append_truncation_footer(output: &str, raw_bytes: usize) -> String {
     if output_bytes > 0 && pct >= 100.0 {
         pct = 99.9;
     }
+    let retrieval = stash_id
+        .map(|id| format!("; full output: terse raw {id}"))
+        .unwrap_or_default();
     format!(
-        "{output}[output truncated: showing {output_bytes} of {raw_bytes} bytes ({pct:.2}% removed)]"
+        "{output}[output truncated: showing {output_bytes} of {raw_bytes} bytes ({pct:.2}% removed){retrieval}]"
     )
 }
 
-/// Combine stdout and stderr into a single string for token counting.
-fn combine_stdout_stderr(stdout: &str, stderr: &str) -> String {
-    if stderr.is_empty() {
-        stdout.to_string()
-    } else if stdout.is_empty() {
-        stderr.to_string()
+/// Prefix optimized output with the wrapped command's exit status when it is
+/// non-zero.
+///
+/// Optimizers may compact away the lines that reveal a failure (e.g. the
+/// final `error: test failed` of a long test run), so the status is stated
+/// explicitly at the top of any compacted output.
+fn prepend_exit_header(output: &str, exit_code: Option<i32>, timed_out: bool) -> String {
+    let note = if timed_out {
+        " (timed out, output is partial)"
     } else {
-        format!("{stdout}\n{stderr}")
+        ""
+    };
+    match exit_code {
+        Some(code) if code != 0 => format!("[exit code: {code}{note}]\n{output}"),
+        _ => output.to_string(),
+    }
+}
+
+/// Outcome of a deadline-bounded fast-path attempt.
+enum FastPathOutcome {
+    Optimized(OptimizedOutput),
+    /// No optimizer produced output.
+    Failed,
+    /// The optimizer exceeded `fast_path.timeout_ms` and was abandoned.
+    TimedOut,
+}
+
+/// Run the optimizer registry on a worker thread, waiting at most
+/// `timeout_ms` (`0` waits indefinitely).
+///
+/// An overrunning optimizer cannot be cancelled; its thread is detached and
+/// dies with the short-lived `terse run` process.
+fn optimize_with_deadline(
+    registry: OptimizerRegistry,
+    command: &str,
+    text: &str,
+    chunks: &[OutputChunk],
+    timeout_ms: u64,
+) -> FastPathOutcome {
+    let (tx, rx) = mpsc::channel();
+    let command = command.to_string();
+    let text = text.to_string();
+    let chunks = chunks.to_vec();
+    std::thread::spawn(move || {
+        let _ = tx.send(registry.optimize_first_with_output(&command, &text, &chunks));
+    });
+
+    let received = if timeout_ms == 0 {
+        rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected)
+    } else {
+        rx.recv_timeout(Duration::from_millis(timeout_ms))
+    };
+    match received {
+        Ok(Some(result)) => FastPathOutcome::Optimized(result),
+        // A panicking optimizer disconnects the channel — treat as failure.
+        Ok(None) | Err(mpsc::RecvTimeoutError::Disconnected) => FastPathOutcome::Failed,
+        Err(mpsc::RecvTimeoutError::Timeout) => FastPathOutcome::TimedOut,
+    }
+}
+
+/// Apply the `[stderr]` mode to optimized text.
+///
+/// A line is stderr-derived when, ignoring ANSI codes and surrounding
+/// whitespace, it matches a raw stderr line and no raw stdout line. Marking
+/// only helps when the command wrote to both streams, so single-stream output
+/// is returned unchanged. Returns `(stdout text, stderr text)`.
+fn split_stderr_lines(
+    text: &str,
+    raw_output: &ProcessOutput,
+    cfg: &StderrConfig,
+) -> (String, String) {
+    let unchanged = (text.to_string(), String::new());
+    if cfg.mode == StderrMode::Merge
+        || raw_output.stdout.trim().is_empty()
+        || raw_output.stderr.trim().is_empty()
+    {
+        return unchanged;
+    }
+
+    let key = |line: &str| strip_ansi(line).trim().to_string();
+    let stdout_lines: HashSet<String> = raw_output.stdout.lines().map(key).collect();
+    let stderr_lines: HashSet<String> = raw_output
+        .stderr
+        .lines()
+        .map(key)
+        .filter(|l| !l.is_empty() && !stdout_lines.contains(l))
+        .collect();
+    if stderr_lines.is_empty() {
+        return unchanged;
+    }
+
+    let mut body = String::with_capacity(text.len());
+    let mut stderr = String::new();
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        if !stderr_lines.contains(content.trim()) {
+            body.push_str(line);
+            continue;
+        }
+        match cfg.mode {
+            StderrMode::Separate => {
+                stderr.push_str(content);
+                stderr.push('\n');
+            }
+            _ => {
+                body.push_str(&cfg.marker);
+                body.push_str(line);
+            }
+        }
+    }
+    (body, stderr)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn footer_omitted_when_output_not_reduced() {
+        assert_eq!(append_truncation_footer("abc", 3, Some("abcd1234")), "abc");
+    }
+
+    #[test]
+    fn footer_names_stash_id() {
+        let footer = append_truncation_footer("ab", 4, Some("abcd1234"));
+        assert_eq!(
+            footer,
+            "ab[output truncated: showing 2 of 4 bytes (50.00% removed); full output: terse raw abcd1234]"
+        );
+    }
+
+    #[test]
+    fn footer_without_stash_id() {
+        let footer = append_truncation_footer("ab", 4, None);
+        assert!(footer.ends_with("(50.00% removed)]"));
+    }
+
+    #[test]
+    fn exit_header_omitted_on_success() {
+        assert_eq!(prepend_exit_header("ok", Some(0), false), "ok");
+        assert_eq!(prepend_exit_header("ok", None, false), "ok");
+    }
+
+    #[test]
+    fn exit_header_prepended_on_failure() {
+        assert_eq!(
+            prepend_exit_header("1 failed", Some(101), false),
+            "[exit code: 101]\n1 failed"
+        );
+    }
+
+    #[test]
+    fn exit_header_notes_timeout() {
+        assert_eq!(
+            prepend_exit_header("partial", Some(124), true),
+            "[exit code: 124 (timed out, output is partial)]\npartial"
+        );
+    }
+
+    #[test]
+    fn fast_path_returns_optimizer_output_without_deadline() {
+        let outcome = optimize_with_deadline(
+            OptimizerRegistry::new(),
+            "git branch",
+            "* main\n  feature\n",
+            &[],
+            0,
+        );
+        assert!(matches!(outcome, FastPathOutcome::Optimized(_)));
+    }
+
+    #[test]
+    fn fast_path_reports_no_match_as_failure() {
+        let cfg = crate::config::schema::OptimizersConfig {
+            generic: crate::config::schema::GenericOptimizerConfig {
+                enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let outcome = optimize_with_deadline(
+            OptimizerRegistry::from_config(&cfg),
+            "some-unknown-tool --flag",
+            "output",
+            &[],
+            1000,
+        );
+        assert!(matches!(outcome, FastPathOutcome::Failed));
+    }
+
+    fn captured(stdout: &str, stderr: &str) -> ProcessOutput {
+        ProcessOutput {
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+            chunks: Vec::new(),
+            exit_code: Some(0),
+            success: true,
+            timed_out: false,
+        }
+    }
+
+    fn stderr_config(mode: StderrMode) -> StderrConfig {
+        StderrConfig {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn stderr_lines_are_marked_when_streams_mix() {
+        let raw = captured("ok 1\nok 2\n", "\x1b[33mwarning: slow\x1b[0m\n");
+        let (body, stderr) = split_stderr_lines(
+            "ok 1\nwarning: slow\n[2 lines]\n",
+            &raw,
+            &stderr_config(StderrMode::Mark),
+        );
+        assert_eq!(body, "ok 1\n[stderr] warning: slow\n[2 lines]\n");
+        assert!(stderr.is_empty());
+    }
+
+    #[test]
+    fn stderr_lines_move_to_stderr_in_separate_mode() {
+        let raw = captured("ok 1\n", "warning: slow\n");
+        let (body, stderr) = split_stderr_lines(
+            "ok 1\nwarning: slow",
+            &raw,
+            &stderr_config(StderrMode::Separate),
+        );
+        assert_eq!(body, "ok 1\n");
+        assert_eq!(stderr, "warning: slow\n");
+    }
+
+    #[test]
+    fn single_stream_and_merge_mode_are_unchanged() {
+        let text = "Compiling x\nerror: boom\n";
+        let stderr_only = captured("", text);
+        let mark = stderr_config(StderrMode::Mark);
+        assert_eq!(split_stderr_lines(text, &stderr_only, &mark).0, text);
+
+        let mixed = captured("out\n", "error: boom\n");
+        let merge = stderr_config(StderrMode::Merge);
+        assert_eq!(split_stderr_lines(text, &mixed, &merge).0, text);
+
+        // Lines that also appear on stdout are ambiguous and left alone.
+        let both = captured("error: boom\n", "error: boom\n");
+        assert_eq!(split_stderr_lines(text, &both, &mark).0, text);
     }
 }
@@ -3,23 +3,37 @@ use std::io::Write;
 use anyhow::{Context, Result};
 
 use crate::analytics::logger::log_command_result_full;
+use crate::preprocessing::redact::Redactor;
 use crate::router;
+use crate::utils::encoding::decode_base64;
+
+/// Decode the base64 payload passed as `terse run --encoded <payload>`.
+///
+/// The hook encodes the original command so the agent's shell passes it
+/// through byte-for-byte (see [`crate::hook::encoded_run_command`]).
+pub fn decode_command(payload: &str) -> Result<String> {
+    let bytes = decode_base64(payload).context("invalid --encoded payload")?;
+    String::from_utf8(bytes).context("--encoded payload is not valid UTF-8")
+}
 
 /// Execute a command with optimization and print the result to stdout.
 ///
 /// This is the entry point for `terse run "command"`. When the PreToolUse hook
-/// rewrites a command to `terse run "original_command"`, Claude Code executes
+/// rewrites a command to `terse run --encoded <payload>`, Claude Code executes
 /// terse as a subprocess. This function delegates to the router's execution
 /// pipeline and handles I/O:
 ///
 /// 1. Calls [`router::execute_run`] which tries fast path → smart path → passthrough
-/// 2. Logs token analytics to `~/.terse/command-log.jsonl`
+/// 2. Logs token analytics and the redacted command to `~/.terse/command-log.jsonl`
 /// 3. Prints the optimized result to stdout (which Claude sees as the command output)
-pub fn execute(command: &str) -> Result<()> {
+///
+/// Returns the exit code `terse run` should exit with — the wrapped command's
+/// own code, so failures stay visible to the agent and to `&&` chains.
+pub fn execute(command: &str) -> Result<i32> {
     let result = router::execute_run(command)?;
 
     log_command_result_full(
-        command,
+        &Redactor::load().redact_command(command),
         &result.path.to_string(),
         result.original_tokens,
         result.optimized_tokens,
@@ -29,6 +43,8 @@ pub fn execute(command: &str) -> Result<()> {
         result.preprocessing_bytes_removed,
         result.preprocessing_pct,
         result.preprocessing_duration_ms,
+        result.exit_code,
+        Some(result.redactions).filter(|&n| n > 0),
     );
 
     std::io::stdout()
@@ -41,5 +57,13 @@ pub fn execute(command: &str) -> Result<()> {
             .context("failed writing stderr output")?;
     }
 
-    Ok(())
+    // The caller exits the process directly, which skips buffered-writer
+    // destructors — flush explicitly so no output is lost.
+    std::io::stdout()
+        .flush()
+        .context("failed flushing stdout")?;
+
+    // A missing code means the status could not be determined at all;
+    // report it as a generic failure rather than a silent success.
+    Ok(result.exit_code.unwrap_or(1))
 }
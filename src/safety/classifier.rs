@@ -1,16 +1,24 @@
 /// Command classification for routing decisions.
 ///
 /// Classifies commands as either safe to optimize or requiring passthrough.
-/// Destructive commands (rm, mv) and interactive editors (vim, code) are never
-/// optimized. Commands with file output redirections (`>`, `>>`) are also
+/// Destructive commands (rm, `git reset --hard`, `kubectl delete`), file
+/// modification (`sed -i`, `tee`), privilege escalation (sudo), and
+/// interactive editors (vim, code) are never optimized. Commands with file output redirections (`>`, `>>`) are also
 /// passed through since the redirect consumes stdout and there is nothing
 /// useful for terse to capture.
 ///
-/// The classifier examines both the **core command** (extracted by the
-/// matching engine) for command-name checks, and the **full original command**
-/// for redirect detection.
+/// Every command the shell would run is checked — each segment of a chain or
+/// pipeline, command substitutions, shell wrappers (`bash -c`), and command
+/// wrappers such as `xargs`, `env`, `timeout`, and `find -exec`. Commands
+/// that do not parse are never optimized.
+use std::sync::LazyLock;
+
+use regex::Regex;
+
 use crate::config;
+use crate::config::schema::{PassthroughConfig, PassthroughRule};
 use crate::matching;
+use crate::matching::shell::{self, List};
 
 /// Classification result for a command.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -21,8 +29,8 @@ pub enum CommandClass {
     Optimizable,
 }
 
-/// Built-in commands that must never be optimized, matched by first word of
-/// the core command.
+/// Built-in commands that must never be optimized, matched by program name
+/// wherever they appear in the command.
 ///
 /// These are the minimum set that is always enforced even if the user's config
 /// removes them. Additional commands can be added via `passthrough.commands`
@@ -32,6 +40,13 @@ const BUILTIN_PASSTHROUGH_COMMANDS: &[&str] = &[
     "rm",
     "rmdir",
     "mv",
+    "dd",
+    "shred",
+    "unlink",
+    "truncate",
+    "mkfs",
+    // Unix file-writing commands (act like redirects)
+    "tee",
     // Windows cmd destructive commands
     "del",
     "erase",
@@ -64,87 +79,383 @@ const BUILTIN_PASSTHROUGH_COMMANDS: &[&str] = &[
     "notepad++",
 ];
 
+/// Privilege escalation — whatever runs under these is never optimized.
+const PRIVILEGE_ESCALATION_COMMANDS: &[&str] = &["sudo", "doas", "su", "pkexec", "runas"];
+
+/// Built-in subcommand-aware danger table: `(command, subcommand, flags)`.
+///
+/// Same semantics as a `[[passthrough.rules]]` entry: the subcommand is a
+/// space-separated list of leading positional arguments, and a non-empty
+/// flag list requires any one of the flags.
+const BUILTIN_DANGER_RULES: &[(&str, &str, &[&str])] = &[
+    ("find", "", &["-delete"]),
+    ("sed", "", &["-i", "--in-place"]),
+    // git: discards work or rewrites shared history
+    ("git", "clean", &[]),
+    ("git", "reset", &["--hard"]),
+    ("git", "checkout", &["-f", "--force", "--"]),
+    ("git", "restore", &[]),
+    (
+        "git",
+        "push",
+        &["--force", "-f", "--force-with-lease", "--delete", "-d"],
+    ),
+    ("git", "branch", &["-D", "-d", "--delete"]),
+    ("git", "stash drop", &[]),
+    ("git", "stash clear", &[]),
+    ("git", "rm", &[]),
+    ("git", "filter-branch", &[]),
+    ("git", "worktree remove", &[]),
+    // docker: removes containers, images, volumes, networks
+    ("docker", "rm", &[]),
+    ("docker", "rmi", &[]),
+    ("docker", "system prune", &[]),
+    ("docker", "container prune", &[]),
+    ("docker", "image prune", &[]),
+    ("docker", "volume prune", &[]),
+    ("docker", "network prune", &[]),
+    ("docker", "builder prune", &[]),
+    ("docker", "container rm", &[]),
+    ("docker", "image rm", &[]),
+    ("docker", "volume rm", &[]),
+    ("docker", "network rm", &[]),
+    ("docker", "compose down", &[]),
+    // cluster and infrastructure teardown
+    ("kubectl", "delete", &[]),
+    ("kubectl", "drain", &[]),
+    ("helm", "uninstall", &[]),
+    ("helm", "delete", &[]),
+    ("terraform", "destroy", &[]),
+];
+
+static BUILTIN_RULES: LazyLock<Vec<PassthroughRule>> = LazyLock::new(|| {
+    BUILTIN_DANGER_RULES
+        .iter()
+        .map(|&(command, subcommand, flags)| PassthroughRule {
+            command: command.to_string(),
+            subcommand: subcommand.to_string(),
+            flags: flags.iter().map(|f| f.to_string()).collect(),
+            pattern: None,
+        })
+        .collect()
+});
+
 /// Classify a command for routing purposes.
 ///
 /// `command` is the full original command string as received from Claude Code.
-/// Internally, the core command is extracted for name matching, while the
-/// full string is checked for file output redirections.
-///
-/// Passthrough commands are loaded from config (merging with built-in set).
+/// Every command it would execute is checked against the built-in danger
+/// tables and the `passthrough` config; the full string is also checked for
+/// file output redirections.
 pub fn classify(command: &str) -> CommandClass {
-    let core = matching::extract_core_command(command);
-    let first = first_word(core);
+    classify_with(command, &config::load().passthrough)
+}
 
-    // Check built-in passthrough commands (always enforced).
-    if BUILTIN_PASSTHROUGH_COMMANDS
-        .iter()
-        .any(|&cmd| first.eq_ignore_ascii_case(cmd))
-    {
+/// Classify a command against an explicit passthrough config.
+///
+/// The built-in tables are always enforced; `passthrough` only extends them.
+pub fn classify_with(command: &str, passthrough: &PassthroughConfig) -> CommandClass {
+    // The shell would reject it too; nothing about it can be trusted.
+    let Ok(script) = shell::parse(command) else {
         return CommandClass::NeverOptimize;
-    }
+    };
 
-    // Check config-supplied passthrough commands.
-    let cfg = config::load();
-    if cfg
-        .passthrough
-        .commands
-        .iter()
-        .any(|cmd| first.eq_ignore_ascii_case(cmd))
-    {
+    if script_is_dangerous(command, &script, passthrough) {
         return CommandClass::NeverOptimize;
     }
 
-    // Check the full command for output redirections (> or >>).
-    if has_file_redirect(command) {
+    // Check the full command for stdout redirections to files (> or >>).
+    if has_file_redirect(command, &script) {
         return CommandClass::NeverOptimize;
     }
 
     CommandClass::Optimizable
 }
 
-/// Extract the first whitespace-delimited word from a string.
-fn first_word(s: &str) -> &str {
-    s.split_whitespace().next().unwrap_or("")
+/// Whether any command executed by `script` — including substitutions and
+/// wrapped scripts — is dangerous. A nested script that fails to parse
+/// counts as dangerous.
+fn script_is_dangerous(src: &str, script: &List, passthrough: &PassthroughConfig) -> bool {
+    let mut found = false;
+    let walked = matching::for_each_executed_script(src, script, &mut |list| {
+        for command in list.simple_commands() {
+            let words: Vec<String> = command
+                .command_words()
+                .iter()
+                .map(|w| w.text.clone())
+                .collect();
+            found = found || is_dangerous(&words, passthrough);
+        }
+    });
+    found || walked.is_err()
 }
 
-/// Detect unquoted output redirections (`>` or `>>`) in a command string.
-///
-/// Skips `>` characters inside single or double quotes, and ignores
-/// fd-duplication patterns like `>&` / `2>&1` which redirect between file
-/// descriptors rather than to files.
-fn has_file_redirect(command: &str) -> bool {
-    let bytes = command.as_bytes();
-    let len = bytes.len();
-
-    let mut in_single_quote = false;
-    let mut in_double_quote = false;
-    let mut i = 0;
+/// Whether a single command (quote-removed words) must not be optimized,
+/// looking through privilege and command wrappers to the commands they run.
+fn is_dangerous(words: &[String], passthrough: &PassthroughConfig) -> bool {
+    let Some((name, args)) = words.split_first() else {
+        return false;
+    };
+    let program = matching::program_name(name);
+    let is_named = |cmd: &str| program.eq_ignore_ascii_case(cmd) || name.eq_ignore_ascii_case(cmd);
 
-    while i < len {
-        match bytes[i] {
-            b'\'' if !in_double_quote => in_single_quote = !in_single_quote,
-            b'"' if !in_single_quote => in_double_quote = !in_double_quote,
-            b'>' if !in_single_quote && !in_double_quote => {
-                // Skip heredoc / herestring: preceded by `<`
-                if i > 0 && bytes[i - 1] == b'<' {
-                    i += 1;
-                    continue;
-                }
-                // Skip fd duplication: `>&` (e.g. 2>&1)
-                if i + 1 < len && bytes[i + 1] == b'&' {
-                    i += 2;
-                    continue;
+    if PRIVILEGE_ESCALATION_COMMANDS.contains(&program.as_str())
+        // `mkfs.ext4`, `mkfs.vfat`, ...
+        || program.starts_with("mkfs.")
+        // `sed -i.bak`: the backup suffix is attached to the flag.
+        || (program == "sed" && args.iter().any(|arg| arg.starts_with("-i")))
+        // `perl -i`, `perl -pi.bak -e ...`
+        || (program == "perl" && perl_edits_in_place(args))
+        || BUILTIN_PASSTHROUGH_COMMANDS.iter().any(|cmd| is_named(cmd))
+        || passthrough.commands.iter().any(|cmd| is_named(cmd))
+    {
+        return true;
+    }
+
+    if BUILTIN_RULES
+        .iter()
+        .chain(&passthrough.rules)
+        .any(|rule| rule_matches(rule, &program, args))
+    {
+        return true;
+    }
+
+    if let Some(script) = posix_shell_script(&program, args) {
+        return nested_script_is_dangerous(script, passthrough);
+    }
+
+    // `eval` joins its arguments and runs the result as a script.
+    if program == "eval" {
+        return nested_script_is_dangerous(&args.join(" "), passthrough);
+    }
+
+    wrapped_commands(&program, args)
+        .iter()
+        .any(|inner| is_dangerous(inner, passthrough))
+}
+
+/// Whether a script run by a wrapper or `eval` is dangerous or writes
+/// stdout to a file. A script that fails to parse counts as dangerous.
+fn nested_script_is_dangerous(script: &str, passthrough: &PassthroughConfig) -> bool {
+    match shell::parse(script) {
+        Ok(list) => {
+            script_is_dangerous(script, &list, passthrough) || has_file_redirect(script, &list)
+        }
+        Err(_) => true,
+    }
+}
+
+/// Whether perl's switches include `-i`, alone (`-i`, `-i.bak`) or bundled
+/// (`-pi`, `-pi.orig`). Switches that take the rest of their word as a value
+/// (`-Mstrict`, `-I/lib`) end the bundle, and `-e` consumes its code.
+fn perl_edits_in_place(args: &[String]) -> bool {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(switches) = arg.strip_prefix('-') else {
+            break;
+        };
+        if switches.is_empty() || switches == "-" {
+            break;
+        }
+        for (i, switch) in switches.char_indices() {
+            match switch {
+                'i' => return true,
+                'e' | 'E' => {
+                    if i + 1 == switches.len() {
+                        args.next();
+                    }
+                    break;
                 }
-                return true;
+                'M' | 'm' | 'I' | 'd' | 'D' | 'x' | 'C' | 'l' | '0' => break,
+                _ => {}
             }
-            _ => {}
         }
-        i += 1;
     }
-
     false
 }
 
+/// Whether a passthrough rule matches a command.
+fn rule_matches(rule: &PassthroughRule, program: &str, args: &[String]) -> bool {
+    if !program.eq_ignore_ascii_case(&matching::program_name(&rule.command)) {
+        return false;
+    }
+
+    let subcommand: Vec<&str> = rule.subcommand.split_whitespace().collect();
+    if !subcommand.is_empty() && !has_subcommand(args, &subcommand) {
+        return false;
+    }
+
+    if !rule.flags.is_empty() && !rule.flags.iter().any(|flag| has_flag(args, flag)) {
+        return false;
+    }
+
+    match rule.pattern.as_deref().map(Regex::new) {
+        Some(Ok(re)) => re.is_match(&args.join(" ")),
+        // An invalid pattern must not disable the rest of the rule.
+        Some(Err(_)) | None => true,
+    }
+}
+
+/// Whether the leading positional arguments are `subcommand`.
+///
+/// The subcommand may also start at the second positional argument when
+/// the first one is the value of a leading option, so `git -C /repo reset`
+/// and `kubectl -n prod delete` are recognized.
+fn has_subcommand(args: &[String], subcommand: &[&str]) -> bool {
+    let positionals: Vec<(usize, &str)> = args
+        .iter()
+        .enumerate()
+        .filter(|(_, arg)| !arg.starts_with('-'))
+        .map(|(i, arg)| (i, arg.as_str()))
+        .collect();
+    let matches_at = |start: usize| {
+        positionals.len() >= start + subcommand.len()
+            && positionals[start..start + subcommand.len()]
+                .iter()
+                .zip(subcommand)
+                .all(|((_, arg), want)| arg == want)
+    };
+
+    let Some(&(first, _)) = positionals.first() else {
+        return false;
+    };
+    let after_option = first > 0 && !args[first - 1].contains('=');
+    matches_at(0) || (after_option && matches_at(1))
+}
+
+/// Whether `flag` is present. Flags are case-sensitive (`-D` is not `-d`);
+/// a short flag also matches inside a bundle (`-f` in `-fdx`) and a long
+/// flag also matches with an attached value (`--force=true`).
+pub(crate) fn has_flag(args: &[String], flag: &str) -> bool {
+    let short = flag
+        .strip_prefix('-')
+        .filter(|f| f.len() == 1 && f.bytes().all(|b| b.is_ascii_alphanumeric()));
+    args.iter().any(|arg| {
+        if arg == flag {
+            return true;
+        }
+        if flag.starts_with("--")
+            && let Some(value) = arg.strip_prefix(flag)
+        {
+            return value.starts_with('=');
+        }
+        match (short, arg.strip_prefix('-')) {
+            (Some(short), Some(bundle)) => {
+                !bundle.starts_with('-')
+                    && bundle.bytes().all(|b| b.is_ascii_alphabetic())
+                    && bundle.contains(short)
+            }
+            _ => false,
+        }
+    })
+}
+
+/// The script of `sh|bash|zsh|dash|ksh -c SCRIPT` when it appears inside a
+/// command wrapper (`xargs sh -c '...'`). Top-level wrappers are already
+/// walked by the matching engine.
+fn posix_shell_script<'a>(program: &str, args: &'a [String]) -> Option<&'a str> {
+    if !matches!(program, "sh" | "bash" | "zsh" | "dash" | "ksh") {
+        return None;
+    }
+    let flag = args.iter().position(|arg| {
+        arg.len() > 1
+            && arg.starts_with('-')
+            && arg[1..].bytes().all(|b| b.is_ascii_alphabetic())
+            && arg.contains('c')
+    })?;
+    args.get(flag + 1).map(String::as_str)
+}
+
+/// Commands run by a command wrapper (`xargs rm`, `env X=1 rm`,
+/// `timeout 5 rm`, `busybox rm`, `find -exec rm {} ;`, ...).
+pub(crate) fn wrapped_commands(program: &str, args: &[String]) -> Vec<Vec<String>> {
+    let inner = match program {
+        "xargs" => skip_options(args, &["-a", "-d", "-E", "-I", "-L", "-n", "-P", "-s"]),
+        "env" => {
+            let rest = skip_options(args, &["-u", "-C", "-S"]);
+            let assignments = rest.iter().take_while(|arg| arg.contains('=')).count();
+            &rest[assignments..]
+        }
+        "nice" => skip_options(args, &["-n"]),
+        "timeout" => {
+            let rest = skip_options(args, &["-s", "-k"]);
+            rest.get(1..).unwrap_or_default()
+        }
+        "stdbuf" => skip_options(args, &["-i", "-o", "-e"]),
+        "watch" => {
+            // A single argument is a command line run through `sh -c`.
+            return match skip_options(args, &["-n"]) {
+                [line] => vec![shell::split_words(line)],
+                rest => vec![rest.to_vec()],
+            };
+        }
+        "nohup" | "time" | "exec" | "builtin" => skip_options(args, &["-a", "-f", "-o"]),
+        // `busybox rm -rf x` runs the `rm` applet.
+        "busybox" => skip_options(args, &[]),
+        "command" => {
+            if args.iter().any(|arg| arg == "-v" || arg == "-V") {
+                return Vec::new();
+            }
+            skip_options(args, &[])
+        }
+        "find" => return find_exec_commands(args),
+        _ => return Vec::new(),
+    };
+    vec![inner.to_vec()]
+}
+
+/// Skip leading options; `value_options` take the following word as value.
+fn skip_options<'a>(args: &'a [String], value_options: &[&str]) -> &'a [String] {
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        if arg == "--" {
+            return &args[i + 1..];
+        }
+        if !arg.starts_with('-') || arg.len() == 1 {
+            break;
+        }
+        i += if value_options.contains(&arg.as_str()) {
+            2
+        } else {
+            1
+        };
+    }
+    args.get(i..).unwrap_or_default()
+}
+
+/// Commands run by `find ... -exec|-execdir|-ok|-okdir CMD ... ;|+`.
+fn find_exec_commands(args: &[String]) -> Vec<Vec<String>> {
+    let mut commands = Vec::new();
+    let mut rest = args;
+    while let Some(start) = rest
+        .iter()
+        .position(|arg| matches!(arg.as_str(), "-exec" | "-execdir" | "-ok" | "-okdir"))
+    {
+        rest = &rest[start + 1..];
+        let end = rest
+            .iter()
+            .position(|arg| arg == ";" || arg == "+")
+            .unwrap_or(rest.len());
+        commands.push(rest[..end].to_vec());
+        rest = rest.get(end + 1..).unwrap_or_default();
+    }
+    commands
+}
+
+/// Detect redirections that send stdout to a file (`>`, `>>`, `&>`).
+///
+/// Walks every command in the parsed script, including subshells, groups,
+/// and shell-wrapper scripts. Quoted `>` characters, fd duplication like
+/// `2>&1`, and stderr-only redirects (`2>err.log`) do not count — stdout is
+/// still there for terse to capture. A wrapper script that fails to parse
+/// counts as a redirect.
+fn has_file_redirect(command: &str, script: &List) -> bool {
+    let mut found = false;
+    let walked = matching::for_each_script(command, script, &mut |list| {
+        found |= list.redirects().iter().any(|r| r.writes_stdout_to_file());
+    });
+    found || walked.is_err()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,6 +470,31 @@ mod tests {
         assert_eq!(classify("rmdir empty_dir"), CommandClass::NeverOptimize);
     }
 
+    #[test]
+    fn file_modifying_commands_are_never_optimized() {
+        for command in [
+            "mkfs.ext4 /dev/sdb1",
+            "sed -i 's/a/b/' file.txt",
+            "sed -i.bak 's/a/b/' file.txt",
+            "sed -Ei 's/a/b/' file.txt",
+            "sed --in-place=.orig 's/a/b/' file.txt",
+            "cargo build 2>&1 | tee build.log",
+            "perl -i -pe 's/a/b/' file.txt",
+            "perl -pi.bak -e 's/a/b/' file.txt",
+            "perl -w -i.orig -pe 's/a/b/' file.txt",
+        ] {
+            assert_eq!(classify(command), CommandClass::NeverOptimize, "{command}");
+        }
+        assert_eq!(
+            classify("sed -n '1,5p' file.txt"),
+            CommandClass::Optimizable
+        );
+        assert_eq!(
+            classify("perl -Mstrict -ne 'print if /x/' file.txt"),
+            CommandClass::Optimizable
+        );
+    }
+
     #[test]
     fn editor_commands_are_never_optimized() {
         assert_eq!(classify("vim file.rs"), CommandClass::NeverOptimize);
@@ -193,6 +529,19 @@ mod tests {
             classify("cd /repo && git status"),
             CommandClass::Optimizable
         );
+        assert_eq!(classify("busybox rm -rf x"), CommandClass::NeverOptimize);
+        assert_eq!(classify("busybox ls -la"), CommandClass::Optimizable);
+    }
+
+    #[test]
+    fn eval_scripts_are_classified() {
+        assert_eq!(
+            classify("eval \"rm -rf build\""),
+            CommandClass::NeverOptimize
+        );
+        assert_eq!(classify("eval rm -rf build"), CommandClass::NeverOptimize);
+        assert_eq!(classify("eval 'ls > out.txt'"), CommandClass::NeverOptimize);
+        assert_eq!(classify("eval \"git status\""), CommandClass::Optimizable);
     }
 
     // --- Redirect detection ---
@@ -225,7 +574,186 @@ mod tests {
     fn heredoc_redirect_is_not_file_redirect() {
         // <<EOF is a heredoc, not an output redirect.
         // (Heredocs are handled separately by the matching engine.)
-        assert!(!has_file_redirect("cat <<EOF\nhello\nEOF"));
+        assert!(!redirects("cat <<EOF\nhello\nEOF"));
+    }
+
+    #[test]
+    fn stderr_redirect_leaves_stdout_optimizable() {
+        assert_eq!(
+            classify("cargo build 2> err.log"),
+            CommandClass::Optimizable
+        );
+        assert_eq!(
+            classify("cargo build &> all.log"),
+            CommandClass::NeverOptimize
+        );
+    }
+
+    #[test]
+    fn redirect_in_subshell_or_wrapper_is_detected() {
+        assert!(redirects("(cd /repo && make) > build.log"));
+        assert!(redirects("bash -c 'git log > log.txt'"));
+        assert!(!redirects("echo \"$(cat a > /dev/null)\" 'x > y'"));
+    }
+
+    #[test]
+    fn unparseable_commands_are_never_optimized() {
+        assert_eq!(classify("echo 'unterminated"), CommandClass::NeverOptimize);
+        assert_eq!(classify("git status &&"), CommandClass::NeverOptimize);
+    }
+
+    #[test]
+    fn quoted_command_name_is_still_classified() {
+        assert_eq!(classify("'rm' -rf build"), CommandClass::NeverOptimize);
+        assert_eq!(classify("\\rm -rf build"), CommandClass::NeverOptimize);
+    }
+
+    // --- Every segment, wrapper, and subcommand ---
+
+    fn class(command: &str) -> CommandClass {
+        classify_with(command, &PassthroughConfig::default())
+    }
+
+    #[test]
+    fn dangerous_command_anywhere_in_chain_or_pipeline() {
+        assert_eq!(class("rm -rf build && ls"), CommandClass::NeverOptimize);
+        assert_eq!(class("find . -delete | wc -l"), CommandClass::NeverOptimize);
+        assert_eq!(class("ls; git status; mv a b"), CommandClass::NeverOptimize);
+        assert_eq!(class("(cd x && rm y) || true"), CommandClass::NeverOptimize);
+    }
+
+    #[test]
+    fn dangerous_command_in_substitution_or_wrapper() {
+        assert_eq!(class("echo $(rm x)"), CommandClass::NeverOptimize);
+        assert_eq!(class("echo \"`rm x`\""), CommandClass::NeverOptimize);
+        assert_eq!(class("bash -c 'ls; rm x'"), CommandClass::NeverOptimize);
+        assert_eq!(
+            class("find . -name '*.o' | xargs rm"),
+            CommandClass::NeverOptimize
+        );
+        assert_eq!(class("xargs -n 1 -I{} rm {}"), CommandClass::NeverOptimize);
+        assert_eq!(class("find . -exec rm {} \\;"), CommandClass::NeverOptimize);
+        assert_eq!(class("env -u X FOO=1 rm x"), CommandClass::NeverOptimize);
+        assert_eq!(
+            class("timeout -s KILL 5 git clean -fd"),
+            CommandClass::NeverOptimize
+        );
+        assert_eq!(class("nice -n 10 nohup rm x"), CommandClass::NeverOptimize);
+        assert_eq!(
+            class("xargs sh -c 'git reset --hard'"),
+            CommandClass::NeverOptimize
+        );
+        assert_eq!(
+            class("watch 'kubectl delete pod x'"),
+            CommandClass::NeverOptimize
+        );
+        assert_eq!(class("/usr/bin/RM.exe x"), CommandClass::NeverOptimize);
+    }
+
+    #[test]
+    fn privilege_escalation_is_never_optimized() {
+        assert_eq!(class("sudo ls"), CommandClass::NeverOptimize);
+        assert_eq!(
+            class("cd /etc && doas cat shadow"),
+            CommandClass::NeverOptimize
+        );
+        assert_eq!(class("xargs sudo ls"), CommandClass::NeverOptimize);
+    }
+
+    #[test]
+    fn destructive_subcommands_are_never_optimized() {
+        for command in [
+            "git clean -fdx",
+            "git reset --hard HEAD~1",
+            "git -C /repo reset --hard",
+            "git push -f origin main",
+            "git push --force-with-lease=main origin",
+            "git branch -D feature",
+            "git checkout -- src/main.rs",
+            "git stash drop",
+            "docker rm web",
+            "docker system prune -af",
+            "docker compose down -v",
+            "kubectl delete pod x",
+            "kubectl -n prod delete deploy api",
+            "helm uninstall api",
+            "terraform destroy",
+        ] {
+            assert_eq!(class(command), CommandClass::NeverOptimize, "{command}");
+        }
+    }
+
+    #[test]
+    fn safe_subcommands_stay_optimizable() {
+        for command in [
+            "git reset HEAD~1",
+            "git log --grep clean",
+            "git branch -a",
+            "git push origin main",
+            "git stash list",
+            "git checkout main",
+            "docker ps",
+            "docker compose ps",
+            "kubectl get pods",
+            "find . -name '*.rs' | wc -l",
+            "command -v rm",
+            "echo rm -rf /",
+        ] {
+            assert_eq!(class(command), CommandClass::Optimizable, "{command}");
+        }
+    }
+
+    #[test]
+    fn user_rules_extend_the_danger_table() {
+        let passthrough = PassthroughConfig {
+            commands: vec!["deploy.sh".to_string()],
+            rules: vec![
+                PassthroughRule {
+                    command: "terraform".to_string(),
+                    subcommand: "apply".to_string(),
+                    flags: vec!["-auto-approve".to_string()],
+                    pattern: None,
+                },
+                PassthroughRule {
+                    command: "make".to_string(),
+                    pattern: Some("^(deploy|release)\\b".to_string()),
+                    ..Default::default()
+                },
+                PassthroughRule {
+                    command: "npm".to_string(),
+                    subcommand: "publish".to_string(),
+                    pattern: Some("(".to_string()),
+                    ..Default::default()
+                },
+            ],
+        };
+        let class = |command| classify_with(command, &passthrough);
+
+        assert_eq!(class("./scripts/deploy.sh"), CommandClass::NeverOptimize);
+        assert_eq!(
+            class("terraform apply -auto-approve"),
+            CommandClass::NeverOptimize
+        );
+        assert_eq!(class("terraform apply"), CommandClass::Optimizable);
+        assert_eq!(class("make release VERSION=1"), CommandClass::NeverOptimize);
+        assert_eq!(class("make test"), CommandClass::Optimizable);
+        // Invalid pattern: the rest of the rule still applies.
+        assert_eq!(class("npm publish"), CommandClass::NeverOptimize);
+        assert_eq!(class("npm test"), CommandClass::Optimizable);
+    }
+
+    #[test]
+    fn flag_matching_handles_bundles_and_values() {
+        let args = |s: &str| shell::split_words(s);
+        assert!(has_flag(&args("-fdx"), "-f"));
+        assert!(has_flag(&args("--force=yes"), "--force"));
+        assert!(!has_flag(&args("-d"), "-D"));
+        assert!(!has_flag(&args("--forced"), "--force"));
+        assert!(!has_flag(&args("-f1"), "-x"));
+    }
+
+    fn redirects(command: &str) -> bool {
+        has_file_redirect(command, &shell::parse(command).unwrap())
     }
 
     // --- Windows-specific commands (Phase 10) ---
@@ -0,0 +1,512 @@
+/// Interactive and long-running command detection.
+///
+/// `terse run` captures output through pipes with
+/// `run_shell_command_with_timeout`: stdin is closed, reader threads
+/// collect stdout and stderr, and nothing is returned until the command
+/// exits or its deadline kills it. Pagers, full-screen UIs, REPLs,
+/// interactive sessions, `tail -f`-style followers, and dev servers would
+/// hang or lose their UI, so the hook leaves them alone.
+///
+/// Detection is flag-aware — `tail -f` follows but `tail -n 20` does not,
+/// `docker exec -it` is a session but `docker exec web ls` is not, and a
+/// REPL binary fed through a pipe or `<` redirect is just a filter. Every
+/// command in a chain, pipeline, substitution, or wrapper is checked.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::matching;
+use crate::matching::shell::{self, Command, List, RedirectOp, SimpleCommand};
+use crate::safety::classifier::{has_flag, wrapped_commands};
+
+/// Why a command needs a terminal or never finishes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InteractionKind {
+    /// Pager or manual viewer (`less`, `more`, `man`).
+    Pager,
+    /// Full-screen terminal UI (`top`, `watch`, `tmux`, `k9s`).
+    FullScreen,
+    /// Interactive session (`ssh host`, `docker exec -it`, `kubectl attach`).
+    Session,
+    /// Language or database REPL (`python`, `node`, `psql`).
+    Repl,
+    /// Follows output forever (`tail -f`, `kubectl logs -f`, `ping`).
+    Follow,
+    /// Server or watch mode (`npm run dev`, `tsc --watch`, `port-forward`).
+    Server,
+}
+
+impl fmt::Display for InteractionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pager => write!(f, "pager"),
+            Self::FullScreen => write!(f, "full-screen UI"),
+            Self::Session => write!(f, "interactive session"),
+            Self::Repl => write!(f, "REPL"),
+            Self::Follow => write!(f, "follows output"),
+            Self::Server => write!(f, "server or watch mode"),
+        }
+    }
+}
+
+/// Package-manager scripts that conventionally start a dev server.
+const DEV_SCRIPTS: &[&str] = &["dev", "start", "serve", "watch", "preview", "develop"];
+
+/// Full-screen terminal programs.
+const FULL_SCREEN_COMMANDS: &[&str] = &[
+    "htop",
+    "btop",
+    "atop",
+    "iotop",
+    "nvtop",
+    "nmon",
+    "watch",
+    "tmux",
+    "screen",
+    "fzf",
+    "ncdu",
+    "mc",
+    "ranger",
+    "nnn",
+    "tig",
+    "lazygit",
+    "lazydocker",
+    "k9s",
+];
+
+/// Detect an interactive or long-running command anywhere in `command`.
+///
+/// Returns `None` for commands that do not parse; the classifier already
+/// keeps those out of `terse run`.
+pub fn detect(command: &str) -> Option<InteractionKind> {
+    let script = shell::parse(command).ok()?;
+    let mut found = None;
+    let _ = matching::for_each_executed_script(command, &script, &mut |list| {
+        if found.is_none() {
+            found = detect_in_list(list, false);
+        }
+    });
+    found
+}
+
+/// Check each pipeline stage; stages after the first read a pipe.
+fn detect_in_list(list: &List, piped: bool) -> Option<InteractionKind> {
+    list.items.iter().find_map(|item| {
+        item.pipeline
+            .commands
+            .iter()
+            .enumerate()
+            .find_map(|(i, command)| match command {
+                Command::Simple(simple) => {
+                    let has_input = piped || i > 0 || reads_redirected_stdin(simple);
+                    let words: Vec<String> = simple
+                        .command_words()
+                        .iter()
+                        .map(|w| w.text.clone())
+                        .collect();
+                    detect_words(&words, has_input)
+                }
+                Command::Subshell { body, .. } | Command::Group { body, .. } => {
+                    detect_in_list(body, piped || i > 0)
+                }
+            })
+    })
+}
+
+fn reads_redirected_stdin(command: &SimpleCommand) -> bool {
+    command.redirects.iter().any(|r| {
+        matches!(r.fd, None | Some(0))
+            && matches!(
+                r.op,
+                RedirectOp::Input
+                    | RedirectOp::Heredoc
+                    | RedirectOp::HeredocStripTabs
+                    | RedirectOp::HereString
+            )
+    })
+}
+
+/// Classify one command (quote-removed words). `has_input` means stdin is
+/// a pipe or file, so a REPL would read it as a script and exit.
+fn detect_words(words: &[String], has_input: bool) -> Option<InteractionKind> {
+    let (name, args) = words.split_first()?;
+    let program = matching::program_name(name);
+    let flag = |f: &str| has_flag(args, f);
+    let positionals: Vec<&str> = args
+        .iter()
+        .filter(|a| !a.starts_with('-'))
+        .map(String::as_str)
+        .collect();
+    let sub = positionals.first().copied().unwrap_or("");
+    let repl = |interactive: bool| (interactive && !has_input).then_some(InteractionKind::Repl);
+
+    let kind = match program.as_str() {
+        "less" | "more" | "most" | "man" => Some(InteractionKind::Pager),
+        "top" if !flag("-b") => Some(InteractionKind::FullScreen),
+        p if FULL_SCREEN_COMMANDS.contains(&p) => Some(InteractionKind::FullScreen),
+        "ssh" => ssh_is_interactive(args).then_some(InteractionKind::Session),
+        "telnet" | "mosh" | "ftp" => Some(InteractionKind::Session),
+        "sftp" if !flag("-b") => Some(InteractionKind::Session),
+
+        // Followers
+        "tail" if flag("-f") || flag("-F") || flag("--follow") => Some(InteractionKind::Follow),
+        "journalctl" if flag("-f") || flag("--follow") => Some(InteractionKind::Follow),
+        "dmesg" if flag("-w") || flag("-W") || flag("--follow") => Some(InteractionKind::Follow),
+        "ping" if flag("-t") || (!cfg!(windows) && !flag("-c")) => Some(InteractionKind::Follow),
+
+        // Containers and clusters
+        "docker" | "podman" => container_kind(args, sub),
+        "kubectl" | "oc" => kubectl_kind(args, sub),
+
+        // REPLs
+        "python" | "python3" | "python2" | "py" | "pypy" | "pypy3" => python_kind(args, has_input),
+        "node" => repl(
+            flag("-i")
+                || flag("--interactive")
+                || (positionals.is_empty()
+                    && !["-e", "--eval", "-p", "--print"].iter().any(|f| flag(f))),
+        ),
+        "deno" => repl(args.is_empty() || sub == "repl"),
+        "irb" | "ghci" | "ipython" | "bpython" | "ptpython" | "jshell" | "iex" | "erl" => {
+            repl(positionals.is_empty())
+        }
+        "psql" => repl(
+            !["-c", "--command", "-f", "--file", "-l", "--list"]
+                .iter()
+                .any(|f| flag(f)),
+        ),
+        "mysql" | "mariadb" => repl(!flag("-e") && !flag("--execute")),
+        "sqlite3" => repl(positionals.len() < 2),
+
+        // Dev servers and watchers
+        "npm" | "pnpm" | "yarn" | "bun" => package_script_kind(&positionals, has_input),
+        "npx" | "pnpx" | "bunx" => {
+            return detect_words(skip_leading_options(args), has_input);
+        }
+        "vite" if sub != "build" && sub != "optimize" => Some(InteractionKind::Server),
+        "next" | "nuxt" | "astro" if sub == "dev" || sub == "start" => {
+            Some(InteractionKind::Server)
+        }
+        "nodemon" | "live-server" | "http-server" | "serve" | "uvicorn" | "gunicorn"
+        | "hypercorn" => Some(InteractionKind::Server),
+        "cargo" if sub == "watch" => Some(InteractionKind::Server),
+        "flask" if sub == "run" => Some(InteractionKind::Server),
+        "rails" => match sub {
+            "s" | "server" => Some(InteractionKind::Server),
+            "c" | "console" => repl(true),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    kind.or_else(|| flag("--watch").then_some(InteractionKind::Server))
+        .or_else(|| {
+            // A timeout bounds whatever it wraps.
+            if program == "timeout" {
+                return None;
+            }
+            wrapped_commands(&program, args)
+                .iter()
+                .find_map(|inner| detect_words(inner, has_input))
+        })
+}
+
+/// `ssh` without a remote command, or with a forced tty, is a login shell.
+fn ssh_is_interactive(args: &[String]) -> bool {
+    const VALUE_OPTIONS: &[&str] = &[
+        "-b", "-c", "-D", "-E", "-e", "-F", "-I", "-i", "-J", "-L", "-l", "-m", "-O", "-o", "-p",
+        "-Q", "-R", "-S", "-W", "-w",
+    ];
+    if has_flag(args, "-t") {
+        return true;
+    }
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        if !arg.starts_with('-') {
+            // Host found; anything after it is the remote command.
+            return i + 1 >= args.len();
+        }
+        i += if VALUE_OPTIONS.contains(&arg.as_str()) {
+            2
+        } else {
+            1
+        };
+    }
+    true
+}
+
+fn container_kind(args: &[String], sub: &str) -> Option<InteractionKind> {
+    let flag = |f: &str| has_flag(args, f);
+    // `exec`/`run` options precede the container or image; flags after it
+    // belong to the command run inside.
+    let tty = || {
+        let options = container_options(args, sub);
+        ["-i", "-t", "--interactive", "--tty"]
+            .iter()
+            .any(|f| has_flag(options, f))
+    };
+    match sub {
+        "logs" if flag("-f") || flag("--follow") => Some(InteractionKind::Follow),
+        "exec" | "run" if tty() => Some(InteractionKind::Session),
+        "attach" => Some(InteractionKind::Session),
+        "stats" if !flag("--no-stream") => Some(InteractionKind::Follow),
+        "events" => Some(InteractionKind::Follow),
+        "compose" => {
+            let compose_sub = args
+                .iter()
+                .skip_while(|a| a.as_str() != "compose")
+                .skip(1)
+                .find(|a| !a.starts_with('-'))
+                .map_or("", String::as_str);
+            match compose_sub {
+                "logs" if flag("-f") || flag("--follow") => Some(InteractionKind::Follow),
+                "up" if !flag("-d") && !flag("--detach") => Some(InteractionKind::Server),
+                "watch" => Some(InteractionKind::Server),
+                "attach" => Some(InteractionKind::Session),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Options between a docker subcommand and its container or image operand.
+fn container_options<'a>(args: &'a [String], sub: &str) -> &'a [String] {
+    const VALUE_OPTIONS: &[&str] = &[
+        "-e",
+        "--env",
+        "--env-file",
+        "-w",
+        "--workdir",
+        "-u",
+        "--user",
+        "--name",
+        "-v",
+        "--volume",
+        "-p",
+        "--publish",
+        "--network",
+        "--entrypoint",
+        "-l",
+        "--label",
+        "--platform",
+        "--mount",
+        "-m",
+        "--memory",
+        "--cpus",
+    ];
+    let Some(start) = args.iter().position(|a| a == sub) else {
+        return &[];
+    };
+    let options = &args[start + 1..];
+    let mut i = 0;
+    while let Some(arg) = options.get(i) {
+        if !arg.starts_with('-') {
+            break;
+        }
+        i += if VALUE_OPTIONS.contains(&arg.as_str()) {
+            2
+        } else {
+            1
+        };
+    }
+    &options[..i.min(options.len())]
+}
+
+fn kubectl_kind(args: &[String], sub: &str) -> Option<InteractionKind> {
+    // Flags after `--` belong to the command run in the container.
+    let args = args.split(|a| a == "--").next().unwrap_or_default();
+    let flag = |f: &str| has_flag(args, f);
+    match sub {
+        "logs" if flag("-f") || flag("--follow") => Some(InteractionKind::Follow),
+        "get" | "events" if flag("-w") || flag("--watch") || flag("--watch-only") => {
+            Some(InteractionKind::Follow)
+        }
+        "exec" | "run" | "debug"
+            if flag("-i") || flag("-t") || flag("--stdin") || flag("--tty") =>
+        {
+            Some(InteractionKind::Session)
+        }
+        "attach" => Some(InteractionKind::Session),
+        "edit" => Some(InteractionKind::FullScreen),
+        "port-forward" | "proxy" => Some(InteractionKind::Server),
+        _ => None,
+    }
+}
+
+fn python_kind(args: &[String], has_input: bool) -> Option<InteractionKind> {
+    let mut interactive = false;
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        match arg.as_str() {
+            "-i" => interactive = true,
+            "-c" => return None,
+            "-m" => {
+                return match args.get(i + 1).map(String::as_str) {
+                    Some("http.server") => Some(InteractionKind::Server),
+                    _ => None,
+                };
+            }
+            "-W" | "-X" => i += 1,
+            a if a.starts_with('-') && a.len() > 1 => {}
+            // A script (or `-` for stdin).
+            _ => return interactive.then_some(InteractionKind::Repl),
+        }
+        i += 1;
+    }
+    (interactive || !has_input).then_some(InteractionKind::Repl)
+}
+
+/// `npm run dev`, `npm start`, `yarn dev`, `pnpm run serve`, `bun repl`, ...
+fn package_script_kind(positionals: &[&str], has_input: bool) -> Option<InteractionKind> {
+    let script = match positionals {
+        ["run" | "run-script", script, ..] => *script,
+        [script, ..] => *script,
+        [] => return None,
+    };
+    if script == "repl" {
+        return (!has_input).then_some(InteractionKind::Repl);
+    }
+    DEV_SCRIPTS
+        .contains(&script)
+        .then_some(InteractionKind::Server)
+}
+
+fn skip_leading_options(args: &[String]) -> &[String] {
+    let start = args
+        .iter()
+        .position(|a| !a.starts_with('-'))
+        .unwrap_or(args.len());
+    &args[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(command: &str) -> Option<InteractionKind> {
+        detect(command)
+    }
+
+    #[test]
+    fn pagers_and_full_screen_programs() {
+        assert_eq!(kind("less README.md"), Some(InteractionKind::Pager));
+        assert_eq!(kind("git log | less"), Some(InteractionKind::Pager));
+        assert_eq!(kind("man ls"), Some(InteractionKind::Pager));
+        assert_eq!(kind("top"), Some(InteractionKind::FullScreen));
+        assert_eq!(
+            kind("watch -n 1 kubectl get pods"),
+            Some(InteractionKind::FullScreen)
+        );
+        assert_eq!(kind("top -b -n 1"), None);
+    }
+
+    #[test]
+    fn followers_need_follow_flags() {
+        assert_eq!(kind("tail -f app.log"), Some(InteractionKind::Follow));
+        assert_eq!(kind("tail -fn 100 app.log"), Some(InteractionKind::Follow));
+        assert_eq!(
+            kind("tail --follow=name app.log"),
+            Some(InteractionKind::Follow)
+        );
+        assert_eq!(kind("docker logs -f web"), Some(InteractionKind::Follow));
+        assert_eq!(
+            kind("kubectl logs --follow api"),
+            Some(InteractionKind::Follow)
+        );
+        assert_eq!(kind("kubectl get pods -w"), Some(InteractionKind::Follow));
+        assert_eq!(
+            kind("journalctl -u nginx -f"),
+            Some(InteractionKind::Follow)
+        );
+        assert_eq!(kind("tail -n 20 app.log"), None);
+        assert_eq!(kind("docker logs web"), None);
+        assert_eq!(kind("kubectl logs api --tail 50"), None);
+        assert_eq!(kind("ping -c 3 example.com"), None);
+    }
+
+    #[test]
+    fn sessions_need_a_tty() {
+        assert_eq!(kind("ssh prod"), Some(InteractionKind::Session));
+        assert_eq!(
+            kind("ssh -i key.pem -t prod ls"),
+            Some(InteractionKind::Session)
+        );
+        assert_eq!(
+            kind("docker exec -it web sh"),
+            Some(InteractionKind::Session)
+        );
+        assert_eq!(
+            kind("kubectl exec -ti api -- bash"),
+            Some(InteractionKind::Session)
+        );
+        assert_eq!(kind("ssh -p 2222 prod uptime"), None);
+        assert_eq!(kind("docker exec web ls /app"), None);
+        assert_eq!(kind("docker run --rm alpine echo hi"), None);
+        assert_eq!(
+            kind("docker exec -e A=1 -it web sh"),
+            Some(InteractionKind::Session)
+        );
+        assert_eq!(kind("docker exec web ls -t"), None);
+        assert_eq!(kind("kubectl exec api -- ls -t"), None);
+    }
+
+    #[test]
+    fn repls_unless_given_a_script_or_input() {
+        assert_eq!(kind("python"), Some(InteractionKind::Repl));
+        assert_eq!(kind("python3 -i script.py"), Some(InteractionKind::Repl));
+        assert_eq!(kind("node"), Some(InteractionKind::Repl));
+        assert_eq!(kind("psql -h db mydb"), Some(InteractionKind::Repl));
+        assert_eq!(kind("python script.py"), None);
+        assert_eq!(kind("python -c 'print(1)'"), None);
+        assert_eq!(kind("python -m pytest"), None);
+        assert_eq!(kind("node -e 'console.log(1)'"), None);
+        assert_eq!(kind("echo 'print(1)' | python"), None);
+        assert_eq!(kind("psql mydb < schema.sql"), None);
+        assert_eq!(kind("psql -c 'select 1'"), None);
+    }
+
+    #[test]
+    fn dev_servers_and_watch_mode() {
+        assert_eq!(kind("npm run dev"), Some(InteractionKind::Server));
+        assert_eq!(kind("npm start"), Some(InteractionKind::Server));
+        assert_eq!(kind("yarn dev"), Some(InteractionKind::Server));
+        assert_eq!(kind("npx vite"), Some(InteractionKind::Server));
+        assert_eq!(kind("tsc --watch"), Some(InteractionKind::Server));
+        assert_eq!(
+            kind("python -m http.server 8000"),
+            Some(InteractionKind::Server)
+        );
+        assert_eq!(kind("docker compose up"), Some(InteractionKind::Server));
+        assert_eq!(
+            kind("kubectl port-forward svc/api 8080:80"),
+            Some(InteractionKind::Server)
+        );
+        assert_eq!(kind("npm run build"), None);
+        assert_eq!(kind("npm test"), None);
+        assert_eq!(kind("npx vite build"), None);
+        assert_eq!(kind("docker compose up -d"), None);
+    }
+
+    #[test]
+    fn checks_every_segment_and_wrapper() {
+        assert_eq!(kind("cd app && npm run dev"), Some(InteractionKind::Server));
+        assert_eq!(
+            kind("make && tail -f out.log"),
+            Some(InteractionKind::Follow)
+        );
+        assert_eq!(kind("bash -c 'less x'"), Some(InteractionKind::Pager));
+        assert_eq!(kind("env DEBUG=1 npm start"), Some(InteractionKind::Server));
+        assert_eq!(
+            kind("(tail -f a.log) | grep ERROR"),
+            Some(InteractionKind::Follow)
+        );
+        assert_eq!(kind("timeout 10 tail -f app.log"), None);
+        assert_eq!(kind("git status && cargo test"), None);
+    }
+
+    #[test]
+    fn unparseable_commands_are_left_to_the_classifier() {
+        assert_eq!(kind("less 'unterminated"), None);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod circuit_breaker;
 pub mod classifier;
+pub mod interactive;
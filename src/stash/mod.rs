@@ -0,0 +1,394 @@
+//! Raw-output stash — retrievable full output behind the truncation footer.
+//!
+//! Whenever `terse run` returns output that is smaller than what the wrapped
+//! command produced, the full raw stdout/stderr is saved under
+//! `~/.terse/raw/<id>.json` and the truncation footer names the id. The agent
+//! can then fetch exactly the slice it needs with
+//! `terse raw <id> [--lines a-b] [--grep pat] [--stderr]` instead of
+//! re-running a slow or non-idempotent command.
+//!
+//! The stash is bounded by total size and entry count. Eviction is LRU:
+//! reads refresh an entry's modification time, and the oldest entries are
+//! removed first. All I/O on the write path is best-effort — a failure to
+//! stash never blocks command output.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::config::schema::StashConfig;
+use crate::utils::process::terse_home_dir;
+
+// ---------------------------------------------------------------------------
+// Stash entry
+// ---------------------------------------------------------------------------
+
+/// A single stashed command output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashEntry {
+    pub id: String,
+    pub timestamp: String,
+    pub command: String,
+    pub stdout: String,
+    pub stderr: String,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+}
+
+/// Directory holding stashed outputs: `~/.terse/raw/`.
+pub fn stash_dir() -> Option<PathBuf> {
+    terse_home_dir().map(|h| h.join("raw"))
+}
+
+// ---------------------------------------------------------------------------
+// Writing
+// ---------------------------------------------------------------------------
+
+/// Stash the raw output of a command and return its id.
+///
+/// Returns `None` when the stash is disabled or the entry could not be
+/// written. Runs LRU eviction after every successful write.
+pub fn save(
+    command: &str,
+    stdout: &str,
+    stderr: &str,
+    exit_code: Option<i32>,
+    cfg: &StashConfig,
+) -> Option<String> {
+    if !cfg.enabled {
+        return None;
+    }
+    let dir = stash_dir()?;
+    let id = save_in(&dir, command, stdout, stderr, exit_code).ok()?;
+    evict(&dir, cfg.max_total_bytes, cfg.max_entries);
+    Some(id)
+}
+
+/// Write a stash entry into `dir` and return its id.
+fn save_in(
+    dir: &Path,
+    command: &str,
+    stdout: &str,
+    stderr: &str,
+    exit_code: Option<i32>,
+) -> Result<String> {
+    create_private_dir(dir).context("failed creating stash directory")?;
+
+    // Re-derive on the off chance of a collision with an existing entry.
+    let mut salt = 0u32;
+    let id = loop {
+        let candidate = generate_id(command, salt);
+        if !entry_path(dir, &candidate).exists() {
+            break candidate;
+        }
+        salt += 1;
+    };
+
+    let entry = StashEntry {
+        id: id.clone(),
+        timestamp: Utc::now().to_rfc3339(),
+        command: command.to_string(),
+        stdout: stdout.to_string(),
+        stderr: stderr.to_string(),
+        exit_code,
+    };
+    let json = serde_json::to_string(&entry).context("failed serializing stash entry")?;
+    write_private(&entry_path(dir, &id), json.as_bytes()).context("failed writing stash entry")?;
+    Ok(id)
+}
+
+/// Create `dir` accessible only to its owner: stashed output holds whatever
+/// the command printed.
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        fs::create_dir_all(dir)
+    }
+}
+
+/// Write a new file at `path` readable and writable only by its owner.
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+/// Generate a short, URL- and shell-safe id (8 hex chars).
+fn generate_id(command: &str, salt: u32) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let mut hasher = DefaultHasher::new();
+    command.hash(&mut hasher);
+    nanos.hash(&mut hasher);
+    std::process::id().hash(&mut hasher);
+    salt.hash(&mut hasher);
+    format!("{:08x}", hasher.finish() as u32)
+}
+
+fn entry_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{id}.json"))
+}
+
+/// Evict least-recently-used entries until the stash fits both bounds.
+fn evict(dir: &Path, max_total_bytes: u64, max_entries: usize) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut entries: Vec<(PathBuf, u64, SystemTime)> = read_dir
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            let modified = meta.modified().unwrap_or(UNIX_EPOCH);
+            Some((e.path(), meta.len(), modified))
+        })
+        .collect();
+
+    // Oldest first
+    entries.sort_by_key(|(_, _, modified)| *modified);
+
+    let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+    let mut count = entries.len();
+    for (path, len, _) in &entries {
+        if total <= max_total_bytes && count <= max_entries {
+            break;
+        }
+        if fs::remove_file(path).is_ok() {
+            total = total.saturating_sub(*len);
+            count -= 1;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Reading
+// ---------------------------------------------------------------------------
+
+/// Load a stashed entry by id, refreshing its LRU position.
+pub fn load(id: &str) -> Result<StashEntry> {
+    let dir = stash_dir().context("could not determine home directory")?;
+    load_from(&dir, id)
+}
+
+fn load_from(dir: &Path, id: &str) -> Result<StashEntry> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        bail!("invalid stash id: {id}");
+    }
+    let path = entry_path(dir, id);
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("no stashed output with id {id} (it may have been evicted)"))?;
+    let entry: StashEntry =
+        serde_json::from_str(&content).with_context(|| format!("corrupt stash entry {id}"))?;
+
+    // Touch for LRU — best-effort.
+    if let Ok(file) = fs::File::options().append(true).open(&path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+
+    Ok(entry)
+}
+
+// ---------------------------------------------------------------------------
+// Slicing
+// ---------------------------------------------------------------------------
+
+/// Parse a 1-based inclusive line range: `a-b`, `a-` (to end), or `a`.
+pub fn parse_line_range(spec: &str) -> Result<(usize, usize)> {
+    let spec = spec.trim();
+    let (start, end) = match spec.split_once('-') {
+        Some((a, b)) => {
+            let start = a.trim().parse::<usize>().context("invalid range start")?;
+            let end = if b.trim().is_empty() {
+                usize::MAX
+            } else {
+                b.trim().parse::<usize>().context("invalid range end")?
+            };
+            (start, end)
+        }
+        None => {
+            let line = spec.parse::<usize>().context("invalid line number")?;
+            (line, line)
+        }
+    };
+    if start == 0 || end < start {
+        bail!("invalid line range: {spec} (expected 1-based a-b with a <= b)");
+    }
+    Ok((start, end))
+}
+
+/// Select lines from stashed text.
+///
+/// With no filters the text is returned unchanged. `--lines` restricts to a
+/// range; `--grep` keeps matching lines and prefixes them with their line
+/// number (`N:`) so they can be followed up with a `--lines` request.
+pub fn slice(text: &str, range: Option<(usize, usize)>, pattern: Option<&Regex>) -> String {
+    if range.is_none() && pattern.is_none() {
+        return text.to_string();
+    }
+
+    let (start, end) = range.unwrap_or((1, usize::MAX));
+    let mut out: Vec<String> = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let n = idx + 1;
+        if n < start {
+            continue;
+        }
+        if n > end {
+            break;
+        }
+        match pattern {
+            Some(re) if re.is_match(line) => out.push(format!("{n}:{line}")),
+            Some(_) => {}
+            None => out.push(line.to_string()),
+        }
+    }
+    out.join("\n")
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("terse-stash-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = scratch_dir("roundtrip");
+        let id = save_in(&dir, "cargo test", "out\nmore", "err", Some(101)).unwrap();
+        assert_eq!(id.len(), 8);
+
+        let entry = load_from(&dir, &id).unwrap();
+        assert_eq!(entry.command, "cargo test");
+        assert_eq!(entry.stdout, "out\nmore");
+        assert_eq!(entry.stderr, "err");
+        assert_eq!(entry.exit_code, Some(101));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn entries_are_private_to_the_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch_dir("perms");
+        let id = save_in(&dir, "env", "TOKEN=abc", "", Some(0)).unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir), 0o700);
+        assert_eq!(mode(&entry_path(&dir, &id)), 0o600);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn load_rejects_path_like_ids() {
+        let dir = scratch_dir("badid");
+        assert!(load_from(&dir, "../config").is_err());
+        assert!(load_from(&dir, "").is_err());
+    }
+
+    #[test]
+    fn load_missing_id_mentions_eviction() {
+        let dir = scratch_dir("missing");
+        let err = load_from(&dir, "deadbeef").unwrap_err();
+        assert!(format!("{err:#}").contains("evicted"));
+    }
+
+    #[test]
+    fn evict_enforces_entry_limit_oldest_first() {
+        let dir = scratch_dir("evict");
+        let first = save_in(&dir, "a", "1", "", None).unwrap();
+        let old = SystemTime::now() - std::time::Duration::from_secs(60);
+        fs::File::options()
+            .append(true)
+            .open(entry_path(&dir, &first))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let second = save_in(&dir, "b", "2", "", None).unwrap();
+
+        evict(&dir, u64::MAX, 1);
+        assert!(!entry_path(&dir, &first).exists());
+        assert!(entry_path(&dir, &second).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn evict_enforces_size_limit() {
+        let dir = scratch_dir("evict-size");
+        let id = save_in(&dir, "big", &"x".repeat(4096), "", None).unwrap();
+        evict(&dir, 1024, 100);
+        assert!(!entry_path(&dir, &id).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parse_line_range_forms() {
+        assert_eq!(parse_line_range("10-20").unwrap(), (10, 20));
+        assert_eq!(parse_line_range("5-").unwrap(), (5, usize::MAX));
+        assert_eq!(parse_line_range("7").unwrap(), (7, 7));
+        assert!(parse_line_range("0-3").is_err());
+        assert!(parse_line_range("9-3").is_err());
+        assert!(parse_line_range("abc").is_err());
+    }
+
+    #[test]
+    fn slice_without_filters_is_identity() {
+        assert_eq!(slice("a\nb\nc", None, None), "a\nb\nc");
+    }
+
+    #[test]
+    fn slice_by_range() {
+        assert_eq!(slice("a\nb\nc\nd", Some((2, 3)), None), "b\nc");
+    }
+
+    #[test]
+    fn slice_by_grep_numbers_lines() {
+        let re = Regex::new("err").unwrap();
+        assert_eq!(
+            slice("ok\nerror one\nok\nerror two", None, Some(&re)),
+            "2:error one\n4:error two"
+        );
+    }
+
+    #[test]
+    fn slice_grep_within_range() {
+        let re = Regex::new("err").unwrap();
+        assert_eq!(
+            slice("error a\nok\nerror b", Some((2, 3)), Some(&re)),
+            "3:error b"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Base64 (RFC 4648, standard alphabet, padded) for shell-safe transport.
+//!
+//! The hook hands the original command to `terse run --encoded <payload>`.
+//! The encoded alphabet (`A-Z a-z 0-9 + / =`) has no meaning to sh, bash,
+//! zsh, cmd, or PowerShell, so the outer shell cannot expand, unquote, or
+//! split the payload — `terse run` receives the original bytes exactly.
+
+use anyhow::{Result, bail};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes as padded standard base64.
+pub fn encode_base64(input: &[u8]) -> String {
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 {
+            ALPHABET[(n >> 6) as usize & 63] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            ALPHABET[n as usize & 63] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
+/// Decode padded or unpadded standard base64.
+///
+/// Surrounding whitespace is ignored; any other character outside the
+/// alphabet is an error.
+pub fn decode_base64(input: &str) -> Result<Vec<u8>> {
+    let trimmed = input.trim().trim_end_matches('=');
+    if trimmed.len() % 4 == 1 {
+        bail!("invalid base64 length");
+    }
+
+    let mut out = Vec::with_capacity(trimmed.len() * 3 / 4);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for c in trimmed.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => bail!("invalid base64 character {:?}", c as char),
+        };
+        acc = (acc << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Ok(out)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(encode_base64(plain.as_bytes()), encoded);
+            assert_eq!(decode_base64(encoded).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn round_trips_all_byte_values() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode_base64(&encode_base64(&bytes)).unwrap(), bytes);
+    }
+
+    #[test]
+    fn decodes_unpadded_input() {
+        assert_eq!(decode_base64("Zm9vYg").unwrap(), b"foob");
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(decode_base64("Zm9v!").is_err());
+        assert!(decode_base64("Zm9vY").is_err());
+    }
+}
@@ -1,2 +1,3 @@
+pub mod encoding;
 pub mod process;
 pub mod token_counter;
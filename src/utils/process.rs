@@ -1,16 +1,70 @@
+use std::io::Read;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 
+/// Exit code reported when the execution deadline kills a command — the
+/// same convention as GNU `timeout(1)`.
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Which child stream a piece of output came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Whole lines of output from one stream, in arrival order.
+///
+/// Only the final chunk of each stream may lack a trailing newline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputChunk {
+    pub stream: OutputStream,
+    pub text: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct ProcessOutput {
     pub stdout: String,
     pub stderr: String,
-    #[allow(dead_code)]
+    /// Stdout and stderr as one time-ordered stream of tagged chunks.
+    /// Concatenating the chunks of one stream yields `stdout` / `stderr`.
+    pub chunks: Vec<OutputChunk>,
+    /// Exit code of the child. Signal terminations on Unix are mapped to the
+    /// shell convention of `128 + signal`; `None` only when neither is known.
     pub exit_code: Option<i32>,
     #[allow(dead_code)]
     pub success: bool,
+    /// The execution deadline expired and the process tree was killed;
+    /// `stdout`/`stderr` hold only what was captured before that.
+    pub timed_out: bool,
+}
+
+impl ProcessOutput {
+    /// Rewrite the text of every chunk, keeping `stdout` and `stderr` in
+    /// sync with the chunks.
+    pub fn map_chunks(&mut self, mut f: impl FnMut(&str) -> String) {
+        for chunk in &mut self.chunks {
+            chunk.text = f(&chunk.text);
+        }
+        (self.stdout, self.stderr) = split_streams(&self.chunks);
+    }
+
+    /// Stdout and stderr interleaved line by line in the order the child
+    /// wrote them.
+    pub fn interleaved(&self) -> String {
+        let mut out = String::with_capacity(self.stdout.len() + self.stderr.len() + 1);
+        for chunk in &self.chunks {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str(&chunk.text);
+        }
+        out
+    }
 }
 
 /// Execute a shell command using the platform's default shell.
@@ -18,49 +72,304 @@ pub struct ProcessOutput {
 /// On Windows, uses `pwsh -Command`. Falls back to `cmd /C` if `pwsh` is
 /// not available. On Unix, uses `sh -c`.
 pub fn run_shell_command(command: &str) -> Result<ProcessOutput> {
-    let output = platform_shell_exec(command)?;
+    run_shell_command_with_timeout(command, None)
+}
 
+/// Execute a shell command with an optional execution deadline.
+///
+/// Output is captured incrementally by reader threads while a watchdog
+/// polls the child. Both readers append complete lines to one shared log,
+/// so [`ProcessOutput::chunks`] preserves the order in which lines arrived.
+/// If the deadline passes, the child's whole process tree is killed and the
+/// output captured so far is returned with [`ProcessOutput::timed_out`]
+/// set, exit code [`TIMEOUT_EXIT_CODE`], and a marker line appended to
+/// stdout.
+pub fn run_shell_command_with_timeout(
+    command: &str,
+    timeout: Option<Duration>,
+) -> Result<ProcessOutput> {
+    let start = Instant::now();
+    let mut child = spawn_platform_shell(command)?;
+
+    let capture = Arc::new(Mutex::new(Capture::default()));
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|pipe| spawn_reader(pipe, OutputStream::Stdout, Arc::clone(&capture))),
+        child
+            .stderr
+            .take()
+            .map(|pipe| spawn_reader(pipe, OutputStream::Stderr, Arc::clone(&capture))),
+    ];
+
+    let deadline_passed = || timeout.is_some_and(|t| start.elapsed() >= t);
+
+    // Wait for the child to exit, polling with a short backoff.
+    let mut poll = Duration::from_millis(1);
+    let mut status = None;
+    let mut timed_out = false;
+    loop {
+        if let Some(s) = child.try_wait().context("failed waiting for command")? {
+            status = Some(s);
+            break;
+        }
+        if deadline_passed() {
+            timed_out = true;
+            break;
+        }
+        std::thread::sleep(poll);
+        poll = (poll * 2).min(Duration::from_millis(20));
+    }
+
+    // Wait for the pipes to drain. A background grandchild can hold them
+    // open after the shell exits, so the deadline applies here as well.
+    while !timed_out && readers.iter().flatten().any(|r| !r.is_finished()) {
+        if deadline_passed() {
+            timed_out = true;
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    if timed_out {
+        kill_process_tree(&mut child);
+        // Give the readers a moment to collect what the dying tree flushed.
+        let grace = Instant::now();
+        while readers.iter().flatten().any(|r| !r.is_finished())
+            && grace.elapsed() < Duration::from_millis(200)
+        {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        if status.is_none() {
+            let _ = child.wait();
+        }
+    }
+
+    for reader in readers.into_iter().flatten() {
+        if reader.is_finished() {
+            let _ = reader.join();
+        }
+    }
+
+    let mut chunks = lock_capture(&capture).finish();
+
+    if timed_out {
+        let secs = timeout.unwrap_or_default().as_secs_f64();
+        if let Some(last) = chunks
+            .iter_mut()
+            .rfind(|c| c.stream == OutputStream::Stdout)
+            && !last.text.ends_with('\n')
+        {
+            last.text.push('\n');
+        }
+        chunks.push(OutputChunk {
+            stream: OutputStream::Stdout,
+            text: format!(
+                "[terse: command timed out after {secs:.0}s; process tree killed, output above is partial]\n"
+            ),
+        });
+        let (stdout, stderr) = split_streams(&chunks);
+        return Ok(ProcessOutput {
+            stdout,
+            stderr,
+            chunks,
+            exit_code: Some(TIMEOUT_EXIT_CODE),
+            success: false,
+            timed_out: true,
+        });
+    }
+
+    let status = match status {
+        Some(s) => s,
+        None => child.wait().context("failed waiting for command")?,
+    };
+    let (stdout, stderr) = split_streams(&chunks);
     Ok(ProcessOutput {
-        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        exit_code: output.status.code(),
-        success: output.status.success(),
+        stdout,
+        stderr,
+        chunks,
+        exit_code: exit_status_code(&status),
+        success: status.success(),
+        timed_out: false,
     })
 }
 
-/// Platform-specific shell execution.
+/// Resolve an [`ExitStatus`](std::process::ExitStatus) to a numeric code.
+///
+/// Returns the exit code when the process exited normally. On Unix, a
+/// process killed by a signal has no exit code, so this returns `128 + signal`
+/// the same way a POSIX shell reports it in `$?`.
+pub fn exit_status_code(status: &std::process::ExitStatus) -> Option<i32> {
+    if let Some(code) = status.code() {
+        return Some(code);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal().map(|sig| 128 + sig)
+    }
+
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Output shared by both reader threads.
+///
+/// Each stream's bytes collect in `pending` until a newline completes a
+/// line; complete lines move to the ordered log. Splitting only at newlines
+/// keeps every chunk valid UTF-8 on its own and stops a partial line on one
+/// stream from being interrupted by the other.
+#[derive(Default)]
+struct Capture {
+    log: Vec<(OutputStream, Vec<u8>)>,
+    pending: [Vec<u8>; 2],
+}
+
+impl Capture {
+    fn push(&mut self, stream: OutputStream, bytes: &[u8]) {
+        let pending = &mut self.pending[stream as usize];
+        pending.extend_from_slice(bytes);
+        if let Some(end) = pending.iter().rposition(|&b| b == b'\n') {
+            let lines: Vec<u8> = pending.drain(..=end).collect();
+            self.append(stream, lines);
+        }
+    }
+
+    fn append(&mut self, stream: OutputStream, bytes: Vec<u8>) {
+        match self.log.last_mut() {
+            Some((last, text)) if *last == stream => text.extend_from_slice(&bytes),
+            _ => self.log.push((stream, bytes)),
+        }
+    }
+
+    /// Flush unterminated trailing lines and decode the log.
+    fn finish(&mut self) -> Vec<OutputChunk> {
+        for stream in [OutputStream::Stdout, OutputStream::Stderr] {
+            let rest = std::mem::take(&mut self.pending[stream as usize]);
+            if !rest.is_empty() {
+                self.log.push((stream, rest));
+            }
+        }
+        std::mem::take(&mut self.log)
+            .into_iter()
+            .map(|(stream, bytes)| OutputChunk {
+                stream,
+                text: String::from_utf8_lossy(&bytes).into_owned(),
+            })
+            .collect()
+    }
+}
+
+/// Concatenate the chunks of each stream.
+fn split_streams(chunks: &[OutputChunk]) -> (String, String) {
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    for chunk in chunks {
+        match chunk.stream {
+            OutputStream::Stdout => stdout.push_str(&chunk.text),
+            OutputStream::Stderr => stderr.push_str(&chunk.text),
+        }
+    }
+    (stdout, stderr)
+}
+
+/// Copy everything a pipe produces into the shared capture until EOF.
+fn spawn_reader<R: Read + Send + 'static>(
+    mut pipe: R,
+    stream: OutputStream,
+    capture: Arc<Mutex<Capture>>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut chunk = [0u8; 8192];
+        loop {
+            match pipe.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => lock_capture(&capture).push(stream, &chunk[..n]),
+            }
+        }
+    })
+}
+
+fn lock_capture(capture: &Mutex<Capture>) -> std::sync::MutexGuard<'_, Capture> {
+    capture
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Platform-specific shell spawn with piped output.
 ///
 /// Windows: tries `pwsh` first, falls back to `cmd /C`.
-/// Unix: uses `sh -c`.
-fn platform_shell_exec(command: &str) -> Result<std::process::Output> {
+/// Unix: uses `sh -c` in a new process group so the watchdog can kill the
+/// whole tree. Stdin is closed, as with [`Command::output`].
+fn spawn_platform_shell(command: &str) -> Result<Child> {
     #[cfg(target_os = "windows")]
     {
         // Prefer pwsh (PowerShell 7+), fall back to cmd.exe
-        match Command::new("pwsh")
-            .arg("-NoProfile")
-            .arg("-Command")
-            .arg(command)
-            .output()
-        {
-            Ok(output) => Ok(output),
-            Err(_) => Command::new("cmd")
-                .arg("/C")
-                .arg(command)
-                .output()
-                .with_context(|| format!("failed executing command: {command}")),
+        let mut pwsh = Command::new("pwsh");
+        pwsh.arg("-NoProfile").arg("-Command").arg(command);
+        match piped(&mut pwsh).spawn() {
+            Ok(child) => Ok(child),
+            Err(_) => {
+                let mut cmd = Command::new("cmd");
+                cmd.arg("/C").arg(command);
+                piped(&mut cmd)
+                    .spawn()
+                    .with_context(|| format!("failed executing command: {command}"))
+            }
         }
     }
 
     #[cfg(not(target_os = "windows"))]
     {
-        Command::new("sh")
-            .arg("-c")
-            .arg(command)
-            .output()
+        use std::os::unix::process::CommandExt;
+
+        let mut sh = Command::new("sh");
+        sh.arg("-c").arg(command).process_group(0);
+        piped(&mut sh)
+            .spawn()
             .with_context(|| format!("failed executing command: {command}"))
     }
 }
 
+fn piped(cmd: &mut Command) -> &mut Command {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+}
+
+/// Kill a child and all of its descendants. Best-effort.
+///
+/// Unix: the child leads its own process group, so signalling the negative
+/// pid reaches every descendant that did not detach. Windows: `taskkill /T`
+/// walks the process tree.
+fn kill_process_tree(child: &mut Child) {
+    let pid = child.id().to_string();
+
+    #[cfg(target_os = "windows")]
+    let killed = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+
+    #[cfg(not(target_os = "windows"))]
+    let killed = Command::new("kill")
+        .args(["-KILL", "--", &format!("-{pid}")])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+
+    if !killed {
+        let _ = child.kill();
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Path normalization
 // ---------------------------------------------------------------------------
@@ -250,9 +559,100 @@ mod tests {
     fn test_run_shell_command() {
         let result = run_shell_command("echo hello").expect("echo should work");
         assert!(result.success);
+        assert_eq!(result.exit_code, Some(0));
         assert!(result.stdout.contains("hello"));
     }
 
+    #[test]
+    fn test_run_shell_command_nonzero_exit() {
+        let result = run_shell_command("exit 3").expect("exit should work");
+        assert!(!result.success);
+        assert_eq!(result.exit_code, Some(3));
+    }
+
+    #[test]
+    fn test_run_shell_command_without_deadline_completes() {
+        let result = run_shell_command_with_timeout("echo done", Some(Duration::from_secs(30)))
+            .expect("echo should work");
+        assert!(!result.timed_out);
+        assert!(result.stdout.contains("done"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_shell_command_timeout_returns_partial_output() {
+        let start = Instant::now();
+        let result = run_shell_command_with_timeout(
+            "echo partial; sleep 30; echo never",
+            Some(Duration::from_millis(300)),
+        )
+        .expect("command should spawn");
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(result.timed_out);
+        assert_eq!(result.exit_code, Some(TIMEOUT_EXIT_CODE));
+        assert!(result.stdout.contains("partial"));
+        assert!(!result.stdout.contains("never"));
+        assert!(result.stdout.contains("[terse: command timed out"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_shell_command_timeout_kills_background_children() {
+        // The backgrounded sleep inherits the pipes; without killing the
+        // process group the readers would block until it exits.
+        let start = Instant::now();
+        let result = run_shell_command_with_timeout(
+            "sleep 30 & echo started",
+            Some(Duration::from_millis(300)),
+        )
+        .expect("command should spawn");
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(result.timed_out);
+        assert!(result.stdout.contains("started"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_shell_command_preserves_stream_order() {
+        let result = run_shell_command(
+            "echo out1; sleep 0.05; echo err1 >&2; sleep 0.05; printf 'out2'; sleep 0.05; echo err2 >&2",
+        )
+        .expect("command should run");
+        assert_eq!(result.stdout, "out1\nout2");
+        assert_eq!(result.stderr, "err1\nerr2\n");
+        assert_eq!(result.interleaved(), "out1\nerr1\nerr2\nout2");
+        let streams: Vec<OutputStream> = result.chunks.iter().map(|c| c.stream).collect();
+        assert_eq!(
+            streams,
+            [
+                OutputStream::Stdout,
+                OutputStream::Stderr,
+                OutputStream::Stdout
+            ]
+        );
+    }
+
+    #[test]
+    fn capture_splits_only_at_line_ends() {
+        let mut capture = Capture::default();
+        capture.push(OutputStream::Stdout, "partial ".as_bytes());
+        capture.push(OutputStream::Stderr, "warn\n".as_bytes());
+        capture.push(OutputStream::Stdout, "line\nnext".as_bytes());
+        capture.push(OutputStream::Stdout, "é".as_bytes()[..1].as_ref());
+        capture.push(OutputStream::Stdout, "é\n".as_bytes()[1..].as_ref());
+        let chunks = capture.finish();
+        let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["warn\n", "partial line\nnexté\n"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_shell_command_signal_exit() {
+        let result = run_shell_command("kill -TERM $$").expect("kill should work");
+        assert!(!result.success);
+        assert_eq!(result.exit_code, Some(128 + 15));
+    }
+
     #[test]
     fn test_is_command_available() {
         // 'echo' should be available on all platforms (built into shell)
@@ -26,6 +26,7 @@ struct StatsResponse {
     total_original_tokens: usize,
     total_optimized_tokens: usize,
     total_savings_pct: f64,
+    total_redactions: usize,
     path_distribution: PathDistributionResponse,
     command_stats: Vec<CommandStatResponse>,
 }
@@ -145,6 +146,7 @@ pub fn get_stats(url: &str) -> Result<Response<Cursor<Vec<u8>>>> {
         total_original_tokens: stats.total_original_tokens,
         total_optimized_tokens: stats.total_optimized_tokens,
         total_savings_pct: stats.total_savings_pct,
+        total_redactions: stats.total_redactions,
         path_distribution: PathDistributionResponse {
             fast: stats.path_distribution.fast,
             smart: stats.path_distribution.smart,
@@ -322,6 +324,7 @@ mod tests {
             total_original_tokens: 50000,
             total_optimized_tokens: 15000,
             total_savings_pct: 70.0,
+            total_redactions: 0,
             path_distribution: PathDistributionResponse {
                 fast: 60,
                 smart: 20,
@@ -0,0 +1,105 @@
+/// Hook rewrite transport tests.
+///
+/// The hook rewrites a command to `"<terse>" run --encoded <base64>`. These
+/// tests prove the transport is byte-exact: a corpus of commands full of
+/// shell metacharacters must decode to the original text, and — on Unix —
+/// executing the rewritten command through `sh` must hand the wrapped
+/// command exactly the argv the original command receives.
+use terse::hook::encoded_run_command;
+use terse::matching::is_terse_invocation;
+use terse::run::decode_command;
+
+/// Commands whose arguments the outer shell would mangle if the command
+/// were embedded in a double-quoted string. Each prints its argv one
+/// bracketed argument per line so any difference is visible.
+const NASTY_COMMANDS: &[&str] = &[
+    r#"printf '<%s>\n' "$HOME" '$HOME' $HOME"#,
+    r#"printf '<%s>\n' "$(echo sub)" `echo tick` '$(echo literal)'"#,
+    r#"printf '<%s>\n' "say \"hi\"" 'it'\''s' "back\\slash" 'back\slash'"#,
+    r#"printf '<%s>\n' 'bang!' "bang!" !! '!$'"#,
+    r#"printf '<%s>\n' a\ b "tab	here" '' "" 'trailing '"#,
+    "printf '<%s>\\n' 'multi\nline' \"also\nmulti\"",
+    r#"X=1; printf '<%s>\n' "$X" '$X' "${X}y" "${UNSET:-default}""#,
+    r#"printf '<%s>\n' a | tr a b; printf '<%s>\n' '%%' '|' ';' '&&'"#,
+    r#"printf '<%s>\n' héllo 日本語 'emoji 🚀' "\t\n" '\t\n'"#,
+    r#"printf '<%s>\n' /dev/nul* '/dev/nul*' "*" \*"#,
+    r#"printf '<%s>\n' 'terse run "nested"' "C:\Windows\System32" '#not-a-comment' #comment"#,
+];
+
+#[test]
+fn encoded_payload_round_trips_exactly() {
+    for command in NASTY_COMMANDS {
+        let rewritten = encoded_run_command("/usr/local/bin/terse", command);
+        let payload = rewritten
+            .rsplit(' ')
+            .next()
+            .expect("rewritten command has a payload");
+        assert_eq!(&decode_command(payload).unwrap(), command);
+    }
+}
+
+#[test]
+fn encoded_payload_contains_no_shell_metacharacters() {
+    for command in NASTY_COMMANDS {
+        let rewritten = encoded_run_command("/usr/local/bin/terse", command);
+        let payload = rewritten
+            .strip_prefix("\"/usr/local/bin/terse\" run --encoded ")
+            .expect("rewritten command has the expected shape");
+        assert!(
+            payload
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=')),
+            "unsafe payload for {command:?}: {payload}"
+        );
+    }
+}
+
+#[test]
+fn rewritten_commands_are_caught_by_loop_guard() {
+    for command in NASTY_COMMANDS {
+        let rewritten = encoded_run_command("/usr/local/bin/terse", command);
+        assert!(is_terse_invocation(&rewritten), "{rewritten}");
+    }
+}
+
+#[test]
+fn decode_rejects_invalid_payloads() {
+    assert!(decode_command("not base64!").is_err());
+    // Valid base64, invalid UTF-8.
+    assert!(decode_command("/w==").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn rewritten_command_receives_identical_argv() {
+    use std::process::Command;
+
+    // Isolated home: keeps the test off the real ~/.terse and gives both
+    // runs the same $HOME.
+    let home = std::env::temp_dir().join(format!("terse-transport-{}", std::process::id()));
+    std::fs::create_dir_all(&home).unwrap();
+
+    let sh = |script: &str| {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .env("HOME", &home)
+            .output()
+            .expect("failed to spawn sh");
+        assert!(output.status.success(), "{script} failed: {output:?}");
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    for command in NASTY_COMMANDS {
+        let rewritten = encoded_run_command(env!("CARGO_BIN_EXE_terse"), command);
+        let direct = sh(command);
+        let through_terse = sh(&rewritten);
+        assert_eq!(
+            through_terse.trim_end(),
+            direct.trim_end(),
+            "argv differs for {command:?}"
+        );
+    }
+
+    let _ = std::fs::remove_dir_all(&home);
+}
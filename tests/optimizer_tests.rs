@@ -1,7 +1,7 @@
 use terse::matching::{extract_core_command, is_terse_invocation};
 use terse::optimizers::{
     BuildOptimizer, CommandContext, DockerOptimizer, FileOptimizer, GenericOptimizer, GitOptimizer,
-    Optimizer,
+    KubectlOptimizer, Optimizer, SearchOptimizer,
 };
 
 // ---------------------------------------------------------------------------
@@ -294,6 +294,20 @@ fn file_optimizer_find_compacts_many_results() {
     assert!(result.output.contains("+40 more (80 total)"));
 }
 
+#[test]
+fn file_optimizer_find_piped_to_head_is_not_capped() {
+    if cfg!(target_os = "windows") {
+        return;
+    }
+    let opt = FileOptimizer::new();
+    let ctx = CommandContext::new("find . -name '*.rs' | head -n 60");
+    let lines: Vec<String> = (0..60).map(|i| format!("./src/file{i}.rs")).collect();
+    let input = lines.join("\n");
+    let result = opt.optimize_output(&ctx, &input).unwrap();
+    assert!(result.output.contains("./src/file59.rs"));
+    assert!(!result.output.contains("more"));
+}
+
 #[test]
 fn file_optimizer_cat_truncates_long_file() {
     let opt = FileOptimizer::new();
@@ -306,6 +320,46 @@ fn file_optimizer_cat_truncates_long_file() {
     assert!(result.output.contains("line 199")); // tail
 }
 
+fn long_rust_source() -> String {
+    let body: String = (0..150).map(|i| format!("    let x{i} = {i};\n")).collect();
+    format!("use std::io;\n\npub fn run() -> io::Result<()> {{\n{body}    Ok(())\n}}\n")
+}
+
+#[test]
+fn file_optimizer_cat_outlines_source_files() {
+    let opt = FileOptimizer::new();
+    let ctx = CommandContext::new("cat src/run.rs");
+    let result = opt.optimize_output(&ctx, &long_rust_source()).unwrap();
+    assert!(
+        result
+            .output
+            .contains("  3  pub fn run() -> io::Result<()> {")
+    );
+    assert!(result.output.contains("... 151 lines"));
+    assert!(result.output.contains("155  }"));
+}
+
+#[test]
+fn file_optimizer_cat_keeps_explicit_ranges_whole() {
+    let opt = FileOptimizer::new();
+    let source = long_rust_source();
+    for command in [
+        "head -n 200 src/run.rs",
+        "tail -n 200 src/run.rs",
+        "cat src/run.rs | sed -n 1,200p",
+    ] {
+        let result = opt
+            .optimize_output(&CommandContext::new(command), &source)
+            .unwrap();
+        assert_eq!(result.output, source.trim(), "{command}");
+    }
+    // Not a range: neither outlined (flags) nor kept whole.
+    let result = opt
+        .optimize_output(&CommandContext::new("cat -n src/run.rs"), &source)
+        .unwrap();
+    assert!(result.output.contains("lines omitted"));
+}
+
 // ===========================================================================
 // BuildOptimizer — integration tests
 // ===========================================================================
@@ -372,9 +426,7 @@ test test_ten ... ok
 test result: ok. 10 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out";
 
     let result = opt.optimize_output(&ctx, input).unwrap();
-    assert!(result.output.contains("[2 compilation steps]"));
-    assert!(result.output.contains("[10 tests passed]"));
-    assert!(result.output.contains("test result:"));
+    assert_eq!(result.output, "Tests: 10 passed");
     // Individual tests should NOT appear
     assert!(!result.output.contains("test test_one"));
 }
@@ -396,9 +448,89 @@ thread 'bad' panicked at 'assertion failed: false'
 test result: FAILED. 2 passed; 1 failed; 0 ignored";
 
     let result = opt.optimize_output(&ctx, input).unwrap();
-    assert!(result.output.contains("FAILURES:"));
-    assert!(result.output.contains("bad"));
-    assert!(result.output.contains("panicked"));
+    assert!(result.output.starts_with("Tests: 1 failed, 2 passed"));
+    assert!(result.output.contains("FAILED bad"));
+    assert!(result.output.contains("assertion failed: false"));
+}
+
+#[test]
+fn build_optimizer_reports_pytest_failures() {
+    let opt = BuildOptimizer::new();
+    let ctx = CommandContext::new("python3 -m pytest -q");
+    let input = "\
+..F.
+=================================== FAILURES ===================================
+_________________________________ test_divides _________________________________
+
+    def test_divides():
+>       assert divide(6, 3) == 3.5
+E       assert 2.0 == 3.5
+
+tests/test_math.py:14: AssertionError
+=========================== short test summary info ============================
+FAILED tests/test_math.py::test_divides - assert 2.0 == 3.5
+1 failed, 3 passed in 0.05s";
+
+    let result = opt.optimize_output(&ctx, input).unwrap();
+    assert_eq!(
+        result.output,
+        "Tests: 1 failed, 3 passed (0.05s)\n\
+         FAILED tests/test_math.py::test_divides (tests/test_math.py:14)\n\
+         \x20 >       assert divide(6, 3) == 3.5\n\
+         \x20 assert 2.0 == 3.5"
+    );
+}
+
+#[test]
+fn build_optimizer_reports_go_test_failures() {
+    let opt = BuildOptimizer::new();
+    let ctx = CommandContext::new("go test ./...");
+    let input = "\
+--- FAIL: TestDivide (0.00s)
+    math_test.go:21: got 2, want 3
+FAIL
+FAIL\texample.com/math\t0.004s
+ok  \texample.com/util\t0.002s";
+
+    let result = opt.optimize_output(&ctx, input).unwrap();
+    assert!(result.output.starts_with("Tests: 1 failed\n"));
+    assert!(
+        result
+            .output
+            .contains("FAILED TestDivide (math_test.go:21)")
+    );
+    assert!(result.output.ends_with("Packages: 1 ok, 1 failed"));
+}
+
+#[test]
+fn build_optimizer_groups_tsc_errors() {
+    let opt = BuildOptimizer::new();
+    let ctx = CommandContext::new("npx tsc --noEmit");
+    let mut input = String::new();
+    for i in 0..200 {
+        input.push_str(&format!(
+            "src/api/handler{}.ts({},9): error TS2345: Argument of type 'string' is not assignable to parameter of type 'UserId'.\n",
+            i % 20,
+            i + 1
+        ));
+    }
+    input.push_str("src/index.ts(1,1): error TS2307: Cannot find module './missing'.\n");
+    input.push_str("\nFound 201 errors in 21 files.\n");
+
+    let result = opt.optimize_output(&ctx, &input).unwrap();
+    assert!(
+        result
+            .output
+            .starts_with("201 errors in 21 files (2 codes)")
+    );
+    assert!(result.output.contains("TS2345 ×200 in 20 files:"));
+    assert!(result.output.contains("+15 more"));
+    assert!(
+        result
+            .output
+            .contains("TS2307 Cannot find module './missing'. — src/index.ts:1:1")
+    );
+    assert!(result.output.lines().count() < 10);
 }
 
 #[test]
@@ -416,6 +548,88 @@ fn build_optimizer_compacts_build_success() {
     assert!(result.output.contains("Finished"));
 }
 
+#[test]
+fn build_optimizer_reads_long_json_diagnostics_after_preprocessing() {
+    // Real cargo JSON records run 1.7-3.2 KB, past `max_line_chars`.
+    let command = "cargo build --message-format=json";
+    let rendered = format!(
+        "error[E0308]: mismatched types\n --> src/main.rs:4:18\n  |\n4 |     let x: u32 = \"{}\";\n  |            ---   ^ expected `u32`, found `&str`\n",
+        "a".repeat(1500)
+    );
+    let record = serde_json::json!({
+        "reason": "compiler-message",
+        "package_id": "path+file:///repo#demo@0.1.0",
+        "manifest_path": "/repo/Cargo.toml",
+        "target": {"kind": ["bin"], "name": "demo", "src_path": "/repo/src/main.rs"},
+        "message": {
+            "$message_type": "diagnostic",
+            "level": "error",
+            "message": "mismatched types",
+            "code": {"code": "E0308", "explanation": "Expected type did not match the received type.\n".repeat(10)},
+            "spans": [{
+                "file_name": "src/main.rs",
+                "line_start": 4,
+                "column_start": 18,
+                "is_primary": true,
+                "label": "expected `u32`, found `&str`"
+            }],
+            "children": [],
+            "rendered": rendered
+        }
+    });
+    let line = record.to_string();
+    assert!(line.len() > 2000);
+    let input = format!(
+        "{line}\n{}\n",
+        serde_json::json!({"reason": "build-finished", "success": false})
+    );
+
+    let preprocessed = terse::preprocessing::preprocess(&input, command);
+    let result = BuildOptimizer::new()
+        .optimize_output(&CommandContext::new(command), &preprocessed.text)
+        .unwrap();
+    assert!(result.output.contains("E0308"), "{}", result.output);
+    assert!(
+        result.output.contains("src/main.rs:4:18"),
+        "{}",
+        result.output
+    );
+}
+
+// ===========================================================================
+// SearchOptimizer — integration tests
+// ===========================================================================
+
+#[test]
+fn search_optimizer_handles_commands() {
+    let opt = SearchOptimizer::new();
+    assert!(opt.can_handle(&CommandContext::new("grep -rn TODO src")));
+    assert!(opt.can_handle(&CommandContext::new("rg --json foo")));
+    assert!(opt.can_handle(&CommandContext::new("ag -l foo")));
+    assert!(opt.can_handle(&CommandContext::new("git grep -c foo")));
+    assert!(opt.can_handle(&CommandContext::new("cd /repo && rg foo")));
+    assert!(!opt.can_handle(&CommandContext::new("git log --grep foo")));
+    assert!(!opt.can_handle(&CommandContext::new("ls -la")));
+}
+
+#[test]
+fn search_optimizer_groups_by_file() {
+    let opt = SearchOptimizer::new();
+    let ctx = CommandContext::new("git grep -n unwrap");
+    let mut input = String::new();
+    for i in 1..=40 {
+        input.push_str(&format!("src/main.rs:{i}:    let v = x.unwrap();\n"));
+    }
+    input.push_str("src/lib.rs:7:    y.unwrap()\n");
+
+    let result = opt.optimize_output(&ctx, &input).unwrap();
+    assert_eq!(result.optimizer_used, "search");
+    assert!(result.output.starts_with("41 matches in 2 files"));
+    assert!(result.output.contains("src/main.rs (40)"));
+    assert!(result.output.contains("...+35 more matches"));
+    assert!(result.output.contains("src/lib.rs (1)\n  7: y.unwrap()"));
+}
+
 // ===========================================================================
 // DockerOptimizer — integration tests
 // ===========================================================================
@@ -442,6 +656,21 @@ fn docker_optimizer_skips_custom_format() {
     )));
 }
 
+#[test]
+fn docker_optimizer_keeps_explicit_log_tail() {
+    let opt = DockerOptimizer::new();
+    let lines: Vec<String> = (0..200).map(|i| format!("request {i} served")).collect();
+    let input = lines.join("\n");
+
+    let ctx = CommandContext::new("docker logs --tail 200 api");
+    let result = opt.optimize_output(&ctx, &input).unwrap();
+    assert_eq!(result.output, input);
+
+    let ctx = CommandContext::new("docker logs api");
+    let result = opt.optimize_output(&ctx, &input).unwrap();
+    assert!(result.output.contains("TAIL (30 of 200 lines)"));
+}
+
 #[test]
 fn docker_optimizer_rejects_unrelated() {
     let opt = DockerOptimizer::new();
@@ -478,6 +707,123 @@ docker.io/library/nginx:latest";
     assert!(result.output.contains("Digest"));
 }
 
+// ===========================================================================
+// KubectlOptimizer — integration tests
+// ===========================================================================
+
+#[test]
+fn kubectl_optimizer_handles_commands() {
+    let opt = KubectlOptimizer::new();
+    assert!(opt.can_handle(&CommandContext::new("kubectl get pods -A")));
+    assert!(opt.can_handle(&CommandContext::new("kubectl -n prod describe pod api")));
+    assert!(opt.can_handle(&CommandContext::new("kubectl logs deploy/api")));
+    assert!(opt.can_handle(&CommandContext::new("kubectl rollout status deploy/api")));
+    assert!(opt.can_handle(&CommandContext::new("helm list -A")));
+    assert!(opt.can_handle(&CommandContext::new("helm status api")));
+    assert!(opt.can_handle(&CommandContext::new("helm history api")));
+}
+
+#[test]
+fn kubectl_optimizer_rejects_unrelated() {
+    let opt = KubectlOptimizer::new();
+    assert!(!opt.can_handle(&CommandContext::new("kubectl apply -f deploy.yaml")));
+    assert!(!opt.can_handle(&CommandContext::new("kubectl get pod api -o yaml")));
+    assert!(!opt.can_handle(&CommandContext::new("helm upgrade api ./chart")));
+    assert!(!opt.can_handle(&CommandContext::new("docker ps")));
+}
+
+#[test]
+fn kubectl_optimizer_get_pods_keeps_failing() {
+    let opt = KubectlOptimizer::new();
+    let ctx = CommandContext::new("kubectl get pods");
+    let mut input = "NAME           READY   STATUS             RESTARTS   AGE\n".to_string();
+    for i in 0..40 {
+        input.push_str(&format!(
+            "api-{i:<9}  1/1     Running            0          2d\n"
+        ));
+    }
+    input.push_str("worker-abc     0/1     ImagePullBackOff   0          5m\n");
+
+    let result = opt.optimize_output(&ctx, &input).unwrap();
+    assert_eq!(result.optimizer_used, "kubectl");
+    assert!(!result.output.contains("api-1 "));
+    assert!(result.output.contains("ImagePullBackOff (1):"));
+    assert!(result.output.contains("worker-abc"));
+    assert!(result.output.contains("[+40 healthy: 40 Running]"));
+}
+
+#[test]
+fn kubectl_optimizer_logs_keeps_errors() {
+    let opt = KubectlOptimizer::new();
+    let ctx = CommandContext::new("kubectl logs api");
+    let mut input = String::new();
+    for i in 0..100 {
+        input.push_str(&format!("INFO request {i} ok\n"));
+    }
+    input.push_str("ERROR connection refused\n");
+
+    let result = opt.optimize_output(&ctx, &input).unwrap();
+    assert!(result.output.contains("ERROR connection refused"));
+    assert!(!result.output.contains("request 0 ok"));
+}
+
+// ===========================================================================
+// JsonOptimizer — integration tests
+// ===========================================================================
+
+#[test]
+fn registry_summarizes_json_from_unclaimed_commands() {
+    use terse::optimizers::OptimizerRegistry;
+
+    let pulls: Vec<String> = (0..40)
+        .map(|i| {
+            format!(
+                r#"{{"number": {i}, "title": "Change {i}", "body": "{}", "labels": [], "milestone": null}}"#,
+                "details ".repeat(60)
+            )
+        })
+        .collect();
+    let input = format!("[{}]", pulls.join(",\n"));
+
+    let registry = OptimizerRegistry::new();
+    let result = registry
+        .optimize_first_with_output("gh api repos/o/r/pulls", &input, &[])
+        .unwrap();
+    assert_eq!(result.optimizer_used, "json");
+    assert!(result.output.contains(
+        "[40 objects, showing 3; schema {number: number, title: string, body: string, labels: array, milestone: null}]"
+    ));
+    assert!(result.output.contains("… [+280 chars]"));
+    assert!(result.output.len() < input.len() / 10);
+}
+
+#[test]
+fn registry_passes_plain_text_past_json() {
+    use terse::optimizers::OptimizerRegistry;
+
+    let registry = OptimizerRegistry::new();
+    let result = registry
+        .optimize_first_with_output("curl localhost:8080/health", "OK\n", &[])
+        .unwrap();
+    assert_eq!(result.optimizer_used, "generic");
+}
+
+#[test]
+fn docker_inspect_uses_json_summary() {
+    let opt = DockerOptimizer::new();
+    let ctx = CommandContext::new("docker inspect web");
+    let env: Vec<String> = (0..30).map(|i| format!(r#""VAR_{i}=value""#)).collect();
+    let input = format!(
+        r#"[{{"Id": "abc123", "Name": "/web", "Config": {{"Env": [{}], "Volumes": null}}}}]"#,
+        env.join(", ")
+    );
+
+    let result = opt.optimize_output(&ctx, &input).unwrap();
+    assert!(result.output.contains("\"Name\": \"/web\""));
+    assert!(result.output.contains("[30 strings, showing 3]"));
+    assert!(!result.output.contains("Volumes"));
+}
+
 // ===========================================================================
 // GenericOptimizer — integration tests
 // ===========================================================================
@@ -540,6 +886,13 @@ fn registry_routes_to_correct_optimizer() {
     assert!(registry.can_handle("docker ps"));
     assert!(registry.can_handle("docker images"));
 
+    // Search commands handled
+    assert!(registry.can_handle("rg -n foo"));
+
+    // Kubernetes commands handled
+    assert!(registry.can_handle("kubectl get pods"));
+    assert!(registry.can_handle("helm list"));
+
     // Unknown commands handled by generic optimizer
     assert!(registry.can_handle("some-unknown-tool --flag"));
 }
@@ -551,9 +904,10 @@ fn registry_git_has_priority_over_generic() {
     let registry = OptimizerRegistry::new();
 
     // Git diff with sample output — should use git optimizer, not generic
-    let result = registry.optimize_first(
+    let result = registry.optimize_first_with_output(
         "git diff",
         "diff --git a/file.rs b/file.rs\n--- a/file.rs\n+++ b/file.rs\n@@ -1 +1 @@\n-old\n+new\n",
+        &[],
     );
     assert!(result.is_some());
     assert_eq!(result.unwrap().optimizer_used, "git");
@@ -564,9 +918,10 @@ fn registry_build_has_priority_over_generic() {
     use terse::optimizers::OptimizerRegistry;
 
     let registry = OptimizerRegistry::new();
-    let result = registry.optimize_first(
+    let result = registry.optimize_first_with_output(
         "cargo test",
         "running 1 test\ntest mytest ... ok\ntest result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out",
+        &[],
     );
     assert!(result.is_some());
     assert_eq!(result.unwrap().optimizer_used, "build");
@@ -584,9 +939,10 @@ fn registry_disabled_optimizer_not_loaded() {
 
     // Git commands should no longer be handled by the git optimizer
     // (generic will still catch them as a fallback)
-    let result = registry.optimize_first(
+    let result = registry.optimize_first_with_output(
         "git status",
         "On branch main\nnothing to commit, working tree clean",
+        &[],
     );
     assert!(result.is_some());
     // Should fall through to generic, not git
@@ -607,7 +963,8 @@ fn registry_all_disabled_except_generic() {
     let registry = OptimizerRegistry::from_config(&cfg);
 
     // Everything falls through to generic
-    let result = registry.optimize_first("git status", "On branch main\nnothing to commit");
+    let result =
+        registry.optimize_first_with_output("git status", "On branch main\nnothing to commit", &[]);
     assert!(result.is_some());
     assert_eq!(result.unwrap().optimizer_used, "generic");
 }
@@ -622,11 +979,16 @@ fn registry_all_disabled_returns_none() {
     cfg.file.enabled = false;
     cfg.build.enabled = false;
     cfg.docker.enabled = false;
+    cfg.json.enabled = false;
     cfg.generic.enabled = false;
 
     let registry = OptimizerRegistry::from_config(&cfg);
 
     // Nothing can handle it
     assert!(!registry.can_handle("git status"));
-    assert!(registry.optimize_first("git status", "output").is_none());
+    assert!(
+        registry
+            .optimize_first_with_output("git status", "output", &[])
+            .is_none()
+    );
 }
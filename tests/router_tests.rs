@@ -196,18 +196,24 @@ fn hook_rewrites_unknown_safe_commands() {
 #[test]
 fn decision_cache_stores_and_retrieves() {
     let mut cache = DecisionCache::new(300);
-    cache.insert("git".to_string(), OptimizationPath::FastPath);
-    cache.insert("docker".to_string(), OptimizationPath::SmartPath);
+    cache.insert("git status".to_string(), HookDecision::Rewrite);
+    cache.insert(
+        "rm -rf target".to_string(),
+        HookDecision::Passthrough(PassthroughReason::NeverOptimize),
+    );
 
-    assert_eq!(cache.get("git"), Some(OptimizationPath::FastPath));
-    assert_eq!(cache.get("docker"), Some(OptimizationPath::SmartPath));
+    assert_eq!(cache.get("git status"), Some(HookDecision::Rewrite));
+    assert_eq!(
+        cache.get("rm -rf target"),
+        Some(HookDecision::Passthrough(PassthroughReason::NeverOptimize))
+    );
     assert_eq!(cache.get("unknown"), None);
 }
 
 #[test]
 fn decision_cache_entries_expire() {
     let mut cache = DecisionCache::new(0); // 0-second TTL → expires immediately
-    cache.insert("git".to_string(), OptimizationPath::FastPath);
+    cache.insert("git".to_string(), HookDecision::Rewrite);
     std::thread::sleep(std::time::Duration::from_millis(10));
     assert_eq!(cache.get("git"), None);
 }